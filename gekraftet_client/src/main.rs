@@ -1,3 +1,4 @@
+use std::sync::{ Arc, Mutex };
 use std::time::Instant;
 
 mod camera;
@@ -26,34 +27,59 @@ fn main() {
     let (tx, rx) = std::sync::mpsc::channel::<(i32, i32, i32, mesh::Mesh)>();
     let (bound0, bound1) = (-16i32, 16i32);
 
-    let world_minister = std::thread::spawn(move || {
-        let tx = tx;
-        
-        for x in bound0..bound1 {
-            for y in bound0..bound1 {
-                let tx = tx.clone();
-                let mut noise = Noise::<Perlin3D>::with_option(
-                    NoiseGenOption::new()
-                        .octaves(16)
-                        .amplitude(10.0)
-                        .persistance(0.5)
-                        .frequency(628.318530)
-                        .lacunarity(0.5),
-                    ((x << 6) ^ (y + 123456)) as u64,
-                );
-
-                std::thread::spawn(move || {
-                    let pos = Point3::<i32>::new(x, 0, y);
-                    let chunk = Chunk::new(pos, &mut noise);
-                    let mesher = world::GreedyCubeMesher::from_chunk(&chunk);
-                    let mesh = mesher.generate_mesh();
-                    tx.send((pos.x, pos.y, pos.z, mesh))
-                });
+    // The world type and seed can be picked on the command line, for example
+    // `gekraftet_client flat;7,2x3,2 42`.
+    let mut args = std::env::args().skip(1);
+    let preset = args.next()
+        .map(|preset| WorldPreset::parse(&preset).expect("invalid world type"))
+        .unwrap_or_default();
+    let seed = args.next()
+        .map(|seed| seed.parse().expect("invalid seed"))
+        .unwrap_or(123456);
+
+    // A single generator is shared by every worker, so that the terrain is
+    // continuous across chunk borders.
+    let generator: Arc<dyn WorldGenerator> = Arc::from(preset.into_generator(seed));
+
+    let (job_tx, job_rx) = std::sync::mpsc::channel::<Point3<i32>>();
+    let job_rx = Arc::new(Mutex::new(job_rx));
+
+    let workers = std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(4);
+
+    for _ in 0..workers {
+        let tx = tx.clone();
+        let generator = Arc::clone(&generator);
+        let job_rx = Arc::clone(&job_rx);
+
+        std::thread::spawn(move || loop {
+            // The lock is released as soon as a job is received.
+            let job = job_rx.lock().unwrap().recv();
+            let pos = match job {
+                Ok(pos) => pos,
+                Err(_) => break,
+            };
+
+            let chunk = generator.generate_chunk(pos.into());
+            let mesher = world::GreedyCubeMesher::from_chunk(&chunk);
+            let mesh = mesher.generate_mesh();
+
+            if tx.send((pos.x, pos.y, pos.z, mesh)).is_err() {
+                break
             }
+        });
+    }
+
+    drop(tx);
+
+    for x in bound0..bound1 {
+        for y in bound0..bound1 {
+            job_tx.send(Point3::<i32>::new(x, 0, y)).unwrap();
         }
+    }
 
-        drop(tx);
-    });
+    drop(job_tx);
     
     let speed = 10.0;
 
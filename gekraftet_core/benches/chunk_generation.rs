@@ -0,0 +1,76 @@
+use cgmath::{ Point3, Vector3 };
+use criterion::{ black_box, criterion_group, criterion_main, Criterion };
+use gekraftet_core::world::*;
+
+// Run with `--features simd` to compare against the SIMD lattice path.
+
+fn noise() -> Noise<Perlin3D> {
+    // The same generator the client uses.
+    Noise::with_option(
+        NoiseGenOption::new()
+            .octaves(16)
+            .amplitude(10.0)
+            .persistance(0.5)
+            .frequency(628.31854)
+            .lacunarity(0.5),
+        123456,
+    )
+}
+
+fn lattice(c: &mut Criterion) {
+    let noise = noise();
+    let size = [NOISE_SAMPLES_X + 1, NOISE_SAMPLES_Y * 16 + 1, NOISE_SAMPLES_Z + 1];
+    let step = Vector3::new(
+        NOISE_FACTOR_X as f32,
+        NOISE_FACTOR_Y as f32,
+        NOISE_FACTOR_Z as f32,
+    );
+    let mut output = vec![0.0; size[0] * size[1] * size[2]];
+
+    let mut group = c.benchmark_group("chunk_lattice");
+
+    group.bench_function("per_point", |b| b.iter(|| {
+        let mut output = output.iter_mut();
+
+        for x in 0..size[0] {
+            for y in 0..size[1] {
+                for z in 0..size[2] {
+                    let at = Point3::new(
+                        x as f32 * step.x,
+                        y as f32 * step.y,
+                        z as f32 * step.z,
+                    );
+                    *output.next().unwrap() = noise.generate_noise(black_box(at));
+                }
+            }
+        }
+    }));
+
+    group.bench_function("batched", |b| b.iter(|| {
+        noise.generate_noise_lattice(black_box(Point3::new(0.0, 0.0, 0.0)), step, size, &mut output);
+    }));
+
+    group.finish();
+}
+
+fn chunk(c: &mut Criterion) {
+    let noise = noise();
+
+    let mut group = c.benchmark_group("chunk_generation");
+    group.sample_size(20);
+
+    group.bench_function("section_by_section", |b| b.iter(|| {
+        (0..16)
+            .map(|y| Section::new(SectionPos::new(0, y, 0), &noise))
+            .collect::<Vec<_>>()
+    }));
+
+    group.bench_function("chunk", |b| b.iter(|| {
+        Chunk::new(black_box(Point3::new(0, 0, 0)), &noise)
+    }));
+
+    group.finish();
+}
+
+criterion_group!(benches, lattice, chunk);
+criterion_main!(benches);
@@ -0,0 +1,342 @@
+use cgmath::{ InnerSpace, Point3, Vector3 };
+use rand::Rng;
+use crate::physics::PLAYER_EYE_HEIGHT;
+use crate::player::{ GameMode, Player };
+use crate::utils::Random;
+use crate::world::{ self, block_ids, BlockPos, Level, Path };
+use super::*;
+
+/// How far hostile mobs notice the players, and how far they look for a way
+/// to get to them.
+pub const FOLLOW_RANGE: f64 = 16.0;
+/// The ticks a passive mob runs away for after it is hurt.
+pub const FLEE_TIME: i16 = 60;
+
+// One in this many ticks, a mob which is not going anywhere sets off.
+const WANDER_CHANCE: u32 = 80;
+// How far a wandering mob goes, across and up.
+const WANDER_RANGE: (i32, i32) = (6, 3);
+// The places a wandering mob picks from.
+const WANDER_TRIES: usize = 10;
+// One in this many ticks, a mob chasing a player looks for a new path.
+const REPATH_CHANCE: u32 = 20;
+
+// The ticks between two hits of a mob fighting in melee, and how close it has
+// to be.
+const MELEE_COOLDOWN: i16 = 20;
+const MELEE_RANGE: f64 = 2.0;
+// The ticks between two arrows of a skeleton, and how far it shoots.
+const ARROW_COOLDOWN: i16 = 30;
+const ARROW_RANGE: f64 = 10.0;
+const ARROW_SPEED: f64 = 0.6;
+// How close a creeper gets before it starts hissing.
+const CREEPER_RANGE: f64 = 3.0;
+// How far a spider leaps at the players.
+const SPIDER_LEAP_RANGE: f64 = 6.0;
+// Spiders only go after the players in the dark.
+const SPIDER_MAX_LIGHT: u8 = 7;
+
+const JUMP_VELOCITY: f64 = 0.42;
+const SWIM_VELOCITY: f64 = 0.04;
+
+/// What a mob is up to. It decides where it goes every tick, and walks there
+/// along a path.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MobAi {
+    /// The entity id of the player a hostile mob goes after.
+    pub target: Option<EntityId>,
+    pub path: Option<Path>,
+    /// The ticks left for a hurt passive mob to run away.
+    pub flee_time: i16,
+    /// How fast the mob walks towards its yaw this tick.
+    pub forward: f64,
+    pub jumping: bool,
+}
+
+/// A mob hitting a player, which the player takes damage from.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct MobAttack {
+    pub attacker: EntityId,
+    pub target: EntityId,
+    pub damage: i16,
+}
+
+/// Something a mob does to a player.
+#[derive(Clone, Debug, PartialEq)]
+pub enum MobAction {
+    /// The mob hit a player in melee.
+    Attack(MobAttack),
+    /// A skeleton shot an arrow from `from`.
+    Shoot {
+        from: Point3<f64>,
+        velocity: Vector3<f64>,
+    },
+}
+
+/// Whether a mob goes after the player at all.
+fn can_target(player: &Player) -> bool {
+    player.health > 0 && player.game_mode == GameMode::Survival
+}
+
+fn horizontal_distance(a: Point3<f64>, b: Point3<f64>) -> f64 {
+    ((a.x - b.x) * (a.x - b.x) + (a.z - b.z) * (a.z - b.z)).sqrt()
+}
+
+/// The yaw facing from `from` towards `to`, in degrees.
+fn yaw_towards(from: Point3<f64>, to: Point3<f64>) -> f32 {
+    (-(to.x - from.x)).atan2(to.z - from.z).to_degrees() as f32
+}
+
+/// Whether nothing opaque is between the two points.
+fn can_see(level: &Level, from: Point3<f64>, to: Point3<f64>) -> bool {
+    let direction = to - from;
+    let distance = direction.magnitude();
+    if distance == 0.0 {
+        return true
+    }
+
+    world::raycast(
+        from.cast().unwrap(),
+        direction.cast().unwrap(),
+        distance as f32,
+        |at| level.block(at).is_some_and(|b| b.info().opaque),
+    ).is_none()
+}
+
+fn is_water(level: &Level, at: BlockPos) -> bool {
+    level.block(at).is_some_and(|b| matches!(b.id, block_ids::FLOWING_WATER | block_ids::WATER))
+}
+
+/// Picks a place around `start` for a mob to wander off to, and finds the way
+/// there. Passive mobs like grass and light, and hostile mobs the dark.
+fn wander(level: &Level, start: BlockPos, size: (i32, i32), hostile: bool, rng: &mut Random) -> Option<Path> {
+    let (across, up) = WANDER_RANGE;
+
+    let weight = |at: BlockPos| {
+        let light = level.light(at) as f32 / world::MAX_LIGHT as f32;
+        if hostile {
+            0.5 - light
+        } else if level.block(at.offset(0, -1, 0)).is_some_and(|b| b.id == block_ids::GRASS) {
+            10.0
+        } else {
+            light - 0.5
+        }
+    };
+
+    let mut best: Option<(f32, BlockPos)> = None;
+    for _ in 0..WANDER_TRIES {
+        let at = start.offset(
+            rng.gen_range(-across, across + 1),
+            rng.gen_range(-up, up + 1),
+            rng.gen_range(-across, across + 1),
+        );
+
+        let weight = weight(at);
+        if best.is_none_or(|(best, _)| weight > best) {
+            best = Some((weight, at));
+        }
+    }
+
+    let (_, to) = best?;
+    level.find_path(start, to, size, FOLLOW_RANGE as i32)
+}
+
+impl Entity {
+    /// The block a mob stands in with its lowest corner, from where its
+    /// paths start.
+    fn path_start(&self) -> BlockPos {
+        let aabb = self.body.aabb();
+        BlockPos::new(aabb.min.x.floor() as i32, aabb.min.y.floor() as i32, aabb.min.z.floor() as i32)
+    }
+
+    /// The blocks a mob takes up along its paths, across and up.
+    fn path_size(&self) -> (i32, i32) {
+        ((self.body.width + 1.0) as i32, (self.body.height + 1.0) as i32)
+    }
+
+    /// Decides what a mob does this tick: where it walks to, and whether it
+    /// attacks a player. Passive mobs wander around, and run away when they
+    /// are hurt. Hostile mobs go after the closest player they notice, and
+    /// wander around when there is none. Skeletons shoot arrows, and
+    /// creepers hiss when they are close enough.
+    pub fn think(&mut self, level: &Level, players: &[Player], rng: &mut Random) -> Option<MobAction> {
+        let position = self.body.position;
+        let eyes = position + Vector3::new(0.0, self.body.height * 0.85, 0.0);
+        let start = self.path_start();
+        let size = self.path_size();
+        let in_water = is_water(level, start);
+
+        let mob = match &mut self.kind {
+            EntityKind::Mob(mob) => mob,
+            _ => return None,
+        };
+
+        mob.ai.forward = 0.0;
+        mob.ai.jumping = false;
+        if mob.health <= 0 {
+            mob.ai.path = None;
+            return None
+        }
+
+        let mut speed = mob.mob.move_speed();
+        let mut action = None;
+
+        // A hostile mob keeps its target as long as it is in range, or looks
+        // for the closest player otherwise.
+        let in_range = |player: &&Player| {
+            can_target(player) && (player.body.position - position).magnitude() <= FOLLOW_RANGE
+        };
+        let mut target = mob.ai.target
+            .and_then(|id| players.iter().find(|p| p.entity_id == id))
+            .filter(in_range);
+
+        let notices = || mob.mob != MobType::Spider || level.light(start) <= SPIDER_MAX_LIGHT;
+        if target.is_none() && mob.mob.is_hostile() && notices() {
+            target = players.iter()
+                .filter(in_range)
+                .min_by(|a, b| {
+                    let a = (a.body.position - position).magnitude();
+                    let b = (b.body.position - position).magnitude();
+                    a.partial_cmp(&b).unwrap()
+                });
+        }
+        mob.ai.target = target.map(|p| p.entity_id);
+
+        // Whether the mob stands still to attack.
+        let mut attacking = false;
+
+        if let Some(player) = target {
+            let distance = (player.body.position - position).magnitude();
+            let player_eyes = player.body.position + Vector3::new(0.0, PLAYER_EYE_HEIGHT, 0.0);
+            self.yaw = yaw_towards(position, player.body.position);
+
+            match mob.mob {
+                MobType::Creeper if distance < CREEPER_RANGE => {
+                    mob.fuse += 1;
+                    attacking = true;
+                },
+
+                MobType::Skeleton if distance < ARROW_RANGE && can_see(level, eyes, player_eyes) => {
+                    if mob.attack_time == 0 {
+                        mob.attack_time = ARROW_COOLDOWN;
+
+                        // Arrows are aimed a bit higher the further the
+                        // player is, since they fall on the way.
+                        let mut aim = player_eyes - eyes;
+                        aim.y -= 0.7;
+                        aim.y += horizontal_distance(player_eyes, eyes) * 0.2;
+
+                        action = Some(MobAction::Shoot {
+                            from: eyes,
+                            velocity: aim.normalize() * ARROW_SPEED,
+                        });
+                    }
+                    attacking = true;
+                },
+
+                MobType::Zombie | MobType::Spider if distance < MELEE_RANGE => {
+                    if mob.attack_time == 0 {
+                        mob.attack_time = MELEE_COOLDOWN;
+                        action = Some(MobAction::Attack(MobAttack {
+                            attacker: self.id,
+                            target: player.entity_id,
+                            damage: mob.mob.attack_damage(),
+                        }));
+                    }
+                    attacking = true;
+                },
+
+                MobType::Spider if distance < SPIDER_LEAP_RANGE && self.body.on_ground && rng.gen_range(0, 10) == 0 => {
+                    let towards = Vector3::new(player.body.position.x - position.x, 0.0, player.body.position.z - position.z);
+                    let leap = towards / horizontal_distance(player.body.position, position) * 0.4;
+                    self.body.velocity = Vector3::new(
+                        leap.x + self.body.velocity.x * 0.2,
+                        0.4,
+                        leap.z + self.body.velocity.z * 0.2,
+                    );
+                },
+
+                _ => {},
+            }
+
+            if !attacking {
+                let stale = mob.ai.path.as_ref().is_none_or(Path::is_done);
+                if stale || rng.gen_range(0, REPATH_CHANCE) == 0 {
+                    let to = BlockPos::new(
+                        player.body.position.x.floor() as i32,
+                        player.body.position.y.floor() as i32,
+                        player.body.position.z.floor() as i32,
+                    );
+                    mob.ai.path = level.find_path(start, to, size, FOLLOW_RANGE as i32);
+                }
+            }
+        } else if mob.ai.flee_time > 0 {
+            mob.ai.flee_time -= 1;
+            speed *= 2.0;
+
+            if mob.ai.path.as_ref().is_none_or(Path::is_done) {
+                mob.ai.path = wander(level, start, size, false, rng);
+            }
+        } else if mob.ai.path.is_none() && rng.gen_range(0, WANDER_CHANCE) == 0 {
+            mob.ai.path = wander(level, start, size, mob.mob.is_hostile(), rng);
+        }
+
+        // Creepers calm down when the player gets away.
+        if mob.mob == MobType::Creeper && !attacking {
+            mob.fuse = (mob.fuse - 1).max(0);
+        }
+
+        if attacking {
+            mob.ai.path = None;
+        }
+
+        if let Some(path) = &mut mob.ai.path {
+            // The mob is at a point of its path once its center is close
+            // enough to the center of the point.
+            let reach = self.body.width * 2.0;
+            let center = |at: BlockPos| Point3::new(
+                at.x as f64 + size.0 as f64 * 0.5,
+                at.y as f64,
+                at.z as f64 + size.0 as f64 * 0.5,
+            );
+
+            while path.next().is_some_and(|at| horizontal_distance(center(at), position) < reach) {
+                path.advance();
+            }
+
+            match path.next() {
+                Some(at) => {
+                    self.yaw = yaw_towards(position, center(at));
+                    mob.ai.forward = speed;
+                    mob.ai.jumping = at.y > start.y;
+                },
+                None => mob.ai.path = None,
+            }
+        }
+
+        // Mobs jump over what is in their way, and swim up in water.
+        if self.body.collided_horizontally && mob.ai.forward > 0.0 || in_water {
+            mob.ai.jumping = true;
+        }
+
+        action
+    }
+
+    /// Moves a mob as it decided to in `think`.
+    pub(super) fn walk(&mut self, level: &Level) -> Option<f64> {
+        let (forward, jumping) = match &self.kind {
+            EntityKind::Mob(mob) => (mob.ai.forward, mob.ai.jumping),
+            _ => (0.0, false),
+        };
+
+        if jumping {
+            if self.body.on_ground {
+                self.body.velocity.y = JUMP_VELOCITY;
+            } else if is_water(level, self.path_start()) {
+                self.body.velocity.y += SWIM_VELOCITY;
+            }
+        }
+
+        self.body.tick_with_input(level, 0.0, forward, self.yaw)
+    }
+}
@@ -0,0 +1,513 @@
+use std::collections::BTreeMap;
+use cgmath::{ Point3, Vector3 };
+use rand::Rng;
+use crate::item::ItemStack;
+use crate::nbt::ValueData;
+use crate::player::{ DamageSource, Player };
+use crate::utils::Random;
+use crate::world::{ can_fall_into, Block, BlockPos, ChunkPos, Explosion, Level };
+use super::*;
+
+/// Every entity of a world, keyed by their ids. The entities are ticked in
+/// the order of their ids, so that ticking the same entities twice gives the
+/// same result.
+#[derive(Clone, Debug)]
+pub struct Entities {
+    next_id: EntityId,
+    entities: BTreeMap<EntityId, Entity>,
+    attacks: Vec<MobAttack>,
+}
+
+impl Default for Entities {
+    fn default() -> Self {
+        Self {
+            next_id: 1,
+            entities: BTreeMap::new(),
+            attacks: Vec::new(),
+        }
+    }
+}
+
+impl Entities {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reserves a new entity id. Players take their ids from here as well,
+    /// since they share the ids with the other entities in the protocol.
+    pub fn allocate_id(&mut self) -> EntityId {
+        let id = self.next_id;
+        self.next_id += 1;
+        id
+    }
+
+    /// Adds a new entity to the world, returning its id.
+    pub fn spawn(&mut self, kind: EntityKind, position: Point3<f64>) -> EntityId {
+        let id = self.allocate_id();
+        self.entities.insert(id, Entity::new(id, kind, position));
+        id
+    }
+
+    /// Adds an entity which already has an id, returning the entity with the
+    /// same id which it replaces.
+    pub fn insert(&mut self, entity: Entity) -> Option<Entity> {
+        self.next_id = self.next_id.max(entity.id + 1);
+        self.entities.insert(entity.id, entity)
+    }
+
+    pub fn remove(&mut self, id: EntityId) -> Option<Entity> {
+        self.entities.remove(&id)
+    }
+
+    pub fn get(&self, id: EntityId) -> Option<&Entity> {
+        self.entities.get(&id)
+    }
+
+    pub fn get_mut(&mut self, id: EntityId) -> Option<&mut Entity> {
+        self.entities.get_mut(&id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Entity> {
+        self.entities.values()
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Entity> {
+        self.entities.values_mut()
+    }
+
+    pub fn in_chunk(&self, at: ChunkPos) -> impl Iterator<Item = &Entity> {
+        self.entities.values().filter(move |e| e.chunk() == at)
+    }
+
+    /// Takes out the hits the mobs have dealt to the players since this was
+    /// last called.
+    pub fn take_attacks(&mut self) -> Vec<MobAttack> {
+        std::mem::take(&mut self.attacks)
+    }
+
+    /// Adds the entities the level asked for, such as falling sand.
+    fn spawn_queued(&mut self, level: &mut Level) {
+        for (kind, position) in level.take_spawns() {
+            self.spawn(kind, position);
+        }
+    }
+
+    /// Advances every entity by a tick, and takes out the ones which are
+    /// gone so that they can be destroyed on the clients. The mobs decide
+    /// what to do about the players first, and the hits which hurt a player
+    /// are kept for `take_attacks`, along with the arrows which hit one.
+    /// Falling blocks which are gone are placed where they landed, or dropped
+    /// as items, dead mobs drop their loot, and primed TNT and creepers
+    /// explode.
+    pub fn tick(&mut self, level: &mut Level, players: &mut [Player], rng: &mut Random) -> Vec<Entity> {
+        self.spawn_queued(level);
+
+        let mut hits = Vec::new();
+        let mut arrows = Vec::new();
+        for entity in self.entities.values_mut() {
+            match entity.think(level, players, rng) {
+                Some(MobAction::Attack(attack)) => hits.push(attack),
+                Some(MobAction::Shoot { from, velocity }) => arrows.push((entity.id, from, velocity)),
+                None => {},
+            }
+
+            entity.tick(level);
+
+            if let EntityKind::Projectile { shooter, stuck_in: None, .. } = entity.kind {
+                let target = players.iter().find(|p| {
+                    Some(p.entity_id) != shooter && p.body.aabb().inflate(0.3).contains(entity.body.position)
+                });
+
+                if let Some(player) = target {
+                    entity.removed = true;
+                    hits.push(MobAttack {
+                        attacker: shooter.unwrap_or(entity.id),
+                        target: player.entity_id,
+                        damage: ARROW_DAMAGE,
+                    });
+                }
+            }
+        }
+
+        for hit in hits {
+            let player = players.iter_mut().find(|p| p.entity_id == hit.target);
+            if player.is_some_and(|p| p.hurt(hit.damage, DamageSource::Mob(hit.attacker))) {
+                self.attacks.push(hit);
+            }
+        }
+
+        for (shooter, from, velocity) in arrows {
+            let id = self.spawn(EntityKind::projectile(ProjectileType::Arrow, Some(shooter)), from);
+            self.entities.get_mut(&id).unwrap().body.velocity = velocity;
+        }
+
+        let removed = self.entities.iter()
+            .filter(|(_, e)| e.removed)
+            .map(|(&id, _)| id)
+            .collect::<Vec<_>>();
+
+        let removed = removed.into_iter()
+            .filter_map(|id| self.entities.remove(&id))
+            .collect::<Vec<_>>();
+
+        for entity in removed.iter() {
+            if let EntityKind::FallingBlock { block } = entity.kind {
+                if let Some(stack) = place_falling_block(level, entity, block) {
+                    self.spawn(EntityKind::item(stack), entity.position());
+                }
+            }
+
+            if let Some(mob) = entity.mob().filter(|mob| mob.health <= 0) {
+                for stack in mob.loot(rng) {
+                    self.spawn(EntityKind::item(stack), entity.position());
+                }
+            }
+
+            if let Some((center, power)) = entity.explosion() {
+                self.explode(level, players, center, power, rng);
+            }
+        }
+
+        removed
+    }
+
+    /// A player hits an entity, such as when it left-clicks it. Only living
+    /// mobs within reach can be hit, and not again while they are still
+    /// hurt. The mob is pushed away, and a hostile mob goes after the player.
+    /// Returns whether the mob was hit.
+    pub fn attack(&mut self, player: &mut Player, target: EntityId) -> bool {
+        let entity = match self.entities.get_mut(&target) {
+            Some(entity) => entity,
+            None => return false,
+        };
+
+        let away = entity.body.position - player.body.position;
+        let mob = match &mut entity.kind {
+            EntityKind::Mob(mob) => mob,
+            _ => return false,
+        };
+
+        if player.is_dead() || mob.health <= 0 || mob.hurt_time > 0 || away.magnitude() > ATTACK_REACH {
+            return false
+        }
+
+        mob.hurt(player.strike());
+        if mob.mob.is_hostile() {
+            mob.ai.target = Some(player.entity_id);
+        }
+
+        let horizontal = Vector3::new(away.x, 0.0, away.z);
+        let push = if horizontal.magnitude() > 0.0 { horizontal.normalize() * 0.4 } else { horizontal };
+        let velocity = entity.body.velocity / 2.0 + push;
+        entity.body.velocity = Vector3::new(velocity.x, (velocity.y + 0.4).min(0.4), velocity.z);
+
+        true
+    }
+
+    /// Throws items out in random directions from where a player is, such as
+    /// when it dies.
+    pub fn scatter_items(&mut self, player: &Player, items: Vec<ItemStack>, rng: &mut Random) {
+        let from = player.eye_position() - Vector3::new(0.0, 0.3, 0.0);
+
+        for stack in items {
+            let speed = rng.gen::<f64>() * 0.5;
+            let angle = rng.gen::<f64>() * std::f64::consts::PI * 2.0;
+
+            let kind = EntityKind::Item {
+                stack,
+                age: 0,
+                pickup_delay: 40,
+            };
+            let id = self.spawn(kind, from);
+            self.entities.get_mut(&id).unwrap().body.velocity = Vector3::new(-angle.sin() * speed, 0.2, angle.cos() * speed);
+        }
+    }
+
+    /// Sets off an explosion. The entities and the players around it are hurt
+    /// and pushed away, and the blocks it reaches are destroyed. Items hit
+    /// hard enough are destroyed as well, and are taken out at the next tick.
+    pub fn explode(&mut self, level: &mut Level, players: &mut [Player], center: Point3<f64>, power: f32, rng: &mut Random) -> Explosion {
+        let explosion = Explosion::new(level, center, power, rng);
+
+        for player in players.iter_mut() {
+            if let Some((damage, knockback)) = explosion.impact(level, &player.body) {
+                player.hurt(damage, DamageSource::Explosion);
+                player.body.velocity += knockback;
+            }
+        }
+
+        for entity in self.entities.values_mut() {
+            let (damage, knockback) = match explosion.impact(level, &entity.body) {
+                Some(impact) => impact,
+                None => continue,
+            };
+
+            match &mut entity.kind {
+                EntityKind::Mob(mob) if mob.health > 0 => mob.hurt(damage),
+                EntityKind::Item { .. } if damage >= ITEM_HEALTH => entity.removed = true,
+                _ => {},
+            }
+
+            entity.body.velocity += knockback;
+        }
+
+        explosion.destroy_blocks(level, rng);
+        self.spawn_queued(level);
+        explosion
+    }
+
+    /// Writes the entities in a chunk as the `Entities` list of the chunk.
+    pub fn chunk_to_nbt(&self, at: ChunkPos) -> ValueData {
+        ValueData::List(10, self.in_chunk(at)
+            .map(|e| ValueData::Compound(e.to_nbt()))
+            .collect())
+    }
+
+    /// Reads the `Entities` list of a chunk, giving new ids to the entities.
+    /// Returns the ids of the entities which were added. Entities which are
+    /// unknown or invalid are skipped, along with the reason why.
+    pub fn load_chunk_nbt(&mut self, entities: &[ValueData]) -> (Vec<EntityId>, Vec<EntityError>) {
+        let mut ids = Vec::new();
+        let mut errors = Vec::new();
+
+        for entity in entities {
+            let tag = match entity.compound() {
+                Some(tag) => tag,
+                None => {
+                    errors.push(EntityError::InvalidTag("Entities"));
+                    continue
+                },
+            };
+
+            match Entity::from_nbt(self.next_id, tag) {
+                Ok(entity) => {
+                    ids.push(entity.id);
+                    self.insert(entity);
+                },
+                Err(e) => errors.push(e),
+            }
+        }
+
+        (ids, errors)
+    }
+
+    /// Takes out the entities in a chunk, such as when it is unloaded.
+    pub fn unload_chunk(&mut self, at: ChunkPos) -> Vec<Entity> {
+        let ids = self.in_chunk(at).map(|e| e.id).collect::<Vec<_>>();
+
+        ids.into_iter()
+            .filter_map(|id| self.entities.remove(&id))
+            .collect()
+    }
+}
+
+/// How far above its feet a falling block is looked up in Beta. A block which
+/// landed on a slab is then still in the cell of the slab, and drops.
+const FALLING_BLOCK_OFFSET: f64 = 0.49;
+
+/// Places a falling block where it landed. Returns the item it drops instead
+/// if it cannot be placed there, such as when it landed on a torch or a slab,
+/// or when it fell for too long.
+fn place_falling_block(level: &mut Level, entity: &Entity, block: Block) -> Option<ItemStack> {
+    let position = entity.position();
+    let at = BlockPos::new(
+        position.x.floor() as i32,
+        (position.y + FALLING_BLOCK_OFFSET).floor() as i32,
+        position.z.floor() as i32,
+    );
+
+    let fits = entity.body.on_ground && level.block(at).is_some_and(can_fall_into);
+    if fits && level.set_block_with_notify(at, block) {
+        None
+    } else {
+        Some(ItemStack::new(block.id, 1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use crate::player::MAX_HEALTH;
+    use crate::world::{ block_ids, SuperflatGenerator, WorldGenerator };
+    use super::*;
+
+    // The world is 7 by 7 chunks of grass around the player, who stands in
+    // the middle.
+    const RADIUS: i32 = 3;
+    const GROUND: i32 = 4;
+
+    fn flat_world() -> (Level, SuperflatGenerator) {
+        let generator = SuperflatGenerator::from_layers("7,2x3,2").unwrap();
+        let mut level = Level::new();
+        for x in -RADIUS..=RADIUS {
+            for z in -RADIUS..=RADIUS {
+                level.insert_chunk(generator.generate_chunk(ChunkPos::new(x, 0, z)));
+            }
+        }
+
+        (level, generator)
+    }
+
+    /// What became of the mobs: their ids, types, health and where they are.
+    type Outcome = Vec<(EntityId, MobType, i16, Point3<f64>)>;
+
+    /// Runs the world for `ticks` ticks, as the server does: the mobs appear
+    /// every second, and the entities and the players are ticked. Nothing
+    /// but `rng` decides what happens.
+    fn simulate(level: &mut Level, generator: &SuperflatGenerator, entities: &mut Entities,
+                players: &mut [Player], rng: &mut Random, ticks: u32) -> Vec<MobAttack> {
+        let mut attacks = Vec::new();
+
+        for tick in 0..ticks {
+            if tick % 20 == 0 {
+                entities.spawn_mobs(level, generator, players, rng);
+            }
+            entities.tick(level, players, rng);
+            attacks.extend(entities.take_attacks());
+
+            for player in players.iter_mut() {
+                player.tick(level);
+            }
+        }
+
+        attacks
+    }
+
+    fn night(seed: u64) -> Outcome {
+        let (mut level, generator) = flat_world();
+        level.set_sky_darkness(11);
+
+        let mut entities = Entities::new();
+        let id = entities.allocate_id();
+        let mut players = vec![Player::new("player", id, Point3::new(8.5, GROUND as f64, 8.5))];
+        let mut rng = Random::seed_from_u64(seed);
+
+        simulate(&mut level, &generator, &mut entities, &mut players, &mut rng, 300);
+
+        entities.iter()
+            .filter_map(|e| e.mob().map(|mob| (e.id, mob.mob, mob.health, e.position())))
+            .collect()
+    }
+
+    #[test]
+    fn simulation_is_deterministic() {
+        let outcome = night(47);
+
+        // Hostile mobs appear in the dark, far enough from the player, and
+        // walk around on the grass.
+        assert!(outcome.iter().any(|&(_, mob, ..)| mob.is_hostile()));
+        for &(_, _, health, at) in outcome.iter() {
+            assert!(health > 0);
+            assert_eq!(at.y, GROUND as f64);
+            assert!((at.x - 8.5).abs() <= RADIUS as f64 * 16.0 + 8.0);
+            assert!((at.z - 8.5).abs() <= RADIUS as f64 * 16.0 + 8.0);
+        }
+
+        assert_eq!(night(47), outcome);
+        assert_ne!(night(48), outcome);
+    }
+
+    #[test]
+    fn zombie_goes_around_a_wall_to_the_player() {
+        let (mut level, generator) = flat_world();
+
+        // A wall 3 blocks high between the zombie and the player, with a gap
+        // at one end.
+        for x in -6..=4 {
+            for y in GROUND..GROUND + 3 {
+                level.set_block(BlockPos::new(x, y, 4), Block::new(block_ids::STONE));
+            }
+        }
+
+        let mut entities = Entities::new();
+        let id = entities.allocate_id();
+        let mut players = vec![Player::new("player", id, Point3::new(0.5, GROUND as f64, 0.5))];
+        let zombie = entities.spawn(EntityKind::Mob(Mob::new(MobType::Zombie)), Point3::new(0.5, GROUND as f64, 8.5));
+        let mut rng = Random::seed_from_u64(47);
+
+        // It is day, so that no other mob appears.
+        let attacks = simulate(&mut level, &generator, &mut entities, &mut players, &mut rng, 200);
+
+        assert!(entities.iter().all(|e| e.id == zombie));
+        assert!(!attacks.is_empty());
+        assert!(attacks.iter().all(|attack| attack.attacker == zombie && attack.target == id));
+
+        // The zombie hits once a second at most, and the player is not hurt
+        // again while it still is.
+        let damage = MobType::Zombie.attack_damage();
+        assert_eq!(players[0].health, MAX_HEALTH - damage * attacks.len() as i16);
+        assert!(attacks.len() <= 10);
+
+        let at = entities.get(zombie).unwrap().position();
+        assert!((at - players[0].position()).magnitude() < 2.0);
+    }
+
+    /// Drops sand onto `below`, placed on the grass, and ticks it until it
+    /// lands. Returns the level and the entities left.
+    fn drop_sand_onto(below: Block) -> (Level, Entities) {
+        let (mut level, _) = flat_world();
+        level.set_block(BlockPos::new(0, GROUND, 0), below);
+
+        let mut entities = Entities::new();
+        let sand = Block::new(block_ids::SAND);
+        entities.spawn(EntityKind::FallingBlock { block: sand }, Point3::new(0.5, GROUND as f64 + 5.0, 0.5));
+
+        let mut rng = Random::seed_from_u64(43);
+        for _ in 0..100 {
+            entities.tick(&mut level, &mut [], &mut rng);
+        }
+
+        (level, entities)
+    }
+
+    /// The sand dropped as items.
+    fn dropped_sand(entities: &Entities) -> u32 {
+        entities.iter()
+            .map(|e| match &e.kind {
+                EntityKind::Item { stack, .. } if stack.id == block_ids::SAND => stack.count as u32,
+                _ => 0,
+            })
+            .sum()
+    }
+
+    fn placed_sand(level: &Level) -> usize {
+        (GROUND..GROUND + 6)
+            .filter(|&y| level.block(BlockPos::new(0, y, 0)).unwrap().id == block_ids::SAND)
+            .count()
+    }
+
+    #[test]
+    fn falling_sand_is_placed_on_a_full_block() {
+        let (level, entities) = drop_sand_onto(Block::new(block_ids::STONE));
+
+        assert!(entities.is_empty());
+        assert_eq!(level.block(BlockPos::new(0, GROUND + 1, 0)).unwrap().id, block_ids::SAND);
+        assert_eq!(placed_sand(&level), 1);
+    }
+
+    #[test]
+    fn falling_sand_drops_on_a_slab() {
+        let (level, entities) = drop_sand_onto(Block::new(block_ids::SLAB));
+
+        assert_eq!(dropped_sand(&entities), 1);
+        assert_eq!(level.block(BlockPos::new(0, GROUND, 0)).unwrap().id, block_ids::SLAB);
+        assert_eq!(placed_sand(&level), 0);
+    }
+
+    #[test]
+    fn falling_sand_drops_on_a_torch() {
+        let (level, entities) = drop_sand_onto(Block::new(block_ids::TORCH));
+
+        assert_eq!(dropped_sand(&entities), 1);
+        assert_eq!(level.block(BlockPos::new(0, GROUND, 0)).unwrap().id, block_ids::TORCH);
+        assert_eq!(placed_sand(&level), 0);
+    }
+}
@@ -0,0 +1,311 @@
+use rand::Rng;
+use crate::item::{ item_ids, ItemStack };
+use crate::world::{ block_ids, Block, BlockPos };
+use super::MobAi;
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum MobType {
+    Creeper,
+    Skeleton,
+    Spider,
+    Zombie,
+    Ghast,
+    PigZombie,
+    Pig,
+    Sheep,
+    Cow,
+    Chicken,
+    Squid,
+    Wolf,
+}
+
+impl MobType {
+    pub const ALL: [MobType; 12] = [
+        Self::Creeper, Self::Skeleton, Self::Spider, Self::Zombie,
+        Self::Ghast, Self::PigZombie, Self::Pig, Self::Sheep,
+        Self::Cow, Self::Chicken, Self::Squid, Self::Wolf,
+    ];
+
+    /// The type sent in the `MobSpawn` packet.
+    pub fn protocol_id(self) -> i8 {
+        match self {
+            Self::Creeper => 50,
+            Self::Skeleton => 51,
+            Self::Spider => 52,
+            Self::Zombie => 54,
+            Self::Ghast => 56,
+            Self::PigZombie => 57,
+            Self::Pig => 90,
+            Self::Sheep => 91,
+            Self::Cow => 92,
+            Self::Chicken => 93,
+            Self::Squid => 94,
+            Self::Wolf => 95,
+        }
+    }
+
+    pub fn from_protocol_id(id: i8) -> Option<Self> {
+        Self::ALL.iter().copied().find(|mob| mob.protocol_id() == id)
+    }
+
+    /// The id of the mob in NBT files.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Creeper => "Creeper",
+            Self::Skeleton => "Skeleton",
+            Self::Spider => "Spider",
+            Self::Zombie => "Zombie",
+            Self::Ghast => "Ghast",
+            Self::PigZombie => "PigZombie",
+            Self::Pig => "Pig",
+            Self::Sheep => "Sheep",
+            Self::Cow => "Cow",
+            Self::Chicken => "Chicken",
+            Self::Squid => "Squid",
+            Self::Wolf => "Wolf",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|mob| mob.name() == name)
+    }
+
+    pub fn max_health(self) -> i16 {
+        match self {
+            Self::Chicken => 4,
+            Self::Sheep | Self::Wolf => 8,
+            Self::Ghast | Self::Pig | Self::Cow | Self::Squid => 10,
+            _ => 20,
+        }
+    }
+
+    /// The width and the height of the mob.
+    pub fn size(self) -> (f64, f64) {
+        match self {
+            Self::Spider => (1.4, 0.9),
+            Self::Ghast => (4.0, 4.0),
+            Self::Pig => (0.9, 0.9),
+            Self::Sheep | Self::Cow => (0.9, 1.3),
+            Self::Chicken => (0.3, 0.4),
+            Self::Squid => (0.95, 0.95),
+            Self::Wolf => (0.6, 0.8),
+            _ => (0.6, 1.8),
+        }
+    }
+
+    /// How fast the mob walks, relative to a player.
+    pub fn move_speed(self) -> f64 {
+        match self {
+            Self::Zombie => 0.5,
+            Self::Spider => 0.8,
+            _ => 0.7,
+        }
+    }
+
+    /// The damage the mob deals when it hits a player, or 0 if it does not
+    /// fight in melee.
+    pub fn attack_damage(self) -> i16 {
+        match self {
+            Self::Zombie => 5,
+            Self::Spider => 2,
+            _ => 0,
+        }
+    }
+
+    pub fn is_hostile(self) -> bool {
+        matches!(self,
+            Self::Creeper | Self::Skeleton | Self::Spider |
+            Self::Zombie | Self::Ghast | Self::PigZombie
+        )
+    }
+}
+
+/// The state of a mob which is not shared with other entities.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mob {
+    pub mob: MobType,
+    pub health: i16,
+    pub hurt_time: i16,
+    /// The ticks since the mob died. It disappears after 20 ticks.
+    pub death_time: i16,
+    pub attack_time: i16,
+
+    pub saddled: bool,
+    pub sheared: bool,
+    pub wool_color: u8,
+    pub powered: bool,
+    /// The ticks a creeper has been about to explode for.
+    pub fuse: i16,
+    /// What the mob is up to, which is not saved.
+    pub ai: MobAi,
+}
+
+impl Mob {
+    pub fn new(mob: MobType) -> Self {
+        Self {
+            mob,
+            health: mob.max_health(),
+            hurt_time: 0,
+            death_time: 0,
+            attack_time: 0,
+            saddled: false,
+            sheared: false,
+            wool_color: 0,
+            powered: false,
+            fuse: 0,
+            ai: MobAi::default(),
+        }
+    }
+
+    /// The items the mob drops when it dies, as in Beta. Most mobs drop up
+    /// to 2 items, and sheep drop their wool unless they were sheared.
+    pub fn loot<R: Rng + ?Sized>(&self, rng: &mut R) -> Vec<ItemStack> {
+        let id = match self.mob {
+            MobType::Sheep if self.sheared => return Vec::new(),
+            MobType::Sheep => return vec![ItemStack::with_damage(block_ids::WOOL, 1, self.wool_color as u16)],
+            MobType::Squid => return vec![ItemStack::with_damage(item_ids::DYE, rng.gen_range(1, 4), 0)],
+            MobType::Wolf => return Vec::new(),
+            MobType::Skeleton => {
+                let arrows = ItemStack::new(item_ids::ARROW, rng.gen_range(0, 3));
+                let bones = ItemStack::new(item_ids::BONE, rng.gen_range(0, 3));
+                return vec![arrows, bones].into_iter().filter(|s| s.count > 0).collect()
+            },
+            MobType::Zombie | MobType::Chicken => item_ids::FEATHER,
+            MobType::Spider => item_ids::STRING,
+            MobType::Creeper | MobType::Ghast => item_ids::GUNPOWDER,
+            MobType::Pig => item_ids::PORKCHOP,
+            MobType::PigZombie => item_ids::COOKED_PORKCHOP,
+            MobType::Cow => item_ids::LEATHER,
+        };
+
+        let count = rng.gen_range(0, 3);
+        if count == 0 {
+            Vec::new()
+        } else {
+            vec![ItemStack::new(id, count)]
+        }
+    }
+
+    /// Takes `damage` away from the health of the mob. Passive mobs run away
+    /// when they are hurt.
+    pub fn hurt(&mut self, damage: i16) {
+        self.health -= damage;
+        self.hurt_time = 10;
+
+        if !self.mob.is_hostile() {
+            self.ai.flee_time = super::FLEE_TIME;
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ProjectileType {
+    Arrow,
+    Snowball,
+    Egg,
+}
+
+impl ProjectileType {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Arrow => "Arrow",
+            Self::Snowball => "Snowball",
+            Self::Egg => "Egg",
+        }
+    }
+
+    /// How much faster the projectile falls every tick.
+    pub fn gravity(self) -> f64 {
+        match self {
+            Self::Arrow => 0.05,
+            _ => 0.03,
+        }
+    }
+}
+
+/// What an entity is, along with the data specific to that kind of entity.
+#[derive(Clone, Debug, PartialEq)]
+pub enum EntityKind {
+    /// An item lying on the ground, which players pick up.
+    Item {
+        stack: ItemStack,
+        /// The entity disappears when its age reaches `ITEM_LIFETIME`.
+        age: i16,
+        /// The ticks left before the item can be picked up.
+        pickup_delay: i16,
+    },
+    Mob(Mob),
+    Projectile {
+        projectile: ProjectileType,
+        shooter: Option<i32>,
+        /// The block an arrow is stuck in.
+        stuck_in: Option<BlockPos>,
+        /// The ticks an arrow has been stuck for.
+        stuck_time: i16,
+    },
+    /// Sand or gravel which is falling.
+    FallingBlock {
+        block: Block,
+    },
+    PrimedTnt {
+        /// The ticks left before the TNT explodes.
+        fuse: i16,
+    },
+}
+
+impl EntityKind {
+    pub fn item(stack: ItemStack) -> Self {
+        Self::Item {
+            stack,
+            age: 0,
+            pickup_delay: 10,
+        }
+    }
+
+    pub fn mob(mob: MobType) -> Self {
+        Self::Mob(Mob::new(mob))
+    }
+
+    pub fn projectile(projectile: ProjectileType, shooter: Option<i32>) -> Self {
+        Self::Projectile {
+            projectile,
+            shooter,
+            stuck_in: None,
+            stuck_time: 0,
+        }
+    }
+
+    /// The id of the entity in NBT files.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Item { .. } => "Item",
+            Self::Mob(mob) => mob.mob.name(),
+            Self::Projectile { projectile, .. } => projectile.name(),
+            Self::FallingBlock { .. } => "FallingSand",
+            Self::PrimedTnt { .. } => "PrimedTnt",
+        }
+    }
+
+    /// The width and the height of the entity.
+    pub fn size(&self) -> (f64, f64) {
+        match self {
+            Self::Item { .. } | Self::Projectile { .. } => (0.25, 0.25),
+            Self::Mob(mob) => mob.mob.size(),
+            Self::FallingBlock { .. } | Self::PrimedTnt { .. } => (0.98, 0.98),
+        }
+    }
+
+    /// The type sent in the `AddObject` packet, for the entities which are
+    /// spawned with it rather than `MobSpawn` or `PickupSpawn`.
+    pub fn object_type(&self) -> Option<i8> {
+        match self {
+            Self::Projectile { projectile: ProjectileType::Arrow, .. } => Some(60),
+            Self::Projectile { projectile: ProjectileType::Snowball, .. } => Some(61),
+            Self::Projectile { projectile: ProjectileType::Egg, .. } => Some(62),
+            Self::FallingBlock { block } if block.id == block_ids::GRAVEL => Some(71),
+            Self::FallingBlock { .. } => Some(70),
+            Self::PrimedTnt { .. } => Some(50),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use std::collections::BTreeMap;
+
+/// A single value of the metadata of an entity. The variants are the types
+/// which the protocol can send.
+#[derive(Clone, Debug, PartialEq)]
+pub enum MetadataValue {
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Float(f32),
+    Str(Box<str>),
+    ItemStack(i16, i8, i16), // id, count, damage
+    Position(i32, i32, i32),
+}
+
+/// The metadata of an entity as sent in the `MobSpawn` and `EntityMetadata`
+/// packets, keyed by the index of each value.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EntityMetadata {
+    values: BTreeMap<i8, MetadataValue>,
+}
+
+// The indices used by Beta. Index 0 is shared by every entity, the others
+// depend on the kind of the entity.
+pub const METADATA_FLAGS: i8 = 0;
+pub const METADATA_PIG_SADDLE: i8 = 16;
+pub const METADATA_SHEEP_WOOL: i8 = 16;
+pub const METADATA_CREEPER_FUSE: i8 = 16;
+pub const METADATA_CREEPER_POWERED: i8 = 17;
+
+// The bits of the flags at index 0.
+pub const FLAG_ON_FIRE: i8 = 0x01;
+pub const FLAG_CROUCHED: i8 = 0x02;
+pub const FLAG_RIDING: i8 = 0x04;
+
+impl EntityMetadata {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the value at the given index, which is at most 31. Returns the
+    /// value it replaces.
+    pub fn insert(&mut self, index: i8, value: MetadataValue) -> Option<MetadataValue> {
+        assert!((0..32).contains(&index), "metadata indices are at most 31");
+        self.values.insert(index, value)
+    }
+
+    pub fn get(&self, index: i8) -> Option<&MetadataValue> {
+        self.values.get(&index)
+    }
+
+    /// The values ordered by their index.
+    pub fn iter(&self) -> impl Iterator<Item = (i8, &MetadataValue)> {
+        self.values.iter().map(|(&index, value)| (index, value))
+    }
+}
+
+impl IntoIterator for EntityMetadata {
+    type Item = (i8, MetadataValue);
+    type IntoIter = std::collections::btree_map::IntoIter<i8, MetadataValue>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.values.into_iter()
+    }
+}
@@ -0,0 +1,486 @@
+mod ai;
+mod entities;
+mod kind;
+mod metadata;
+mod spawning;
+
+pub use ai::*;
+pub use entities::Entities;
+pub use kind::*;
+pub use metadata::*;
+pub use spawning::*;
+
+use cgmath::{ InnerSpace, Point3, Vector3 };
+use crate::item::ItemStack;
+use crate::nbt::{ Compound, ValueData };
+use crate::physics::Body;
+use crate::world::{ block_ids, Block, BlockPos, ChunkPos, Level };
+
+pub type EntityId = i32;
+
+/// The ticks an item lies on the ground before it disappears.
+pub const ITEM_LIFETIME: i16 = 6000;
+/// The ticks an arrow stays stuck in a block before it disappears.
+pub const ARROW_LIFETIME: i16 = 1200;
+/// The damage which destroys an item lying on the ground.
+pub const ITEM_HEALTH: i16 = 5;
+/// The ticks a block falls for before it turns into an item.
+pub const FALLING_BLOCK_LIFETIME: u32 = 100;
+/// The ticks TNT burns for before it explodes, when it is lit by hand or by
+/// redstone.
+pub const TNT_FUSE: i16 = 80;
+/// The ticks a creeper hisses for before it explodes.
+pub const CREEPER_FUSE: i16 = 30;
+/// The damage an arrow deals to the player it hits.
+pub const ARROW_DAMAGE: i16 = 4;
+/// How far a player reaches the entities it hits.
+pub const ATTACK_REACH: f64 = 6.0;
+/// The power of the explosion of primed TNT.
+pub const TNT_EXPLOSION_POWER: f32 = 4.0;
+/// The power of the explosion of a creeper, which is doubled for creepers
+/// struck by lightning.
+pub const CREEPER_EXPLOSION_POWER: f32 = 3.0;
+
+#[derive(Debug)]
+pub enum EntityError {
+    MissingTag(&'static str),
+    InvalidTag(&'static str),
+    UnknownEntity(String),
+}
+
+/// Anything in the world which is not a block or a player: mobs, items,
+/// projectiles and so on.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Entity {
+    pub id: EntityId,
+    pub body: Body,
+    pub yaw: f32,
+    pub pitch: f32,
+    /// The ticks left to burn.
+    pub fire: i16,
+    /// The ticks of breath left under water.
+    pub air: i16,
+    /// The ticks since the entity was spawned or loaded.
+    pub ticks: u32,
+    /// Whether the entity is gone, and should be removed from the world.
+    pub removed: bool,
+    pub kind: EntityKind,
+}
+
+// Entities are stored in the `Entities` list of the chunk they are in:
+//
+//     (compound)
+//         id (string)                      see `EntityKind::name`
+//         Pos (list of 3 doubles)          the feet of the entity
+//         Motion (list of 3 doubles)
+//         Rotation (list of 2 floats)      yaw, pitch
+//         FallDistance (float)
+//         Fire, Air (short)
+//         OnGround (byte)
+//
+// followed by the tags specific to the kind of the entity:
+//
+//     Item                 Health, Age (short), Item (compound)
+//     mobs                 Health, HurtTime, DeathTime, AttackTime (short)
+//         Pig              Saddle (byte)
+//         Sheep            Sheared, Color (byte)
+//         Creeper          powered (byte)
+//     projectiles          xTile, yTile, zTile (short), inTile, shake,
+//                          inGround (byte)
+//     FallingSand          Tile (byte)
+//     PrimedTnt            Fuse (byte)
+
+fn doubles(values: &[f64]) -> ValueData {
+    ValueData::List(6, values.iter().map(|&v| ValueData::Double(v)).collect())
+}
+
+fn floats(values: &[f32]) -> ValueData {
+    ValueData::List(5, values.iter().map(|&v| ValueData::Float(v)).collect())
+}
+
+impl Entity {
+    pub fn new(id: EntityId, kind: EntityKind, position: Point3<f64>) -> Self {
+        let (width, height) = kind.size();
+        let mut body = Body::new(position, width, height);
+
+        if let EntityKind::Mob(_) = kind {
+            body.step_height = 0.5;
+        }
+
+        Self {
+            id,
+            body,
+            yaw: 0.0,
+            pitch: 0.0,
+            fire: 0,
+            air: 300,
+            ticks: 0,
+            removed: false,
+            kind,
+        }
+    }
+
+    pub fn position(&self) -> Point3<f64> {
+        self.body.position
+    }
+
+    /// The chunk the entity is in.
+    pub fn chunk(&self) -> ChunkPos {
+        let at = self.body.position;
+        ChunkPos::from(BlockPos::new(at.x.floor() as i32, 0, at.z.floor() as i32))
+    }
+
+    pub fn mob(&self) -> Option<&Mob> {
+        match &self.kind {
+            EntityKind::Mob(mob) => Some(mob),
+            _ => None,
+        }
+    }
+
+    pub fn mob_mut(&mut self) -> Option<&mut Mob> {
+        match &mut self.kind {
+            EntityKind::Mob(mob) => Some(mob),
+            _ => None,
+        }
+    }
+
+    /// The center and the power of the explosion the entity sets off when it
+    /// is removed, for primed TNT and for creepers which are done hissing.
+    pub fn explosion(&self) -> Option<(Point3<f64>, f32)> {
+        match &self.kind {
+            EntityKind::PrimedTnt { fuse } if *fuse <= 0 => {
+                let center = self.body.position + Vector3::new(0.0, self.body.height / 2.0, 0.0);
+                Some((center, TNT_EXPLOSION_POWER))
+            },
+            EntityKind::Mob(mob) if mob.mob == MobType::Creeper && mob.health > 0 && mob.fuse >= CREEPER_FUSE => {
+                let power = if mob.powered { CREEPER_EXPLOSION_POWER * 2.0 } else { CREEPER_EXPLOSION_POWER };
+                Some((self.body.position, power))
+            },
+            _ => None,
+        }
+    }
+
+    /// The metadata of the entity, as sent to the clients.
+    pub fn metadata(&self) -> EntityMetadata {
+        let mut result = EntityMetadata::new();
+
+        let flags = if self.fire > 0 { FLAG_ON_FIRE } else { 0 };
+        result.insert(METADATA_FLAGS, MetadataValue::Byte(flags));
+
+        if let EntityKind::Mob(mob) = &self.kind {
+            match mob.mob {
+                MobType::Pig => {
+                    result.insert(METADATA_PIG_SADDLE, MetadataValue::Byte(mob.saddled as i8));
+                },
+                MobType::Sheep => {
+                    let wool = (mob.wool_color & 0xF) | if mob.sheared { 0x10 } else { 0 };
+                    result.insert(METADATA_SHEEP_WOOL, MetadataValue::Byte(wool as i8));
+                },
+                MobType::Creeper => {
+                    let fuse = if mob.fuse > 0 { 1 } else { -1 };
+                    result.insert(METADATA_CREEPER_FUSE, MetadataValue::Byte(fuse));
+                    result.insert(METADATA_CREEPER_POWERED, MetadataValue::Byte(mob.powered as i8));
+                },
+                _ => {},
+            }
+        }
+
+        result
+    }
+
+    /// Advances the entity by a tick. Returns the distance it has fallen if
+    /// it landed on the ground.
+    pub fn tick(&mut self, level: &Level) -> Option<f64> {
+        self.ticks += 1;
+        if self.fire > 0 {
+            self.fire -= 1;
+        }
+
+        match &mut self.kind {
+            EntityKind::Item { age, pickup_delay, .. } => {
+                *age += 1;
+                if *pickup_delay > 0 {
+                    *pickup_delay -= 1;
+                }
+                if *age >= ITEM_LIFETIME {
+                    self.removed = true;
+                }
+
+                tick_object(&mut self.body, level)
+            },
+
+            EntityKind::Mob(mob) => {
+                if mob.hurt_time > 0 {
+                    mob.hurt_time -= 1;
+                }
+                if mob.attack_time > 0 {
+                    mob.attack_time -= 1;
+                }
+                if mob.health <= 0 {
+                    mob.death_time += 1;
+                    if mob.death_time >= 20 {
+                        self.removed = true;
+                    }
+                } else if mob.mob == MobType::Creeper && mob.fuse >= CREEPER_FUSE {
+                    self.removed = true;
+                }
+
+                self.walk(level)
+            },
+
+            EntityKind::Projectile { projectile, stuck_in, stuck_time, .. } => {
+                if let Some(at) = *stuck_in {
+                    let still_there = level.block(at).is_some_and(|b| b.id != block_ids::AIR);
+
+                    if still_there {
+                        *stuck_time += 1;
+                        if *stuck_time >= ARROW_LIFETIME {
+                            self.removed = true;
+                        }
+                        return None
+                    }
+
+                    // The block is gone, so the arrow falls again.
+                    *stuck_in = None;
+                    *stuck_time = 0;
+                }
+
+                let velocity = self.body.velocity;
+                let speed = velocity.magnitude();
+                let origin = self.body.position;
+
+                let hit = if speed > 0.0 {
+                    level.raycast(
+                        origin.cast().unwrap(),
+                        velocity.cast().unwrap(),
+                        speed as f32,
+                    )
+                } else {
+                    None
+                };
+
+                match hit {
+                    Some(hit) if *projectile == ProjectileType::Arrow => {
+                        self.body.position = origin + velocity * (hit.distance as f64 / speed);
+                        self.body.velocity = Vector3::new(0.0, 0.0, 0.0);
+                        *stuck_in = Some(hit.block);
+                        return None
+                    },
+                    Some(_) => {
+                        self.removed = true;
+                        return None
+                    },
+                    None => self.body.position = origin + velocity,
+                }
+
+                // Projectiles face the way they fly.
+                let horizontal = (velocity.x * velocity.x + velocity.z * velocity.z).sqrt();
+                self.yaw = velocity.x.atan2(velocity.z).to_degrees() as f32;
+                self.pitch = velocity.y.atan2(horizontal).to_degrees() as f32;
+
+                self.body.velocity *= 0.99;
+                self.body.velocity.y -= projectile.gravity();
+                None
+            },
+
+            EntityKind::FallingBlock { .. } => {
+                let landed = tick_object(&mut self.body, level);
+                if self.body.on_ground || self.ticks > FALLING_BLOCK_LIFETIME {
+                    self.removed = true;
+                }
+
+                landed
+            },
+
+            EntityKind::PrimedTnt { fuse } => {
+                *fuse -= 1;
+                if *fuse <= 0 {
+                    self.removed = true;
+                }
+
+                tick_object(&mut self.body, level)
+            },
+        }
+    }
+
+    pub fn to_nbt(&self) -> Compound {
+        let body = &self.body;
+
+        let mut tag = Compound::new();
+        let mut insert = |name: &str, value| { tag.insert(name.to_string(), value); };
+
+        insert("id", ValueData::Str(self.kind.name().to_string()));
+        insert("Pos", doubles(&[body.position.x, body.position.y, body.position.z]));
+        insert("Motion", doubles(&[body.velocity.x, body.velocity.y, body.velocity.z]));
+        insert("Rotation", floats(&[self.yaw, self.pitch]));
+        insert("FallDistance", ValueData::Float(body.fall_distance as f32));
+        insert("Fire", ValueData::Short(self.fire));
+        insert("Air", ValueData::Short(self.air));
+        insert("OnGround", ValueData::Byte(body.on_ground as i8));
+
+        match &self.kind {
+            EntityKind::Item { stack, age, .. } => {
+                insert("Health", ValueData::Short(ITEM_HEALTH));
+                insert("Age", ValueData::Short(*age));
+                insert("Item", ValueData::Compound(stack.to_item_nbt()));
+            },
+
+            EntityKind::Mob(mob) => {
+                insert("Health", ValueData::Short(mob.health));
+                insert("HurtTime", ValueData::Short(mob.hurt_time));
+                insert("DeathTime", ValueData::Short(mob.death_time));
+                insert("AttackTime", ValueData::Short(mob.attack_time));
+
+                match mob.mob {
+                    MobType::Pig => insert("Saddle", ValueData::Byte(mob.saddled as i8)),
+                    MobType::Sheep => {
+                        insert("Sheared", ValueData::Byte(mob.sheared as i8));
+                        insert("Color", ValueData::Byte(mob.wool_color as i8));
+                    },
+                    MobType::Creeper => insert("powered", ValueData::Byte(mob.powered as i8)),
+                    _ => {},
+                }
+            },
+
+            EntityKind::Projectile { stuck_in, .. } => {
+                let at = stuck_in.unwrap_or_else(|| BlockPos::new(-1, -1, -1));
+                insert("xTile", ValueData::Short(at.x as i16));
+                insert("yTile", ValueData::Short(at.y as i16));
+                insert("zTile", ValueData::Short(at.z as i16));
+                insert("inTile", ValueData::Byte(0));
+                insert("shake", ValueData::Byte(0));
+                insert("inGround", ValueData::Byte(stuck_in.is_some() as i8));
+            },
+
+            EntityKind::FallingBlock { block } => {
+                insert("Tile", ValueData::Byte(block.id as u8 as i8));
+            },
+
+            EntityKind::PrimedTnt { fuse } => {
+                insert("Fuse", ValueData::Byte(*fuse as i8));
+            },
+        }
+
+        tag
+    }
+
+    /// Reads an entity written by `to_nbt`, giving it a new id.
+    pub fn from_nbt(id: EntityId, tag: &Compound) -> Result<Self, EntityError> {
+        let get = |name: &'static str| tag.get(name).ok_or(EntityError::MissingTag(name));
+
+        let list = |name: &'static str, len: usize| -> Result<&Vec<ValueData>, EntityError> {
+            get(name)?
+                .list()
+                .map(|(_, values)| values)
+                .filter(|values| values.len() == len)
+                .ok_or(EntityError::InvalidTag(name))
+        };
+
+        let doubles = |name: &'static str| -> Result<Vec<f64>, EntityError> {
+            list(name, 3)?.iter()
+                .map(|v| v.double().copied().ok_or(EntityError::InvalidTag(name)))
+                .collect()
+        };
+
+        // The tags which are not essential fall back to their defaults.
+        let short = |name: &str, default: i16| {
+            tag.get(name).and_then(ValueData::short).copied().unwrap_or(default)
+        };
+        let byte = |name: &str| tag.get(name).and_then(ValueData::byte).copied().unwrap_or(0);
+
+        let name = get("id")?.string().ok_or(EntityError::InvalidTag("id"))?;
+        let kind = match name {
+            "Item" => {
+                let stack = get("Item")?
+                    .compound()
+                    .and_then(ItemStack::from_item_nbt)
+                    .ok_or(EntityError::InvalidTag("Item"))?;
+
+                EntityKind::Item {
+                    stack,
+                    age: short("Age", 0),
+                    pickup_delay: 0,
+                }
+            },
+
+            "Arrow" | "Snowball" | "Egg" => {
+                let projectile = match name {
+                    "Arrow" => ProjectileType::Arrow,
+                    "Snowball" => ProjectileType::Snowball,
+                    _ => ProjectileType::Egg,
+                };
+
+                let at = BlockPos::new(
+                    short("xTile", -1) as i32,
+                    short("yTile", -1) as i32,
+                    short("zTile", -1) as i32,
+                );
+
+                EntityKind::Projectile {
+                    projectile,
+                    shooter: None,
+                    stuck_in: Some(at).filter(|_| byte("inGround") != 0),
+                    stuck_time: 0,
+                }
+            },
+
+            "FallingSand" => EntityKind::FallingBlock {
+                block: Block::new(byte("Tile") as u8 as u16),
+            },
+
+            "PrimedTnt" => EntityKind::PrimedTnt {
+                fuse: byte("Fuse") as i16,
+            },
+
+            _ => {
+                let mob = MobType::from_name(name)
+                    .ok_or_else(|| EntityError::UnknownEntity(name.to_string()))?;
+
+                let mut mob = Mob::new(mob);
+                mob.health = short("Health", mob.health);
+                mob.hurt_time = short("HurtTime", 0);
+                mob.death_time = short("DeathTime", 0);
+                mob.attack_time = short("AttackTime", 0);
+                mob.saddled = byte("Saddle") != 0;
+                mob.sheared = byte("Sheared") != 0;
+                mob.wool_color = byte("Color") as u8 & 0xF;
+                mob.powered = byte("powered") != 0;
+
+                EntityKind::Mob(mob)
+            },
+        };
+
+        let pos = doubles("Pos")?;
+        let motion = doubles("Motion")?;
+        let rotation = list("Rotation", 2)?.iter()
+            .map(|v| v.float().copied().ok_or(EntityError::InvalidTag("Rotation")))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut entity = Self::new(id, kind, Point3::new(pos[0], pos[1], pos[2]));
+        entity.body.velocity = Vector3::new(motion[0], motion[1], motion[2]);
+        entity.body.fall_distance = tag.get("FallDistance")
+            .and_then(ValueData::float)
+            .map_or(0.0, |&d| d as f64);
+        entity.body.on_ground = byte("OnGround") != 0;
+        entity.yaw = rotation[0];
+        entity.pitch = rotation[1];
+        entity.fire = short("Fire", 0);
+        entity.air = short("Air", 300);
+
+        Ok(entity)
+    }
+}
+
+/// Moves an object which is not alive: it falls, and slows down faster on
+/// the ground.
+fn tick_object(body: &mut Body, level: &Level) -> Option<f64> {
+    body.velocity.y -= 0.04;
+    let landed = body.move_by(level, body.velocity);
+
+    let friction = if body.on_ground { 0.6 * 0.98 } else { 0.98 };
+    body.velocity.x *= friction;
+    body.velocity.y *= 0.98;
+    body.velocity.z *= friction;
+
+    landed
+}
@@ -0,0 +1,223 @@
+use std::collections::BTreeSet;
+use cgmath::Point3;
+use rand::Rng;
+use crate::player::Player;
+use crate::utils::Random;
+use crate::world::{
+    block_ids, Biome, BlockPos, ChunkPos, Level, WorldGenerator,
+    CHUNK_LENGTH_X, CHUNK_LENGTH_Y, CHUNK_LENGTH_Z,
+};
+use super::*;
+
+/// The most hostile mobs there are for every 256 chunks around the players.
+pub const HOSTILE_CAP: usize = 70;
+/// The most passive mobs there are for every 256 chunks around the players.
+pub const PASSIVE_CAP: usize = 15;
+
+// The chunks this close to a player, in chunks, get mobs.
+const SPAWN_RADIUS: i32 = 8;
+// Mobs never appear this close to a player.
+const MIN_PLAYER_DISTANCE: f64 = 24.0;
+// A pack is made of up to this many mobs of the same type, placed in a few
+// random walks from the same block.
+const PACK_SIZE: usize = 4;
+const PACK_WALKS: usize = 3;
+const PACK_SPREAD: i32 = 6;
+// Passive mobs only appear in the light.
+const PASSIVE_MIN_LIGHT: u8 = 9;
+// Only one in this many places a ghast could appear at gets one.
+const GHAST_CHANCE: u32 = 20;
+
+/// The mobs which appear on their own in a biome, along with how often each
+/// of them does compared to the others, as in Beta.
+pub fn spawnable_mobs(biome: Biome, hostile: bool) -> &'static [(MobType, u32)] {
+    const HOSTILE: [(MobType, u32); 4] = [
+        (MobType::Spider, 10),
+        (MobType::Zombie, 10),
+        (MobType::Skeleton, 10),
+        (MobType::Creeper, 10),
+    ];
+    const NETHER: [(MobType, u32); 2] = [
+        (MobType::Ghast, 10),
+        (MobType::PigZombie, 10),
+    ];
+    const PASSIVE: [(MobType, u32); 4] = [
+        (MobType::Sheep, 12),
+        (MobType::Pig, 10),
+        (MobType::Chicken, 10),
+        (MobType::Cow, 8),
+    ];
+
+    match (biome, hostile) {
+        (Biome::Hell, true) => &NETHER,
+        (_, true) => &HOSTILE,
+        // Nothing lives in the deserts, nor in the Nether.
+        (Biome::Desert, false) | (Biome::IceDesert, false) | (Biome::Hell, false) => &[],
+        (_, false) => &PASSIVE,
+    }
+}
+
+/// Picks one of the mobs, each as often as its weight says.
+fn pick_mob(mobs: &[(MobType, u32)], rng: &mut Random) -> Option<MobType> {
+    let total = mobs.iter().map(|&(_, weight)| weight).sum::<u32>();
+    if total == 0 {
+        return None
+    }
+
+    let mut roll = rng.gen_range(0, total);
+    for &(mob, weight) in mobs {
+        if roll < weight {
+            return Some(mob)
+        }
+        roll -= weight;
+    }
+
+    None
+}
+
+/// The color of the wool of a sheep which appears on its own: mostly white,
+/// sometimes black or gray, rarely brown and very rarely pink.
+fn fleece_color(rng: &mut Random) -> u8 {
+    match rng.gen_range(0, 100) {
+        0..=4 => 15,
+        5..=9 => 7,
+        10..=14 => 8,
+        15..=17 => 12,
+        _ if rng.gen_range(0, 500) == 0 => 6,
+        _ => 0,
+    }
+}
+
+/// Whether a mob can appear with its feet in the block: it must stand on an
+/// opaque block, fit, and be in the dark if it is hostile or on grass in the
+/// light, whatever the time of day, if it is passive. Hostile mobs are less
+/// likely to appear the more sky light there is, even at night. The mobs of
+/// the Nether do not mind the light, but ghasts are rare.
+fn can_spawn_at(level: &Level, mob: MobType, at: BlockPos, rng: &mut Random) -> bool {
+    let (width, height) = mob.size();
+    let size = ((width + 1.0) as i32, (height + 1.0) as i32);
+
+    let below = match level.block(at.offset(0, -1, 0)) {
+        Some(below) if below.info().opaque => below,
+        _ => return false,
+    };
+
+    let in_air = level.block(at).is_some_and(|b| b.id == block_ids::AIR);
+    if !in_air || !level.can_stand_at(at, size) {
+        return false
+    }
+
+    if mob == MobType::Ghast {
+        rng.gen_range(0, GHAST_CHANCE) == 0
+    } else if mob == MobType::PigZombie {
+        true
+    } else if mob.is_hostile() {
+        level.sky_light(at) <= rng.gen_range(0, 32) && level.light(at) <= rng.gen_range(0, 8)
+    } else {
+        // The time of day does not matter for passive mobs.
+        let light = level.sky_light(at).max(level.block_light(at));
+        below.id == block_ids::GRASS && light >= PASSIVE_MIN_LIGHT
+    }
+}
+
+impl Entities {
+    /// Makes mobs appear on their own around the players, as in Beta. Every
+    /// loaded chunk close enough to a player gets a pack of mobs of a type
+    /// which lives in its biome, placed at random, as long as there are
+    /// fewer mobs around than the caps allow. Returns the ids of the mobs
+    /// which were added.
+    ///
+    /// The chunks are visited in order, so the same random generator always
+    /// adds the same mobs.
+    pub fn spawn_mobs<W>(&mut self, level: &Level, generator: &W, players: &[Player], rng: &mut Random) -> Vec<EntityId>
+        where W: WorldGenerator + ?Sized
+    {
+        let mut chunks = BTreeSet::new();
+        for player in players {
+            let ChunkPos(center) = ChunkPos::from(BlockPos::new(
+                player.body.position.x.floor() as i32,
+                0,
+                player.body.position.z.floor() as i32,
+            ));
+
+            for x in -SPAWN_RADIUS..=SPAWN_RADIUS {
+                for z in -SPAWN_RADIUS..=SPAWN_RADIUS {
+                    let at = ChunkPos::new(center.x + x, 0, center.z + z);
+                    if level.chunk(at).is_some() {
+                        chunks.insert((at.x, at.z));
+                    }
+                }
+            }
+        }
+
+        let far_from_players = |at: Point3<f64>| players.iter().all(|player| {
+            (player.body.position - at).magnitude2() >= MIN_PLAYER_DISTANCE * MIN_PLAYER_DISTANCE
+        });
+
+        let mut spawned = Vec::new();
+
+        for &(hostile, cap) in [(true, HOSTILE_CAP), (false, PASSIVE_CAP)].iter() {
+            let cap = cap * chunks.len() / 256;
+            let mut count = self.iter()
+                .filter_map(Entity::mob)
+                .filter(|mob| mob.health > 0 && mob.mob.is_hostile() == hostile)
+                .count();
+
+            for &(chunk_x, chunk_z) in chunks.iter() {
+                if count >= cap {
+                    break
+                }
+
+                let origin = BlockPos::new(
+                    chunk_x * CHUNK_LENGTH_X as i32 + rng.gen_range(0, CHUNK_LENGTH_X as i32),
+                    rng.gen_range(0, CHUNK_LENGTH_Y as i32),
+                    chunk_z * CHUNK_LENGTH_Z as i32 + rng.gen_range(0, CHUNK_LENGTH_Z as i32),
+                );
+
+                let mob = match pick_mob(spawnable_mobs(generator.biome_at(origin.x, origin.z), hostile), rng) {
+                    Some(mob) => mob,
+                    None => continue,
+                };
+
+                if level.block(origin).is_none_or(|b| b.info().opaque) {
+                    continue
+                }
+
+                let mut pack = 0;
+                'walks: for _ in 0..PACK_WALKS {
+                    let mut at = origin;
+
+                    for _ in 0..PACK_SIZE {
+                        at = at.offset(
+                            rng.gen_range(0, PACK_SPREAD) - rng.gen_range(0, PACK_SPREAD),
+                            0,
+                            rng.gen_range(0, PACK_SPREAD) - rng.gen_range(0, PACK_SPREAD),
+                        );
+
+                        let feet = Point3::new(at.x as f64 + 0.5, at.y as f64, at.z as f64 + 0.5);
+                        if !far_from_players(feet) || !can_spawn_at(level, mob, at, rng) {
+                            continue
+                        }
+
+                        let mut kind = Mob::new(mob);
+                        if mob == MobType::Sheep {
+                            kind.wool_color = fleece_color(rng);
+                        }
+
+                        let id = self.spawn(EntityKind::Mob(kind), feet);
+                        self.get_mut(id).unwrap().yaw = rng.gen_range(0.0, 360.0);
+                        spawned.push(id);
+
+                        count += 1;
+                        pack += 1;
+                        if pack >= PACK_SIZE {
+                            break 'walks
+                        }
+                    }
+                }
+            }
+        }
+
+        spawned
+    }
+}
@@ -0,0 +1,56 @@
+use rand::Rng;
+use crate::world::{ block_ids, Block };
+use super::{ item_ids, ItemStack };
+
+// The bit set in the metadata of the top half of doors, and of the head of
+// beds.
+const DOOR_TOP: u16 = 8;
+const BED_HEAD: u16 = 8;
+
+/// The items a block drops when it is broken, as in Beta. Blocks such as
+/// glass and fire drop nothing, ores drop what they are made of, and blocks
+/// placed from items drop those items.
+pub fn block_drop<R: Rng + ?Sized>(block: Block, rng: &mut R) -> Option<ItemStack> {
+    use block_ids::*;
+
+    let stack = match block.id {
+        AIR | FLOWING_WATER | WATER | FLOWING_LAVA | LAVA | BEDROCK | GLASS | FIRE | ICE | PORTAL |
+        TNT | PISTON_HEAD | MOVING_PISTON | DEAD_BUSH | SNOW_LAYER | BOOKSHELF | SPAWNER | CAKE |
+        LOCKED_CHEST => return None,
+
+        STONE => ItemStack::new(COBBLESTONE, 1),
+        GRASS | FARMLAND => ItemStack::new(DIRT, 1),
+        GRAVEL if rng.gen_range(0, 10) == 0 => ItemStack::new(item_ids::FLINT, 1),
+        LEAVES if rng.gen_range(0, 20) == 0 => ItemStack::with_damage(SAPLING, 1, block.metadata & 3),
+        LEAVES => return None,
+        TALL_GRASS if rng.gen_range(0, 8) == 0 => ItemStack::new(item_ids::SEEDS, 1),
+        TALL_GRASS => return None,
+        COAL_ORE => ItemStack::new(item_ids::COAL, 1),
+        DIAMOND_ORE => ItemStack::new(item_ids::DIAMOND, 1),
+        LAPIS_ORE => ItemStack::with_damage(item_ids::DYE, rng.gen_range(4, 9), 4),
+        REDSTONE_ORE | LIT_REDSTONE_ORE => ItemStack::new(item_ids::REDSTONE, rng.gen_range(4, 6)),
+        REDSTONE_WIRE => ItemStack::new(item_ids::REDSTONE, 1),
+        UNLIT_REDSTONE_TORCH | REDSTONE_TORCH => ItemStack::new(REDSTONE_TORCH, 1),
+        UNPOWERED_REPEATER | POWERED_REPEATER => ItemStack::new(item_ids::REPEATER, 1),
+        LIT_FURNACE => ItemStack::new(FURNACE, 1),
+        SIGN_POST | WALL_SIGN => ItemStack::new(item_ids::SIGN, 1),
+        WOODEN_DOOR | IRON_DOOR if block.metadata & DOOR_TOP != 0 => return None,
+        WOODEN_DOOR => ItemStack::new(item_ids::WOODEN_DOOR, 1),
+        IRON_DOOR => ItemStack::new(item_ids::IRON_DOOR, 1),
+        BED if block.metadata & BED_HEAD == 0 => return None,
+        BED => ItemStack::new(item_ids::BED, 1),
+        COBWEB => ItemStack::new(item_ids::STRING, 1),
+        SUGAR_CANE => ItemStack::new(item_ids::SUGAR_CANE, 1),
+        CLAY => ItemStack::new(item_ids::CLAY_BALL, 4),
+        SNOW => ItemStack::new(item_ids::SNOWBALL, 4),
+        GLOWSTONE => ItemStack::new(item_ids::GLOWSTONE_DUST, rng.gen_range(2, 5)),
+        WHEAT if block.metadata >= 7 => ItemStack::new(item_ids::WHEAT, 1),
+        WHEAT => ItemStack::new(item_ids::SEEDS, 1),
+        DOUBLE_SLAB => ItemStack::with_damage(SLAB, 2, block.metadata),
+        SAPLING => ItemStack::with_damage(SAPLING, 1, block.metadata & 3),
+        LOG | WOOL | SLAB => ItemStack::with_damage(block.id, 1, block.metadata),
+        id => ItemStack::new(id, 1),
+    };
+
+    Some(stack)
+}
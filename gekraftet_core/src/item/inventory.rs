@@ -0,0 +1,110 @@
+use crate::nbt::ValueData;
+use super::ItemStack;
+
+/// Puts as many of the items as possible into the slots, filling up the
+/// stacks of the same item first and then the empty slots, both in the order
+/// of the slots. Returns the items which did not fit.
+pub(crate) fn insert_stack(slots: &mut [&mut Option<ItemStack>], mut stack: ItemStack) -> Option<ItemStack> {
+    let max = stack.max_stack_size();
+
+    for slot in slots.iter_mut() {
+        if let Some(slot) = slot.as_mut().filter(|s| s.is_same_item(&stack) && s.count < max) {
+            let moved = stack.count.min(max - slot.count);
+            slot.count += moved;
+            stack.count -= moved;
+
+            if stack.count == 0 {
+                return None
+            }
+        }
+    }
+
+    for slot in slots.iter_mut().filter(|slot| slot.is_none()) {
+        let moved = stack.count.min(max);
+        **slot = Some(ItemStack { count: moved, ..stack });
+        stack.count -= moved;
+
+        if stack.count == 0 {
+            return None
+        }
+    }
+
+    Some(stack)
+}
+
+/// A fixed number of item slots, such as the ones of a chest or a furnace.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Inventory {
+    slots: Vec<Option<ItemStack>>,
+}
+
+impl Inventory {
+    /// Creates an empty inventory with the given number of slots.
+    pub fn new(size: usize) -> Self {
+        Self {
+            slots: vec![None; size],
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.slots.iter().all(Option::is_none)
+    }
+
+    pub fn slots(&self) -> &[Option<ItemStack>] {
+        &self.slots
+    }
+
+    pub fn slots_mut(&mut self) -> &mut [Option<ItemStack>] {
+        &mut self.slots
+    }
+
+    pub fn get(&self, slot: usize) -> Option<&ItemStack> {
+        self.slots.get(slot)?.as_ref()
+    }
+
+    pub fn set(&mut self, slot: usize, stack: Option<ItemStack>) {
+        self.slots[slot] = stack;
+    }
+
+    /// Puts the items into the inventory, filling up the stacks of the same
+    /// item first and then the empty slots. Returns the items which did not
+    /// fit.
+    pub fn add_item(&mut self, stack: ItemStack) -> Option<ItemStack> {
+        let mut slots = self.slots.iter_mut().collect::<Vec<_>>();
+        insert_stack(&mut slots, stack)
+    }
+
+    /// Empties the inventory, returning every item it held.
+    pub fn take_all(&mut self) -> Vec<ItemStack> {
+        self.slots.iter_mut().filter_map(Option::take).collect()
+    }
+
+    /// Writes the inventory as the `Items` list of a chest or a furnace.
+    pub fn to_nbt(&self) -> ValueData {
+        ValueData::List(10, self.slots.iter()
+            .enumerate()
+            .filter_map(|(slot, stack)| stack.map(|s| s.to_nbt(slot as i8)))
+            .map(ValueData::Compound)
+            .collect())
+    }
+
+    /// Reads a list written by `to_nbt` into an inventory of the given size.
+    /// Items in slots outside of the inventory are dropped.
+    pub fn from_nbt(size: usize, items: &[ValueData]) -> Self {
+        let mut result = Self::new(size);
+
+        for item in items.iter().filter_map(ValueData::compound) {
+            if let Some((slot, stack)) = ItemStack::from_nbt(item) {
+                if slot >= 0 && (slot as usize) < size {
+                    result.slots[slot as usize] = Some(stack);
+                }
+            }
+        }
+
+        result
+    }
+}
@@ -0,0 +1,109 @@
+//! The numeric ids of the items which are not blocks, as used by the Beta 1.7
+//! protocol and the NBT files. Blocks are items as well, with their block id.
+
+pub const IRON_SHOVEL: u16 = 256;
+pub const IRON_PICKAXE: u16 = 257;
+pub const IRON_AXE: u16 = 258;
+pub const FLINT_AND_STEEL: u16 = 259;
+pub const APPLE: u16 = 260;
+pub const BOW: u16 = 261;
+pub const ARROW: u16 = 262;
+pub const COAL: u16 = 263;
+pub const DIAMOND: u16 = 264;
+pub const IRON_INGOT: u16 = 265;
+pub const GOLD_INGOT: u16 = 266;
+pub const IRON_SWORD: u16 = 267;
+pub const WOODEN_SWORD: u16 = 268;
+pub const WOODEN_SHOVEL: u16 = 269;
+pub const WOODEN_PICKAXE: u16 = 270;
+pub const WOODEN_AXE: u16 = 271;
+pub const STONE_SWORD: u16 = 272;
+pub const STONE_SHOVEL: u16 = 273;
+pub const STONE_PICKAXE: u16 = 274;
+pub const STONE_AXE: u16 = 275;
+pub const DIAMOND_SWORD: u16 = 276;
+pub const DIAMOND_SHOVEL: u16 = 277;
+pub const DIAMOND_PICKAXE: u16 = 278;
+pub const DIAMOND_AXE: u16 = 279;
+pub const STICK: u16 = 280;
+pub const BOWL: u16 = 281;
+pub const MUSHROOM_STEW: u16 = 282;
+pub const GOLDEN_SWORD: u16 = 283;
+pub const GOLDEN_SHOVEL: u16 = 284;
+pub const GOLDEN_PICKAXE: u16 = 285;
+pub const GOLDEN_AXE: u16 = 286;
+pub const STRING: u16 = 287;
+pub const FEATHER: u16 = 288;
+pub const GUNPOWDER: u16 = 289;
+pub const WOODEN_HOE: u16 = 290;
+pub const STONE_HOE: u16 = 291;
+pub const IRON_HOE: u16 = 292;
+pub const DIAMOND_HOE: u16 = 293;
+pub const GOLDEN_HOE: u16 = 294;
+pub const SEEDS: u16 = 295;
+pub const WHEAT: u16 = 296;
+pub const BREAD: u16 = 297;
+pub const LEATHER_HELMET: u16 = 298;
+pub const LEATHER_CHESTPLATE: u16 = 299;
+pub const LEATHER_LEGGINGS: u16 = 300;
+pub const LEATHER_BOOTS: u16 = 301;
+pub const CHAINMAIL_HELMET: u16 = 302;
+pub const CHAINMAIL_CHESTPLATE: u16 = 303;
+pub const CHAINMAIL_LEGGINGS: u16 = 304;
+pub const CHAINMAIL_BOOTS: u16 = 305;
+pub const IRON_HELMET: u16 = 306;
+pub const IRON_CHESTPLATE: u16 = 307;
+pub const IRON_LEGGINGS: u16 = 308;
+pub const IRON_BOOTS: u16 = 309;
+pub const DIAMOND_HELMET: u16 = 310;
+pub const DIAMOND_CHESTPLATE: u16 = 311;
+pub const DIAMOND_LEGGINGS: u16 = 312;
+pub const DIAMOND_BOOTS: u16 = 313;
+pub const GOLDEN_HELMET: u16 = 314;
+pub const GOLDEN_CHESTPLATE: u16 = 315;
+pub const GOLDEN_LEGGINGS: u16 = 316;
+pub const GOLDEN_BOOTS: u16 = 317;
+pub const FLINT: u16 = 318;
+pub const PORKCHOP: u16 = 319;
+pub const COOKED_PORKCHOP: u16 = 320;
+pub const PAINTING: u16 = 321;
+pub const GOLDEN_APPLE: u16 = 322;
+pub const SIGN: u16 = 323;
+pub const WOODEN_DOOR: u16 = 324;
+pub const BUCKET: u16 = 325;
+pub const WATER_BUCKET: u16 = 326;
+pub const LAVA_BUCKET: u16 = 327;
+pub const MINECART: u16 = 328;
+pub const SADDLE: u16 = 329;
+pub const IRON_DOOR: u16 = 330;
+pub const REDSTONE: u16 = 331;
+pub const SNOWBALL: u16 = 332;
+pub const BOAT: u16 = 333;
+pub const LEATHER: u16 = 334;
+pub const MILK_BUCKET: u16 = 335;
+pub const BRICK: u16 = 336;
+pub const CLAY_BALL: u16 = 337;
+pub const SUGAR_CANE: u16 = 338;
+pub const PAPER: u16 = 339;
+pub const BOOK: u16 = 340;
+pub const SLIMEBALL: u16 = 341;
+pub const CHEST_MINECART: u16 = 342;
+pub const FURNACE_MINECART: u16 = 343;
+pub const EGG: u16 = 344;
+pub const COMPASS: u16 = 345;
+pub const FISHING_ROD: u16 = 346;
+pub const CLOCK: u16 = 347;
+pub const GLOWSTONE_DUST: u16 = 348;
+pub const FISH: u16 = 349;
+pub const COOKED_FISH: u16 = 350;
+pub const DYE: u16 = 351;
+pub const BONE: u16 = 352;
+pub const SUGAR: u16 = 353;
+pub const CAKE: u16 = 354;
+pub const BED: u16 = 355;
+pub const REPEATER: u16 = 356;
+pub const COOKIE: u16 = 357;
+pub const MAP: u16 = 358;
+pub const SHEARS: u16 = 359;
+pub const GOLD_RECORD: u16 = 2256;
+pub const GREEN_RECORD: u16 = 2257;
@@ -0,0 +1,222 @@
+use crate::world::block_info;
+use super::{ item_ids::*, ItemStack };
+
+/// The properties shared by every item of the same id.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ItemInfo {
+    pub name: &'static str,
+    /// The largest number of items in a stack.
+    pub max_stack: u8,
+    /// How many times a tool or a piece of armor can be used before it
+    /// breaks, or 0 if it does not wear out.
+    pub durability: u16,
+}
+
+impl ItemInfo {
+    const fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            max_stack: 64,
+            durability: 0,
+        }
+    }
+
+    const fn stack(mut self, max_stack: u8) -> Self {
+        self.max_stack = max_stack;
+        self
+    }
+
+    /// Tools and armor wear out, and do not stack.
+    const fn durability(mut self, durability: u16) -> Self {
+        self.durability = durability;
+        self.max_stack = 1;
+        self
+    }
+}
+
+// Indexed by the item id minus the id of the first item.
+static ITEMS: [ItemInfo; (SHEARS - IRON_SHOVEL) as usize + 1] = [
+    ItemInfo::new("iron_shovel").durability(250),
+    ItemInfo::new("iron_pickaxe").durability(250),
+    ItemInfo::new("iron_axe").durability(250),
+    ItemInfo::new("flint_and_steel").durability(64),
+    ItemInfo::new("apple").stack(1),
+    ItemInfo::new("bow").durability(384),
+    ItemInfo::new("arrow"),
+    ItemInfo::new("coal"),
+    ItemInfo::new("diamond"),
+    ItemInfo::new("iron_ingot"),
+    ItemInfo::new("gold_ingot"),
+    ItemInfo::new("iron_sword").durability(250),
+    ItemInfo::new("wooden_sword").durability(59),
+    ItemInfo::new("wooden_shovel").durability(59),
+    ItemInfo::new("wooden_pickaxe").durability(59),
+    ItemInfo::new("wooden_axe").durability(59),
+    ItemInfo::new("stone_sword").durability(131),
+    ItemInfo::new("stone_shovel").durability(131),
+    ItemInfo::new("stone_pickaxe").durability(131),
+    ItemInfo::new("stone_axe").durability(131),
+    ItemInfo::new("diamond_sword").durability(1561),
+    ItemInfo::new("diamond_shovel").durability(1561),
+    ItemInfo::new("diamond_pickaxe").durability(1561),
+    ItemInfo::new("diamond_axe").durability(1561),
+    ItemInfo::new("stick"),
+    ItemInfo::new("bowl"),
+    ItemInfo::new("mushroom_stew").stack(1),
+    ItemInfo::new("golden_sword").durability(32),
+    ItemInfo::new("golden_shovel").durability(32),
+    ItemInfo::new("golden_pickaxe").durability(32),
+    ItemInfo::new("golden_axe").durability(32),
+    ItemInfo::new("string"),
+    ItemInfo::new("feather"),
+    ItemInfo::new("gunpowder"),
+    ItemInfo::new("wooden_hoe").durability(59),
+    ItemInfo::new("stone_hoe").durability(131),
+    ItemInfo::new("iron_hoe").durability(250),
+    ItemInfo::new("diamond_hoe").durability(1561),
+    ItemInfo::new("golden_hoe").durability(32),
+    ItemInfo::new("seeds"),
+    ItemInfo::new("wheat"),
+    ItemInfo::new("bread").stack(1),
+    ItemInfo::new("leather_helmet").durability(33),
+    ItemInfo::new("leather_chestplate").durability(48),
+    ItemInfo::new("leather_leggings").durability(45),
+    ItemInfo::new("leather_boots").durability(39),
+    ItemInfo::new("chainmail_helmet").durability(66),
+    ItemInfo::new("chainmail_chestplate").durability(96),
+    ItemInfo::new("chainmail_leggings").durability(90),
+    ItemInfo::new("chainmail_boots").durability(78),
+    ItemInfo::new("iron_helmet").durability(132),
+    ItemInfo::new("iron_chestplate").durability(192),
+    ItemInfo::new("iron_leggings").durability(180),
+    ItemInfo::new("iron_boots").durability(156),
+    ItemInfo::new("diamond_helmet").durability(264),
+    ItemInfo::new("diamond_chestplate").durability(384),
+    ItemInfo::new("diamond_leggings").durability(360),
+    ItemInfo::new("diamond_boots").durability(312),
+    ItemInfo::new("golden_helmet").durability(66),
+    ItemInfo::new("golden_chestplate").durability(96),
+    ItemInfo::new("golden_leggings").durability(90),
+    ItemInfo::new("golden_boots").durability(78),
+    ItemInfo::new("flint"),
+    ItemInfo::new("porkchop").stack(1),
+    ItemInfo::new("cooked_porkchop").stack(1),
+    ItemInfo::new("painting"),
+    ItemInfo::new("golden_apple").stack(1),
+    ItemInfo::new("sign").stack(1),
+    ItemInfo::new("wooden_door").stack(1),
+    ItemInfo::new("bucket").stack(1),
+    ItemInfo::new("water_bucket").stack(1),
+    ItemInfo::new("lava_bucket").stack(1),
+    ItemInfo::new("minecart").stack(1),
+    ItemInfo::new("saddle").stack(1),
+    ItemInfo::new("iron_door").stack(1),
+    ItemInfo::new("redstone"),
+    ItemInfo::new("snowball").stack(16),
+    ItemInfo::new("boat").stack(1),
+    ItemInfo::new("leather"),
+    ItemInfo::new("milk_bucket").stack(1),
+    ItemInfo::new("brick"),
+    ItemInfo::new("clay_ball"),
+    ItemInfo::new("sugar_cane"),
+    ItemInfo::new("paper"),
+    ItemInfo::new("book"),
+    ItemInfo::new("slimeball"),
+    ItemInfo::new("chest_minecart").stack(1),
+    ItemInfo::new("furnace_minecart").stack(1),
+    ItemInfo::new("egg").stack(16),
+    ItemInfo::new("compass"),
+    ItemInfo::new("fishing_rod").durability(64),
+    ItemInfo::new("clock"),
+    ItemInfo::new("glowstone_dust"),
+    ItemInfo::new("fish").stack(1),
+    ItemInfo::new("cooked_fish").stack(1),
+    ItemInfo::new("dye"),
+    ItemInfo::new("bone"),
+    ItemInfo::new("sugar"),
+    ItemInfo::new("cake").stack(1),
+    ItemInfo::new("bed").stack(1),
+    ItemInfo::new("repeater"),
+    ItemInfo::new("cookie").stack(8),
+    ItemInfo::new("map").stack(1),
+    ItemInfo::new("shears").durability(238),
+];
+
+const GOLD_RECORD_INFO: ItemInfo = ItemInfo::new("gold_record").stack(1);
+const GREEN_RECORD_INFO: ItemInfo = ItemInfo::new("green_record").stack(1);
+
+/// Returns the properties of the item with the given id. The ids below 256
+/// are blocks, which stack up to 64 and are named after the block.
+pub fn item_info(id: u16) -> ItemInfo {
+    match id {
+        0..=255 => ItemInfo::new(block_info(id).name),
+        GOLD_RECORD => GOLD_RECORD_INFO,
+        GREEN_RECORD => GREEN_RECORD_INFO,
+        _ => ITEMS.get((id - IRON_SHOVEL) as usize).copied().unwrap_or(ItemInfo::new("unknown")),
+    }
+}
+
+impl ItemStack {
+    pub fn info(&self) -> ItemInfo {
+        item_info(self.id)
+    }
+
+    /// The largest number of these items in a stack.
+    pub fn max_stack_size(&self) -> u8 {
+        self.info().max_stack
+    }
+
+    /// Where the item can be worn, as an index into the armor of a player
+    /// (0 for the boots up to 3 for the helmet). Pumpkins can be worn on the
+    /// head too.
+    pub fn armor_slot(&self) -> Option<usize> {
+        match self.id {
+            LEATHER_HELMET..=GOLDEN_BOOTS => Some(3 - (self.id - LEATHER_HELMET) as usize % 4),
+            crate::world::block_ids::PUMPKIN => Some(3),
+            _ => None,
+        }
+    }
+
+    /// How much a piece of armor protects its wearer when it is not worn
+    /// out: 3 for helmets and boots, 6 for leggings and 8 for chestplates,
+    /// whatever they are made of.
+    pub fn armor_points(&self) -> i16 {
+        match self.id {
+            LEATHER_HELMET..=GOLDEN_BOOTS => [3, 8, 6, 3][(self.id - LEATHER_HELMET) as usize % 4],
+            _ => 0,
+        }
+    }
+
+    /// The damage dealt by hitting something with the item, as in Beta.
+    /// Swords deal the most, then axes, pickaxes and shovels. Anything else
+    /// deals as much as a fist.
+    pub fn attack_damage(&self) -> i16 {
+        // How much better each material is, from wood and gold to diamond.
+        let material = match self.id {
+            WOODEN_SWORD..=WOODEN_AXE | GOLDEN_SWORD..=GOLDEN_AXE => 0,
+            STONE_SWORD..=STONE_AXE => 1,
+            IRON_SHOVEL..=IRON_AXE | IRON_SWORD => 2,
+            DIAMOND_SWORD..=DIAMOND_AXE => 3,
+            _ => return 1,
+        };
+
+        match self.id {
+            WOODEN_SWORD | STONE_SWORD | IRON_SWORD | DIAMOND_SWORD | GOLDEN_SWORD => 4 + material * 2,
+            WOODEN_AXE | STONE_AXE | IRON_AXE | DIAMOND_AXE | GOLDEN_AXE => 3 + material,
+            WOODEN_PICKAXE | STONE_PICKAXE | IRON_PICKAXE | DIAMOND_PICKAXE | GOLDEN_PICKAXE => 2 + material,
+            _ => 1 + material,
+        }
+    }
+
+    /// Wears out a tool or a piece of armor by `amount` uses. Returns true if
+    /// it broke, in which case it should be taken away.
+    pub fn wear(&mut self, amount: u16) -> bool {
+        let durability = self.info().durability;
+        if durability == 0 {
+            return false
+        }
+
+        self.damage = self.damage.saturating_add(amount);
+        self.damage > durability
+    }
+}
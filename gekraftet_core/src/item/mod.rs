@@ -0,0 +1,18 @@
+mod drops;
+mod inventory;
+pub mod item_ids;
+mod item_registry;
+mod player_inventory;
+mod recipe;
+mod smelting;
+mod stack;
+mod window;
+
+pub use drops::block_drop;
+pub use inventory::Inventory;
+pub use item_registry::{ item_info, ItemInfo };
+pub use player_inventory::*;
+pub use recipe::{ Ingredient, Recipe, Recipes };
+pub use smelting::*;
+pub use stack::ItemStack;
+pub use window::{ Window, WindowKind, OUTSIDE_WINDOW };
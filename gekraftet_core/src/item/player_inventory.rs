@@ -0,0 +1,156 @@
+use crate::nbt::ValueData;
+use super::{ inventory::insert_stack, ItemStack };
+
+pub const MAIN_SLOTS: usize = 36;
+pub const HOTBAR_SLOTS: usize = 9;
+pub const ARMOR_SLOTS: usize = 4;
+
+// Armor is stored after the main inventory in NBT files, from the boots up
+// to the helmet.
+const ARMOR_NBT_SLOT: i8 = 100;
+
+/// The items carried by a player. The first 9 slots of the main inventory
+/// are the hotbar.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlayerInventory {
+    main: [Option<ItemStack>; MAIN_SLOTS],
+    /// From the boots up to the helmet.
+    armor: [Option<ItemStack>; ARMOR_SLOTS],
+    /// The hotbar slot of the item in hand.
+    held: usize,
+}
+
+impl Default for PlayerInventory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PlayerInventory {
+    pub fn new() -> Self {
+        Self {
+            main: [None; MAIN_SLOTS],
+            armor: [None; ARMOR_SLOTS],
+            held: 0,
+        }
+    }
+
+    pub fn main(&self) -> &[Option<ItemStack>; MAIN_SLOTS] {
+        &self.main
+    }
+
+    pub fn main_mut(&mut self) -> &mut [Option<ItemStack>; MAIN_SLOTS] {
+        &mut self.main
+    }
+
+    /// The armor worn, from the boots up to the helmet.
+    pub fn armor(&self) -> &[Option<ItemStack>; ARMOR_SLOTS] {
+        &self.armor
+    }
+
+    pub fn armor_mut(&mut self) -> &mut [Option<ItemStack>; ARMOR_SLOTS] {
+        &mut self.armor
+    }
+
+    /// Borrows the main inventory and the armor at the same time.
+    pub(crate) fn split_mut(&mut self) -> (&mut [Option<ItemStack>; MAIN_SLOTS], &mut [Option<ItemStack>; ARMOR_SLOTS]) {
+        (&mut self.main, &mut self.armor)
+    }
+
+    pub fn held_slot(&self) -> usize {
+        self.held
+    }
+
+    pub fn set_held_slot(&mut self, slot: usize) {
+        assert!(slot < HOTBAR_SLOTS, "the held item must be in the hotbar");
+        self.held = slot;
+    }
+
+    pub fn held_item(&self) -> Option<&ItemStack> {
+        self.main[self.held].as_ref()
+    }
+
+    pub fn held_item_mut(&mut self) -> &mut Option<ItemStack> {
+        &mut self.main[self.held]
+    }
+
+    /// Puts the items into the inventory, filling up the stacks of the same
+    /// item first and then the empty slots, hotbar first. Returns the items
+    /// which did not fit.
+    pub fn add_item(&mut self, stack: ItemStack) -> Option<ItemStack> {
+        let mut slots = self.main.iter_mut().collect::<Vec<_>>();
+        insert_stack(&mut slots, stack)
+    }
+
+    /// How much the armor worn protects the player, from 0 to 20. Worn out
+    /// armor protects less.
+    pub fn armor_value(&self) -> i16 {
+        let (mut points, mut left, mut total) = (0, 0, 0);
+
+        for stack in self.armor.iter().flatten() {
+            let durability = stack.info().durability as i32;
+            points += stack.armor_points() as i32;
+            left += durability - stack.damage.min(durability as u16) as i32;
+            total += durability;
+        }
+
+        if total == 0 {
+            return 0
+        }
+
+        (((points - 1) * left) / total + 1) as i16
+    }
+
+    /// Wears out the armor worn when the player takes `damage`. Armor which
+    /// breaks is taken away.
+    pub fn wear_armor(&mut self, damage: i16) {
+        let amount = (damage / 4).max(1) as u16;
+
+        for slot in self.armor.iter_mut() {
+            if slot.as_mut().is_some_and(|stack| stack.wear(amount)) {
+                *slot = None;
+            }
+        }
+    }
+
+    /// Empties the inventory, returning every item it held.
+    pub fn take_all(&mut self) -> Vec<ItemStack> {
+        self.main.iter_mut()
+            .chain(self.armor.iter_mut())
+            .filter_map(Option::take)
+            .collect()
+    }
+
+    /// Writes the inventory as the list of items found in player files.
+    pub fn to_nbt(&self) -> ValueData {
+        let main = self.main.iter()
+            .enumerate()
+            .filter_map(|(slot, stack)| stack.map(|s| s.to_nbt(slot as i8)));
+
+        let armor = self.armor.iter()
+            .enumerate()
+            .filter_map(|(slot, stack)| stack.map(|s| s.to_nbt(ARMOR_NBT_SLOT + slot as i8)));
+
+        ValueData::List(10, main.chain(armor).map(ValueData::Compound).collect())
+    }
+
+    /// Reads a list written by `to_nbt`. Items in unknown slots are dropped.
+    pub fn from_nbt(items: &[ValueData]) -> Self {
+        let mut result = Self::new();
+
+        for item in items.iter().filter_map(ValueData::compound) {
+            let (slot, stack) = match ItemStack::from_nbt(item) {
+                Some(item) => item,
+                None => continue,
+            };
+
+            if (0..MAIN_SLOTS as i8).contains(&slot) {
+                result.main[slot as usize] = Some(stack);
+            } else if (ARMOR_NBT_SLOT..ARMOR_NBT_SLOT + ARMOR_SLOTS as i8).contains(&slot) {
+                result.armor[(slot - ARMOR_NBT_SLOT) as usize] = Some(stack);
+            }
+        }
+
+        result
+    }
+}
@@ -0,0 +1,341 @@
+use crate::world::block_ids as blocks;
+use super::{ item_ids as items, ItemStack };
+
+/// What a slot of a crafting grid must hold for a recipe to match.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Ingredient {
+    pub id: u16,
+    /// The damage the item must have, or None if any damage matches.
+    pub damage: Option<u16>,
+}
+
+impl Ingredient {
+    pub fn with_damage(id: u16, damage: u16) -> Self {
+        Self {
+            id,
+            damage: Some(damage),
+        }
+    }
+
+    pub fn matches(&self, stack: &ItemStack) -> bool {
+        self.id == stack.id && self.damage.is_none_or(|d| d == stack.damage)
+    }
+}
+
+/// Any item with the given id, whatever its damage.
+impl From<u16> for Ingredient {
+    fn from(id: u16) -> Self {
+        Self {
+            id,
+            damage: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Recipe {
+    /// The ingredients must be laid out in the grid as in the recipe, or as
+    /// in its mirror image. The recipe can be anywhere in the grid.
+    Shaped {
+        width: usize,
+        height: usize,
+        /// Row by row, with None for the slots which must be empty.
+        ingredients: Vec<Option<Ingredient>>,
+        result: ItemStack,
+    },
+    /// The ingredients can be anywhere in the grid.
+    Shapeless {
+        ingredients: Vec<Ingredient>,
+        result: ItemStack,
+    },
+}
+
+impl Recipe {
+    pub fn result(&self) -> ItemStack {
+        match self {
+            Self::Shaped { result, .. } | Self::Shapeless { result, .. } => *result,
+        }
+    }
+
+    /// Whether the recipe matches a square crafting grid, whose slots are
+    /// given row by row.
+    pub fn matches(&self, grid: &[Option<ItemStack>], grid_width: usize) -> bool {
+        match self {
+            Self::Shaped { width, height, ingredients, .. } => {
+                let grid_height = grid.len() / grid_width;
+                if *width > grid_width || *height > grid_height {
+                    return false
+                }
+
+                (0..=grid_width - width).any(|dx| (0..=grid_height - height).any(|dy| {
+                    [false, true].iter().any(|&mirrored| {
+                        grid.iter().enumerate().all(|(i, stack)| {
+                            let (x, y) = (i % grid_width, i / grid_width);
+
+                            let ingredient = if x >= dx && x < dx + width && y >= dy && y < dy + height {
+                                let x = if mirrored { dx + width - 1 - x } else { x - dx };
+                                ingredients[(y - dy) * width + x]
+                            } else {
+                                None
+                            };
+
+                            match (ingredient, stack) {
+                                (Some(ingredient), Some(stack)) => ingredient.matches(stack),
+                                (None, None) => true,
+                                _ => false,
+                            }
+                        })
+                    })
+                }))
+            },
+
+            Self::Shapeless { ingredients, .. } => {
+                let mut left = ingredients.clone();
+
+                for stack in grid.iter().flatten() {
+                    match left.iter().position(|i| i.matches(stack)) {
+                        Some(found) => { left.swap_remove(found); },
+                        None => return false,
+                    }
+                }
+
+                left.is_empty()
+            },
+        }
+    }
+}
+
+/// A set of crafting recipes.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Recipes {
+    recipes: Vec<Recipe>,
+}
+
+impl Recipes {
+    /// Creates a set without any recipe.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, recipe: Recipe) {
+        self.recipes.push(recipe);
+    }
+
+    /// Adds a shaped recipe. The pattern is given row by row, each character
+    /// standing for the ingredient it is paired with in `keys`, and spaces
+    /// for empty slots.
+    pub fn add_shaped<I>(&mut self, pattern: &[&str], keys: &[(char, I)], result: ItemStack)
+        where I: Into<Ingredient> + Copy
+    {
+        let width = pattern.iter().map(|row| row.len()).max().unwrap_or(0);
+
+        let ingredients = pattern.iter()
+            .flat_map(|row| {
+                let mut row = row.chars()
+                    .map(|c| keys.iter().find(|(k, _)| *k == c).map(|&(_, i)| i.into()))
+                    .collect::<Vec<_>>();
+                row.resize(width, None);
+                row
+            })
+            .collect();
+
+        self.add(Recipe::Shaped {
+            width,
+            height: pattern.len(),
+            ingredients,
+            result,
+        });
+    }
+
+    pub fn add_shapeless<I>(&mut self, ingredients: &[I], result: ItemStack)
+        where I: Into<Ingredient> + Copy
+    {
+        self.add(Recipe::Shapeless {
+            ingredients: ingredients.iter().map(|&i| i.into()).collect(),
+            result,
+        });
+    }
+
+    pub fn recipes(&self) -> &[Recipe] {
+        &self.recipes
+    }
+
+    /// Returns what the items in a square crafting grid make, if anything.
+    /// The slots are given row by row.
+    pub fn find(&self, grid: &[Option<ItemStack>], grid_width: usize) -> Option<ItemStack> {
+        if grid.iter().all(Option::is_none) {
+            return None
+        }
+
+        self.recipes.iter()
+            .find(|recipe| recipe.matches(grid, grid_width))
+            .map(Recipe::result)
+    }
+
+    /// The crafting recipes of Beta 1.7.
+    pub fn beta() -> Self {
+        let mut r = Self::new();
+        let one = |id| ItemStack::new(id, 1);
+        let planks = Ingredient::with_damage(blocks::PLANKS, 0);
+        let stick = Ingredient::from(items::STICK);
+        let dye = |color| Ingredient::with_damage(items::DYE, color);
+
+        // Tools, weapons and armor, for every material.
+        let tools = [
+            (planks, [items::WOODEN_PICKAXE, items::WOODEN_SHOVEL, items::WOODEN_AXE, items::WOODEN_HOE, items::WOODEN_SWORD]),
+            (blocks::COBBLESTONE.into(), [items::STONE_PICKAXE, items::STONE_SHOVEL, items::STONE_AXE, items::STONE_HOE, items::STONE_SWORD]),
+            (items::IRON_INGOT.into(), [items::IRON_PICKAXE, items::IRON_SHOVEL, items::IRON_AXE, items::IRON_HOE, items::IRON_SWORD]),
+            (items::GOLD_INGOT.into(), [items::GOLDEN_PICKAXE, items::GOLDEN_SHOVEL, items::GOLDEN_AXE, items::GOLDEN_HOE, items::GOLDEN_SWORD]),
+            (items::DIAMOND.into(), [items::DIAMOND_PICKAXE, items::DIAMOND_SHOVEL, items::DIAMOND_AXE, items::DIAMOND_HOE, items::DIAMOND_SWORD]),
+        ];
+        for &(material, [pickaxe, shovel, axe, hoe, sword]) in tools.iter() {
+            let keys = [('X', material), ('#', stick)];
+            r.add_shaped(&["XXX", " # ", " # "], &keys, one(pickaxe));
+            r.add_shaped(&["X", "#", "#"], &keys, one(shovel));
+            r.add_shaped(&["XX", "X#", " #"], &keys, one(axe));
+            r.add_shaped(&["XX", " #", " #"], &keys, one(hoe));
+            r.add_shaped(&["X", "X", "#"], &keys, one(sword));
+        }
+
+        let armor = [
+            (items::LEATHER, items::LEATHER_HELMET),
+            (items::IRON_INGOT, items::IRON_HELMET),
+            (items::DIAMOND, items::DIAMOND_HELMET),
+            (items::GOLD_INGOT, items::GOLDEN_HELMET),
+        ];
+        for &(material, helmet) in armor.iter() {
+            // The pieces of a set of armor have consecutive ids.
+            let keys = [('X', material)];
+            r.add_shaped(&["XXX", "X X"], &keys, one(helmet));
+            r.add_shaped(&["X X", "XXX", "XXX"], &keys, one(helmet + 1));
+            r.add_shaped(&["XXX", "X X", "X X"], &keys, one(helmet + 2));
+            r.add_shaped(&["X X", "X X"], &keys, one(helmet + 3));
+        }
+
+        // Storage blocks, and back.
+        let storage = [
+            (Ingredient::from(items::GOLD_INGOT), ItemStack::new(items::GOLD_INGOT, 9), blocks::GOLD_BLOCK),
+            (items::IRON_INGOT.into(), ItemStack::new(items::IRON_INGOT, 9), blocks::IRON_BLOCK),
+            (items::DIAMOND.into(), ItemStack::new(items::DIAMOND, 9), blocks::DIAMOND_BLOCK),
+            (dye(4), ItemStack::with_damage(items::DYE, 9, 4), blocks::LAPIS_BLOCK),
+        ];
+        for &(material, materials, block) in storage.iter() {
+            r.add_shaped(&["###", "###", "###"], &[('#', material)], one(block));
+            r.add_shapeless(&[block], materials);
+        }
+
+        // Basic blocks and materials.
+        r.add_shaped(&["#"], &[('#', blocks::LOG)], ItemStack::new(blocks::PLANKS, 4));
+        r.add_shaped(&["#", "#"], &[('#', planks)], ItemStack::new(items::STICK, 4));
+        r.add_shaped(&["X", "#"], &[('X', Ingredient::from(items::COAL)), ('#', stick)], ItemStack::new(blocks::TORCH, 4));
+        r.add_shaped(&["##", "##"], &[('#', planks)], one(blocks::WORKBENCH));
+        r.add_shaped(&["###", "# #", "###"], &[('#', blocks::COBBLESTONE)], one(blocks::FURNACE));
+        r.add_shaped(&["###", "# #", "###"], &[('#', planks)], one(blocks::CHEST));
+        r.add_shaped(&["##", "##"], &[('#', items::SNOWBALL)], one(blocks::SNOW));
+        r.add_shaped(&["##", "##"], &[('#', items::CLAY_BALL)], one(blocks::CLAY));
+        r.add_shaped(&["##", "##"], &[('#', items::BRICK)], one(blocks::BRICKS));
+        r.add_shaped(&["##", "##"], &[('#', items::GLOWSTONE_DUST)], one(blocks::GLOWSTONE));
+        r.add_shaped(&["##", "##"], &[('#', items::STRING)], one(blocks::WOOL));
+        r.add_shaped(&["##", "##"], &[('#', blocks::SAND)], one(blocks::SANDSTONE));
+        r.add_shaped(&["X#X", "#X#", "X#X"], &[('X', items::GUNPOWDER), ('#', blocks::SAND)], one(blocks::TNT));
+        r.add_shaped(&["###", "XXX", "###"], &[('#', planks), ('X', items::BOOK.into())], one(blocks::BOOKSHELF));
+        r.add_shaped(&["###", "#X#", "###"], &[('#', planks), ('X', items::DIAMOND.into())], one(blocks::JUKEBOX));
+        r.add_shaped(&["###", "#X#", "###"], &[('#', planks), ('X', items::REDSTONE.into())], one(blocks::NOTE_BLOCK));
+        r.add_shaped(&["A", "B"], &[('A', blocks::PUMPKIN), ('B', blocks::TORCH)], one(blocks::JACK_O_LANTERN));
+
+        let slabs = [
+            (Ingredient::from(blocks::STONE), 0),
+            (blocks::SANDSTONE.into(), 1),
+            (planks, 2),
+            (blocks::COBBLESTONE.into(), 3),
+        ];
+        for &(material, kind) in slabs.iter() {
+            r.add_shaped(&["###"], &[('#', material)], ItemStack::with_damage(blocks::SLAB, 3, kind));
+        }
+
+        r.add_shaped(&["#  ", "## ", "###"], &[('#', planks)], ItemStack::new(blocks::WOODEN_STAIRS, 4));
+        r.add_shaped(&["#  ", "## ", "###"], &[('#', blocks::COBBLESTONE)], ItemStack::new(blocks::COBBLESTONE_STAIRS, 4));
+        r.add_shaped(&["# #", "###", "# #"], &[('#', stick)], ItemStack::new(blocks::LADDER, 2));
+        r.add_shaped(&["###", "###"], &[('#', stick)], ItemStack::new(blocks::FENCE, 2));
+        r.add_shaped(&["###", "###"], &[('#', planks)], ItemStack::new(blocks::TRAPDOOR, 2));
+
+        // Things placed in the world.
+        r.add_shaped(&["##", "##", "##"], &[('#', planks)], one(items::WOODEN_DOOR));
+        r.add_shaped(&["##", "##", "##"], &[('#', items::IRON_INGOT)], one(items::IRON_DOOR));
+        r.add_shaped(&["###", "###", " X "], &[('#', planks), ('X', stick)], one(items::SIGN));
+        r.add_shaped(&["###", "XXX"], &[('#', Ingredient::from(blocks::WOOL)), ('X', planks)], one(items::BED));
+        r.add_shaped(&["XXX", "X#X", "XXX"], &[('X', stick), ('#', Ingredient::from(blocks::WOOL))], one(items::PAINTING));
+        r.add_shaped(&["# #", "###"], &[('#', planks)], one(items::BOAT));
+
+        // Rails and minecarts.
+        let iron = Ingredient::from(items::IRON_INGOT);
+        let redstone = Ingredient::from(items::REDSTONE);
+        r.add_shaped(&["X X", "X#X", "X X"], &[('X', iron), ('#', stick)], ItemStack::new(blocks::RAIL, 16));
+        r.add_shaped(&["X X", "X#X", "XRX"], &[('X', items::GOLD_INGOT.into()), ('#', stick), ('R', redstone)], ItemStack::new(blocks::POWERED_RAIL, 6));
+        r.add_shaped(&["X X", "X#X", "XRX"], &[('X', iron), ('#', blocks::STONE_PRESSURE_PLATE.into()), ('R', redstone)], ItemStack::new(blocks::DETECTOR_RAIL, 6));
+        r.add_shaped(&["# #", "###"], &[('#', iron)], one(items::MINECART));
+        r.add_shaped(&["A", "B"], &[('A', blocks::CHEST), ('B', items::MINECART)], one(items::CHEST_MINECART));
+        r.add_shaped(&["A", "B"], &[('A', blocks::FURNACE), ('B', items::MINECART)], one(items::FURNACE_MINECART));
+
+        // Redstone.
+        r.add_shaped(&["X", "#"], &[('X', stick), ('#', blocks::COBBLESTONE.into())], one(blocks::LEVER));
+        r.add_shaped(&["X", "#"], &[('X', redstone), ('#', stick)], one(blocks::REDSTONE_TORCH));
+        r.add_shaped(&["#X#", "III"], &[('#', blocks::REDSTONE_TORCH.into()), ('X', redstone), ('I', blocks::STONE.into())], one(items::REPEATER));
+        r.add_shaped(&["#", "#"], &[('#', blocks::STONE)], one(blocks::STONE_BUTTON));
+        r.add_shaped(&["##"], &[('#', blocks::STONE)], one(blocks::STONE_PRESSURE_PLATE));
+        r.add_shaped(&["##"], &[('#', planks)], one(blocks::WOODEN_PRESSURE_PLATE));
+        r.add_shaped(&["###", "#X#", "#R#"], &[('#', blocks::COBBLESTONE.into()), ('X', items::BOW.into()), ('R', redstone)], one(blocks::DISPENSER));
+        r.add_shaped(&["TTT", "#X#", "#R#"], &[('T', planks), ('#', blocks::COBBLESTONE.into()), ('X', iron), ('R', redstone)], one(blocks::PISTON));
+        r.add_shaped(&["S", "P"], &[('S', items::SLIMEBALL), ('P', blocks::PISTON)], one(blocks::STICKY_PISTON));
+
+        // Other tools.
+        r.add_shapeless(&[items::IRON_INGOT, items::FLINT], one(items::FLINT_AND_STEEL));
+        r.add_shaped(&[" #X", "# X", " #X"], &[('#', items::STICK), ('X', items::STRING)], one(items::BOW));
+        r.add_shaped(&["X", "#", "Y"], &[('X', items::FLINT), ('#', items::STICK), ('Y', items::FEATHER)], ItemStack::new(items::ARROW, 4));
+        r.add_shaped(&["# #", " # "], &[('#', iron)], one(items::BUCKET));
+        r.add_shaped(&[" # ", "#X#", " # "], &[('#', iron), ('X', redstone)], one(items::COMPASS));
+        r.add_shaped(&[" # ", "#X#", " # "], &[('#', items::GOLD_INGOT.into()), ('X', redstone)], one(items::CLOCK));
+        r.add_shaped(&["  #", " #X", "# X"], &[('#', items::STICK), ('X', items::STRING)], one(items::FISHING_ROD));
+        r.add_shaped(&["###", "#X#", "###"], &[('#', items::PAPER), ('X', items::COMPASS)], one(items::MAP));
+        r.add_shaped(&[" #", "# "], &[('#', items::IRON_INGOT)], one(items::SHEARS));
+
+        // Food, paper and books.
+        r.add_shaped(&["# #", " # "], &[('#', planks)], ItemStack::new(items::BOWL, 4));
+        r.add_shapeless(&[blocks::BROWN_MUSHROOM, blocks::RED_MUSHROOM, items::BOWL], one(items::MUSHROOM_STEW));
+        r.add_shaped(&["###"], &[('#', items::WHEAT)], one(items::BREAD));
+        r.add_shaped(&["#X#"], &[('#', Ingredient::from(items::WHEAT)), ('X', dye(3))], ItemStack::new(items::COOKIE, 8));
+        r.add_shaped(&["###", "#X#", "###"], &[('#', blocks::GOLD_BLOCK), ('X', items::APPLE)], one(items::GOLDEN_APPLE));
+        r.add_shaped(&["AAA", "BEB", "CCC"], &[('A', items::MILK_BUCKET), ('B', items::SUGAR), ('E', items::EGG), ('C', items::WHEAT)], one(items::CAKE));
+        r.add_shaped(&["#"], &[('#', items::SUGAR_CANE)], one(items::SUGAR));
+        r.add_shaped(&["###"], &[('#', items::SUGAR_CANE)], ItemStack::new(items::PAPER, 3));
+        r.add_shaped(&["#", "#", "#"], &[('#', items::PAPER)], one(items::BOOK));
+
+        // Dyes, and dyeing wool. The color of a dye is the opposite of the
+        // color of the wool it makes.
+        let dyes = |color, count| ItemStack::with_damage(items::DYE, count, color);
+        r.add_shapeless(&[Ingredient::from(items::BONE)], dyes(15, 3));
+        r.add_shapeless(&[Ingredient::from(blocks::ROSE)], dyes(1, 2));
+        r.add_shapeless(&[Ingredient::from(blocks::DANDELION)], dyes(11, 2));
+        r.add_shapeless(&[dye(1), dye(11)], dyes(14, 2));
+        r.add_shapeless(&[dye(1), dye(15)], dyes(9, 2));
+        r.add_shapeless(&[dye(4), dye(15)], dyes(12, 2));
+        r.add_shapeless(&[dye(2), dye(15)], dyes(10, 2));
+        r.add_shapeless(&[dye(0), dye(15)], dyes(8, 2));
+        r.add_shapeless(&[dye(8), dye(15)], dyes(7, 2));
+        r.add_shapeless(&[dye(0), dye(15), dye(15)], dyes(7, 3));
+        r.add_shapeless(&[dye(1), dye(4)], dyes(5, 2));
+        r.add_shapeless(&[dye(4), dye(2)], dyes(6, 2));
+        r.add_shapeless(&[dye(5), dye(9)], dyes(13, 2));
+        r.add_shapeless(&[dye(4), dye(1), dye(9)], dyes(13, 3));
+        r.add_shapeless(&[dye(4), dye(1), dye(1), dye(15)], dyes(13, 4));
+
+        for color in 0..16 {
+            r.add_shapeless(
+                &[dye(color), Ingredient::with_damage(blocks::WOOL, 0)],
+                ItemStack::with_damage(blocks::WOOL, 1, 15 - color),
+            );
+        }
+
+        r
+    }
+}
@@ -0,0 +1,43 @@
+use crate::world::block_ids;
+use super::{ item_ids, ItemStack };
+
+/// The ticks an item takes to smelt in a furnace.
+pub const SMELT_TIME: i16 = 200;
+
+/// What an item turns into when it is smelted in a furnace, as in Beta.
+pub fn smelting_result(input: &ItemStack) -> Option<ItemStack> {
+    use block_ids::*;
+
+    let result = match input.id {
+        IRON_ORE => ItemStack::new(item_ids::IRON_INGOT, 1),
+        GOLD_ORE => ItemStack::new(item_ids::GOLD_INGOT, 1),
+        DIAMOND_ORE => ItemStack::new(item_ids::DIAMOND, 1),
+        SAND => ItemStack::new(GLASS, 1),
+        COBBLESTONE => ItemStack::new(STONE, 1),
+        // Charcoal.
+        LOG => ItemStack::with_damage(item_ids::COAL, 1, 1),
+        // Cactus green.
+        CACTUS => ItemStack::with_damage(item_ids::DYE, 1, 2),
+        item_ids::PORKCHOP => ItemStack::new(item_ids::COOKED_PORKCHOP, 1),
+        item_ids::FISH => ItemStack::new(item_ids::COOKED_FISH, 1),
+        item_ids::CLAY_BALL => ItemStack::new(item_ids::BRICK, 1),
+        _ => return None,
+    };
+
+    Some(result)
+}
+
+/// The ticks a furnace burns for on a single item of fuel, or 0 if the item
+/// does not burn. Blocks made of wood all burn for as long as planks.
+pub fn fuel_time(fuel: &ItemStack) -> i16 {
+    use block_ids::*;
+
+    match fuel.id {
+        PLANKS | LOG | BOOKSHELF | WOODEN_STAIRS | CHEST | LOCKED_CHEST | WORKBENCH | FENCE |
+        NOTE_BLOCK | JUKEBOX | WOODEN_PRESSURE_PLATE | TRAPDOOR => 300,
+        SAPLING | item_ids::STICK => 100,
+        item_ids::COAL => 1600,
+        item_ids::LAVA_BUCKET => 20000,
+        _ => 0,
+    }
+}
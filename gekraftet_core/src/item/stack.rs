@@ -0,0 +1,66 @@
+use crate::nbt::{ Compound, ValueData };
+
+/// A number of items of the same kind, as found in an inventory slot.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct ItemStack {
+    /// The id of the item. Blocks use their block id.
+    pub id: u16,
+    pub count: u8,
+    /// How worn out a tool is, or which variant of the item this is (such as
+    /// the color of wool).
+    pub damage: u16,
+}
+
+impl ItemStack {
+    pub fn new(id: u16, count: u8) -> Self {
+        Self {
+            id,
+            count,
+            damage: 0,
+        }
+    }
+
+    pub fn with_damage(id: u16, count: u8, damage: u16) -> Self {
+        Self {
+            id,
+            count,
+            damage,
+        }
+    }
+
+    /// Whether the items of both stacks are the same kind of item, so that
+    /// they can be merged.
+    pub fn is_same_item(&self, other: &Self) -> bool {
+        self.id == other.id && self.damage == other.damage
+    }
+
+    /// Writes the stack as it is stored in NBT files, without any slot.
+    pub fn to_item_nbt(&self) -> Compound {
+        let mut item = Compound::new();
+        item.insert("id".to_string(), ValueData::Short(self.id as i16));
+        item.insert("Count".to_string(), ValueData::Byte(self.count as i8));
+        item.insert("Damage".to_string(), ValueData::Short(self.damage as i16));
+        item
+    }
+
+    pub fn from_item_nbt(item: &Compound) -> Option<Self> {
+        let id = *item.get("id")?.short()?;
+        let count = *item.get("Count")?.byte()?;
+        let damage = item.get("Damage").and_then(ValueData::short).copied().unwrap_or(0);
+
+        Some(Self::with_damage(id as u16, count as u8, damage as u16))
+    }
+
+    /// Writes the stack in the layout used by inventories in NBT files.
+    pub fn to_nbt(&self, slot: i8) -> Compound {
+        let mut item = self.to_item_nbt();
+        item.insert("Slot".to_string(), ValueData::Byte(slot));
+        item
+    }
+
+    /// Reads a stack written by `to_nbt`, returning it along with its slot.
+    pub fn from_nbt(item: &Compound) -> Option<(i8, Self)> {
+        let slot = *item.get("Slot")?.byte()?;
+        Some((slot, Self::from_item_nbt(item)?))
+    }
+}
@@ -0,0 +1,459 @@
+use super::{ inventory::insert_stack, item_ids, Inventory, ItemStack, PlayerInventory, Recipes };
+use super::{ HOTBAR_SLOTS, MAIN_SLOTS };
+
+/// The slot sent in `WindowClick` packets when clicking outside of the
+/// window, which drops the item held by the cursor.
+pub const OUTSIDE_WINDOW: i16 = -999;
+
+// The slots of the main inventory which are not in the hotbar.
+const STORAGE_SLOTS: usize = MAIN_SLOTS - HOTBAR_SLOTS;
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum WindowKind {
+    /// The inventory of the player, with its 2x2 crafting grid.
+    Player,
+    /// A chest, or a double chest if it has 6 rows.
+    Chest { rows: usize },
+    Workbench,
+    Furnace,
+    Dispenser,
+}
+
+/// Where a slot of a window really is.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Slot {
+    Container(usize),
+    Armor(usize),
+    Main(usize),
+}
+
+impl WindowKind {
+    /// The type of the window sent in `OpenWindow` packets. The inventory of
+    /// the player is always open, so it does not have one.
+    pub fn protocol_type(self) -> Option<i8> {
+        match self {
+            Self::Player => None,
+            Self::Chest { .. } => Some(0),
+            Self::Workbench => Some(1),
+            Self::Furnace => Some(2),
+            Self::Dispenser => Some(3),
+        }
+    }
+
+    /// The number of slots of the window which do not belong to the player.
+    /// The crafting result counts as a slot.
+    pub fn container_size(self) -> usize {
+        match self {
+            Self::Player => 5,
+            Self::Chest { rows } => rows * 9,
+            Self::Workbench => 10,
+            Self::Furnace => 3,
+            Self::Dispenser => 9,
+        }
+    }
+
+    /// The number of slots of the window, including the ones of the player.
+    pub fn size(self) -> usize {
+        match self {
+            Self::Player => self.container_size() + 4 + MAIN_SLOTS,
+            _ => self.container_size() + MAIN_SLOTS,
+        }
+    }
+
+    /// The width of the crafting grid, for the windows which have one. The
+    /// crafting result is in the first slot, followed by the grid.
+    fn crafting_width(self) -> Option<usize> {
+        match self {
+            Self::Player => Some(2),
+            Self::Workbench => Some(3),
+            _ => None,
+        }
+    }
+
+    // The slots of the container come first, then the storage part of the
+    // main inventory and then the hotbar. The inventory of the player shows
+    // the armor in between, from the helmet down to the boots.
+    fn slot(self, index: usize) -> Option<Slot> {
+        let mut index = index;
+
+        if index < self.container_size() {
+            return Some(Slot::Container(index))
+        }
+        index -= self.container_size();
+
+        if self == Self::Player {
+            if index < 4 {
+                return Some(Slot::Armor(3 - index))
+            }
+            index -= 4;
+        }
+
+        if index < STORAGE_SLOTS {
+            Some(Slot::Main(HOTBAR_SLOTS + index))
+        } else if index < MAIN_SLOTS {
+            Some(Slot::Main(index - STORAGE_SLOTS))
+        } else {
+            None
+        }
+    }
+
+    /// Whether items can only be taken out of the slot.
+    fn is_output(self, slot: Slot) -> bool {
+        matches!((self, slot),
+            (Self::Player, Slot::Container(0)) |
+            (Self::Workbench, Slot::Container(0)) |
+            (Self::Furnace, Slot::Container(2))
+        )
+    }
+
+    /// Where the items of a slot go when it is shift-clicked, as a range of
+    /// slots of the window along with whether the range is filled from its
+    /// end.
+    fn transfer_range(self, index: usize) -> (usize, usize, bool) {
+        let container = self.container_size();
+        let storage = container + if self == Self::Player { 4 } else { 0 };
+        let hotbar = storage + STORAGE_SLOTS;
+        let end = hotbar + HOTBAR_SLOTS;
+
+        match self {
+            Self::Chest { .. } | Self::Dispenser => if index < container {
+                (container, end, true)
+            } else {
+                (0, container, false)
+            },
+
+            _ => if self.slot(index).is_some_and(|slot| self.is_output(slot)) {
+                (storage, end, true)
+            } else if index >= storage && index < hotbar {
+                (hotbar, end, false)
+            } else if index >= hotbar {
+                (storage, hotbar, false)
+            } else {
+                (storage, end, false)
+            },
+        }
+    }
+}
+
+/// A window open on the screen of a player, such as a chest or a workbench,
+/// along with the inventory of the player. The slots are numbered as in the
+/// `WindowClick`, `SetSlot` and `WindowItems` packets.
+///
+/// The window holds the slots which do not belong to the player, which are
+/// copied from the block they belong to when the window is opened.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Window {
+    id: i8,
+    kind: WindowKind,
+    container: Inventory,
+    /// The item picked up with the mouse.
+    cursor: Option<ItemStack>,
+}
+
+impl Window {
+    /// Opens a window with empty slots.
+    pub fn new(id: i8, kind: WindowKind) -> Self {
+        Self::with_container(id, kind, Inventory::new(kind.container_size()))
+    }
+
+    /// The inventory of a player, which is always the window 0.
+    pub fn player() -> Self {
+        Self::new(0, WindowKind::Player)
+    }
+
+    /// Opens a window on the slots of a block, such as a chest.
+    pub fn with_container(id: i8, kind: WindowKind, container: Inventory) -> Self {
+        assert_eq!(container.len(), kind.container_size(), "the container does not fit the window");
+
+        Self {
+            id,
+            kind,
+            container,
+            cursor: None,
+        }
+    }
+
+    pub fn id(&self) -> i8 {
+        self.id
+    }
+
+    pub fn kind(&self) -> WindowKind {
+        self.kind
+    }
+
+    pub fn size(&self) -> usize {
+        self.kind.size()
+    }
+
+    /// The slots which do not belong to the player.
+    pub fn container(&self) -> &Inventory {
+        &self.container
+    }
+
+    pub fn container_mut(&mut self) -> &mut Inventory {
+        &mut self.container
+    }
+
+    pub fn cursor(&self) -> Option<&ItemStack> {
+        self.cursor.as_ref()
+    }
+
+    pub fn set_cursor(&mut self, cursor: Option<ItemStack>) {
+        self.cursor = cursor;
+    }
+
+    /// Returns the item in a slot of the window.
+    pub fn get<'a>(&'a self, player: &'a PlayerInventory, index: usize) -> Option<&'a ItemStack> {
+        match self.kind.slot(index)? {
+            Slot::Container(i) => self.container.get(i),
+            Slot::Armor(i) => player.armor()[i].as_ref(),
+            Slot::Main(i) => player.main()[i].as_ref(),
+        }
+    }
+
+    /// Every slot of the window, as sent in `WindowItems` packets.
+    pub fn slots(&self, player: &PlayerInventory) -> Vec<Option<ItemStack>> {
+        (0..self.size()).map(|i| self.get(player, i).copied()).collect()
+    }
+
+    fn stack_mut<'a>(&'a mut self, player: &'a mut PlayerInventory, slot: Slot) -> &'a mut Option<ItemStack> {
+        match slot {
+            Slot::Container(i) => &mut self.container.slots_mut()[i],
+            Slot::Armor(i) => &mut player.armor_mut()[i],
+            Slot::Main(i) => &mut player.main_mut()[i],
+        }
+    }
+
+    /// The slots in a range of the window, in order or from the end.
+    fn slot_range<'a>(
+        &'a mut self,
+        player: &'a mut PlayerInventory,
+        (start, end, reverse): (usize, usize, bool),
+    ) -> Vec<&'a mut Option<ItemStack>> {
+        let kind = self.kind;
+
+        let mut container = self.container.slots_mut().iter_mut().map(Some).collect::<Vec<_>>();
+        let (main, armor) = player.split_mut();
+        let mut main = main.iter_mut().map(Some).collect::<Vec<_>>();
+        let mut armor = armor.iter_mut().map(Some).collect::<Vec<_>>();
+
+        let mut indices = (start..end).collect::<Vec<_>>();
+        if reverse {
+            indices.reverse();
+        }
+
+        indices.into_iter()
+            .filter_map(|i| match kind.slot(i)? {
+                Slot::Container(i) => container[i].take(),
+                Slot::Armor(i) => armor[i].take(),
+                Slot::Main(i) => main[i].take(),
+            })
+            .collect()
+    }
+
+    /// How many items of the stack can be put into a slot, or None if the
+    /// stack does not belong there.
+    fn slot_limit(&self, slot: Slot, stack: &ItemStack) -> Option<u8> {
+        if self.kind.is_output(slot) {
+            return None
+        }
+
+        match slot {
+            Slot::Armor(i) if stack.armor_slot() == Some(i) => Some(1),
+            Slot::Armor(_) => None,
+            _ => Some(stack.max_stack_size()),
+        }
+    }
+
+    /// Handles a click on a slot of the window, as sent in `WindowClick`
+    /// packets. A left click picks up or puts down whole stacks, a right click
+    /// splits them, and a shift click moves a stack between the container
+    /// and the inventory of the player.
+    ///
+    /// Returns the items dropped by clicking outside of the window.
+    pub fn click(
+        &mut self,
+        player: &mut PlayerInventory,
+        recipes: &Recipes,
+        index: i16,
+        right: bool,
+        shift: bool,
+    ) -> Option<ItemStack> {
+        if index == OUTSIDE_WINDOW {
+            let cursor = self.cursor.as_mut()?;
+
+            if right && cursor.count > 1 {
+                cursor.count -= 1;
+                return Some(ItemStack { count: 1, ..*cursor })
+            }
+
+            return self.cursor.take()
+        }
+
+        if index < 0 {
+            return None
+        }
+        let index = index as usize;
+        let slot = self.kind.slot(index)?;
+
+        if shift {
+            self.transfer(player, recipes, index, slot);
+        } else if self.kind.is_output(slot) {
+            self.take_output(player, recipes, slot);
+        } else {
+            self.click_slot(player, slot, right);
+        }
+
+        self.update_result(recipes);
+        None
+    }
+
+    fn click_slot(&mut self, player: &mut PlayerInventory, slot: Slot, right: bool) {
+        let mut cursor = self.cursor.take();
+        let limit = cursor.and_then(|c| self.slot_limit(slot, &c));
+        let stack = self.stack_mut(player, slot);
+
+        match (&mut cursor, stack.as_mut()) {
+            (None, None) => {},
+
+            // Picking up the stack, or half of it.
+            (None, Some(s)) => {
+                let taken = if right { s.count.div_ceil(2) } else { s.count };
+                cursor = Some(ItemStack { count: taken, ..*s });
+
+                s.count -= taken;
+                if s.count == 0 {
+                    *stack = None;
+                }
+            },
+
+            // Putting down the stack, or a single item of it.
+            (Some(c), None) => if let Some(limit) = limit {
+                let put = if right { 1 } else { c.count }.min(limit);
+                *stack = Some(ItemStack { count: put, ..*c });
+
+                c.count -= put;
+                if c.count == 0 {
+                    cursor = None;
+                }
+            },
+
+            (Some(c), Some(s)) if c.is_same_item(s) => if let Some(limit) = limit {
+                let put = if right { 1 } else { c.count }.min(limit.saturating_sub(s.count));
+                s.count += put;
+
+                c.count -= put;
+                if c.count == 0 {
+                    cursor = None;
+                }
+            },
+
+            (Some(c), Some(s)) => if limit.is_some_and(|limit| c.count <= limit) {
+                std::mem::swap(c, s);
+            },
+        }
+
+        self.cursor = cursor;
+    }
+
+    /// Takes the crafting result or the output of a furnace into the cursor.
+    fn take_output(&mut self, player: &mut PlayerInventory, recipes: &Recipes, slot: Slot) {
+        let output = match *self.stack_mut(player, slot) {
+            Some(output) => output,
+            None => return,
+        };
+
+        match &mut self.cursor {
+            None => self.cursor = Some(output),
+            Some(c) if c.is_same_item(&output) && c.count + output.count <= c.max_stack_size() => {
+                c.count += output.count;
+            },
+            Some(_) => return,
+        }
+
+        if self.kind.crafting_width().is_some() {
+            self.consume_grid();
+            self.update_result(recipes);
+        } else {
+            *self.stack_mut(player, slot) = None;
+        }
+    }
+
+    fn transfer(&mut self, player: &mut PlayerInventory, recipes: &Recipes, index: usize, slot: Slot) {
+        let range = self.kind.transfer_range(index);
+
+        if self.kind.is_output(slot) && self.kind.crafting_width().is_some() {
+            // Crafting as many times as possible, for as long as the results
+            // fit.
+            while let Some(result) = self.container.get(0).copied() {
+                let mut slots = self.slot_range(player, range);
+
+                let room = slots.iter()
+                    .map(|s| match s.as_ref() {
+                        None => result.max_stack_size() as u32,
+                        Some(s) if s.is_same_item(&result) => result.max_stack_size().saturating_sub(s.count) as u32,
+                        Some(_) => 0,
+                    })
+                    .sum::<u32>();
+
+                if room < result.count as u32 {
+                    break
+                }
+
+                insert_stack(&mut slots, result);
+                self.consume_grid();
+                self.update_result(recipes);
+            }
+
+            return
+        }
+
+        let stack = match self.stack_mut(player, slot).take() {
+            Some(stack) => stack,
+            None => return,
+        };
+
+        let left = insert_stack(&mut self.slot_range(player, range), stack);
+        *self.stack_mut(player, slot) = left;
+    }
+
+    /// Uses up an item from every slot of the crafting grid. Buckets are
+    /// emptied rather than used up.
+    fn consume_grid(&mut self) {
+        for slot in self.container.slots_mut().iter_mut().skip(1) {
+            if let Some(stack) = slot {
+                if stack.count > 1 {
+                    stack.count -= 1;
+                } else if let item_ids::MILK_BUCKET | item_ids::WATER_BUCKET | item_ids::LAVA_BUCKET = stack.id {
+                    *slot = Some(ItemStack::new(item_ids::BUCKET, 1));
+                } else {
+                    *slot = None;
+                }
+            }
+        }
+    }
+
+    /// Shows what the crafting grid makes in the result slot.
+    fn update_result(&mut self, recipes: &Recipes) {
+        if let Some(width) = self.kind.crafting_width() {
+            let result = recipes.find(&self.container.slots()[1..], width);
+            self.container.set(0, result);
+        }
+    }
+
+    /// Closes the window. The items left in the crafting grid and the cursor
+    /// go back to the inventory of the player; returns the ones which do not
+    /// fit, which are dropped.
+    pub fn close(&mut self, player: &mut PlayerInventory) -> Vec<ItemStack> {
+        let mut items = self.cursor.take().into_iter().collect::<Vec<_>>();
+
+        if self.kind.crafting_width().is_some() {
+            self.container.set(0, None);
+            items.extend(self.container.take_all());
+        }
+
+        items.into_iter()
+            .filter_map(|stack| player.add_item(stack))
+            .collect()
+    }
+}
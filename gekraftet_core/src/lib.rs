@@ -1,3 +1,7 @@
+pub mod entity;
+pub mod item;
 pub mod nbt;
+pub mod physics;
+pub mod player;
 pub mod utils;
 pub mod world;
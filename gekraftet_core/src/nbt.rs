@@ -1,12 +1,20 @@
 use std::collections::HashMap;
 use std::convert::TryFrom;
+use std::io::{ Read, Write };
 use std::string::FromUtf8Error;
+use flate2::Compression;
+use flate2::read::{ GzDecoder, ZlibDecoder };
+use flate2::write::{ GzEncoder, ZlibEncoder };
 
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// The named values of a compound tag.
+pub type Compound = HashMap<String, ValueData>;
+
 #[derive(Debug)]
 pub enum Error {
     DepthOver512,
+    Io(std::io::Error),
     InvalidList,
     InvalidTag(u8),
     InvalidUtf8(FromUtf8Error),
@@ -15,12 +23,12 @@ pub enum Error {
     UnexpectedEof,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct NamedBinaryTag {
     root: HashMap<String, ValueData>,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum ValueData {
     Byte(i8),
     Short(i16),
@@ -50,6 +58,75 @@ impl NamedBinaryTag {
         Ok(root)
     }
 
+    pub fn from_compound(root: Compound) -> Self {
+        Self {
+            root
+        }
+    }
+
+    /// Parses a gzip-compressed tag, which is how most NBT files are stored.
+    pub fn from_gzip(value: &[u8]) -> Result<Self> {
+        let mut bytes = Vec::new();
+        GzDecoder::new(value).read_to_end(&mut bytes).map_err(Error::Io)?;
+        Self::from_binary(&bytes)
+    }
+
+    /// Parses a zlib-compressed tag, as found in the chunks of region files.
+    pub fn from_zlib(value: &[u8]) -> Result<Self> {
+        let mut bytes = Vec::new();
+        ZlibDecoder::new(value).read_to_end(&mut bytes).map_err(Error::Io)?;
+        Self::from_binary(&bytes)
+    }
+
+    /// The named tags at the root of the file. Usually there is only one,
+    /// a compound holding everything else.
+    pub fn root(&self) -> &Compound {
+        &self.root
+    }
+
+    pub fn root_mut(&mut self) -> &mut Compound {
+        &mut self.root
+    }
+
+    pub fn into_root(self) -> Compound {
+        self.root
+    }
+
+    pub fn get(&self, name: &str) -> Option<&ValueData> {
+        self.root.get(name)
+    }
+
+    pub fn insert<S: Into<String>>(&mut self, name: S, value: ValueData) -> Option<ValueData> {
+        self.root.insert(name.into(), value)
+    }
+
+    pub fn to_binary(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+
+        // The root is not wrapped in a compound tag of its own, so it has
+        // no end tag either.
+        for (name, value) in sorted(&self.root) {
+            value.write_named(name, &mut bytes);
+        }
+
+        bytes
+    }
+
+    pub fn to_gzip(&self) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&self.to_binary())
+            .and_then(|_| encoder.finish())
+            .expect("writing into a Vec never fails")
+    }
+
+    /// Compresses the tag with zlib, as the chunks of region files are.
+    pub fn to_zlib(&self) -> Vec<u8> {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&self.to_binary())
+            .and_then(|_| encoder.finish())
+            .expect("writing into a Vec never fails")
+    }
+
     fn parse_byte(bytes: &[u8]) -> Result<(usize, ValueData)> {
         let result = bytes.get(0).ok_or(Error::UnexpectedEof)?;
         Ok((1, ValueData::Byte(*result as i8)))
@@ -64,44 +141,44 @@ impl NamedBinaryTag {
     fn parse_int(bytes: &[u8]) -> Result<(usize, ValueData)> {
         let result = bytes.get(0..4).ok_or(Error::UnexpectedEof)?;
         let int = i32::from_be_bytes(<[u8; 4]>::try_from(result).unwrap());
-        Ok((2, ValueData::Int(int)))
+        Ok((4, ValueData::Int(int)))
     }
 
     fn parse_long(bytes: &[u8]) -> Result<(usize, ValueData)> {
         let result = bytes.get(0..8).ok_or(Error::UnexpectedEof)?;
         let long = i64::from_be_bytes(<[u8; 8]>::try_from(result).unwrap());
-        Ok((2, ValueData::Long(long)))
+        Ok((8, ValueData::Long(long)))
     }
 
     fn parse_float(bytes: &[u8]) -> Result<(usize, ValueData)> {
         let result = bytes.get(0..4).ok_or(Error::UnexpectedEof)?;
         let float = f32::from_be_bytes(<[u8; 4]>::try_from(result).unwrap());
-        Ok((2, ValueData::Float(float)))
+        Ok((4, ValueData::Float(float)))
     }
     
     fn parse_double(bytes: &[u8]) -> Result<(usize, ValueData)> {
         let result = bytes.get(0..8).ok_or(Error::UnexpectedEof)?;
         let double = f64::from_be_bytes(<[u8; 8]>::try_from(result).unwrap());
-        Ok((2, ValueData::Double(double)))
+        Ok((8, ValueData::Double(double)))
     }
 
     fn parse_string(bytes: &[u8]) -> Result<(usize, ValueData)> {
         let len = bytes.get(0..2).ok_or(Error::UnexpectedEof)?;
         let len = u16::from_be_bytes([len[0], len[1]]) as usize;
         
-        let str_bytes = bytes.get(3..3 + len).ok_or(Error::UnexpectedEof)?;
+        let str_bytes = bytes.get(2..2 + len).ok_or(Error::UnexpectedEof)?;
         let result = String::from_utf8(Vec::from(str_bytes))
             .map(|s| ValueData::Str(s))
             .map_err(|err| Error::InvalidUtf8(err))?;
         
-        Ok((len, result))
+        Ok((2 + len, result))
     }
 
     fn parse_byte_array(bytes: &[u8]) -> Result<(usize, ValueData)> {
         let len = bytes.get(0..4).ok_or(Error::UnexpectedEof)?;
         let len = u32::from_be_bytes(<[u8; 4]>::try_from(len).unwrap()) as usize;
         
-        let bytes = bytes.get(3..3 + len).ok_or(Error::UnexpectedEof)?;
+        let bytes = bytes.get(4..4 + len).ok_or(Error::UnexpectedEof)?;
         let result = unsafe {
             let bytes = bytes.as_ptr() as *const i8;
             let i8_bytes = std::slice::from_raw_parts(bytes, len);
@@ -109,7 +186,7 @@ impl NamedBinaryTag {
         };
 
         
-        Ok((len, result))
+        Ok((4 + len, result))
     }
 
     fn parse_list(bytes: &[u8], depth: u16) -> Result<(usize, ValueData)> {
@@ -118,17 +195,19 @@ impl NamedBinaryTag {
         let len = u32::from_be_bytes(<[u8; 4]>::try_from(len).unwrap()) as usize;
         
         let mut offset = 0;
-        let mut values = Vec::with_capacity(len);
-        for i in 0..len {
+        // The length is not trusted for the allocation, every element is at
+        // least a byte long anyway.
+        let mut values = Vec::with_capacity(len.min(bytes.len()));
+        for _ in 0..len {
             let (len, data) = match tag_id {
                 0 => Err(Error::InvalidList)?,
                 
-                1 => Self::parse_byte(&bytes[5 + i..])?,
-                2 => Self::parse_short(&bytes[5 + i * 2..])?,
-                3 => Self::parse_int(&bytes[5 + i * 4..])?,
-                4 => Self::parse_long(&bytes[5 + i * 8..])?,
-                5 => Self::parse_float(&bytes[5 + i * 4..])?,
-                6 => Self::parse_double(&bytes[5 + i * 8..])?,
+                1 => Self::parse_byte(&bytes[5 + offset..])?,
+                2 => Self::parse_short(&bytes[5 + offset..])?,
+                3 => Self::parse_int(&bytes[5 + offset..])?,
+                4 => Self::parse_long(&bytes[5 + offset..])?,
+                5 => Self::parse_float(&bytes[5 + offset..])?,
+                6 => Self::parse_double(&bytes[5 + offset..])?,
                 7 => Self::parse_byte_array(&bytes[5 + offset..])?,
                 8 => Self::parse_string(&bytes[5 + offset..])?,
                 9 => Self::parse_list(&bytes[5 + offset..], depth + 1)?,
@@ -185,7 +264,7 @@ impl NamedBinaryTag {
             offset += len;
             
             match result.insert(key, value) {
-                Some(x) => Err(Error::TagsOfSameName)?,
+                Some(_) => Err(Error::TagsOfSameName)?,
                 None => {},
             };
         };
@@ -194,6 +273,14 @@ impl NamedBinaryTag {
     }
 }
 
+// The tags of a compound are written ordered by name, so that saving the same
+// data always gives the same file.
+fn sorted(compound: &Compound) -> Vec<(&String, &ValueData)> {
+    let mut tags = compound.iter().collect::<Vec<_>>();
+    tags.sort_by(|a, b| a.0.cmp(b.0));
+    tags
+}
+
 macro_rules! impl_getter {
     ($func:ident, $variant:path, $ret:ty) => {
         pub fn $func(&self) -> Option<&$ret> {
@@ -224,6 +311,64 @@ macro_rules! impl_getter {
 }
 
 impl ValueData {
+    /// The id of the tag type of this value.
+    pub fn tag_id(&self) -> u8 {
+        match self {
+            Self::Byte(_) => 1,
+            Self::Short(_) => 2,
+            Self::Int(_) => 3,
+            Self::Long(_) => 4,
+            Self::Float(_) => 5,
+            Self::Double(_) => 6,
+            Self::ByteArray(_) => 7,
+            Self::Str(_) => 8,
+            Self::List(..) => 9,
+            Self::Compound(_) => 10,
+        }
+    }
+
+    fn write_string(string: &str, bytes: &mut Vec<u8>) {
+        // Strings longer than the length prefix allows are cut off.
+        let len = string.len().min(u16::MAX as usize);
+        bytes.extend_from_slice(&(len as u16).to_be_bytes());
+        bytes.extend_from_slice(&string.as_bytes()[..len]);
+    }
+
+    fn write_named(&self, name: &str, bytes: &mut Vec<u8>) {
+        bytes.push(self.tag_id());
+        Self::write_string(name, bytes);
+        self.write_payload(bytes);
+    }
+
+    fn write_payload(&self, bytes: &mut Vec<u8>) {
+        match self {
+            Self::Byte(x) => bytes.push(*x as u8),
+            Self::Short(x) => bytes.extend_from_slice(&x.to_be_bytes()),
+            Self::Int(x) => bytes.extend_from_slice(&x.to_be_bytes()),
+            Self::Long(x) => bytes.extend_from_slice(&x.to_be_bytes()),
+            Self::Float(x) => bytes.extend_from_slice(&x.to_be_bytes()),
+            Self::Double(x) => bytes.extend_from_slice(&x.to_be_bytes()),
+            Self::ByteArray(x) => {
+                bytes.extend_from_slice(&(x.len() as u32).to_be_bytes());
+                bytes.extend(x.iter().map(|&b| b as u8));
+            },
+            Self::Str(x) => Self::write_string(x, bytes),
+            Self::List(tag, values) => {
+                bytes.push(*tag);
+                bytes.extend_from_slice(&(values.len() as u32).to_be_bytes());
+                for value in values.iter() {
+                    value.write_payload(bytes);
+                }
+            },
+            Self::Compound(values) => {
+                for (name, value) in sorted(values) {
+                    value.write_named(name, bytes);
+                }
+                bytes.push(0);
+            },
+        }
+    }
+
     impl_getter!(byte, Self::Byte, i8);
     impl_getter!(short, Self::Short, i16);
     impl_getter!(int, Self::Int, i32);
@@ -275,3 +420,65 @@ impl ValueData {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A tag with a value of every type, nested in lists and compounds.
+    fn every_type() -> NamedBinaryTag {
+        let mut inner = Compound::new();
+        inner.insert("byte".to_string(), ValueData::Byte(-128));
+        inner.insert("short".to_string(), ValueData::Short(-12345));
+        inner.insert("int".to_string(), ValueData::Int(i32::MIN));
+        inner.insert("long".to_string(), ValueData::Long(1 << 40));
+        inner.insert("float".to_string(), ValueData::Float(0.15625));
+        inner.insert("double".to_string(), ValueData::Double(-1.0e100));
+        inner.insert("bytes".to_string(), ValueData::ByteArray((-128..=127).collect()));
+        inner.insert("string".to_string(), ValueData::Str("Grüße".to_string()));
+        inner.insert("empty".to_string(), ValueData::List(0, Vec::new()));
+        inner.insert("lists".to_string(), ValueData::List(9, vec![
+            ValueData::List(3, vec![ValueData::Int(1), ValueData::Int(2)]),
+            ValueData::List(8, vec![ValueData::Str(String::new())]),
+        ]));
+
+        let mut root = Compound::new();
+        root.insert("Level".to_string(), ValueData::Compound(inner.clone()));
+        root.insert("Items".to_string(), ValueData::List(10, vec![
+            ValueData::Compound(inner),
+            ValueData::Compound(Compound::new()),
+        ]));
+
+        let mut tag = NamedBinaryTag::new();
+        tag.insert("", ValueData::Compound(root));
+        tag
+    }
+
+    #[test]
+    fn written_tags_read_the_same() {
+        let tag = every_type();
+        assert_eq!(NamedBinaryTag::from_binary(&tag.to_binary()).unwrap(), tag);
+        assert_eq!(NamedBinaryTag::from_gzip(&tag.to_gzip()).unwrap(), tag);
+        assert_eq!(NamedBinaryTag::from_zlib(&tag.to_zlib()).unwrap(), tag);
+    }
+
+    // The smallest example of the NBT specification.
+    #[test]
+    fn written_tags_follow_the_specification() {
+        let mut compound = Compound::new();
+        compound.insert("name".to_string(), ValueData::Str("Bananrama".to_string()));
+        let mut tag = NamedBinaryTag::new();
+        tag.insert("hello world", ValueData::Compound(compound));
+
+        let mut expected = vec![10, 0, 11];
+        expected.extend_from_slice(b"hello world");
+        expected.extend_from_slice(&[8, 0, 4]);
+        expected.extend_from_slice(b"name");
+        expected.extend_from_slice(&[0, 9]);
+        expected.extend_from_slice(b"Bananrama");
+        expected.push(0);
+
+        assert_eq!(tag.to_binary(), expected);
+        assert_eq!(NamedBinaryTag::from_binary(&expected).unwrap(), tag);
+    }
+}
@@ -0,0 +1,128 @@
+use cgmath::{ Point3, Vector3 };
+
+/// An axis-aligned bounding box.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+    pub min: Point3<f64>,
+    pub max: Point3<f64>,
+}
+
+impl Aabb {
+    /// Creates the smallest box containing both points.
+    pub fn new(a: Point3<f64>, b: Point3<f64>) -> Self {
+        Self {
+            min: Point3::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z)),
+            max: Point3::new(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z)),
+        }
+    }
+
+    /// Creates the box of an entity standing at `feet`, which is the center
+    /// of the bottom of the box.
+    pub fn around(feet: Point3<f64>, width: f64, height: f64) -> Self {
+        let half = width / 2.0;
+
+        Self {
+            min: Point3::new(feet.x - half, feet.y, feet.z - half),
+            max: Point3::new(feet.x + half, feet.y + height, feet.z + half),
+        }
+    }
+
+    pub fn offset(&self, by: Vector3<f64>) -> Self {
+        Self {
+            min: self.min + by,
+            max: self.max + by,
+        }
+    }
+
+    /// Extends the box towards the given direction, so that it contains
+    /// everything it touches while moving by `by`.
+    pub fn expand_towards(&self, by: Vector3<f64>) -> Self {
+        let mut result = *self;
+
+        if by.x < 0.0 { result.min.x += by.x } else { result.max.x += by.x }
+        if by.y < 0.0 { result.min.y += by.y } else { result.max.y += by.y }
+        if by.z < 0.0 { result.min.z += by.z } else { result.max.z += by.z }
+
+        result
+    }
+
+    /// Grows the box by `by` on every side.
+    pub fn inflate(&self, by: f64) -> Self {
+        let by = Vector3::new(by, by, by);
+
+        Self {
+            min: self.min - by,
+            max: self.max + by,
+        }
+    }
+
+    /// Whether the boxes overlap. Boxes which only touch do not.
+    pub fn intersects(&self, other: &Self) -> bool {
+        self.min.x < other.max.x && other.min.x < self.max.x
+            && self.min.y < other.max.y && other.min.y < self.max.y
+            && self.min.z < other.max.z && other.min.z < self.max.z
+    }
+
+    pub fn contains(&self, point: Point3<f64>) -> bool {
+        self.min.x < point.x && point.x < self.max.x
+            && self.min.y < point.y && point.y < self.max.y
+            && self.min.z < point.z && point.z < self.max.z
+    }
+
+    pub fn center(&self) -> Point3<f64> {
+        Point3::new(
+            (self.min.x + self.max.x) / 2.0,
+            (self.min.y + self.max.y) / 2.0,
+            (self.min.z + self.max.z) / 2.0,
+        )
+    }
+
+    /// Shortens a movement of `moving` along the x axis by `dx`, so that it
+    /// stops when touching this box.
+    pub fn clip_x(&self, moving: &Self, dx: f64) -> f64 {
+        let overlaps = moving.max.y > self.min.y && moving.min.y < self.max.y
+            && moving.max.z > self.min.z && moving.min.z < self.max.z;
+
+        if !overlaps {
+            dx
+        } else if dx > 0.0 && moving.max.x <= self.min.x {
+            dx.min(self.min.x - moving.max.x)
+        } else if dx < 0.0 && moving.min.x >= self.max.x {
+            dx.max(self.max.x - moving.min.x)
+        } else {
+            dx
+        }
+    }
+
+    /// Like `clip_x`, along the y axis.
+    pub fn clip_y(&self, moving: &Self, dy: f64) -> f64 {
+        let overlaps = moving.max.x > self.min.x && moving.min.x < self.max.x
+            && moving.max.z > self.min.z && moving.min.z < self.max.z;
+
+        if !overlaps {
+            dy
+        } else if dy > 0.0 && moving.max.y <= self.min.y {
+            dy.min(self.min.y - moving.max.y)
+        } else if dy < 0.0 && moving.min.y >= self.max.y {
+            dy.max(self.max.y - moving.min.y)
+        } else {
+            dy
+        }
+    }
+
+    /// Like `clip_x`, along the z axis.
+    pub fn clip_z(&self, moving: &Self, dz: f64) -> f64 {
+        let overlaps = moving.max.x > self.min.x && moving.min.x < self.max.x
+            && moving.max.y > self.min.y && moving.min.y < self.max.y;
+
+        if !overlaps {
+            dz
+        } else if dz > 0.0 && moving.max.z <= self.min.z {
+            dz.min(self.min.z - moving.max.z)
+        } else if dz < 0.0 && moving.min.z >= self.max.z {
+            dz.max(self.max.z - moving.min.z)
+        } else {
+            dz
+        }
+    }
+}
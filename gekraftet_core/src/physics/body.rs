@@ -0,0 +1,231 @@
+use cgmath::{ Point3, Vector3 };
+use crate::world::{ block_ids, BlockPos, Level };
+use super::*;
+
+/// The physical state of an entity: where it is, how it moves and how it
+/// collides with the blocks. The position is the center of the bottom of its
+/// bounding box, at the feet of the entity.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Body {
+    pub position: Point3<f64>,
+    /// The distance moved in the next tick.
+    pub velocity: Vector3<f64>,
+
+    pub width: f64,
+    pub height: f64,
+    /// The tallest obstacle the entity walks onto without jumping.
+    pub step_height: f64,
+
+    pub on_ground: bool,
+    pub collided_horizontally: bool,
+    pub collided_vertically: bool,
+    /// How far the entity has fallen since it last stood on the ground.
+    pub fall_distance: f64,
+}
+
+/// Returns the collision boxes of every solid block overlapping `area`.
+/// Blocks in chunks which are not loaded do not collide.
+fn collision_boxes(level: &Level, area: &Aabb) -> Vec<Aabb> {
+    let mut result = Vec::new();
+
+    // Fences are taller than a block, so the blocks right below the area are
+    // included as well.
+    let (min_x, max_x) = (area.min.x.floor() as i32, area.max.x.floor() as i32);
+    let (min_y, max_y) = (area.min.y.floor() as i32 - 1, area.max.y.floor() as i32);
+    let (min_z, max_z) = (area.min.z.floor() as i32, area.max.z.floor() as i32);
+
+    for x in min_x..=max_x {
+        for y in min_y..=max_y {
+            for z in min_z..=max_z {
+                let info = match level.block(BlockPos::new(x, y, z)) {
+                    Some(block) if block.info().solid => block.info(),
+                    _ => continue,
+                };
+
+                let (x, y, z) = (x as f64, y as f64, z as f64);
+                let block = Aabb::new(
+                    Point3::new(x, y, z),
+                    Point3::new(x + 1.0, y + info.height, z + 1.0),
+                );
+
+                if block.intersects(area) {
+                    result.push(block);
+                }
+            }
+        }
+    }
+
+    result
+}
+
+/// Moves `aabb` by `delta`, stopping it at the boxes in its way. The axes are
+/// moved one at a time: first y, then x, then z.
+fn sweep(boxes: &[Aabb], mut aabb: Aabb, delta: Vector3<f64>) -> (Aabb, Vector3<f64>) {
+    let mut delta = delta;
+
+    for b in boxes.iter() {
+        delta.y = b.clip_y(&aabb, delta.y);
+    }
+    aabb = aabb.offset(Vector3::new(0.0, delta.y, 0.0));
+
+    for b in boxes.iter() {
+        delta.x = b.clip_x(&aabb, delta.x);
+    }
+    aabb = aabb.offset(Vector3::new(delta.x, 0.0, 0.0));
+
+    for b in boxes.iter() {
+        delta.z = b.clip_z(&aabb, delta.z);
+    }
+    aabb = aabb.offset(Vector3::new(0.0, 0.0, delta.z));
+
+    (aabb, delta)
+}
+
+impl Body {
+    pub fn new(position: Point3<f64>, width: f64, height: f64) -> Self {
+        Self {
+            position,
+            velocity: Vector3::new(0.0, 0.0, 0.0),
+            width,
+            height,
+            step_height: 0.0,
+            on_ground: false,
+            collided_horizontally: false,
+            collided_vertically: false,
+            fall_distance: 0.0,
+        }
+    }
+
+    /// Creates the body of a player standing at `position`.
+    pub fn player(position: Point3<f64>) -> Self {
+        let mut result = Self::new(position, PLAYER_WIDTH, PLAYER_HEIGHT);
+        result.step_height = PLAYER_STEP_HEIGHT;
+        result
+    }
+
+    pub fn aabb(&self) -> Aabb {
+        Aabb::around(self.position, self.width, self.height)
+    }
+
+    /// Moves the body by `delta`, sliding along the blocks in the way and
+    /// stepping up onto the ones which are low enough. The velocity along the
+    /// axes where the body collided is cancelled.
+    ///
+    /// If the body lands on the ground, returns the distance it has fallen.
+    pub fn move_by(&mut self, level: &Level, delta: Vector3<f64>) -> Option<f64> {
+        let start = self.aabb();
+        let boxes = collision_boxes(level, &start.expand_towards(delta));
+        let (mut aabb, mut moved) = sweep(&boxes, start, delta);
+
+        let was_on_ground = self.on_ground || (moved.y != delta.y && delta.y < 0.0);
+        let blocked = moved.x != delta.x || moved.z != delta.z;
+
+        if self.step_height > 0.0 && was_on_ground && blocked {
+            // Try again from a bit higher, and then go back down as far as
+            // possible. The step is only taken if it goes further.
+            let up = Vector3::new(delta.x, self.step_height, delta.z);
+            let boxes = collision_boxes(level, &start.expand_towards(up));
+            let (stepped, mut stepped_moved) = sweep(&boxes, start, up);
+
+            let mut down = -self.step_height;
+            for b in boxes.iter() {
+                down = b.clip_y(&stepped, down);
+            }
+            let stepped = stepped.offset(Vector3::new(0.0, down, 0.0));
+            stepped_moved.y += down;
+
+            let horizontal = |v: Vector3<f64>| v.x * v.x + v.z * v.z;
+            if horizontal(stepped_moved) > horizontal(moved) {
+                aabb = stepped;
+                moved = stepped_moved;
+            }
+        }
+
+        self.position = Point3::new(
+            (aabb.min.x + aabb.max.x) / 2.0,
+            aabb.min.y,
+            (aabb.min.z + aabb.max.z) / 2.0,
+        );
+
+        self.collided_horizontally = moved.x != delta.x || moved.z != delta.z;
+        self.collided_vertically = moved.y != delta.y;
+        self.on_ground = self.collided_vertically && delta.y < 0.0;
+
+        let mut landed = None;
+        if self.on_ground {
+            landed = Some(self.fall_distance).filter(|&d| d > 0.0);
+            self.fall_distance = 0.0;
+        } else if moved.y < 0.0 {
+            self.fall_distance -= moved.y;
+        }
+
+        if moved.x != delta.x {
+            self.velocity.x = 0.0;
+        }
+        if moved.y != delta.y {
+            self.velocity.y = 0.0;
+        }
+        if moved.z != delta.z {
+            self.velocity.z = 0.0;
+        }
+
+        landed
+    }
+
+    /// How slippery the block the body stands on is.
+    fn slipperiness(&self, level: &Level) -> f64 {
+        let below = BlockPos::new(
+            self.position.x.floor() as i32,
+            self.position.y.floor() as i32 - 1,
+            self.position.z.floor() as i32,
+        );
+
+        match level.block(below) {
+            Some(block) if block.id == block_ids::ICE => ICE_SLIPPERINESS,
+            _ => BLOCK_SLIPPERINESS,
+        }
+    }
+
+    /// Advances the body by a tick, without any input: it moves, falls and
+    /// slows down. Returns the distance fallen if the body landed.
+    pub fn tick(&mut self, level: &Level) -> Option<f64> {
+        self.tick_with_input(level, 0.0, 0.0, 0.0)
+    }
+
+    /// Advances the body by a tick, accelerating it towards the input. The
+    /// input is between -1 and 1 along both axes, and relative to the yaw of
+    /// the entity (in degrees).
+    pub fn tick_with_input(&mut self, level: &Level, strafe: f64, forward: f64, yaw: f32) -> Option<f64> {
+        let friction = if self.on_ground {
+            self.slipperiness(level) * AIR_FRICTION
+        } else {
+            AIR_FRICTION
+        };
+
+        // Entities accelerate faster on the ground, less so on slippery
+        // blocks, so that their top speed stays the same everywhere.
+        let acceleration = if self.on_ground {
+            0.1 * (0.1627714 / (friction * friction * friction))
+        } else {
+            0.02
+        };
+
+        let length = (strafe * strafe + forward * forward).sqrt();
+        if length >= 0.01 {
+            let scale = acceleration / length.max(1.0);
+            let (strafe, forward) = (strafe * scale, forward * scale);
+            let (sin, cos) = (yaw as f64).to_radians().sin_cos();
+
+            self.velocity.x += strafe * cos - forward * sin;
+            self.velocity.z += forward * cos + strafe * sin;
+        }
+
+        let landed = self.move_by(level, self.velocity);
+
+        self.velocity.y = (self.velocity.y - GRAVITY) * VERTICAL_DRAG;
+        self.velocity.x *= friction;
+        self.velocity.z *= friction;
+
+        landed
+    }
+}
@@ -0,0 +1,19 @@
+mod aabb;
+mod body;
+
+pub use aabb::Aabb;
+pub use body::*;
+
+// The constants of the movement of living entities, per tick.
+pub const GRAVITY: f64 = 0.08;
+pub const VERTICAL_DRAG: f64 = 0.98;
+pub const AIR_FRICTION: f64 = 0.91;
+// How slippery most blocks are. Ice is more slippery (0.98).
+pub const BLOCK_SLIPPERINESS: f64 = 0.6;
+pub const ICE_SLIPPERINESS: f64 = 0.98;
+
+// The size of the player, in blocks.
+pub const PLAYER_WIDTH: f64 = 0.6;
+pub const PLAYER_HEIGHT: f64 = 1.8;
+pub const PLAYER_EYE_HEIGHT: f64 = 1.62;
+pub const PLAYER_STEP_HEIGHT: f64 = 0.5;
@@ -0,0 +1,645 @@
+use std::path::{ Path, PathBuf };
+use cgmath::{ InnerSpace, Point3, Vector3 };
+use crate::entity::{ EntityId, ATTACK_REACH };
+use crate::item::{ ItemStack, PlayerInventory };
+use crate::nbt::{ self, Compound, NamedBinaryTag, ValueData };
+use crate::physics::{ Body, PLAYER_EYE_HEIGHT };
+use crate::world::{ block_ids, BlockPos, Dimension, Level, WorldGenerator };
+
+pub const MAX_HEALTH: i16 = 20;
+pub const MAX_AIR: i16 = 300;
+/// The ticks during which a player who was just hurt is not hurt again.
+pub const HURT_TIME: i16 = 10;
+/// The ticks a dead player lies on the ground for.
+pub const DEATH_TIME: i16 = 20;
+/// The distance a player falls without getting hurt.
+pub const SAFE_FALL_DISTANCE: f64 = 3.0;
+/// The ticks a player stands in a portal before it goes through.
+pub const PORTAL_TIME: i16 = 80;
+/// The longest name a player can have.
+pub const MAX_USERNAME_LENGTH: usize = 16;
+/// How far from where a player could move its client may say it is, before
+/// the client is told where the player really is.
+pub const MOVE_TOLERANCE: f64 = 0.25;
+/// The farthest a player moves at once. Clients which say their player went
+/// farther are told it did not move.
+pub const MAX_MOVE_DISTANCE: f64 = 100.0;
+/// The damage dealt by lava to a player in it.
+pub const LAVA_DAMAGE: i16 = 4;
+/// The damage dealt to a player who ran out of breath under water.
+pub const DROWNING_DAMAGE: i16 = 2;
+
+// The ticks a player burns for after touching lava or fire.
+const LAVA_FIRE: i16 = 600;
+const BLOCK_FIRE: i16 = 300;
+// The damage dealt by the void to a player falling into it.
+const VOID_DAMAGE: i16 = 4;
+// Players are hurt by the void this far below the world.
+const VOID_HEIGHT: f64 = -64.0;
+// The ticks spent in a portal which are forgotten for every tick spent out of
+// it.
+const PORTAL_DECAY: i16 = 4;
+
+#[derive(Debug)]
+pub enum PlayerError {
+    Io(std::io::Error),
+    Nbt(nbt::Error),
+    MissingTag(&'static str),
+    InvalidTag(&'static str),
+    /// The name of the player is not a valid username, see
+    /// `is_valid_username`.
+    InvalidUsername(String),
+}
+
+/// Whether a player can be named so: with 1 to 16 letters, digits or
+/// underscores. The player files are named after the players, so any other
+/// name could be a path out of the `players` directory.
+pub fn is_valid_username(username: &str) -> bool {
+    (1..=MAX_USERNAME_LENGTH).contains(&username.len())
+        && username.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_')
+}
+
+/// What hurt a player.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DamageSource {
+    Fall,
+    Drowning,
+    Lava,
+    Fire,
+    Void,
+    Explosion,
+    /// A mob hit the player, or shot it with an arrow.
+    Mob(EntityId),
+    /// Another player hit the player.
+    Player(EntityId),
+}
+
+impl DamageSource {
+    /// Whether armor protects from the damage. It only does from being hit
+    /// and from explosions.
+    pub fn is_blocked_by_armor(self) -> bool {
+        matches!(self, Self::Explosion | Self::Mob(_) | Self::Player(_))
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum GameMode {
+    Survival,
+    Creative,
+}
+
+impl GameMode {
+    pub fn id(self) -> i32 {
+        match self {
+            Self::Survival => 0,
+            Self::Creative => 1,
+        }
+    }
+
+    pub fn from_id(id: i32) -> Option<Self> {
+        match id {
+            0 => Some(Self::Survival),
+            1 => Some(Self::Creative),
+            _ => None,
+        }
+    }
+}
+
+/// A player, along with everything which is saved in its player file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Player {
+    pub username: String,
+    /// The id of the player entity, which only lasts until the player
+    /// disconnects.
+    pub entity_id: i32,
+
+    /// The position of the feet, the velocity and the collision state.
+    pub body: Body,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub dimension: Dimension,
+
+    pub health: i16,
+    /// The ticks of breath left under water.
+    pub air: i16,
+    /// The ticks left to burn, or if negative, the ticks left during which
+    /// the player does not catch fire.
+    pub fire: i16,
+    pub hurt_time: i16,
+    pub death_time: i16,
+    pub attack_time: i16,
+    /// The ticks the player has stood in a portal for, which are not saved.
+    pub portal_time: i16,
+
+    pub sleeping: bool,
+    pub sleep_timer: i16,
+    /// Where the player respawns, if they have slept in a bed.
+    pub spawn: Option<BlockPos>,
+
+    pub game_mode: GameMode,
+    pub inventory: PlayerInventory,
+}
+
+// Player files are laid out as follows:
+//
+//     "" (compound)
+//         Pos (list of 3 doubles)          x, the height of the eyes, z
+//         Motion (list of 3 doubles)
+//         Rotation (list of 2 floats)      yaw, pitch
+//         FallDistance (float)
+//         OnGround (byte)
+//         Dimension (int)
+//         Health, Air, Fire (short)
+//         HurtTime, DeathTime, AttackTime (short)
+//         Sleeping (byte), SleepTimer (short)
+//         SpawnX, SpawnY, SpawnZ (int)     only if the player has a bed
+//         Inventory (list of compounds)    see `PlayerInventory::to_nbt`
+//         playerGameType (int)
+//
+// The player files are found in the `players` directory of the world, and
+// named after the player.
+
+fn doubles(values: &[f64]) -> ValueData {
+    ValueData::List(6, values.iter().map(|&v| ValueData::Double(v)).collect())
+}
+
+fn floats(values: &[f32]) -> ValueData {
+    ValueData::List(5, values.iter().map(|&v| ValueData::Float(v)).collect())
+}
+
+impl Player {
+    pub fn new<S: Into<String>>(username: S, entity_id: i32, position: Point3<f64>) -> Self {
+        Self {
+            username: username.into(),
+            entity_id,
+            body: Body::player(position),
+            yaw: 0.0,
+            pitch: 0.0,
+            dimension: Dimension::Overworld,
+            health: MAX_HEALTH,
+            air: MAX_AIR,
+            fire: -20,
+            hurt_time: 0,
+            death_time: 0,
+            attack_time: 0,
+            portal_time: 0,
+            sleeping: false,
+            sleep_timer: 0,
+            spawn: None,
+            game_mode: GameMode::Survival,
+            inventory: PlayerInventory::new(),
+        }
+    }
+
+    /// The position of the feet of the player.
+    pub fn position(&self) -> Point3<f64> {
+        self.body.position
+    }
+
+    /// The height of the eyes of the player, which is sent along with the
+    /// position in the movement packets.
+    pub fn stance(&self) -> f64 {
+        self.body.position.y + PLAYER_EYE_HEIGHT
+    }
+
+    pub fn eye_position(&self) -> Point3<f64> {
+        let feet = self.body.position;
+        Point3::new(feet.x, self.stance(), feet.z)
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.health <= 0
+    }
+
+    /// Takes `damage` away from the health of the player, less what the
+    /// armor blocks. Players in creative mode are only hurt by the void, and
+    /// players who were just hurt are not hurt again for a while. Returns
+    /// whether the player was hurt.
+    pub fn hurt(&mut self, damage: i16, source: DamageSource) -> bool {
+        let creative = self.game_mode == GameMode::Creative && source != DamageSource::Void;
+        if self.is_dead() || creative || self.hurt_time > 0 || damage <= 0 {
+            return false
+        }
+
+        let mut damage = damage;
+        if source.is_blocked_by_armor() {
+            let armor = self.inventory.armor_value();
+            self.inventory.wear_armor(damage);
+            damage = damage * (25 - armor) / 25;
+        }
+
+        self.health = (self.health - damage).max(0);
+        self.hurt_time = HURT_TIME;
+        true
+    }
+
+    /// The damage dealt by a hit of the player, depending on what it holds.
+    /// The tool or sword held wears out.
+    pub fn strike(&mut self) -> i16 {
+        use crate::item::item_ids::*;
+
+        let held = self.inventory.held_item_mut();
+        let damage = held.map_or(1, |stack| stack.attack_damage());
+
+        // Swords are made for fighting, and wear out slower than tools.
+        let wear = match held.map(|stack| stack.id) {
+            Some(WOODEN_SWORD) | Some(STONE_SWORD) | Some(IRON_SWORD) |
+            Some(DIAMOND_SWORD) | Some(GOLDEN_SWORD) => 1,
+            _ => 2,
+        };
+        if held.as_mut().is_some_and(|stack| stack.wear(wear)) {
+            *held = None;
+        }
+
+        damage
+    }
+
+    /// Hits another player, such as when it left-clicks them. Only living
+    /// players within reach can be hit, and not again while they are still
+    /// hurt. Returns whether the other player was hurt.
+    pub fn attack(&mut self, target: &mut Player) -> bool {
+        let distance = (target.body.position - self.body.position).magnitude();
+        if self.is_dead() || target.is_dead() || target.hurt_time > 0 || distance > ATTACK_REACH {
+            return false
+        }
+
+        let damage = self.strike();
+        target.hurt(damage, DamageSource::Player(self.entity_id))
+    }
+
+    /// Moves the player towards where its client says it is, as far as the
+    /// blocks in the way let it. Players who land after falling too far are
+    /// hurt, unless they land in water.
+    ///
+    /// Returns false if the player ended up farther than `MOVE_TOLERANCE`
+    /// from where the client said, in which case the client must be told
+    /// where the player is.
+    pub fn move_to(&mut self, level: &Level, position: Point3<f64>, on_ground: bool) -> bool {
+        let from = self.body.position;
+        let delta = position - from;
+
+        // The body keeps track of the distance fallen on its own, but the
+        // client knows better when the player lands.
+        let fall_distance = self.body.fall_distance;
+        if delta.magnitude() <= MAX_MOVE_DISTANCE {
+            self.body.move_by(level, delta);
+        }
+        self.body.fall_distance = fall_distance;
+        self.body.on_ground = on_ground;
+
+        let fallen = from.y - self.body.position.y;
+        let moved = (self.body.position - position).magnitude() <= MOVE_TOLERANCE;
+
+        if self.is_in(level, &[block_ids::FLOWING_WATER, block_ids::WATER]) {
+            self.body.fall_distance = 0.0;
+        } else if fallen > 0.0 {
+            self.body.fall_distance += fallen;
+        }
+
+        if on_ground {
+            let fall = std::mem::replace(&mut self.body.fall_distance, 0.0);
+            let damage = (fall - SAFE_FALL_DISTANCE).ceil();
+            if damage > 0.0 {
+                self.hurt(damage as i16, DamageSource::Fall);
+            }
+        }
+
+        moved
+    }
+
+    /// Whether the block at the feet or at the eyes of the player is one of
+    /// `ids`.
+    fn is_in(&self, level: &Level, ids: &[u16]) -> bool {
+        let feet = self.body.position;
+        [feet.y, self.stance()].iter().any(|&y| {
+            let at = BlockPos::new(feet.x.floor() as i32, y.floor() as i32, feet.z.floor() as i32);
+            level.block(at).is_some_and(|b| ids.contains(&b.id))
+        })
+    }
+
+    /// Advances the player by a tick, as far as the server is concerned: it
+    /// runs out of breath under water, burns in lava and fire, and falls
+    /// into the void. Returns whether the player was hurt.
+    pub fn tick(&mut self, level: &Level) -> bool {
+        if self.hurt_time > 0 {
+            self.hurt_time -= 1;
+        }
+        if self.attack_time > 0 {
+            self.attack_time -= 1;
+        }
+        if self.is_dead() {
+            self.death_time = (self.death_time + 1).min(DEATH_TIME);
+            return false
+        }
+
+        let mut hurt = false;
+        let eyes = self.eye_position();
+        let eye_block = BlockPos::new(eyes.x.floor() as i32, eyes.y.floor() as i32, eyes.z.floor() as i32);
+
+        if level.block(eye_block).is_some_and(|b| matches!(b.id, block_ids::FLOWING_WATER | block_ids::WATER)) {
+            self.air -= 1;
+            if self.air <= -20 {
+                self.air = 0;
+                hurt |= self.hurt(DROWNING_DAMAGE, DamageSource::Drowning);
+            }
+        } else {
+            self.air = MAX_AIR;
+        }
+
+        if self.is_in(level, &[block_ids::FLOWING_WATER, block_ids::WATER]) {
+            self.fire = self.fire.min(0);
+        }
+
+        if self.is_in(level, &[block_ids::FLOWING_LAVA, block_ids::LAVA]) {
+            hurt |= self.hurt(LAVA_DAMAGE, DamageSource::Lava);
+            self.fire = LAVA_FIRE;
+        } else if self.is_in(level, &[block_ids::FIRE]) {
+            hurt |= self.hurt(1, DamageSource::Fire);
+            if self.fire <= 0 {
+                self.fire = BLOCK_FIRE;
+            }
+        }
+
+        if self.fire > 0 {
+            if self.fire % 20 == 0 {
+                hurt |= self.hurt(1, DamageSource::Fire);
+            }
+            self.fire -= 1;
+        } else if self.fire < 0 {
+            self.fire += 1;
+        }
+
+        if self.body.position.y < VOID_HEIGHT {
+            hurt |= self.hurt(VOID_DAMAGE, DamageSource::Void);
+        }
+
+        hurt
+    }
+
+    /// Counts the ticks the player stands in a portal, as in Beta. Returns
+    /// true once it has stood in one for `PORTAL_TIME` ticks, when it should
+    /// go through with `travel`. The player does not go through again until
+    /// it has stepped out of the portal it arrives in for a while.
+    pub fn tick_portal(&mut self, level: &Level) -> bool {
+        if self.is_dead() || !self.is_in(level, &[block_ids::PORTAL]) {
+            self.portal_time = (self.portal_time - PORTAL_DECAY).max(0);
+            return false
+        }
+
+        if self.portal_time >= PORTAL_TIME {
+            return false
+        }
+
+        self.portal_time += 1;
+        self.portal_time == PORTAL_TIME
+    }
+
+    /// Sends the player to another dimension. It arrives at the portal
+    /// closest to where it would be in the other dimension, or at a portal
+    /// built for it there. `level` holds the chunks of the other dimension,
+    /// which are generated by `generator` where they are missing.
+    pub fn travel<W>(&mut self, to: Dimension, level: &mut Level, generator: &W)
+        where W: WorldGenerator + ?Sized
+    {
+        let position = self.dimension.convert_position(to, self.body.position);
+        let near = BlockPos::new(position.x.floor() as i32, position.y.floor() as i32, position.z.floor() as i32);
+        let portal = level.portal_destination(near, generator);
+
+        self.body = Body::player(Point3::new(portal.x as f64 + 0.5, portal.y as f64, portal.z as f64 + 0.5));
+        self.dimension = to;
+    }
+
+    /// Takes everything the player carries, such as when it dies.
+    pub fn die(&mut self) -> Vec<ItemStack> {
+        self.health = 0;
+        self.inventory.take_all()
+    }
+
+    /// Brings a dead player back to life at its bed, or at the spawn of the
+    /// world if it has not slept in one. Players always respawn in the
+    /// overworld.
+    pub fn respawn(&mut self, world_spawn: BlockPos) {
+        let at = self.spawn.unwrap_or(world_spawn);
+
+        self.body = Body::player(Point3::new(at.x as f64 + 0.5, at.y as f64, at.z as f64 + 0.5));
+        self.health = MAX_HEALTH;
+        self.air = MAX_AIR;
+        self.fire = -20;
+        self.hurt_time = 0;
+        self.death_time = 0;
+        self.attack_time = 0;
+        self.portal_time = 0;
+        self.sleeping = false;
+        self.sleep_timer = 0;
+        self.dimension = Dimension::Overworld;
+    }
+
+    /// Where the file of the player is, in the directory of the world.
+    /// Fails if the name of the player is not a valid username.
+    pub fn file_path<P: AsRef<Path>>(world: P, username: &str) -> Result<PathBuf, PlayerError> {
+        if !is_valid_username(username) {
+            return Err(PlayerError::InvalidUsername(username.to_string()))
+        }
+
+        Ok(world.as_ref().join("players").join(format!("{}.dat", username)))
+    }
+
+    /// Loads the file of a player from the directory of the world. Returns
+    /// None if the player has never played in the world.
+    pub fn load<P: AsRef<Path>>(world: P, username: &str, entity_id: i32) -> Result<Option<Self>, PlayerError> {
+        let path = Self::file_path(world, username)?;
+        if !path.exists() {
+            return Ok(None)
+        }
+
+        let bytes = std::fs::read(path).map_err(PlayerError::Io)?;
+        let tag = NamedBinaryTag::from_gzip(&bytes).map_err(PlayerError::Nbt)?;
+        Self::from_nbt(username, entity_id, &tag).map(Some)
+    }
+
+    /// Saves the file of the player into the directory of the world.
+    pub fn save<P: AsRef<Path>>(&self, world: P) -> Result<(), PlayerError> {
+        let path = Self::file_path(world, &self.username)?;
+        if let Some(players) = path.parent() {
+            std::fs::create_dir_all(players).map_err(PlayerError::Io)?;
+        }
+
+        std::fs::write(path, self.to_nbt().to_gzip()).map_err(PlayerError::Io)
+    }
+
+    pub fn to_nbt(&self) -> NamedBinaryTag {
+        let body = &self.body;
+
+        let mut root = Compound::new();
+        let mut insert = |name: &str, value| { root.insert(name.to_string(), value); };
+
+        insert("Pos", doubles(&[body.position.x, self.stance(), body.position.z]));
+        insert("Motion", doubles(&[body.velocity.x, body.velocity.y, body.velocity.z]));
+        insert("Rotation", floats(&[self.yaw, self.pitch]));
+        insert("FallDistance", ValueData::Float(body.fall_distance as f32));
+        insert("OnGround", ValueData::Byte(body.on_ground as i8));
+        insert("Dimension", ValueData::Int(self.dimension.id()));
+        insert("Health", ValueData::Short(self.health));
+        insert("Air", ValueData::Short(self.air));
+        insert("Fire", ValueData::Short(self.fire));
+        insert("HurtTime", ValueData::Short(self.hurt_time));
+        insert("DeathTime", ValueData::Short(self.death_time));
+        insert("AttackTime", ValueData::Short(self.attack_time));
+        insert("Sleeping", ValueData::Byte(self.sleeping as i8));
+        insert("SleepTimer", ValueData::Short(self.sleep_timer));
+        insert("Inventory", self.inventory.to_nbt());
+        insert("playerGameType", ValueData::Int(self.game_mode.id()));
+
+        if let Some(spawn) = self.spawn {
+            insert("SpawnX", ValueData::Int(spawn.x));
+            insert("SpawnY", ValueData::Int(spawn.y));
+            insert("SpawnZ", ValueData::Int(spawn.z));
+        }
+
+        let mut tag = NamedBinaryTag::new();
+        tag.insert("", ValueData::Compound(root));
+        tag
+    }
+
+    pub fn from_nbt(username: &str, entity_id: i32, tag: &NamedBinaryTag) -> Result<Self, PlayerError> {
+        let root = tag.get("")
+            .ok_or(PlayerError::MissingTag(""))?
+            .compound()
+            .ok_or(PlayerError::InvalidTag(""))?;
+
+        let get = |name: &'static str| root.get(name).ok_or(PlayerError::MissingTag(name));
+
+        let list = |name: &'static str, len: usize| -> Result<&Vec<ValueData>, PlayerError> {
+            get(name)?
+                .list()
+                .map(|(_, values)| values)
+                .filter(|values| values.len() == len)
+                .ok_or(PlayerError::InvalidTag(name))
+        };
+
+        let doubles = |name: &'static str| -> Result<Vec<f64>, PlayerError> {
+            list(name, 3)?.iter()
+                .map(|v| v.double().copied().ok_or(PlayerError::InvalidTag(name)))
+                .collect()
+        };
+
+        // The tags which are not essential fall back to their defaults.
+        let short = |name: &str, default: i16| {
+            root.get(name).and_then(ValueData::short).copied().unwrap_or(default)
+        };
+        let byte = |name: &str| root.get(name).and_then(ValueData::byte).copied().unwrap_or(0);
+        let int = |name: &str| root.get(name).and_then(ValueData::int).copied();
+
+        let pos = doubles("Pos")?;
+        let motion = doubles("Motion")?;
+        let rotation = list("Rotation", 2)?.iter()
+            .map(|v| v.float().copied().ok_or(PlayerError::InvalidTag("Rotation")))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut player = Self::new(username, entity_id, Point3::new(pos[0], pos[1] - PLAYER_EYE_HEIGHT, pos[2]));
+        player.body.velocity = Vector3::new(motion[0], motion[1], motion[2]);
+        player.body.fall_distance = root.get("FallDistance")
+            .and_then(ValueData::float)
+            .map_or(0.0, |&d| d as f64);
+        player.body.on_ground = byte("OnGround") != 0;
+        player.yaw = rotation[0];
+        player.pitch = rotation[1];
+        player.dimension = int("Dimension")
+            .and_then(Dimension::from_id)
+            .unwrap_or(Dimension::Overworld);
+
+        player.health = short("Health", MAX_HEALTH);
+        player.air = short("Air", MAX_AIR);
+        player.fire = short("Fire", -20);
+        player.hurt_time = short("HurtTime", 0);
+        player.death_time = short("DeathTime", 0);
+        player.attack_time = short("AttackTime", 0);
+        player.sleeping = byte("Sleeping") != 0;
+        player.sleep_timer = short("SleepTimer", 0);
+
+        player.spawn = match (int("SpawnX"), int("SpawnY"), int("SpawnZ")) {
+            (Some(x), Some(y), Some(z)) => Some(BlockPos::new(x, y, z)),
+            _ => None,
+        };
+
+        player.game_mode = int("playerGameType")
+            .and_then(GameMode::from_id)
+            .unwrap_or(GameMode::Survival);
+
+        if let Some(items) = root.get("Inventory") {
+            let (_, items) = items.list().ok_or(PlayerError::InvalidTag("Inventory"))?;
+            player.inventory = PlayerInventory::from_nbt(items);
+        }
+
+        Ok(player)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::item::item_ids;
+    use super::*;
+
+    fn player() -> Player {
+        let mut player = Player::new("Notch", 7, Point3::new(-120.25, 63.0, 5000.75));
+        player.body.velocity = Vector3::new(0.5, -0.25, 0.0);
+        player.body.fall_distance = 2.5;
+        player.yaw = 271.5;
+        player.pitch = -12.25;
+        player.dimension = Dimension::Nether;
+        player.health = 13;
+        player.air = 120;
+        player.fire = 40;
+        player.spawn = Some(BlockPos::new(-3, 70, 12));
+        player.game_mode = GameMode::Creative;
+
+        let inventory = &mut player.inventory;
+        inventory.main_mut()[0] = Some(ItemStack::with_damage(item_ids::DIAMOND_SWORD, 1, 17));
+        inventory.main_mut()[35] = Some(ItemStack::new(block_ids::COBBLESTONE, 64));
+        inventory.armor_mut()[0] = Some(ItemStack::with_damage(item_ids::IRON_BOOTS, 1, 3));
+        inventory.armor_mut()[3] = Some(ItemStack::new(item_ids::DIAMOND_HELMET, 1));
+        player
+    }
+
+    fn remove(tag: &mut NamedBinaryTag, name: &str) {
+        tag.root_mut().get_mut("").unwrap().compound_mut().unwrap().remove(name);
+    }
+
+    #[test]
+    fn player_files_load_the_same() {
+        let world = std::env::temp_dir().join("gekraftet-player-tests");
+        let player = player();
+        player.save(&world).unwrap();
+        assert!(world.join("players").join("Notch.dat").exists());
+
+        let mut loaded = Player::load(&world, "Notch", 8).unwrap().unwrap();
+        assert_eq!(loaded.entity_id, 8);
+        loaded.entity_id = player.entity_id;
+
+        // The height is saved as that of the eyes, which does not round trip
+        // exactly.
+        assert!((loaded.position() - player.position()).magnitude() < 1e-9);
+        loaded.body.position = player.position();
+
+        assert_eq!(loaded, player);
+        assert!(Player::load(&world, "Herobrine", 9).unwrap().is_none());
+    }
+
+    #[test]
+    fn missing_tags_fall_back_to_their_defaults() {
+        let mut tag = player().to_nbt();
+        for name in ["Dimension", "Health", "Inventory", "SpawnX", "playerGameType"].iter() {
+            remove(&mut tag, name);
+        }
+
+        let loaded = Player::from_nbt("Notch", 7, &tag).unwrap();
+        assert_eq!(loaded.dimension, Dimension::Overworld);
+        assert_eq!(loaded.health, MAX_HEALTH);
+        assert_eq!(loaded.inventory, PlayerInventory::new());
+        assert_eq!(loaded.spawn, None);
+        assert_eq!(loaded.game_mode, GameMode::Survival);
+
+        remove(&mut tag, "Pos");
+        assert!(matches!(Player::from_nbt("Notch", 7, &tag), Err(PlayerError::MissingTag("Pos"))));
+    }
+}
@@ -0,0 +1,125 @@
+use crate::world::{ HeightmapNoise, NoiseGen, NoiseGenOption, Perlin2D, block_ids };
+
+/// The biomes of Beta, which decide the surface blocks of the terrain.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Biome {
+    Rainforest,
+    Swampland,
+    SeasonalForest,
+    Forest,
+    Savanna,
+    Shrubland,
+    Taiga,
+    Desert,
+    Plains,
+    IceDesert,
+    Tundra,
+    /// The only biome of the Nether.
+    Hell,
+}
+
+impl Biome {
+    /// Picks the biome of a climate. Both the temperature and the rainfall
+    /// are between 0 and 1.
+    pub fn from_climate(temperature: f64, rainfall: f64) -> Self {
+        let rainfall = rainfall * temperature;
+
+        if temperature < 0.1 {
+            Self::Tundra
+        } else if rainfall < 0.2 {
+            if temperature < 0.5 {
+                Self::Tundra
+            } else if temperature < 0.95 {
+                Self::Savanna
+            } else {
+                Self::Desert
+            }
+        } else if rainfall > 0.5 && temperature < 0.7 {
+            Self::Swampland
+        } else if temperature < 0.5 {
+            Self::Taiga
+        } else if temperature < 0.97 {
+            if rainfall < 0.35 {
+                Self::Shrubland
+            } else {
+                Self::Forest
+            }
+        } else if rainfall < 0.45 {
+            Self::Plains
+        } else if rainfall < 0.9 {
+            Self::SeasonalForest
+        } else {
+            Self::Rainforest
+        }
+    }
+
+    /// The block covering the terrain.
+    pub fn top_block(self) -> u16 {
+        match self {
+            Self::Desert | Self::IceDesert => block_ids::SAND,
+            Self::Hell => block_ids::NETHERRACK,
+            _ => block_ids::GRASS,
+        }
+    }
+
+    /// The blocks right below the top block.
+    pub fn filler_block(self) -> u16 {
+        match self {
+            Self::Desert | Self::IceDesert => block_ids::SAND,
+            Self::Hell => block_ids::NETHERRACK,
+            _ => block_ids::DIRT,
+        }
+    }
+}
+
+/// Decides the biome of every block column from two 2D noises, one for the
+/// temperature and one for the rainfall.
+pub struct BiomeSource {
+    temperature: Vec<Perlin2D>,
+    rainfall: Vec<Perlin2D>,
+}
+
+// Every octave of the climate noises is a generator of its own. The octaves
+// of a single generator share their lattice, so they would all be zero along
+// the same lines, which shows up as straight biome borders.
+fn climate_octaves(seed: u64) -> Vec<Perlin2D> {
+    const OCTAVES: u32 = 4;
+
+    (0..OCTAVES)
+        .map(|i| {
+            let option = NoiseGenOption::new()
+                .octaves(1)
+                .amplitude(0.75 / (1 << i) as f64)
+                .frequency((1 << i) as f32 / 256.0);
+
+            Perlin2D::with_option_and_seed(option, seed.wrapping_add(i as u64))
+        })
+        .collect()
+}
+
+impl BiomeSource {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            temperature: climate_octaves(seed.wrapping_mul(9871)),
+            rainfall: climate_octaves(seed.wrapping_mul(39811)),
+        }
+    }
+
+    /// Returns the temperature and the rainfall of a block column.
+    pub fn climate_at(&self, x: i32, z: i32) -> (f64, f64) {
+        let sample = |octaves: &[Perlin2D]| {
+            let value = octaves.iter()
+                .map(|octave| octave.generate_height_at(x as f32, z as f32))
+                .sum::<f64>();
+
+            (value * 0.5 + 0.5).clamp(0.0, 1.0)
+        };
+
+        (sample(&self.temperature), sample(&self.rainfall))
+    }
+
+    pub fn biome_at(&self, x: i32, z: i32) -> Biome {
+        let (temperature, rainfall) = self.climate_at(x, z);
+        Biome::from_climate(temperature, rainfall)
+    }
+}
@@ -1,4 +1,4 @@
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub struct Block {
     pub metadata: u16,
     pub id: u16,
@@ -11,4 +11,11 @@ impl Block {
             metadata: 0,
         }
     }
+
+    pub fn with_metadata(id: u16, metadata: u16) -> Self {
+        Self {
+            id,
+            metadata,
+        }
+    }
 }
@@ -0,0 +1,457 @@
+use crate::item::{ fuel_time, smelting_result, Inventory, ItemStack, WindowKind, SMELT_TIME };
+use crate::nbt::{ Compound, ValueData };
+use super::*;
+
+/// The slots of a chest. Two chests next to each other make a double chest,
+/// with twice as many slots.
+pub const CHEST_SIZE: usize = 27;
+pub const DISPENSER_SIZE: usize = 9;
+/// The slots of a furnace: the item being smelted, the fuel and the result.
+pub const FURNACE_SIZE: usize = 3;
+/// The most characters a line of a sign holds.
+pub const SIGN_LINE_LENGTH: usize = 15;
+/// The pitches of a note block, in semitones from F#.
+pub const NOTE_COUNT: u8 = 25;
+
+#[derive(Debug)]
+pub enum BlockEntityError {
+    MissingTag(&'static str),
+    InvalidTag(&'static str),
+    UnknownBlockEntity(String),
+}
+
+/// The state of a furnace.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Furnace {
+    pub items: Inventory,
+    /// The ticks left before the furnace runs out of fuel.
+    pub burn_time: i16,
+    /// The ticks the last item of fuel burns for in total. This is not
+    /// saved, so it is taken from the fuel left when loading.
+    pub fuel_time: i16,
+    /// The ticks the item being smelted has been smelting for.
+    pub cook_time: i16,
+}
+
+/// The data of a block which does not fit in its metadata, such as the items
+/// in a chest or the text of a sign.
+#[derive(Clone, Debug, PartialEq)]
+pub enum BlockEntity {
+    Sign {
+        lines: [String; 4],
+    },
+    Chest {
+        items: Inventory,
+    },
+    Furnace(Furnace),
+    Dispenser {
+        items: Inventory,
+    },
+    MobSpawner {
+        /// The name of the mob spawned, such as "Zombie".
+        mob: String,
+        /// The ticks left before the next mobs are spawned.
+        delay: i16,
+    },
+    NoteBlock {
+        note: u8,
+        /// Whether the block was powered the last time its neighbours changed,
+        /// so that it only plays when it gets powered. This is not saved.
+        powered: bool,
+    },
+}
+
+// Block entities are stored in the `TileEntities` list of the chunk they are
+// in:
+//
+//     (compound)
+//         id (string)                      see `BlockEntity::name`
+//         x, y, z (int)                    the position of the block
+//
+// followed by the tags specific to the kind of the block entity:
+//
+//     Sign                 Text1, Text2, Text3, Text4 (string)
+//     Chest, Trap          Items (list of compounds)
+//     Furnace              Items (list of compounds), BurnTime, CookTime (short)
+//     MobSpawner           EntityId (string), Delay (short)
+//     Music                note (byte)
+
+impl Furnace {
+    pub fn new() -> Self {
+        Self {
+            items: Inventory::new(FURNACE_SIZE),
+            burn_time: 0,
+            fuel_time: 0,
+            cook_time: 0,
+        }
+    }
+
+    pub fn is_burning(&self) -> bool {
+        self.burn_time > 0
+    }
+
+    /// Whether the item in the furnace can be smelted, with room for the
+    /// result.
+    fn can_smelt(&self) -> bool {
+        let result = match self.items.get(0).and_then(smelting_result) {
+            Some(result) => result,
+            None => return false,
+        };
+
+        match self.items.get(2) {
+            None => true,
+            Some(output) => output.is_same_item(&result)
+                && output.count + result.count <= output.max_stack_size(),
+        }
+    }
+
+    fn smelt(&mut self) {
+        let input = match self.items.get(0).copied() {
+            Some(input) => input,
+            None => return,
+        };
+        let result = match smelting_result(&input) {
+            Some(result) => result,
+            None => return,
+        };
+
+        let output = match self.items.get(2) {
+            Some(output) => ItemStack { count: output.count + result.count, ..*output },
+            None => result,
+        };
+        self.items.set(2, Some(output));
+
+        let left = ItemStack { count: input.count - 1, ..input };
+        self.items.set(0, Some(left).filter(|s| s.count > 0));
+    }
+
+    /// Advances the furnace by a tick, as in Beta: it takes a new item of fuel
+    /// when it runs out and has something to smelt, and smelts an item every
+    /// `SMELT_TIME` ticks while it burns.
+    pub fn tick(&mut self) {
+        if self.burn_time > 0 {
+            self.burn_time -= 1;
+        }
+
+        if self.burn_time == 0 && self.can_smelt() {
+            let fuel = self.items.get(1).copied();
+            self.fuel_time = fuel.as_ref().map_or(0, fuel_time);
+            self.burn_time = self.fuel_time;
+
+            if let Some(fuel) = fuel.filter(|_| self.burn_time > 0) {
+                let left = ItemStack { count: fuel.count - 1, ..fuel };
+                self.items.set(1, Some(left).filter(|s| s.count > 0));
+            }
+        }
+
+        if self.is_burning() && self.can_smelt() {
+            self.cook_time += 1;
+            if self.cook_time == SMELT_TIME {
+                self.cook_time = 0;
+                self.smelt();
+            }
+        } else {
+            self.cook_time = 0;
+        }
+    }
+
+    /// The values of the progress bars of the window, as sent in
+    /// `UpdateProgressBar` packets: the progress of the arrow, the fire left
+    /// and the time the fire lasts in total.
+    pub fn progress_bars(&self) -> [(i16, i16); 3] {
+        [(0, self.cook_time), (1, self.burn_time), (2, self.fuel_time)]
+    }
+}
+
+impl Default for Furnace {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BlockEntity {
+    /// The block entity a block of the given id starts with when it is
+    /// placed, if it has one.
+    pub fn for_block(id: u16) -> Option<Self> {
+        let entity = match id {
+            block_ids::SIGN_POST | block_ids::WALL_SIGN => Self::sign(&["", "", "", ""]),
+            block_ids::CHEST => Self::Chest { items: Inventory::new(CHEST_SIZE) },
+            block_ids::FURNACE | block_ids::LIT_FURNACE => Self::Furnace(Furnace::new()),
+            block_ids::DISPENSER => Self::Dispenser { items: Inventory::new(DISPENSER_SIZE) },
+            block_ids::SPAWNER => Self::MobSpawner { mob: "Pig".to_string(), delay: 20 },
+            block_ids::NOTE_BLOCK => Self::NoteBlock { note: 0, powered: false },
+            _ => return None,
+        };
+
+        Some(entity)
+    }
+
+    /// A sign with the given text. Lines longer than `SIGN_LINE_LENGTH` are
+    /// cut, as the client does.
+    pub fn sign(lines: &[&str; 4]) -> Self {
+        let line = |i: usize| lines[i].chars().take(SIGN_LINE_LENGTH).collect();
+
+        Self::Sign {
+            lines: [line(0), line(1), line(2), line(3)],
+        }
+    }
+
+    /// The id of the block entity in NBT files.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Sign { .. } => "Sign",
+            Self::Chest { .. } => "Chest",
+            Self::Furnace(_) => "Furnace",
+            Self::Dispenser { .. } => "Trap",
+            Self::MobSpawner { .. } => "MobSpawner",
+            Self::NoteBlock { .. } => "Music",
+        }
+    }
+
+    /// The slots of the block, for the ones which hold items.
+    pub fn items(&self) -> Option<&Inventory> {
+        match self {
+            Self::Chest { items } | Self::Dispenser { items } => Some(items),
+            Self::Furnace(furnace) => Some(&furnace.items),
+            _ => None,
+        }
+    }
+
+    pub fn items_mut(&mut self) -> Option<&mut Inventory> {
+        match self {
+            Self::Chest { items } | Self::Dispenser { items } => Some(items),
+            Self::Furnace(furnace) => Some(&mut furnace.items),
+            _ => None,
+        }
+    }
+
+    /// The kind of window opened on the block, for the ones which hold items.
+    /// Chests are opened as single chests.
+    pub fn window_kind(&self) -> Option<WindowKind> {
+        match self {
+            Self::Chest { .. } => Some(WindowKind::Chest { rows: CHEST_SIZE / 9 }),
+            Self::Furnace(_) => Some(WindowKind::Furnace),
+            Self::Dispenser { .. } => Some(WindowKind::Dispenser),
+            _ => None,
+        }
+    }
+
+    pub fn to_nbt(&self, at: BlockPos) -> Compound {
+        let mut tag = Compound::new();
+        let mut insert = |name: &str, value| { tag.insert(name.to_string(), value); };
+
+        insert("id", ValueData::Str(self.name().to_string()));
+        insert("x", ValueData::Int(at.x));
+        insert("y", ValueData::Int(at.y));
+        insert("z", ValueData::Int(at.z));
+
+        match self {
+            Self::Sign { lines } => {
+                for (i, line) in lines.iter().enumerate() {
+                    insert(&format!("Text{}", i + 1), ValueData::Str(line.clone()));
+                }
+            },
+            Self::Chest { items } | Self::Dispenser { items } => insert("Items", items.to_nbt()),
+            Self::Furnace(furnace) => {
+                insert("Items", furnace.items.to_nbt());
+                insert("BurnTime", ValueData::Short(furnace.burn_time));
+                insert("CookTime", ValueData::Short(furnace.cook_time));
+            },
+            Self::MobSpawner { mob, delay } => {
+                insert("EntityId", ValueData::Str(mob.clone()));
+                insert("Delay", ValueData::Short(*delay));
+            },
+            Self::NoteBlock { note, .. } => insert("note", ValueData::Byte(*note as i8)),
+        }
+
+        tag
+    }
+
+    /// Reads a block entity written by `to_nbt`, along with its position.
+    pub fn from_nbt(tag: &Compound) -> Result<(BlockPos, Self), BlockEntityError> {
+        let get = |name: &'static str| tag.get(name).ok_or(BlockEntityError::MissingTag(name));
+        let int = |name: &'static str| -> Result<i32, BlockEntityError> {
+            get(name)?.int().copied().ok_or(BlockEntityError::InvalidTag(name))
+        };
+        let items = |size: usize| -> Result<Inventory, BlockEntityError> {
+            let (_, items) = get("Items")?.list().ok_or(BlockEntityError::InvalidTag("Items"))?;
+            Ok(Inventory::from_nbt(size, items))
+        };
+
+        // The tags which are not essential fall back to their defaults.
+        let short = |name: &str| tag.get(name).and_then(ValueData::short).copied().unwrap_or(0);
+        let string = |name: &str| tag.get(name).and_then(ValueData::string).unwrap_or("");
+
+        let at = BlockPos::new(int("x")?, int("y")?, int("z")?);
+
+        let name = get("id")?.string().ok_or(BlockEntityError::InvalidTag("id"))?;
+        let entity = match name {
+            "Sign" => Self::sign(&[string("Text1"), string("Text2"), string("Text3"), string("Text4")]),
+            "Chest" => Self::Chest { items: items(CHEST_SIZE)? },
+            "Trap" => Self::Dispenser { items: items(DISPENSER_SIZE)? },
+            "Furnace" => {
+                let items = items(FURNACE_SIZE)?;
+                let fuel_time = items.get(1).map_or(0, fuel_time);
+
+                Self::Furnace(Furnace {
+                    items,
+                    burn_time: short("BurnTime"),
+                    fuel_time,
+                    cook_time: short("CookTime"),
+                })
+            },
+            "MobSpawner" => Self::MobSpawner {
+                mob: get("EntityId")?.string().ok_or(BlockEntityError::InvalidTag("EntityId"))?.to_string(),
+                delay: short("Delay"),
+            },
+            "Music" => Self::NoteBlock {
+                note: tag.get("note").and_then(ValueData::byte).map_or(0, |&n| n as u8 % NOTE_COUNT),
+                powered: false,
+            },
+            _ => return Err(BlockEntityError::UnknownBlockEntity(name.to_string())),
+        };
+
+        Ok((at, entity))
+    }
+}
+
+/// The instrument a note block plays, which depends on the block below it:
+/// stone for the bass drum, sand for the snare drum, glass for clicks, wood
+/// for the bass, and the harp for everything else.
+fn instrument(below: Block) -> u8 {
+    use block_ids::*;
+
+    match below.id {
+        STONE | COBBLESTONE | BEDROCK | GOLD_ORE | IRON_ORE | COAL_ORE | LAPIS_ORE | LAPIS_BLOCK |
+        DISPENSER | SANDSTONE | DOUBLE_SLAB | SLAB | BRICKS | MOSSY_COBBLESTONE | OBSIDIAN |
+        SPAWNER | DIAMOND_ORE | FURNACE | LIT_FURNACE | COBBLESTONE_STAIRS | STONE_PRESSURE_PLATE |
+        REDSTONE_ORE | LIT_REDSTONE_ORE | NETHERRACK => 1,
+        SAND | GRAVEL | SOUL_SAND => 2,
+        GLASS | GLOWSTONE => 3,
+        PLANKS | LOG | NOTE_BLOCK | BOOKSHELF | WOODEN_STAIRS | CHEST | LOCKED_CHEST | WORKBENCH |
+        SIGN_POST | WOODEN_DOOR | WALL_SIGN | WOODEN_PRESSURE_PLATE | JUKEBOX | FENCE | TRAPDOOR => 4,
+        _ => 0,
+    }
+}
+
+/// Plays the note of the note block at the given position, unless the block
+/// above it is in the way.
+pub(crate) fn play_note(level: &mut Level, at: BlockPos) {
+    let note = match level.block_entity(at) {
+        Some(BlockEntity::NoteBlock { note, .. }) => *note,
+        _ => return,
+    };
+
+    if level.block(at.offset(0, 1, 0)).is_some_and(|b| b.id != block_ids::AIR) {
+        return
+    }
+
+    let instrument = level.block(at.offset(0, -1, 0)).map_or(0, instrument);
+    level.add_block_action(at, [instrument, note]);
+}
+
+impl Level {
+    /// Advances the furnaces by a tick. Furnaces which start or stop burning
+    /// are swapped between the lit and the unlit block.
+    pub(super) fn tick_block_entities(&mut self) {
+        let mut furnaces = self.chunks()
+            .flat_map(|chunk| chunk.block_entities().iter())
+            .filter(|(_, entity)| matches!(entity, BlockEntity::Furnace(_)))
+            .map(|(&at, _)| at)
+            .collect::<Vec<_>>();
+        furnaces.sort_by_key(|at| (at.x, at.y, at.z));
+
+        for at in furnaces {
+            let burning = match self.block_entity_mut(at) {
+                Some(BlockEntity::Furnace(furnace)) => {
+                    furnace.tick();
+                    furnace.is_burning()
+                },
+                _ => continue,
+            };
+
+            let id = if burning { block_ids::LIT_FURNACE } else { block_ids::FURNACE };
+            if let Some(block) = self.block(at).filter(|b| b.id != id) {
+                self.set_block(at, Block::with_metadata(id, block.metadata));
+            }
+        }
+    }
+
+    /// Changes the text of the sign at the given position, as players do
+    /// with `UpdateSign` packets. Returns false if there is no sign there.
+    pub fn set_sign_text(&mut self, at: BlockPos, lines: &[&str; 4]) -> bool {
+        match self.block_entity_mut(at) {
+            Some(entity @ BlockEntity::Sign { .. }) => {
+                *entity = BlockEntity::sign(lines);
+                true
+            },
+            _ => false,
+        }
+    }
+
+    /// The chest which makes a double chest with the one at the given
+    /// position, if any. The one with the lowest coordinates comes first.
+    fn chest_pair(&self, at: BlockPos) -> Option<(BlockPos, BlockPos)> {
+        [BlockFace::Left, BlockFace::Right, BlockFace::Back, BlockFace::Front].iter()
+            .map(|&face| at.neighbor(face))
+            .find(|&other| self.block(other).is_some_and(|b| b.id == block_ids::CHEST))
+            .map(|other| if (other.x, other.z) < (at.x, at.z) { (other, at) } else { (at, other) })
+    }
+
+    /// The slots of the block at the given position, along with the kind of
+    /// window showing them, for blocks which hold items. Chests next to
+    /// another chest show the slots of both as a double chest.
+    pub fn container(&self, at: BlockPos) -> Option<(WindowKind, Inventory)> {
+        let entity = self.block_entity(at)?;
+        let items = entity.items()?;
+
+        if let BlockEntity::Chest { .. } = entity {
+            if let Some((first, second)) = self.chest_pair(at) {
+                let mut slots = Vec::with_capacity(CHEST_SIZE * 2);
+                for at in [first, second].iter() {
+                    match self.block_entity(*at).and_then(BlockEntity::items) {
+                        Some(items) => slots.extend_from_slice(items.slots()),
+                        None => slots.extend_from_slice(&[None; CHEST_SIZE]),
+                    }
+                }
+
+                let mut double = Inventory::new(slots.len());
+                double.slots_mut().copy_from_slice(&slots);
+                return Some((WindowKind::Chest { rows: CHEST_SIZE * 2 / 9 }, double))
+            }
+        }
+
+        Some((entity.window_kind()?, items.clone()))
+    }
+
+    /// Stores the slots of a window opened with `container` back into the
+    /// block. Returns false if the block does not hold items anymore, or
+    /// holds a different number of them.
+    pub fn set_container(&mut self, at: BlockPos, container: &Inventory) -> bool {
+        let double = container.len() == CHEST_SIZE * 2
+            && matches!(self.block_entity(at), Some(BlockEntity::Chest { .. }));
+
+        let parts = match self.chest_pair(at).filter(|_| double) {
+            Some((first, second)) => vec![(first, 0), (second, CHEST_SIZE)],
+            None => vec![(at, 0)],
+        };
+
+        let fits = parts.iter().all(|&(at, start)| {
+            let len = self.block_entity(at).and_then(BlockEntity::items).map(Inventory::len);
+            len.is_some_and(|len| if double { start + len <= container.len() } else { len == container.len() })
+        });
+
+        if !fits {
+            return false
+        }
+
+        for (at, start) in parts {
+            if let Some(items) = self.block_entity_mut(at).and_then(BlockEntity::items_mut) {
+                let end = start + items.len();
+                items.slots_mut().copy_from_slice(&container.slots()[start..end]);
+            }
+        }
+
+        true
+    }
+}
@@ -0,0 +1,100 @@
+//! The numeric ids of the blocks, as used by the Beta 1.7 protocol and the
+//! chunk format.
+
+pub const AIR: u16 = 0;
+pub const STONE: u16 = 1;
+pub const GRASS: u16 = 2;
+pub const DIRT: u16 = 3;
+pub const COBBLESTONE: u16 = 4;
+pub const PLANKS: u16 = 5;
+pub const SAPLING: u16 = 6;
+pub const BEDROCK: u16 = 7;
+pub const FLOWING_WATER: u16 = 8;
+pub const WATER: u16 = 9;
+pub const FLOWING_LAVA: u16 = 10;
+pub const LAVA: u16 = 11;
+pub const SAND: u16 = 12;
+pub const GRAVEL: u16 = 13;
+pub const GOLD_ORE: u16 = 14;
+pub const IRON_ORE: u16 = 15;
+pub const COAL_ORE: u16 = 16;
+pub const LOG: u16 = 17;
+pub const LEAVES: u16 = 18;
+pub const SPONGE: u16 = 19;
+pub const GLASS: u16 = 20;
+pub const LAPIS_ORE: u16 = 21;
+pub const LAPIS_BLOCK: u16 = 22;
+pub const DISPENSER: u16 = 23;
+pub const SANDSTONE: u16 = 24;
+pub const NOTE_BLOCK: u16 = 25;
+pub const BED: u16 = 26;
+pub const POWERED_RAIL: u16 = 27;
+pub const DETECTOR_RAIL: u16 = 28;
+pub const STICKY_PISTON: u16 = 29;
+pub const COBWEB: u16 = 30;
+pub const TALL_GRASS: u16 = 31;
+pub const DEAD_BUSH: u16 = 32;
+pub const PISTON: u16 = 33;
+pub const PISTON_HEAD: u16 = 34;
+pub const WOOL: u16 = 35;
+pub const MOVING_PISTON: u16 = 36;
+pub const DANDELION: u16 = 37;
+pub const ROSE: u16 = 38;
+pub const BROWN_MUSHROOM: u16 = 39;
+pub const RED_MUSHROOM: u16 = 40;
+pub const GOLD_BLOCK: u16 = 41;
+pub const IRON_BLOCK: u16 = 42;
+pub const DOUBLE_SLAB: u16 = 43;
+pub const SLAB: u16 = 44;
+pub const BRICKS: u16 = 45;
+pub const TNT: u16 = 46;
+pub const BOOKSHELF: u16 = 47;
+pub const MOSSY_COBBLESTONE: u16 = 48;
+pub const OBSIDIAN: u16 = 49;
+pub const TORCH: u16 = 50;
+pub const FIRE: u16 = 51;
+pub const SPAWNER: u16 = 52;
+pub const WOODEN_STAIRS: u16 = 53;
+pub const CHEST: u16 = 54;
+pub const REDSTONE_WIRE: u16 = 55;
+pub const DIAMOND_ORE: u16 = 56;
+pub const DIAMOND_BLOCK: u16 = 57;
+pub const WORKBENCH: u16 = 58;
+pub const WHEAT: u16 = 59;
+pub const FARMLAND: u16 = 60;
+pub const FURNACE: u16 = 61;
+pub const LIT_FURNACE: u16 = 62;
+pub const SIGN_POST: u16 = 63;
+pub const WOODEN_DOOR: u16 = 64;
+pub const LADDER: u16 = 65;
+pub const RAIL: u16 = 66;
+pub const COBBLESTONE_STAIRS: u16 = 67;
+pub const WALL_SIGN: u16 = 68;
+pub const LEVER: u16 = 69;
+pub const STONE_PRESSURE_PLATE: u16 = 70;
+pub const IRON_DOOR: u16 = 71;
+pub const WOODEN_PRESSURE_PLATE: u16 = 72;
+pub const REDSTONE_ORE: u16 = 73;
+pub const LIT_REDSTONE_ORE: u16 = 74;
+pub const UNLIT_REDSTONE_TORCH: u16 = 75;
+pub const REDSTONE_TORCH: u16 = 76;
+pub const STONE_BUTTON: u16 = 77;
+pub const SNOW_LAYER: u16 = 78;
+pub const ICE: u16 = 79;
+pub const SNOW: u16 = 80;
+pub const CACTUS: u16 = 81;
+pub const CLAY: u16 = 82;
+pub const SUGAR_CANE: u16 = 83;
+pub const JUKEBOX: u16 = 84;
+pub const FENCE: u16 = 85;
+pub const PUMPKIN: u16 = 86;
+pub const NETHERRACK: u16 = 87;
+pub const SOUL_SAND: u16 = 88;
+pub const GLOWSTONE: u16 = 89;
+pub const PORTAL: u16 = 90;
+pub const JACK_O_LANTERN: u16 = 91;
+pub const CAKE: u16 = 92;
+pub const UNPOWERED_REPEATER: u16 = 93;
+pub const POWERED_REPEATER: u16 = 94;
+pub const LOCKED_CHEST: u16 = 95;
+pub const TRAPDOOR: u16 = 96;
@@ -0,0 +1,180 @@
+use super::{ block_ids::*, Block };
+
+/// The properties shared by every block of the same id.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BlockInfo {
+    pub name: &'static str,
+    /// Whether entities collide with the block.
+    pub solid: bool,
+    /// Whether the block fills its whole space and hides its neighbours.
+    pub opaque: bool,
+    /// The height of the collision box of the block. Fences are taller than a
+    /// block, slabs are shorter.
+    pub height: f64,
+    /// How well the block withstands explosions.
+    pub resistance: f32,
+    /// The light the block gives off, from 0 to 15.
+    pub light: u8,
+}
+
+impl BlockInfo {
+    const fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            solid: true,
+            opaque: true,
+            height: 1.0,
+            resistance: 0.0,
+            light: 0,
+        }
+    }
+
+    /// Entities pass through the block, like through flowers and water.
+    const fn non_solid(mut self) -> Self {
+        self.solid = false;
+        self.opaque = false;
+        self
+    }
+
+    /// The neighbours of the block can be seen through it.
+    const fn transparent(mut self) -> Self {
+        self.opaque = false;
+        self
+    }
+
+    const fn resistance(mut self, resistance: f32) -> Self {
+        self.resistance = resistance;
+        self
+    }
+
+    const fn light(mut self, light: u8) -> Self {
+        self.light = light;
+        self
+    }
+
+    const fn height(mut self, height: f64) -> Self {
+        self.height = height;
+        self.opaque = false;
+        self
+    }
+}
+
+// The blocks with ids which are not in the table are treated as stone-like.
+const UNKNOWN: BlockInfo = BlockInfo::new("unknown").resistance(30.0);
+
+// Indexed by the block id.
+static BLOCKS: [BlockInfo; TRAPDOOR as usize + 1] = [
+    BlockInfo::new("air").non_solid(),
+    BlockInfo::new("stone").resistance(30.0),
+    BlockInfo::new("grass").resistance(3.0),
+    BlockInfo::new("dirt").resistance(2.5),
+    BlockInfo::new("cobblestone").resistance(30.0),
+    BlockInfo::new("planks").resistance(15.0),
+    BlockInfo::new("sapling").non_solid(),
+    BlockInfo::new("bedrock").resistance(18_000_000.0),
+    BlockInfo::new("flowing_water").non_solid().resistance(500.0),
+    BlockInfo::new("water").non_solid().resistance(500.0),
+    BlockInfo::new("flowing_lava").non_solid().light(15),
+    BlockInfo::new("lava").non_solid().resistance(500.0).light(15),
+    BlockInfo::new("sand").resistance(2.5),
+    BlockInfo::new("gravel").resistance(3.0),
+    BlockInfo::new("gold_ore").resistance(15.0),
+    BlockInfo::new("iron_ore").resistance(15.0),
+    BlockInfo::new("coal_ore").resistance(15.0),
+    BlockInfo::new("log").resistance(10.0),
+    BlockInfo::new("leaves").transparent().resistance(1.0),
+    BlockInfo::new("sponge").resistance(3.0),
+    BlockInfo::new("glass").transparent().resistance(1.5),
+    BlockInfo::new("lapis_ore").resistance(15.0),
+    BlockInfo::new("lapis_block").resistance(15.0),
+    BlockInfo::new("dispenser").resistance(17.5),
+    BlockInfo::new("sandstone").resistance(4.0),
+    BlockInfo::new("note_block").resistance(4.0),
+    BlockInfo::new("bed").height(0.5625).resistance(1.0),
+    BlockInfo::new("powered_rail").non_solid().resistance(3.5),
+    BlockInfo::new("detector_rail").non_solid().resistance(3.5),
+    BlockInfo::new("sticky_piston").transparent().resistance(2.5),
+    BlockInfo::new("cobweb").non_solid().resistance(20.0),
+    BlockInfo::new("tall_grass").non_solid(),
+    BlockInfo::new("dead_bush").non_solid(),
+    BlockInfo::new("piston").transparent().resistance(2.5),
+    BlockInfo::new("piston_head").transparent().resistance(2.5),
+    BlockInfo::new("wool").resistance(4.0),
+    BlockInfo::new("moving_piston").non_solid(),
+    BlockInfo::new("dandelion").non_solid(),
+    BlockInfo::new("rose").non_solid(),
+    BlockInfo::new("brown_mushroom").non_solid().light(1),
+    BlockInfo::new("red_mushroom").non_solid(),
+    BlockInfo::new("gold_block").resistance(30.0),
+    BlockInfo::new("iron_block").resistance(30.0),
+    BlockInfo::new("double_slab").resistance(30.0),
+    BlockInfo::new("slab").height(0.5).resistance(30.0),
+    BlockInfo::new("bricks").resistance(30.0),
+    BlockInfo::new("tnt"),
+    BlockInfo::new("bookshelf").resistance(7.5),
+    BlockInfo::new("mossy_cobblestone").resistance(30.0),
+    BlockInfo::new("obsidian").resistance(6000.0),
+    BlockInfo::new("torch").non_solid().light(14),
+    BlockInfo::new("fire").non_solid().light(15),
+    BlockInfo::new("spawner").transparent().resistance(25.0),
+    BlockInfo::new("wooden_stairs").transparent().resistance(15.0),
+    BlockInfo::new("chest").transparent().resistance(12.5),
+    BlockInfo::new("redstone_wire").non_solid(),
+    BlockInfo::new("diamond_ore").resistance(15.0),
+    BlockInfo::new("diamond_block").resistance(30.0),
+    BlockInfo::new("workbench").resistance(12.5),
+    BlockInfo::new("wheat").non_solid(),
+    BlockInfo::new("farmland").transparent().resistance(3.0),
+    BlockInfo::new("furnace").resistance(17.5),
+    BlockInfo::new("lit_furnace").resistance(17.5).light(13),
+    BlockInfo::new("sign_post").non_solid().resistance(5.0),
+    BlockInfo::new("wooden_door").transparent().resistance(15.0),
+    BlockInfo::new("ladder").non_solid().resistance(2.0),
+    BlockInfo::new("rail").non_solid().resistance(3.5),
+    BlockInfo::new("cobblestone_stairs").transparent().resistance(30.0),
+    BlockInfo::new("wall_sign").non_solid().resistance(5.0),
+    BlockInfo::new("lever").non_solid().resistance(2.5),
+    BlockInfo::new("stone_pressure_plate").non_solid().resistance(2.5),
+    BlockInfo::new("iron_door").transparent().resistance(25.0),
+    BlockInfo::new("wooden_pressure_plate").non_solid().resistance(2.5),
+    BlockInfo::new("redstone_ore").resistance(15.0),
+    BlockInfo::new("lit_redstone_ore").resistance(15.0).light(9),
+    BlockInfo::new("unlit_redstone_torch").non_solid(),
+    BlockInfo::new("redstone_torch").non_solid().light(7),
+    BlockInfo::new("stone_button").non_solid().resistance(2.5),
+    BlockInfo::new("snow_layer").non_solid().resistance(0.5),
+    BlockInfo::new("ice").transparent().resistance(2.5),
+    BlockInfo::new("snow").resistance(1.0),
+    BlockInfo::new("cactus").transparent().resistance(2.0),
+    BlockInfo::new("clay").resistance(3.0),
+    BlockInfo::new("sugar_cane").non_solid(),
+    BlockInfo::new("jukebox").resistance(30.0),
+    BlockInfo::new("fence").height(1.5).resistance(15.0),
+    BlockInfo::new("pumpkin").resistance(5.0),
+    BlockInfo::new("netherrack").resistance(2.0),
+    BlockInfo::new("soul_sand").height(0.875).resistance(2.5),
+    BlockInfo::new("glowstone").resistance(1.5).light(15),
+    BlockInfo::new("portal").non_solid().light(11),
+    BlockInfo::new("jack_o_lantern").resistance(5.0).light(15),
+    BlockInfo::new("cake").height(0.5).resistance(2.5),
+    BlockInfo::new("unpowered_repeater").height(0.125),
+    BlockInfo::new("powered_repeater").height(0.125),
+    BlockInfo::new("locked_chest"),
+    BlockInfo::new("trapdoor").height(0.1875).resistance(15.0),
+];
+
+/// Returns the properties of the blocks with the given id.
+pub fn block_info(id: u16) -> &'static BlockInfo {
+    BLOCKS.get(id as usize).unwrap_or(&UNKNOWN)
+}
+
+/// Whether there are blocks with the given id in Beta.
+pub fn is_known_block(id: u16) -> bool {
+    (id as usize) < BLOCKS.len()
+}
+
+impl Block {
+    pub fn info(&self) -> &'static BlockInfo {
+        block_info(self.id)
+    }
+}
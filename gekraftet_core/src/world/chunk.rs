@@ -1,5 +1,7 @@
-use std::ops::Deref;
-use cgmath::Vector3;
+use std::collections::HashMap;
+use std::ops::{ Deref, DerefMut };
+use cgmath::{ EuclideanSpace, Point3, Vector3 };
+use crate::nbt::ValueData;
 use crate::utils::{ lerp, PartialArray, PartialHeapArray };
 use super::*;
 
@@ -7,6 +9,7 @@ use super::*;
 pub struct Chunk {
     position: ChunkPos,
     sections: [Section; CHUNK_LENGTH_Y / SECTION_LENGTH_Y], 
+    block_entities: HashMap<BlockPos, BlockEntity>,
 }
 
 #[derive(Clone, Debug)]
@@ -15,24 +18,88 @@ pub struct Section {
 }
 
 impl Chunk {
-    pub fn new<A, G>(at: A, noise: &mut Noise<G>) -> Self 
+    pub fn new<A, G>(at: A, noise: &Noise<G>) -> Self 
         where A: Into<ChunkPos>,
               G: NoiseGen
     {
+        Self::from_terrain(at, noise, DEFAULT_BASE_HEIGHT, 1.0)
+    }
+
+    /// Generates a chunk out of a density noise. A block is solid when the
+    /// noise, multiplied by `height_scale`, plus `base_height` is above its
+    /// y coordinate.
+    pub(crate) fn from_terrain<A, G>(
+        at: A,
+        noise: &Noise<G>,
+        base_height: f64,
+        height_scale: f64,
+    ) -> Self 
+        where A: Into<ChunkPos>,
+              G: NoiseGen
+    {
+        const SECTIONS: usize = CHUNK_LENGTH_Y / SECTION_LENGTH_Y;
+        const SAMPLES_Y: usize = NOISE_SAMPLES_Y * SECTIONS + 1;
+
         let at = at.into();
+        let ChunkPos(pos) = at;
+
+        // Adjacent sections share a layer of noise samples, so sampling the
+        // whole chunk at once takes 585 samples instead of the 720 taken by
+        // sampling every section separately, about a fifth fewer.
+        let origin = Vector3::new(
+            pos.x * CHUNK_LENGTH_X as i32,
+            pos.y * CHUNK_LENGTH_Y as i32,
+            pos.z * CHUNK_LENGTH_Z as i32,
+        );
+        let mut samples = vec![0.0; (NOISE_SAMPLES_X + 1) * SAMPLES_Y * (NOISE_SAMPLES_Z + 1)];
+        noise.generate_noise_lattice(
+            Point3::from_vec(origin.cast::<f32>().unwrap()),
+            NOISE_STEP,
+            [NOISE_SAMPLES_X + 1, SAMPLES_Y, NOISE_SAMPLES_Z + 1],
+            &mut samples,
+        );
 
         // Avoid unnecessary copies with MaybeUninit
-        let mut sections = PartialArray::<Section, 16>::new();
+        let mut sections = PartialArray::<Section, SECTIONS>::new();
+
+        for i in 0..SECTIONS {
+            let sect = SectionPos::new(pos.x, pos.y * 16 + i as i32, pos.z);
 
-        for i in 0..(CHUNK_LENGTH_Y / SECTION_LENGTH_Y) as i32 {
-            let ChunkPos(pos) = at;
-            let sect = SectionPos::new(pos.x, pos.y * 16 + i, pos.z);
-            sections.push(Section::new(sect, noise)).unwrap();
+            let mut noises = [[[0.0; NOISE_SAMPLES_X + 1]; NOISE_SAMPLES_Y + 1]; NOISE_SAMPLES_Z + 1];
+            for (x, noises) in noises.iter_mut().enumerate() {
+                for (y, noises) in noises.iter_mut().enumerate() {
+                    let start = (x * SAMPLES_Y + i * NOISE_SAMPLES_Y + y) * (NOISE_SAMPLES_Z + 1);
+                    noises.copy_from_slice(&samples[start..start + NOISE_SAMPLES_Z + 1]);
+                }
+            }
+
+            sections.push(Section::from_noise(sect, &noises, base_height, height_scale)).unwrap();
         };
 
         Self {
             position: at,
-            sections: sections.into_full_array().unwrap()
+            sections: sections.into_full_array().unwrap(),
+            block_entities: HashMap::new(),
+        }
+    }
+
+    /// Creates a chunk filled with air.
+    pub fn empty<A: Into<ChunkPos>>(at: A) -> Self {
+        Self {
+            position: at.into(),
+            sections: std::array::from_fn(|_| Section::empty()),
+            block_entities: HashMap::new(),
+        }
+    }
+
+    pub fn from_sections<A: Into<ChunkPos>>(
+        at: A,
+        sections: [Section; CHUNK_LENGTH_Y / SECTION_LENGTH_Y],
+    ) -> Self {
+        Self {
+            position: at.into(),
+            sections,
+            block_entities: HashMap::new(),
         }
     }
 
@@ -43,6 +110,76 @@ impl Chunk {
     pub fn sections(&self) -> &[Section] {
         self.sections.as_ref()
     }
+
+    pub fn sections_mut(&mut self) -> &mut [Section] {
+        self.sections.as_mut()
+    }
+
+    /// Returns the block at the given coordinates, relative to the chunk.
+    pub fn block(&self, x: usize, y: usize, z: usize) -> &Block {
+        &self.sections[y / SECTION_LENGTH_Y][x][z][y % SECTION_LENGTH_Y]
+    }
+
+    /// Replaces the block at the given coordinates, relative to the chunk.
+    pub fn set_block(&mut self, x: usize, y: usize, z: usize, block: Block) {
+        self.sections[y / SECTION_LENGTH_Y][x][z][y % SECTION_LENGTH_Y] = block;
+    }
+
+    /// The extra data of the blocks in the chunk which need it (such as the
+    /// items in a chest), keyed by their position in the world.
+    pub fn block_entities(&self) -> &HashMap<BlockPos, BlockEntity> {
+        &self.block_entities
+    }
+
+    pub fn block_entities_mut(&mut self) -> &mut HashMap<BlockPos, BlockEntity> {
+        &mut self.block_entities
+    }
+
+    /// Whether the block at the given position in the world is in the chunk.
+    fn contains(&self, at: BlockPos) -> bool {
+        let chunk = ChunkPos::from(at);
+        chunk.x == self.position.x && chunk.z == self.position.z
+    }
+
+    /// Writes the block entities of the chunk as its `TileEntities` list.
+    pub fn block_entities_to_nbt(&self) -> ValueData {
+        let mut entities = self.block_entities.iter().collect::<Vec<_>>();
+        entities.sort_by_key(|(at, _)| (at.y, at.z, at.x));
+
+        ValueData::List(10, entities.into_iter()
+            .map(|(&at, entity)| ValueData::Compound(entity.to_nbt(at)))
+            .collect())
+    }
+
+    /// Reads the `TileEntities` list of the chunk, replacing the block
+    /// entities it already has. Block entities which are unknown, invalid or
+    /// outside of the chunk are skipped, along with the reason why.
+    pub fn load_block_entities_nbt(&mut self, entities: &[ValueData]) -> Vec<BlockEntityError> {
+        let mut errors = Vec::new();
+        self.block_entities.clear();
+
+        for entity in entities {
+            let tag = match entity.compound() {
+                Some(tag) => tag,
+                None => {
+                    errors.push(BlockEntityError::InvalidTag("TileEntities"));
+                    continue
+                },
+            };
+
+            match BlockEntity::from_nbt(tag) {
+                Ok((at, _)) if !self.contains(at) => {
+                    errors.push(BlockEntityError::InvalidTag("x"));
+                },
+                Ok((at, entity)) => {
+                    self.block_entities.insert(at, entity);
+                },
+                Err(e) => errors.push(e),
+            }
+        }
+
+        errors
+    }
 }
 
 impl Deref for Section {
@@ -53,32 +190,58 @@ impl Deref for Section {
     }
 }
 
+impl DerefMut for Section {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.blocks
+    }
+}
+
 impl Section {
-    pub fn new<G>(at: SectionPos, noise: &mut Noise<G>) -> Self 
+    /// Creates a section filled with air.
+    pub fn empty() -> Self {
+        Self::filled(Block::new(block_ids::AIR))
+    }
+
+    /// Creates a section where every block is `block`.
+    pub fn filled(block: Block) -> Self {
+        Self {
+            blocks: Box::new([[[block; SECTION_LENGTH_Y]; SECTION_LENGTH_Z]; SECTION_LENGTH_X]),
+        }
+    }
+
+    pub fn new<G>(at: SectionPos, noise: &Noise<G>) -> Self 
         where G: NoiseGen
     {
         let SectionPos(pos) = at;
         let starting = pos * SECTION_LENGTH_X as i32;
 
+        let mut samples = [0.0; (NOISE_SAMPLES_X + 1) * (NOISE_SAMPLES_Y + 1) * (NOISE_SAMPLES_Z + 1)];
+        noise.generate_noise_lattice(
+            starting.cast::<f32>().unwrap(),
+            NOISE_STEP,
+            [NOISE_SAMPLES_X + 1, NOISE_SAMPLES_Y + 1, NOISE_SAMPLES_Z + 1],
+            &mut samples,
+        );
+
         let mut noises = [[[0.0; NOISE_SAMPLES_X + 1]; NOISE_SAMPLES_Y + 1]; NOISE_SAMPLES_Z + 1];
-        
-        for x in 0..=NOISE_SAMPLES_X as i32 {
-            for z in 0..=NOISE_SAMPLES_Z as i32 {
-                for y in 0..=NOISE_SAMPLES_Y as i32 {
-                    let relative_pos = Vector3::<i32>::new(
-                        x * NOISE_FACTOR_X as i32,
-                        y * NOISE_FACTOR_Y as i32,
-                        z * NOISE_FACTOR_Z as i32
-                    );
-
-                    let block_pos = (starting + relative_pos).cast::<f32>().unwrap();
-                    let noise = noise.generate_noise(block_pos);
-                    let (x, y, z) = (x as usize, y as usize, z as usize);
-                    noises[x][y][z] = noise;
-                }
-            }
+        for (noises, samples) in noises.iter_mut().flatten().zip(samples.chunks_exact(NOISE_SAMPLES_Z + 1)) {
+            noises.copy_from_slice(samples);
         }
 
+        Self::from_noise(at, &noises, DEFAULT_BASE_HEIGHT, 1.0)
+    }
+
+    /// Builds a section out of the noise sampled at the corners of its
+    /// interpolation cells.
+    fn from_noise(
+        at: SectionPos,
+        noises: &[[[f64; NOISE_SAMPLES_X + 1]; NOISE_SAMPLES_Y + 1]; NOISE_SAMPLES_Z + 1],
+        base_height: f64,
+        height_scale: f64,
+    ) -> Self {
+        let SectionPos(pos) = at;
+        let starting = pos * SECTION_LENGTH_X as i32;
+
         // This is allocated on heap because otherwise we will blow the stack up.
         let mut blox = PartialHeapArray::<_, SECTION_LENGTH_Y>::new();
 
@@ -122,10 +285,10 @@ impl Section {
                         lerp(lerp0, lerp1, w)
                     };
 
-                    let id = if noise + 64.0 - actual_pos.y as f64 > 0.0 {
-                        1
+                    let id = if noise * height_scale + base_height - actual_pos.y as f64 > 0.0 {
+                        block_ids::STONE
                     } else {
-                        0
+                        block_ids::AIR
                     };
 
                     bloy.push(Block::new(id)).unwrap();
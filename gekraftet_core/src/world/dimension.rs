@@ -0,0 +1,71 @@
+use std::path::{ Path, PathBuf };
+use cgmath::Point3;
+use super::*;
+
+/// The dimensions of a world. Each of them has its own chunks, saved in its
+/// own directory, and its own generator.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum Dimension {
+    #[default]
+    Overworld,
+    Nether,
+}
+
+impl Dimension {
+    pub const ALL: [Dimension; 2] = [Self::Overworld, Self::Nether];
+
+    /// The id of the dimension, as sent in the `LoginRequest` and `Respawn`
+    /// packets and saved in the player files.
+    pub fn id(self) -> i32 {
+        match self {
+            Self::Overworld => 0,
+            Self::Nether => -1,
+        }
+    }
+
+    pub fn from_id(id: i32) -> Option<Self> {
+        Self::ALL.iter().copied().find(|d| d.id() == id)
+    }
+
+    /// The directory the dimension is saved in, in the directory of the
+    /// world. The overworld is saved in the world directory itself, and the
+    /// other dimensions in `DIM<id>`, such as `DIM-1` for the Nether.
+    pub fn directory<P: AsRef<Path>>(self, world: P) -> PathBuf {
+        match self {
+            Self::Overworld => world.as_ref().to_path_buf(),
+            _ => world.as_ref().join(format!("DIM{}", self.id())),
+        }
+    }
+
+    /// How many blocks of the overworld a block of the dimension stands for,
+    /// across. Travelling a block in the Nether is travelling 8 blocks in the
+    /// overworld.
+    pub fn scale(self) -> f64 {
+        match self {
+            Self::Overworld => 1.0,
+            Self::Nether => 8.0,
+        }
+    }
+
+    /// Whether the sky lights the dimension. The Nether has a ceiling of
+    /// bedrock instead.
+    pub fn has_sky(self) -> bool {
+        self == Self::Overworld
+    }
+
+    /// Where a position of this dimension is in another dimension. Only the
+    /// horizontal coordinates are scaled.
+    pub fn convert_position(self, to: Dimension, position: Point3<f64>) -> Point3<f64> {
+        let factor = self.scale() / to.scale();
+        Point3::new(position.x * factor, position.y, position.z * factor)
+    }
+
+    /// Creates the generator of the dimension. The overworld is shaped by the
+    /// preset, and the other dimensions ignore it.
+    pub fn generator(self, preset: WorldPreset, seed: u64) -> Box<dyn WorldGenerator> {
+        match self {
+            Self::Overworld => preset.into_generator(seed),
+            Self::Nether => Box::new(NetherGenerator::new(seed)),
+        }
+    }
+}
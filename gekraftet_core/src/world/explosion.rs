@@ -0,0 +1,211 @@
+use std::collections::HashSet;
+use cgmath::{ InnerSpace, Point3, Vector3 };
+use rand::Rng;
+use crate::entity::{ EntityKind, TNT_FUSE };
+use crate::item::block_drop;
+use crate::physics::Body;
+use crate::utils::Random;
+use super::*;
+
+// The rays of an explosion leave from its center towards the blocks on the
+// border of a cube this many blocks wide.
+const RAYS_PER_EDGE: i32 = 16;
+// The distance between two points a ray checks.
+const RAY_STEP: f32 = 0.3;
+// The chance for a destroyed block to drop its item.
+const DROP_CHANCE: f32 = 0.3;
+
+/// The blocks destroyed by an explosion. This follows the rules of Beta:
+/// rays leave from the center in every direction, losing strength as they go
+/// and as they cross blocks, depending on the blast resistance of the blocks.
+/// Every block reached by a ray while it still has strength left is
+/// destroyed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Explosion {
+    pub center: Point3<f64>,
+    /// The power of the explosion: 4 for TNT, 3 for creepers.
+    pub power: f32,
+    /// The blocks reached by the explosion, air included, in the order they
+    /// were reached in.
+    pub blocks: Vec<BlockPos>,
+}
+
+impl Explosion {
+    /// Finds the blocks an explosion at the given position reaches. Blocks in
+    /// chunks which are not loaded are left alone.
+    pub fn new(level: &Level, center: Point3<f64>, power: f32, rng: &mut Random) -> Self {
+        let mut seen = HashSet::new();
+        let mut blocks = Vec::new();
+        let last = RAYS_PER_EDGE - 1;
+
+        for i in 0..RAYS_PER_EDGE {
+            for j in 0..RAYS_PER_EDGE {
+                for k in 0..RAYS_PER_EDGE {
+                    let on_border = [i, j, k].iter().any(|&n| n == 0 || n == last);
+                    if !on_border {
+                        continue
+                    }
+
+                    let direction = Vector3::new(i, j, k)
+                        .cast::<f64>().unwrap()
+                        .map(|n| n / last as f64 * 2.0 - 1.0)
+                        .normalize() * RAY_STEP as f64;
+
+                    let mut strength = power * (0.7 + rng.gen::<f32>() * 0.6);
+                    let mut position = center;
+
+                    while strength > 0.0 {
+                        let at = BlockPos::new(
+                            position.x.floor() as i32,
+                            position.y.floor() as i32,
+                            position.z.floor() as i32,
+                        );
+
+                        let block = match level.block(at) {
+                            Some(block) => block,
+                            None => break,
+                        };
+
+                        if block.id != block_ids::AIR {
+                            strength -= (block.info().resistance / 5.0 + 0.3) * RAY_STEP;
+                        }
+
+                        if strength > 0.0 && seen.insert(at) {
+                            blocks.push(at);
+                        }
+
+                        position += direction;
+                        strength -= RAY_STEP * 0.75;
+                    }
+                }
+            }
+        }
+
+        Self { center, power, blocks }
+    }
+
+    /// The reached blocks as sent in the `Explosion` packet: their offsets
+    /// from the block of the center, rounded towards zero as the client does.
+    pub fn records(&self) -> Vec<[u8; 3]> {
+        let origin = [self.center.x as i32, self.center.y as i32, self.center.z as i32];
+
+        self.blocks.iter()
+            .map(|at| [
+                (at.x - origin[0]) as i8 as u8,
+                (at.y - origin[1]) as i8 as u8,
+                (at.z - origin[2]) as i8 as u8,
+            ])
+            .collect()
+    }
+
+    /// The damage dealt to a body, and the velocity it is pushed away with.
+    /// Bodies further away than twice the power are not hit, and the damage
+    /// is lower the more of the body is hidden behind solid blocks.
+    pub fn impact(&self, level: &Level, body: &Body) -> Option<(i16, Vector3<f64>)> {
+        let size = self.power as f64 * 2.0;
+        let offset = body.position - self.center;
+        let distance = offset.magnitude();
+        let scaled = distance / size;
+
+        if scaled > 1.0 || distance == 0.0 {
+            return None
+        }
+
+        let exposure = (1.0 - scaled) * self.exposure(level, body);
+        let damage = ((exposure * exposure + exposure) / 2.0 * 8.0 * size + 1.0) as i16;
+
+        Some((damage, offset / distance * exposure))
+    }
+
+    /// The share of points of the body which the center can be seen from.
+    fn exposure(&self, level: &Level, body: &Body) -> f64 {
+        let aabb = body.aabb();
+        let size = aabb.max - aabb.min;
+        let steps = size.map(|n| (n * 2.0 + 1.0).recip());
+
+        let mut seen = 0;
+        let mut total = 0;
+
+        let mut x = 0.0;
+        while x <= 1.0 {
+            let mut y = 0.0;
+            while y <= 1.0 {
+                let mut z = 0.0;
+                while z <= 1.0 {
+                    let point = aabb.min + Vector3::new(size.x * x, size.y * y, size.z * z);
+                    let towards = self.center - point;
+
+                    let hidden = raycast(
+                        point.cast().unwrap(),
+                        towards.cast().unwrap(),
+                        towards.magnitude() as f32,
+                        |at| level.block(at).is_some_and(|b| b.info().solid),
+                    );
+
+                    if hidden.is_none() {
+                        seen += 1;
+                    }
+                    total += 1;
+                    z += steps.z;
+                }
+                y += steps.y;
+            }
+            x += steps.x;
+        }
+
+        seen as f64 / total as f64
+    }
+
+    /// Destroys the reached blocks, and records the explosion for the players
+    /// watching the level. Some of the blocks drop their items, and TNT is
+    /// primed with a short fuse rather than destroyed.
+    pub fn destroy_blocks(&self, level: &mut Level, rng: &mut Random) {
+        level.push_event(BlockEvent::Explosion {
+            center: self.center,
+            power: self.power,
+            records: self.records(),
+        });
+
+        for &at in self.blocks.iter().rev() {
+            let block = match level.block(at) {
+                Some(block) if block.id != block_ids::AIR => block,
+                _ => continue,
+            };
+
+            if rng.gen::<f32>() < DROP_CHANCE {
+                if let Some(stack) = block_drop(block, rng) {
+                    let position = Point3::new(
+                        at.x as f64 + rng.gen::<f64>() * 0.7 + 0.15,
+                        at.y as f64 + rng.gen::<f64>() * 0.7 + 0.15,
+                        at.z as f64 + rng.gen::<f64>() * 0.7 + 0.15,
+                    );
+                    level.spawn_entity(EntityKind::item(stack), position);
+                }
+            }
+
+            level.set_block_with_notify(at, Block::new(block_ids::AIR));
+
+            if block.id == block_ids::TNT {
+                let fuse = rng.gen_range(0, TNT_FUSE / 4) + TNT_FUSE / 8;
+                level.spawn_entity(EntityKind::PrimedTnt { fuse }, block_center(at));
+            }
+        }
+    }
+}
+
+/// The feet of an entity standing in the middle of a block.
+fn block_center(at: BlockPos) -> Point3<f64> {
+    Point3::new(at.x as f64 + 0.5, at.y as f64, at.z as f64 + 0.5)
+}
+
+/// Called when TNT is placed, or when a neighbour of it changes. `source` is
+/// the id of the block which caused the change, if any. TNT which is powered
+/// is primed.
+pub(crate) fn on_tnt_changed(level: &mut Level, at: BlockPos, source: Option<u16>) {
+    if source.is_some_and(|id| !redstone::is_power_source(id)) || !redstone::is_powered(level, at) {
+        return
+    }
+
+    level.set_block_with_notify(at, Block::new(block_ids::AIR));
+    level.spawn_entity(EntityKind::PrimedTnt { fuse: TNT_FUSE }, block_center(at));
+}
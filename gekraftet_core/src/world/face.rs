@@ -0,0 +1,77 @@
+use cgmath::Vector3;
+
+/// A face of a block. The names follow the `Face` bitfield of the client:
+/// left and right are the faces along the x axis, front and back the ones
+/// along the z axis.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum BlockFace {
+    /// Facing +y.
+    Top,
+    /// Facing -y.
+    Bottom,
+    /// Facing -x.
+    Left,
+    /// Facing +x.
+    Right,
+    /// Facing +z.
+    Front,
+    /// Facing -z.
+    Back,
+}
+
+impl BlockFace {
+    pub const ALL: [BlockFace; 6] = [
+        Self::Top, Self::Bottom, Self::Left, Self::Right, Self::Front, Self::Back,
+    ];
+
+    /// The bit of this face in the `Face` bitfield of the client.
+    pub fn bitfield(self) -> u8 {
+        match self {
+            Self::Top => 1,
+            Self::Bottom => 2,
+            Self::Left => 4,
+            Self::Right => 8,
+            Self::Front => 16,
+            Self::Back => 32,
+        }
+    }
+
+    /// The number used for this face by the packets of the protocol.
+    pub fn protocol_id(self) -> i8 {
+        match self {
+            Self::Bottom => 0,
+            Self::Top => 1,
+            Self::Back => 2,
+            Self::Front => 3,
+            Self::Left => 4,
+            Self::Right => 5,
+        }
+    }
+
+    pub fn from_protocol_id(id: i8) -> Option<Self> {
+        Self::ALL.iter().copied().find(|face| face.protocol_id() == id)
+    }
+
+    /// The direction this face is facing.
+    pub fn normal(self) -> Vector3<i32> {
+        match self {
+            Self::Top => Vector3::new(0, 1, 0),
+            Self::Bottom => Vector3::new(0, -1, 0),
+            Self::Left => Vector3::new(-1, 0, 0),
+            Self::Right => Vector3::new(1, 0, 0),
+            Self::Front => Vector3::new(0, 0, 1),
+            Self::Back => Vector3::new(0, 0, -1),
+        }
+    }
+
+    pub fn opposite(self) -> Self {
+        match self {
+            Self::Top => Self::Bottom,
+            Self::Bottom => Self::Top,
+            Self::Left => Self::Right,
+            Self::Right => Self::Left,
+            Self::Front => Self::Back,
+            Self::Back => Self::Front,
+        }
+    }
+}
@@ -0,0 +1,34 @@
+use cgmath::Point3;
+use crate::entity::EntityKind;
+use super::*;
+
+/// The ticks between sand or gravel losing its support and it falling.
+pub const FALLING_TICK_RATE: u64 = 3;
+
+/// Whether blocks of the given id fall when there is nothing below them.
+pub fn falls(id: u16) -> bool {
+    id == block_ids::SAND || id == block_ids::GRAVEL
+}
+
+/// Whether falling blocks fall through the block, or replace it when they
+/// land in it.
+pub fn can_fall_into(block: Block) -> bool {
+    matches!(block.id, block_ids::AIR | block_ids::FIRE) || Fluid::of(block).is_some()
+}
+
+/// Called when sand or gravel is placed, or when a neighbour changes.
+pub(crate) fn on_changed(level: &mut Level, at: BlockPos, block: Block) {
+    level.schedule_tick(at, block.id, FALLING_TICK_RATE, 0);
+}
+
+/// Turns sand or gravel which has nothing below it into a falling block.
+pub(crate) fn on_scheduled_tick(level: &mut Level, at: BlockPos, block: Block) {
+    let below = at.offset(0, -1, 0);
+    if !level.block(below).is_some_and(can_fall_into) {
+        return
+    }
+
+    level.set_block_with_notify(at, Block::new(block_ids::AIR));
+    let position = Point3::new(at.x as f64 + 0.5, at.y as f64, at.z as f64 + 0.5);
+    level.spawn_entity(EntityKind::FallingBlock { block }, position);
+}
@@ -0,0 +1,475 @@
+use crate::utils::Random;
+use rand::Rng;
+use super::*;
+
+// The metadata of a fluid is its level: 0 for a source, then 1 to 7 further
+// away from it. Fluids falling down have this bit set on top of the level of
+// the fluid they fall from.
+pub const FALLING: u16 = 8;
+
+/// The ticks between two updates of flowing water.
+pub const WATER_TICK_RATE: u64 = 5;
+/// The ticks between two updates of flowing lava.
+pub const LAVA_TICK_RATE: u64 = 30;
+
+// The horizontal directions, in the order Beta checks them.
+const SIDES: [BlockFace; 4] = [BlockFace::Left, BlockFace::Right, BlockFace::Back, BlockFace::Front];
+
+// How far ahead flowing fluids look for a way down.
+const MAX_SEARCH_DEPTH: u32 = 4;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Fluid {
+    Water,
+    Lava,
+}
+
+impl Fluid {
+    /// Which fluid the block is, still or flowing.
+    pub fn of(block: Block) -> Option<Self> {
+        match block.id {
+            block_ids::FLOWING_WATER | block_ids::WATER => Some(Self::Water),
+            block_ids::FLOWING_LAVA | block_ids::LAVA => Some(Self::Lava),
+            _ => None,
+        }
+    }
+
+    pub fn flowing_id(self) -> u16 {
+        match self {
+            Self::Water => block_ids::FLOWING_WATER,
+            Self::Lava => block_ids::FLOWING_LAVA,
+        }
+    }
+
+    pub fn still_id(self) -> u16 {
+        match self {
+            Self::Water => block_ids::WATER,
+            Self::Lava => block_ids::LAVA,
+        }
+    }
+
+    pub fn tick_rate(self) -> u64 {
+        match self {
+            Self::Water => WATER_TICK_RATE,
+            Self::Lava => LAVA_TICK_RATE,
+        }
+    }
+
+    /// How much the level drops from a block to the next.
+    fn level_drop(self) -> u16 {
+        match self {
+            Self::Water => 1,
+            Self::Lava => 2,
+        }
+    }
+}
+
+/// The metadata of the fluid at the given position, if it is the given fluid.
+fn level_of(level: &Level, at: BlockPos, fluid: Fluid) -> Option<u16> {
+    level.block(at)
+        .filter(|&b| Fluid::of(b) == Some(fluid))
+        .map(|b| b.metadata)
+}
+
+/// Whether fluids cannot flow into the block.
+fn blocks_flow(level: &Level, at: BlockPos) -> bool {
+    match level.block(at) {
+        // Flowing into chunks which are not loaded would lose the fluid.
+        None => true,
+        Some(block) => match block.id {
+            block_ids::WOODEN_DOOR | block_ids::IRON_DOOR | block_ids::SIGN_POST |
+            block_ids::WALL_SIGN | block_ids::LADDER | block_ids::SUGAR_CANE => true,
+            block_ids::AIR => false,
+            _ => block.info().solid,
+        },
+    }
+}
+
+/// Whether a fluid flowing into the block replaces it.
+fn can_displace(level: &Level, at: BlockPos, fluid: Fluid) -> bool {
+    match level.block(at).and_then(Fluid::of) {
+        Some(other) if other == fluid || other == Fluid::Lava => false,
+        _ => !blocks_flow(level, at),
+    }
+}
+
+/// Whether a fluid can spread into the block without stopping there: it is
+/// neither in the way nor a source of the same fluid.
+fn is_open(level: &Level, at: BlockPos, fluid: Fluid) -> bool {
+    !blocks_flow(level, at) && level_of(level, at, fluid) != Some(0)
+}
+
+/// How many blocks away the closest way down is when going towards `from`,
+/// up to `MAX_SEARCH_DEPTH` blocks.
+fn flow_cost(level: &Level, at: BlockPos, fluid: Fluid, depth: u32, from: BlockFace) -> u32 {
+    let mut cost = 1000;
+
+    for &side in SIDES.iter().filter(|&&side| side != from.opposite()) {
+        let next = at.neighbor(side);
+        if !is_open(level, next, fluid) {
+            continue
+        }
+
+        if !blocks_flow(level, next.offset(0, -1, 0)) {
+            return depth
+        }
+
+        if depth < MAX_SEARCH_DEPTH {
+            cost = cost.min(flow_cost(level, next, fluid, depth + 1, side));
+        }
+    }
+
+    cost
+}
+
+/// The sides a fluid spreads to: the ones leading to the closest way down.
+fn flow_directions(level: &Level, at: BlockPos, fluid: Fluid) -> [bool; 4] {
+    let mut costs = [1000; 4];
+
+    for (cost, &side) in costs.iter_mut().zip(SIDES.iter()) {
+        let next = at.neighbor(side);
+        if !is_open(level, next, fluid) {
+            continue
+        }
+
+        *cost = if blocks_flow(level, next.offset(0, -1, 0)) {
+            flow_cost(level, next, fluid, 1, side)
+        } else {
+            0
+        };
+    }
+
+    let min = *costs.iter().min().unwrap();
+    let mut result = [false; 4];
+    for (flows, &cost) in result.iter_mut().zip(costs.iter()) {
+        *flows = cost == min;
+    }
+    result
+}
+
+fn flow_into(level: &mut Level, at: BlockPos, fluid: Fluid, metadata: u16) {
+    if can_displace(level, at, fluid) {
+        level.set_block_with_notify(at, Block::with_metadata(fluid.flowing_id(), metadata));
+    }
+}
+
+/// Turns lava touching water into obsidian if it is a source, or into
+/// cobblestone if it is close to its source. Returns true if it did.
+pub(crate) fn harden(level: &mut Level, at: BlockPos) -> bool {
+    let block = match level.block(at) {
+        Some(block) if Fluid::of(block) == Some(Fluid::Lava) => block,
+        _ => return false,
+    };
+
+    let touches_water = BlockFace::ALL.iter()
+        .filter(|&&face| face != BlockFace::Bottom)
+        .any(|&face| level.block(at.neighbor(face)).and_then(Fluid::of) == Some(Fluid::Water));
+
+    if !touches_water {
+        return false
+    }
+
+    match block.metadata {
+        0 => level.set_block_with_notify(at, Block::new(block_ids::OBSIDIAN)),
+        1..=4 => level.set_block_with_notify(at, Block::new(block_ids::COBBLESTONE)),
+        _ => return false,
+    };
+    true
+}
+
+/// Called when a fluid block is placed.
+pub(crate) fn on_added(level: &mut Level, at: BlockPos) {
+    if harden(level, at) {
+        return
+    }
+
+    if let Some(block) = level.block(at) {
+        if let Some(fluid) = Fluid::of(block).filter(|f| f.flowing_id() == block.id) {
+            level.schedule_tick(at, block.id, fluid.tick_rate(), 0);
+        }
+    }
+}
+
+/// Called when a block next to a fluid changes. Still fluids start flowing
+/// again, since they might have somewhere to go.
+pub(crate) fn on_neighbor_changed(level: &mut Level, at: BlockPos) {
+    if harden(level, at) {
+        return
+    }
+
+    let block = match level.block(at) {
+        Some(block) => block,
+        None => return,
+    };
+
+    if let Some(fluid) = Fluid::of(block).filter(|f| f.still_id() == block.id) {
+        level.set_block(at, Block::with_metadata(fluid.flowing_id(), block.metadata));
+        level.schedule_tick(at, fluid.flowing_id(), fluid.tick_rate(), 0);
+    }
+}
+
+/// Spreads a flowing fluid. This follows the rules of Beta:
+///
+/// * A fluid takes the level of its highest neighbour, plus one for water or
+///   two for lava, and dries out when that is above 7.
+/// * Fluids below another fluid are falling, and keep the level of the fluid
+///   above them.
+/// * Water next to two sources, above a solid block or another source,
+///   becomes a source as well.
+/// * Fluids fall down if they can, and lava falling onto water turns it
+///   into stone. Otherwise they spread sideways towards the
+///   closest way down, looking up to 4 blocks away.
+pub(crate) fn on_scheduled_tick(level: &mut Level, at: BlockPos, rng: &mut Random) {
+    let block = match level.block(at) {
+        Some(block) => block,
+        None => return,
+    };
+
+    let fluid = match Fluid::of(block).filter(|f| f.flowing_id() == block.id) {
+        Some(fluid) => fluid,
+        None => return,
+    };
+
+    let mut current = block.metadata;
+    let drop = fluid.level_drop();
+    let mut settles = true;
+
+    if current > 0 {
+        let mut highest: Option<u16> = None;
+        let mut sources = 0;
+
+        for &side in SIDES.iter() {
+            if let Some(neighbor) = level_of(level, at.neighbor(side), fluid) {
+                if neighbor == 0 {
+                    sources += 1;
+                }
+
+                // Falling fluids spread as if they were sources.
+                let neighbor = if neighbor >= FALLING { 0 } else { neighbor };
+                highest = Some(highest.map_or(neighbor, |h| h.min(neighbor)));
+            }
+        }
+
+        let mut next = match highest {
+            Some(highest) if highest + drop < FALLING => Some(highest + drop),
+            _ => None,
+        };
+
+        if let Some(above) = level_of(level, at.offset(0, 1, 0), fluid) {
+            next = Some(if above >= FALLING { above } else { above + FALLING });
+        }
+
+        if sources >= 2 && fluid == Fluid::Water {
+            let below = at.offset(0, -1, 0);
+            if level.block(below).is_some_and(|b| b.info().solid) || level_of(level, below, fluid) == Some(0) {
+                next = Some(0);
+            }
+        }
+
+        // Lava spreads out slowly and unevenly.
+        if fluid == Fluid::Lava && current < FALLING {
+            let spreads = next.is_some_and(|n| n < FALLING && n > current);
+            if spreads && rng.gen_range(0, 4) != 0 {
+                next = Some(current);
+                settles = false;
+            }
+        }
+
+        if next != Some(current) {
+            match next {
+                None => {
+                    level.set_block_with_notify(at, Block::new(block_ids::AIR));
+                    return
+                },
+                Some(next) => {
+                    current = next;
+                    level.set_block(at, Block::with_metadata(fluid.flowing_id(), next));
+                    level.schedule_tick(at, fluid.flowing_id(), fluid.tick_rate(), 0);
+                    level.notify_neighbors(at);
+                },
+            }
+        } else if settles {
+            level.set_block(at, Block::with_metadata(fluid.still_id(), current));
+        }
+    } else {
+        level.set_block(at, Block::with_metadata(fluid.still_id(), current));
+    }
+
+    let below = at.offset(0, -1, 0);
+    if fluid == Fluid::Lava && level.block(below).and_then(Fluid::of) == Some(Fluid::Water) {
+        // Lava falling onto water turns it into stone.
+        level.set_block_with_notify(below, Block::new(block_ids::STONE));
+    } else if can_displace(level, below, fluid) {
+        let falling = if current >= FALLING { current } else { current + FALLING };
+        level.set_block_with_notify(below, Block::with_metadata(fluid.flowing_id(), falling));
+    } else if current == 0 || blocks_flow(level, below) {
+        let spread = if current >= FALLING { 1 } else { current + drop };
+        if spread >= FALLING {
+            return
+        }
+
+        let directions = flow_directions(level, at, fluid);
+        for (&side, &flows) in SIDES.iter().zip(directions.iter()) {
+            if flows {
+                flow_into(level, at.neighbor(side), fluid, spread);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use super::*;
+
+    const FLOOR: i32 = 10;
+
+    /// A single chunk with a floor of stone.
+    fn level() -> Level {
+        let mut level = Level::new();
+        level.insert_chunk(Chunk::empty(ChunkPos::new(0, 0, 0)));
+        for x in 0..CHUNK_LENGTH_X as i32 {
+            for z in 0..CHUNK_LENGTH_Z as i32 {
+                level.set_block(BlockPos::new(x, FLOOR, z), Block::new(block_ids::STONE));
+            }
+        }
+        level
+    }
+
+    /// Walls in a trench one block wide along x, on the floor.
+    fn trench(level: &mut Level, z: i32, from: i32, to: i32) {
+        for x in from - 1..=to + 1 {
+            for &(dz, wall) in &[(-1, true), (0, x < from || x > to), (1, true)] {
+                if wall {
+                    level.set_block(BlockPos::new(x, FLOOR + 1, z + dz), Block::new(block_ids::STONE));
+                }
+            }
+        }
+    }
+
+    fn run(level: &mut Level, ticks: u32) {
+        let mut rng = Random::seed_from_u64(41);
+        for _ in 0..ticks {
+            level.tick(&mut rng);
+        }
+    }
+
+    fn at(level: &Level, x: i32, y: i32, z: i32) -> Block {
+        level.block(BlockPos::new(x, y, z)).unwrap()
+    }
+
+    #[test]
+    fn water_drops_off_after_seven_blocks() {
+        let mut level = level();
+        level.set_block_with_notify(BlockPos::new(8, FLOOR + 1, 8), Block::new(block_ids::FLOWING_WATER));
+        run(&mut level, 200);
+
+        for x in 0..CHUNK_LENGTH_X as i32 {
+            for z in 0..CHUNK_LENGTH_Z as i32 {
+                let distance = ((x - 8).abs() + (z - 8).abs()) as u16;
+                let block = at(&level, x, FLOOR + 1, z);
+
+                if distance <= 7 {
+                    assert_eq!(Fluid::of(block), Some(Fluid::Water), "at {}, {}", x, z);
+                    assert_eq!(block.metadata, distance, "at {}, {}", x, z);
+                    assert_eq!(block.id, block_ids::WATER, "still flowing at {}, {}", x, z);
+                } else {
+                    assert_eq!(block.id, block_ids::AIR, "at {}, {}", x, z);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn water_between_two_sources_becomes_a_source() {
+        let mut level = level();
+        trench(&mut level, 8, 4, 6);
+        level.set_block_with_notify(BlockPos::new(4, FLOOR + 1, 8), Block::new(block_ids::FLOWING_WATER));
+        level.set_block_with_notify(BlockPos::new(6, FLOOR + 1, 8), Block::new(block_ids::FLOWING_WATER));
+        run(&mut level, 50);
+
+        assert_eq!(at(&level, 5, FLOOR + 1, 8), Block::new(block_ids::WATER));
+    }
+
+    #[test]
+    fn water_above_air_does_not_become_a_source() {
+        let mut level = level();
+        trench(&mut level, 8, 4, 6);
+        // Under the middle of the trench is a well, one block deep.
+        level.set_block(BlockPos::new(5, FLOOR, 8), Block::new(block_ids::AIR));
+        level.set_block_with_notify(BlockPos::new(4, FLOOR + 1, 8), Block::new(block_ids::FLOWING_WATER));
+        level.set_block_with_notify(BlockPos::new(6, FLOOR + 1, 8), Block::new(block_ids::FLOWING_WATER));
+        run(&mut level, 50);
+
+        assert_ne!(at(&level, 5, FLOOR, 8).metadata, 0);
+        assert_eq!(Fluid::of(at(&level, 5, FLOOR, 8)), Some(Fluid::Water));
+    }
+
+    #[test]
+    fn lava_does_not_make_sources() {
+        let mut level = level();
+        trench(&mut level, 8, 4, 6);
+        level.set_block_with_notify(BlockPos::new(4, FLOOR + 1, 8), Block::new(block_ids::FLOWING_LAVA));
+        level.set_block_with_notify(BlockPos::new(6, FLOOR + 1, 8), Block::new(block_ids::FLOWING_LAVA));
+        run(&mut level, 2000);
+
+        let middle = at(&level, 5, FLOOR + 1, 8);
+        assert_eq!(Fluid::of(middle), Some(Fluid::Lava));
+        assert_eq!(middle.metadata, 2);
+    }
+
+    #[test]
+    fn lava_drops_off_after_three_blocks() {
+        let mut level = level();
+        trench(&mut level, 8, 2, 12);
+        level.set_block_with_notify(BlockPos::new(2, FLOOR + 1, 8), Block::new(block_ids::FLOWING_LAVA));
+        run(&mut level, 3000);
+
+        let levels = (2..=12)
+            .map(|x| at(&level, x, FLOOR + 1, 8))
+            .map(|b| Fluid::of(b).map(|_| b.metadata))
+            .collect::<Vec<_>>();
+        let mut expected = vec![Some(0), Some(2), Some(4), Some(6)];
+        expected.resize(11, None);
+        assert_eq!(levels, expected);
+    }
+
+    #[test]
+    fn water_flowing_onto_a_lava_source_makes_obsidian() {
+        let mut level = level();
+        trench(&mut level, 8, 2, 6);
+        level.set_block(BlockPos::new(2, FLOOR + 1, 8), Block::new(block_ids::LAVA));
+        level.set_block_with_notify(BlockPos::new(6, FLOOR + 1, 8), Block::new(block_ids::FLOWING_WATER));
+        run(&mut level, 100);
+
+        assert_eq!(at(&level, 2, FLOOR + 1, 8).id, block_ids::OBSIDIAN);
+        assert_eq!(Fluid::of(at(&level, 3, FLOOR + 1, 8)), Some(Fluid::Water));
+    }
+
+    #[test]
+    fn lava_flowing_next_to_water_makes_cobblestone() {
+        let mut level = level();
+        trench(&mut level, 8, 2, 6);
+        // Still water beside the trench, which does not flow until something
+        // changes next to it.
+        level.set_block(BlockPos::new(4, FLOOR + 1, 9), Block::new(block_ids::WATER));
+        level.set_block_with_notify(BlockPos::new(2, FLOOR + 1, 8), Block::new(block_ids::FLOWING_LAVA));
+        run(&mut level, 3000);
+
+        assert_eq!(Fluid::of(at(&level, 2, FLOOR + 1, 8)), Some(Fluid::Lava));
+        assert_eq!(at(&level, 3, FLOOR + 1, 8).metadata, 2);
+        assert_eq!(at(&level, 4, FLOOR + 1, 8).id, block_ids::COBBLESTONE);
+        assert_eq!(at(&level, 5, FLOOR + 1, 8).id, block_ids::AIR);
+    }
+
+    #[test]
+    fn lava_falling_onto_water_makes_stone() {
+        let mut level = level();
+        level.set_block(BlockPos::new(8, FLOOR + 1, 8), Block::new(block_ids::WATER));
+        level.set_block_with_notify(BlockPos::new(8, FLOOR + 3, 8), Block::new(block_ids::FLOWING_LAVA));
+        run(&mut level, 200);
+
+        assert_eq!(at(&level, 8, FLOOR + 1, 8).id, block_ids::STONE);
+        assert_eq!(Fluid::of(at(&level, 8, FLOOR + 2, 8)), Some(Fluid::Lava));
+    }
+}
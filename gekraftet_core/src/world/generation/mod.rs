@@ -0,0 +1,156 @@
+mod nether;
+mod superflat;
+mod terrain;
+
+pub use nether::{ NetherGenerator, NETHER_HEIGHT, NETHER_LAVA_LEVEL };
+pub use superflat::SuperflatGenerator;
+pub use terrain::TerrainGenerator;
+
+use super::{ Biome, Chunk, ChunkPos, DungeonFeature, StructureGenerator };
+
+// The layers of the "flat" preset: bedrock, two layers of dirt and grass.
+const FLAT_LAYERS: &str = "7,2x3,2";
+
+/// The entry point of world generation. A generator is shared by every thread
+/// generating chunks, and must always produce the same chunk for the same
+/// position.
+pub trait WorldGenerator: Send + Sync {
+    fn generate_chunk(&self, at: ChunkPos) -> Chunk;
+
+    /// Returns the biome of the block column at the given coordinates.
+    fn biome_at(&self, _x: i32, _z: i32) -> Biome {
+        Biome::Plains
+    }
+}
+
+#[derive(Debug)]
+pub enum GeneratorError {
+    UnknownPreset(String),
+    InvalidLayer(String),
+    UnknownBlock(u16),
+    /// A superflat preset lists no layers. The void preset is how a world of
+    /// nothing but air is asked for.
+    NoLayers,
+    TooManyLayers(usize),
+}
+
+/// The named world types, as selected in a configuration file.
+///
+/// ```text
+/// default              the noise-based terrain, with dungeons
+/// amplified            the same terrain, with 4 times the height
+/// flat                 superflat with the layers 7,2x3,2
+/// flat;<layers>        superflat with custom layers, see SuperflatGenerator
+/// void                 nothing but air
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum WorldPreset {
+    #[default]
+    Default,
+    Amplified,
+    Flat(SuperflatGenerator),
+    Void,
+}
+
+impl WorldPreset {
+    pub fn parse(preset: &str) -> Result<Self, GeneratorError> {
+        let mut parts = preset.splitn(2, ';');
+        let name = parts.next().unwrap_or("").trim();
+        let settings = parts.next().map(str::trim);
+
+        match (name.to_ascii_lowercase().as_str(), settings) {
+            ("default", None) => Ok(Self::Default),
+            ("amplified", None) => Ok(Self::Amplified),
+            ("flat", None) => SuperflatGenerator::from_layers(FLAT_LAYERS).map(Self::Flat),
+            ("flat", Some(layers)) => SuperflatGenerator::from_layers(layers).map(Self::Flat),
+            ("void", None) => Ok(Self::Void),
+            _ => Err(GeneratorError::UnknownPreset(preset.to_string())),
+        }
+    }
+
+    /// Creates the generator of this preset. The seed is ignored by the
+    /// superflat presets.
+    pub fn into_generator(self, seed: u64) -> Box<dyn WorldGenerator> {
+        match self {
+            Self::Default => Box::new(
+                StructureGenerator::new(TerrainGenerator::new(seed), seed)
+                    .with_feature(DungeonFeature::new())
+            ),
+            Self::Amplified => Box::new(
+                StructureGenerator::new(TerrainGenerator::new(seed).height_scale(4.0), seed)
+                    .with_feature(DungeonFeature::new())
+            ),
+            Self::Flat(generator) => Box::new(generator),
+            Self::Void => Box::new(SuperflatGenerator::new(Vec::new()).unwrap()),
+        }
+    }
+}
+
+impl<W: WorldGenerator + ?Sized> WorldGenerator for Box<W> {
+    fn generate_chunk(&self, at: ChunkPos) -> Chunk {
+        (**self).generate_chunk(at)
+    }
+
+    fn biome_at(&self, x: i32, z: i32) -> Biome {
+        (**self).biome_at(x, z)
+    }
+}
+
+impl<W: WorldGenerator + ?Sized> WorldGenerator for std::sync::Arc<W> {
+    fn generate_chunk(&self, at: ChunkPos) -> Chunk {
+        (**self).generate_chunk(at)
+    }
+
+    fn biome_at(&self, x: i32, z: i32) -> Biome {
+        (**self).biome_at(x, z)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::world::{ block_ids, Block };
+    use super::*;
+
+    #[test]
+    fn presets_are_parsed() {
+        assert_eq!(WorldPreset::parse("default").unwrap(), WorldPreset::Default);
+        assert_eq!(WorldPreset::parse(" Amplified ").unwrap(), WorldPreset::Amplified);
+        assert_eq!(WorldPreset::parse("void").unwrap(), WorldPreset::Void);
+        assert_eq!(
+            WorldPreset::parse("flat").unwrap(),
+            WorldPreset::Flat(SuperflatGenerator::from_layers(FLAT_LAYERS).unwrap()),
+        );
+
+        let flat = match WorldPreset::parse("FLAT; 7,3x1,12") {
+            Ok(WorldPreset::Flat(flat)) => flat,
+            result => panic!("{:?}", result),
+        };
+        assert_eq!(flat.layers(), &[
+            Block::new(block_ids::BEDROCK),
+            Block::new(block_ids::STONE),
+            Block::new(block_ids::STONE),
+            Block::new(block_ids::STONE),
+            Block::new(block_ids::SAND),
+        ]);
+    }
+
+    #[test]
+    fn malformed_presets_are_rejected() {
+        for preset in &["", "mountains", "default;7", "void;1"] {
+            assert!(matches!(WorldPreset::parse(preset), Err(GeneratorError::UnknownPreset(_))), "{:?}", preset);
+        }
+
+        assert!(matches!(WorldPreset::parse("flat;7,ax3"), Err(GeneratorError::InvalidLayer(_))));
+        assert!(matches!(WorldPreset::parse("flat;7,0x3"), Err(GeneratorError::InvalidLayer(_))));
+        assert!(matches!(WorldPreset::parse("flat;7;2"), Err(GeneratorError::InvalidLayer(_))));
+        assert!(matches!(WorldPreset::parse("flat;7,2x300"), Err(GeneratorError::UnknownBlock(300))));
+        assert!(matches!(WorldPreset::parse("flat;"), Err(GeneratorError::NoLayers)));
+        assert!(matches!(WorldPreset::parse("flat;300x1"), Err(GeneratorError::TooManyLayers(300))));
+    }
+
+    #[test]
+    fn the_void_preset_generates_air() {
+        let chunk = WorldPreset::Void.into_generator(0).generate_chunk(ChunkPos::new(0, 0, 0));
+        assert_eq!(*chunk.block(0, 0, 0), Block::new(block_ids::AIR));
+    }
+}
@@ -0,0 +1,264 @@
+use cgmath::{ Point3, Vector3 };
+use rand::{ Rng, SeedableRng };
+use crate::utils::Random;
+use crate::world::*;
+use super::WorldGenerator;
+
+/// The height of the Nether, from the bedrock floor to the bedrock ceiling.
+pub const NETHER_HEIGHT: usize = 128;
+/// The caves of the Nether are filled with lava up to this height.
+pub const NETHER_LAVA_LEVEL: usize = 32;
+
+// The bedrock of the floor and of the ceiling is up to this thick.
+const BEDROCK_DEPTH: i32 = 5;
+// How much more solid the terrain gets towards the floor and the ceiling,
+// which closes the caves there.
+const EDGE_DENSITY: f64 = 12.0;
+// How much of the terrain is hollowed out into caves.
+const OPENNESS: f64 = 1.0;
+// Soul sand and gravel cover the netherrack this far around the lava level.
+const SHORE_RANGE: (usize, usize) = (NETHER_LAVA_LEVEL - 1, NETHER_LAVA_LEVEL + 3);
+// How deep the soul sand and the gravel go.
+const SHORE_DEPTH: usize = 3;
+// The noise of the shores is above this for soul sand, and below its
+// opposite for gravel.
+const SHORE_THRESHOLD: f64 = 0.5;
+// The clusters of glowstone hanging from the ceiling of a chunk, and the
+// blocks tried for each of them.
+const GLOWSTONE_CLUSTERS: u32 = 10;
+const GLOWSTONE_TRIES: usize = 1500;
+
+/// The generator of the Nether: caves of netherrack between a floor and a
+/// ceiling of bedrock, a lava ocean, shores of soul sand and gravel, and
+/// glowstone hanging from the ceiling.
+pub struct NetherGenerator<G: NoiseGen = Perlin3D> {
+    noise: Noise<G>,
+    shores: Noise<G>,
+    seed: u64,
+}
+
+impl NetherGenerator<Perlin3D> {
+    pub fn new(seed: u64) -> Self {
+        Self::with_noise(
+            Noise::with_option(Self::default_option(), seed),
+            Noise::with_option(Self::shore_option(), seed.wrapping_add(1)),
+            seed,
+        )
+    }
+
+    /// The options of the density noise used by `new`.
+    pub fn default_option() -> NoiseGenOption {
+        NoiseGenOption::new()
+            .octaves(4)
+            .amplitude(4.0)
+            .persistance(0.5)
+            .frequency(0.05)
+            .lacunarity(2.0)
+    }
+
+    /// The options of the noise deciding where the shores are soul sand or
+    /// gravel, used by `new`.
+    pub fn shore_option() -> NoiseGenOption {
+        NoiseGenOption::new()
+            .octaves(2)
+            .amplitude(1.0)
+            .persistance(0.5)
+            .frequency(0.06)
+            .lacunarity(2.0)
+    }
+}
+
+impl<G: NoiseGen> NetherGenerator<G> {
+    pub fn with_noise(noise: Noise<G>, shores: Noise<G>, seed: u64) -> Self {
+        Self {
+            noise,
+            shores,
+            seed,
+        }
+    }
+
+    /// Fills the chunk with netherrack where the density noise is solid, and
+    /// with lava below the lava level elsewhere. The noise is sampled every
+    /// few blocks and interpolated in between, as in `Chunk::from_terrain`.
+    fn shape(&self, chunk: &mut Chunk) {
+        const SAMPLES_Y: usize = NETHER_HEIGHT / NOISE_FACTOR_Y + 1;
+        const SAMPLES_Z: usize = NOISE_SAMPLES_Z + 1;
+
+        let ChunkPos(pos) = chunk.position();
+        let origin = Point3::new(
+            (pos.x * CHUNK_LENGTH_X as i32) as f32,
+            0.0,
+            (pos.z * CHUNK_LENGTH_Z as i32) as f32,
+        );
+
+        let mut samples = vec![0.0; (NOISE_SAMPLES_X + 1) * SAMPLES_Y * SAMPLES_Z];
+        self.noise.generate_noise_lattice(
+            origin,
+            NOISE_STEP,
+            [NOISE_SAMPLES_X + 1, SAMPLES_Y, SAMPLES_Z],
+            &mut samples,
+        );
+        let sample = |x: usize, y: usize, z: usize| samples[(x * SAMPLES_Y + y) * SAMPLES_Z + z];
+
+        for x in 0..CHUNK_LENGTH_X {
+            for y in 0..NETHER_HEIGHT {
+                for z in 0..CHUNK_LENGTH_Z {
+                    let (sx, fx) = (x / NOISE_FACTOR_X, (x % NOISE_FACTOR_X) as f64 / NOISE_FACTOR_X as f64);
+                    let (sy, fy) = (y / NOISE_FACTOR_Y, (y % NOISE_FACTOR_Y) as f64 / NOISE_FACTOR_Y as f64);
+                    let (sz, fz) = (z / NOISE_FACTOR_Z, (z % NOISE_FACTOR_Z) as f64 / NOISE_FACTOR_Z as f64);
+
+                    let lerp = |a: f64, b: f64, t: f64| a + (b - a) * t;
+                    let along_z = |x, y| lerp(sample(x, y, sz), sample(x, y, sz + 1), fz);
+                    let along_y = |x| lerp(along_z(x, sy), along_z(x, sy + 1), fy);
+                    let noise = lerp(along_y(sx), along_y(sx + 1), fx);
+
+                    // From 0 in the middle of the Nether to 1 at the floor
+                    // and at the ceiling.
+                    let edge = (y as f64 - NETHER_HEIGHT as f64 / 2.0).abs() / (NETHER_HEIGHT as f64 / 2.0);
+                    let density = noise + EDGE_DENSITY * edge.powi(4) - OPENNESS;
+
+                    let id = if density > 0.0 {
+                        block_ids::NETHERRACK
+                    } else if y < NETHER_LAVA_LEVEL {
+                        block_ids::LAVA
+                    } else {
+                        continue
+                    };
+                    chunk.set_block(x, y, z, Block::new(id));
+                }
+            }
+        }
+    }
+
+    /// Covers the netherrack around the lava level with soul sand or gravel,
+    /// and lays the bedrock of the floor and of the ceiling.
+    fn replace_surface(&self, chunk: &mut Chunk, rng: &mut Random) {
+        let ChunkPos(pos) = chunk.position();
+
+        for x in 0..CHUNK_LENGTH_X {
+            for z in 0..CHUNK_LENGTH_Z {
+                let shore = self.shores.generate_noise(Point3::new(
+                    (pos.x * CHUNK_LENGTH_X as i32 + x as i32) as f32,
+                    0.0,
+                    (pos.z * CHUNK_LENGTH_Z as i32 + z as i32) as f32,
+                ));
+
+                let cover = if shore > SHORE_THRESHOLD {
+                    Some(block_ids::SOUL_SAND)
+                } else if shore < -SHORE_THRESHOLD {
+                    Some(block_ids::GRAVEL)
+                } else {
+                    None
+                };
+
+                // The number of netherrack blocks since the last air or lava
+                // block.
+                let mut depth = None;
+                for y in (0..NETHER_HEIGHT).rev() {
+                    if chunk.block(x, y, z).id != block_ids::NETHERRACK {
+                        depth = Some(0);
+                        continue
+                    }
+
+                    let in_range = y >= SHORE_RANGE.0 && y <= SHORE_RANGE.1;
+                    match (cover, depth) {
+                        (Some(cover), Some(d)) if in_range && d < SHORE_DEPTH => {
+                            chunk.set_block(x, y, z, Block::new(cover));
+                        },
+                        _ => {},
+                    }
+                    depth = depth.map(|d| d + 1);
+                }
+
+                for y in 0..BEDROCK_DEPTH {
+                    if y <= rng.gen_range(0, BEDROCK_DEPTH) {
+                        chunk.set_block(x, y as usize, z, Block::new(block_ids::BEDROCK));
+                    }
+
+                    let top = NETHER_HEIGHT - 1 - y as usize;
+                    if y <= rng.gen_range(0, BEDROCK_DEPTH) {
+                        chunk.set_block(x, top, z, Block::new(block_ids::BEDROCK));
+                    }
+                }
+            }
+        }
+    }
+
+    /// Hangs clusters of glowstone from the ceilings of the caves. A cluster
+    /// grows down from the netherrack above a random block of air, a block
+    /// at a time, where the new block touches exactly one block of glowstone,
+    /// as in Beta. The clusters do not reach past the chunk, so that the
+    /// chunks can be generated in any order.
+    fn add_glowstone(&self, chunk: &mut Chunk, rng: &mut Random) {
+        let is = |chunk: &Chunk, at: Point3<i32>, id: u16| {
+            (0..CHUNK_LENGTH_X as i32).contains(&at.x)
+                && (0..NETHER_HEIGHT as i32).contains(&at.y)
+                && (0..CHUNK_LENGTH_Z as i32).contains(&at.z)
+                && chunk.block(at.x as usize, at.y as usize, at.z as usize).id == id
+        };
+
+        for _ in 0..rng.gen_range(0, GLOWSTONE_CLUSTERS + 1) {
+            let mut start = Point3::new(
+                rng.gen_range(1, CHUNK_LENGTH_X as i32 - 1),
+                rng.gen_range(NETHER_LAVA_LEVEL as i32 + 4, NETHER_HEIGHT as i32 - BEDROCK_DEPTH),
+                rng.gen_range(1, CHUNK_LENGTH_Z as i32 - 1),
+            );
+
+            // The cluster hangs from the ceiling above the random block.
+            if !is(chunk, start, block_ids::AIR) {
+                continue
+            }
+            while is(chunk, start + Vector3::unit_y(), block_ids::AIR) {
+                start.y += 1;
+            }
+            if !is(chunk, start + Vector3::unit_y(), block_ids::NETHERRACK) {
+                continue
+            }
+            chunk.set_block(start.x as usize, start.y as usize, start.z as usize, Block::new(block_ids::GLOWSTONE));
+
+            for _ in 0..GLOWSTONE_TRIES {
+                let at = start + Vector3::new(
+                    rng.gen_range(0, 8) - rng.gen_range(0, 8),
+                    -rng.gen_range(0, 12),
+                    rng.gen_range(0, 8) - rng.gen_range(0, 8),
+                );
+
+                if !is(chunk, at, block_ids::AIR) {
+                    continue
+                }
+
+                let touching = [
+                    Vector3::unit_x(), -Vector3::unit_x(),
+                    Vector3::unit_y(), -Vector3::unit_y(),
+                    Vector3::unit_z(), -Vector3::unit_z(),
+                ].iter().filter(|&&offset| is(chunk, at + offset, block_ids::GLOWSTONE)).count();
+
+                if touching == 1 {
+                    chunk.set_block(at.x as usize, at.y as usize, at.z as usize, Block::new(block_ids::GLOWSTONE));
+                }
+            }
+        }
+    }
+}
+
+impl<G: NoiseGen> WorldGenerator for NetherGenerator<G> {
+    fn generate_chunk(&self, at: ChunkPos) -> Chunk {
+        let mut chunk = Chunk::empty(at);
+
+        // The chunk is decorated by a generator of its own, so that it does
+        // not depend on the other chunks.
+        let seed = (at.x as i64 as u64).wrapping_mul(341873128712)
+            .wrapping_add((at.z as i64 as u64).wrapping_mul(132897987541))
+            .wrapping_add(self.seed);
+        let mut rng = Random::seed_from_u64(seed);
+
+        self.shape(&mut chunk);
+        self.replace_surface(&mut chunk, &mut rng);
+        self.add_glowstone(&mut chunk, &mut rng);
+        chunk
+    }
+
+    fn biome_at(&self, _x: i32, _z: i32) -> Biome {
+        Biome::Hell
+    }
+}
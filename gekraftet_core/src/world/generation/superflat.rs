@@ -0,0 +1,157 @@
+use crate::world::{ is_known_block, Block, Chunk, ChunkPos, CHUNK_LENGTH_X, CHUNK_LENGTH_Y, CHUNK_LENGTH_Z };
+use super::{ GeneratorError, WorldGenerator };
+
+/// A generator of flat worlds, made of the same layers of blocks everywhere.
+///
+/// The layers are described from the bottom up by a comma-separated list of
+/// block ids, each optionally preceded by a repeat count and followed by the
+/// block metadata. For example, `7,2x3,2` is a layer of bedrock, two layers
+/// of dirt and a layer of grass, and `7,60x1,35:14` puts 60 layers of stone
+/// and a layer of red wool on top of the bedrock.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SuperflatGenerator {
+    layers: Vec<Block>,
+}
+
+impl SuperflatGenerator {
+    /// Creates a generator out of the layers, listed from the bottom up.
+    pub fn new(layers: Vec<Block>) -> Result<Self, GeneratorError> {
+        if layers.len() > CHUNK_LENGTH_Y {
+            return Err(GeneratorError::TooManyLayers(layers.len()))
+        }
+
+        Ok(Self {
+            layers
+        })
+    }
+
+    /// Parses the layers as described above. Every layer must be repeated at
+    /// least once and be made of a block which exists in Beta, and there must
+    /// be at least one layer.
+    pub fn from_layers(layers: &str) -> Result<Self, GeneratorError> {
+        let mut result = Vec::new();
+
+        for layer in layers.split(',').map(str::trim).filter(|l| !l.is_empty()) {
+            let invalid = || GeneratorError::InvalidLayer(layer.to_string());
+
+            let (count, block) = match layer.split_once(['x', '*']) {
+                Some((count, block)) => match count.trim().parse::<usize>() {
+                    Ok(count) if count > 0 => (count, block),
+                    _ => return Err(invalid()),
+                },
+                None => (1, layer),
+            };
+
+            let (id, metadata) = match block.split_once(':') {
+                Some((id, metadata)) => (id, metadata.trim().parse::<u16>().map_err(|_| invalid())?),
+                None => (block, 0),
+            };
+            let id = id.trim().parse::<u16>().map_err(|_| invalid())?;
+            if !is_known_block(id) {
+                return Err(GeneratorError::UnknownBlock(id))
+            }
+
+            if result.len() + count > CHUNK_LENGTH_Y {
+                return Err(GeneratorError::TooManyLayers(result.len() + count))
+            }
+
+            result.extend(std::iter::repeat_n(Block::with_metadata(id, metadata), count));
+        }
+
+        if result.is_empty() {
+            return Err(GeneratorError::NoLayers)
+        }
+
+        Self::new(result)
+    }
+
+    pub fn layers(&self) -> &[Block] {
+        &self.layers
+    }
+}
+
+impl WorldGenerator for SuperflatGenerator {
+    fn generate_chunk(&self, at: ChunkPos) -> Chunk {
+        let mut chunk = Chunk::empty(at);
+
+        // Only the lowest chunk of a column contains the layers.
+        if at.y != 0 {
+            return chunk
+        }
+
+        for (y, layer) in self.layers.iter().enumerate() {
+            for x in 0..CHUNK_LENGTH_X {
+                for z in 0..CHUNK_LENGTH_Z {
+                    chunk.set_block(x, y, z, *layer);
+                }
+            }
+        }
+
+        chunk
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::world::block_ids;
+    use super::*;
+
+    // The height of the highest block of the column, plus one.
+    fn column_height(chunk: &Chunk, x: usize, z: usize) -> usize {
+        (0..CHUNK_LENGTH_Y).rev()
+            .find(|&y| chunk.block(x, y, z).id != block_ids::AIR)
+            .map_or(0, |y| y + 1)
+    }
+
+    #[test]
+    fn layers_are_stacked_from_the_bottom_up() {
+        let generator = SuperflatGenerator::from_layers("7, 60x1, 2*3 ,35:14").unwrap();
+        assert_eq!(generator.layers().len(), 64);
+
+        let chunk = generator.generate_chunk(ChunkPos::new(-3, 0, 8));
+        for x in 0..CHUNK_LENGTH_X {
+            for z in 0..CHUNK_LENGTH_Z {
+                assert_eq!(column_height(&chunk, x, z), 64);
+            }
+        }
+
+        assert_eq!(*chunk.block(4, 0, 4), Block::new(block_ids::BEDROCK));
+        assert_eq!(*chunk.block(4, 1, 4), Block::new(block_ids::STONE));
+        assert_eq!(*chunk.block(4, 60, 4), Block::new(block_ids::STONE));
+        assert_eq!(*chunk.block(4, 61, 4), Block::new(block_ids::DIRT));
+        assert_eq!(*chunk.block(4, 62, 4), Block::new(block_ids::DIRT));
+        assert_eq!(*chunk.block(4, 63, 4), Block::with_metadata(block_ids::WOOL, 14));
+
+        // The chunks above the lowest one of a column are empty.
+        assert_eq!(column_height(&generator.generate_chunk(ChunkPos::new(0, 1, 0)), 0, 0), 0);
+    }
+
+    #[test]
+    fn a_full_chunk_of_layers_is_allowed() {
+        let generator = SuperflatGenerator::from_layers(&format!("{}x1", CHUNK_LENGTH_Y)).unwrap();
+        assert_eq!(column_height(&generator.generate_chunk(ChunkPos::new(0, 0, 0)), 15, 15), CHUNK_LENGTH_Y);
+    }
+
+    #[test]
+    fn malformed_layers_are_rejected() {
+        for layers in &["x1", "0x1", "-2x1", "ax1", "2x", "1:a", "1:", "1:-1", "stone"] {
+            match SuperflatGenerator::from_layers(layers) {
+                Err(GeneratorError::InvalidLayer(layer)) => assert_eq!(&layer, layers),
+                result => panic!("{}: {:?}", layers, result),
+            }
+        }
+
+        assert!(matches!(SuperflatGenerator::from_layers("7,2x3,400"), Err(GeneratorError::UnknownBlock(400))));
+        assert!(matches!(SuperflatGenerator::from_layers("7,97"), Err(GeneratorError::UnknownBlock(97))));
+
+        for layers in &["", " ", ",", " , ,"] {
+            assert!(matches!(SuperflatGenerator::from_layers(layers), Err(GeneratorError::NoLayers)), "{:?}", layers);
+        }
+
+        let too_many = format!("7,{}x1", CHUNK_LENGTH_Y);
+        assert!(matches!(
+            SuperflatGenerator::from_layers(&too_many),
+            Err(GeneratorError::TooManyLayers(n)) if n == CHUNK_LENGTH_Y + 1
+        ));
+    }
+}
@@ -0,0 +1,113 @@
+use crate::world::*;
+use super::WorldGenerator;
+
+// How many blocks of filler lie below the top block of the terrain.
+const FILLER_DEPTH: usize = 3;
+
+/// The default generator, which shapes the terrain with a 3D density noise
+/// and covers it with the surface blocks of the biomes.
+pub struct TerrainGenerator<G: NoiseGen = Perlin3D> {
+    noise: Noise<G>,
+    biomes: BiomeSource,
+    base_height: f64,
+    height_scale: f64,
+}
+
+impl TerrainGenerator<Perlin3D> {
+    pub fn new(seed: u64) -> Self {
+        Self::with_noise(Noise::with_option(Self::default_option(), seed))
+            .biomes(BiomeSource::new(seed))
+    }
+
+    /// The options of the density noise used by `new`.
+    pub fn default_option() -> NoiseGenOption {
+        NoiseGenOption::new()
+            .octaves(16)
+            .amplitude(10.0)
+            .persistance(0.5)
+            .frequency(628.31854)
+            .lacunarity(0.5)
+    }
+}
+
+impl<G: NoiseGen> TerrainGenerator<G> {
+    pub fn with_noise(noise: Noise<G>) -> Self {
+        Self {
+            noise,
+            biomes: BiomeSource::new(0),
+            base_height: DEFAULT_BASE_HEIGHT,
+            height_scale: 1.0,
+        }
+    }
+
+    /// Changes the source of the biomes (the default is seeded with 0).
+    pub fn biomes(mut self, biomes: BiomeSource) -> Self {
+        self.biomes = biomes;
+        self
+    }
+
+    /// Changes the height around which the terrain lies (the default is
+    /// `DEFAULT_BASE_HEIGHT`).
+    pub fn base_height(mut self, base_height: f64) -> Self {
+        self.base_height = base_height;
+        self
+    }
+
+    /// Multiplies the noise before it is turned into blocks, which makes the
+    /// hills taller and the valleys deeper.
+    pub fn height_scale(mut self, height_scale: f64) -> Self {
+        self.height_scale = height_scale;
+        self
+    }
+
+    pub fn noise(&self) -> &Noise<G> {
+        &self.noise
+    }
+
+    /// Replaces the stone at the surface of the terrain with the top and
+    /// filler blocks of the biome.
+    fn replace_surface(&self, chunk: &mut Chunk) {
+        let ChunkPos(pos) = chunk.position();
+
+        for x in 0..CHUNK_LENGTH_X {
+            for z in 0..CHUNK_LENGTH_Z {
+                let biome = self.biomes.biome_at(
+                    pos.x * CHUNK_LENGTH_X as i32 + x as i32,
+                    pos.z * CHUNK_LENGTH_Z as i32 + z as i32,
+                );
+
+                // The number of solid blocks since the last air block, or
+                // None if no air has been seen yet.
+                let mut depth = None;
+
+                for y in (0..CHUNK_LENGTH_Y).rev() {
+                    if chunk.block(x, y, z).id == block_ids::AIR {
+                        depth = Some(0);
+                        continue
+                    }
+
+                    let surface = match depth {
+                        Some(0) => biome.top_block(),
+                        Some(d) if d <= FILLER_DEPTH => biome.filler_block(),
+                        _ => continue,
+                    };
+
+                    chunk.set_block(x, y, z, Block::new(surface));
+                    depth = depth.map(|d| d + 1);
+                }
+            }
+        }
+    }
+}
+
+impl<G: NoiseGen> WorldGenerator for TerrainGenerator<G> {
+    fn generate_chunk(&self, at: ChunkPos) -> Chunk {
+        let mut chunk = Chunk::from_terrain(at, &self.noise, self.base_height, self.height_scale);
+        self.replace_surface(&mut chunk);
+        chunk
+    }
+
+    fn biome_at(&self, x: i32, z: i32) -> Biome {
+        self.biomes.biome_at(x, z)
+    }
+}
@@ -0,0 +1,116 @@
+use rand::Rng;
+use crate::utils::Random;
+use super::*;
+
+// Grass dies under opaque blocks giving off less light than this, and
+// spreads from grass which gets at least `SPREAD_LIGHT` onto dirt which gets
+// at least this much.
+const MIN_LIGHT: u8 = 4;
+const SPREAD_LIGHT: u8 = 9;
+
+/// The light which reaches grass from the block above it. Opaque blocks let
+/// no light through, but give off their own.
+fn light_through(level: &Level, above: BlockPos) -> u8 {
+    match level.block(above) {
+        Some(block) if block.info().opaque => block.info().light,
+        _ => level.light(above),
+    }
+}
+
+fn is_opaque(level: &Level, at: BlockPos) -> bool {
+    level.block(at).is_some_and(|b| b.info().opaque)
+}
+
+/// Called when grass gets a random tick. Grass under an opaque block which
+/// is too dark turns into dirt, one time out of four. Grass in the light spreads to
+/// a dirt block nearby, from a block below to three blocks above it, as in
+/// Beta.
+pub(crate) fn on_random_tick(level: &mut Level, at: BlockPos, rng: &mut Random) {
+    let light = light_through(level, at.offset(0, 1, 0));
+
+    if light < MIN_LIGHT && is_opaque(level, at.offset(0, 1, 0)) {
+        if rng.gen_range(0, 4) == 0 {
+            level.set_block_with_notify(at, Block::new(block_ids::DIRT));
+        }
+    } else if light >= SPREAD_LIGHT {
+        let to = at.offset(rng.gen_range(-1, 2), rng.gen_range(-3, 2), rng.gen_range(-1, 2));
+        let above = to.offset(0, 1, 0);
+
+        let is_dirt = level.block(to).is_some_and(|b| b.id == block_ids::DIRT);
+        if is_dirt && !is_opaque(level, above) && light_through(level, above) >= MIN_LIGHT {
+            level.set_block_with_notify(to, Block::new(block_ids::GRASS));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use super::*;
+
+    // A flat world of dirt, with a block of grass in the middle.
+    fn level() -> Level {
+        let generator = SuperflatGenerator::from_layers("7,3x3").unwrap();
+        let mut level = Level::new();
+        for x in -1..=1 {
+            for z in -1..=1 {
+                level.load_chunk(ChunkPos::new(x, 0, z), &generator);
+            }
+        }
+        level.set_block(BlockPos::new(0, 3, 0), Block::new(block_ids::GRASS));
+        level
+    }
+
+    #[test]
+    fn grass_spreads_in_the_light() {
+        let mut level = level();
+        let mut rng = Random::seed_from_u64(40);
+
+        for _ in 0..200 {
+            on_random_tick(&mut level, BlockPos::new(0, 3, 0), &mut rng);
+        }
+
+        // It only spreads to the dirt next to it at the surface, since the
+        // dirt below is covered.
+        for x in -1..=1 {
+            for z in -1..=1 {
+                assert_eq!(level.block(BlockPos::new(x, 3, z)).unwrap().id, block_ids::GRASS, "{:?}", (x, z));
+                assert_eq!(level.block(BlockPos::new(x, 2, z)).unwrap().id, block_ids::DIRT, "{:?}", (x, z));
+            }
+        }
+
+        // At night, the sky is too dark for it to spread.
+        level.set_sky_darkness(11);
+        for _ in 0..200 {
+            on_random_tick(&mut level, BlockPos::new(1, 3, 1), &mut rng);
+        }
+        assert_eq!(level.block(BlockPos::new(2, 3, 2)).unwrap().id, block_ids::DIRT);
+    }
+
+    #[test]
+    fn grass_dies_in_the_dark() {
+        let mut level = level();
+        let mut rng = Random::seed_from_u64(40);
+
+        // Glass lets the light through.
+        level.set_block(BlockPos::new(0, 4, 0), Block::new(block_ids::GLASS));
+        for _ in 0..20 {
+            on_random_tick(&mut level, BlockPos::new(0, 3, 0), &mut rng);
+        }
+        assert_eq!(level.block(BlockPos::new(0, 3, 0)).unwrap().id, block_ids::GRASS);
+
+        // Neither does glowstone, but it gives off light.
+        level.set_block(BlockPos::new(0, 4, 0), Block::new(block_ids::GLOWSTONE));
+        for _ in 0..20 {
+            on_random_tick(&mut level, BlockPos::new(0, 3, 0), &mut rng);
+        }
+        assert_eq!(level.block(BlockPos::new(0, 3, 0)).unwrap().id, block_ids::GRASS);
+
+        // Stone does neither, even in daylight.
+        level.set_block(BlockPos::new(0, 4, 0), Block::new(block_ids::STONE));
+        for _ in 0..20 {
+            on_random_tick(&mut level, BlockPos::new(0, 3, 0), &mut rng);
+        }
+        assert_eq!(level.block(BlockPos::new(0, 3, 0)).unwrap().id, block_ids::DIRT);
+    }
+}
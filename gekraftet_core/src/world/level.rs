@@ -0,0 +1,300 @@
+use std::collections::HashMap;
+use cgmath::Point3;
+use rand::Rng;
+use crate::entity::EntityKind;
+use crate::utils::Random;
+use super::*;
+
+// The chunks are keyed by their x and z coordinates.
+type ChunkKey = (i32, i32);
+
+/// A change to the blocks of a level which players watching it should see.
+#[derive(Clone, Debug, PartialEq)]
+pub enum BlockEvent {
+    /// The block at the given position was replaced.
+    Change {
+        at: BlockPos,
+        block: Block,
+    },
+    /// A block did something the client animates, such as a piston moving or
+    /// a note block playing. The meaning of the two bytes depends on the
+    /// block.
+    Action {
+        at: BlockPos,
+        data: [u8; 2],
+    },
+    /// An explosion destroyed the blocks at the given offsets from the block
+    /// of its center, as given by `Explosion::records`.
+    Explosion {
+        center: Point3<f64>,
+        power: f32,
+        records: Vec<[u8; 3]>,
+    },
+}
+
+/// The loaded chunks of a world, which allows accessing the blocks by their
+/// position in the world rather than by their position in a chunk.
+#[derive(Clone, Debug, Default)]
+pub struct Level {
+    chunks: HashMap<ChunkKey, Chunk>,
+    scheduler: TickScheduler,
+    events: Vec<BlockEvent>,
+    spawns: Vec<(EntityKind, Point3<f64>)>,
+    pub(super) redstone: redstone::RedstoneState,
+    pub(super) sky_darkness: u8,
+}
+
+/// Splits a block position into the key of its chunk and the coordinates of
+/// the block within that chunk. Returns None if the block is above or below
+/// the world.
+fn locate(at: BlockPos) -> Option<(ChunkKey, [usize; 3])> {
+    if at.y < 0 || at.y >= CHUNK_LENGTH_Y as i32 {
+        return None
+    }
+
+    let chunk = ChunkPos::from(at);
+    let local = [
+        at.x.rem_euclid(CHUNK_LENGTH_X as i32) as usize,
+        at.y as usize,
+        at.z.rem_euclid(CHUNK_LENGTH_Z as i32) as usize,
+    ];
+
+    Some(((chunk.x, chunk.z), local))
+}
+
+impl Level {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a chunk to the level, returning the chunk it replaces.
+    pub fn insert_chunk(&mut self, chunk: Chunk) -> Option<Chunk> {
+        let ChunkPos(pos) = chunk.position();
+        self.chunks.insert((pos.x, pos.z), chunk)
+    }
+
+    pub fn remove_chunk(&mut self, at: ChunkPos) -> Option<Chunk> {
+        self.chunks.remove(&(at.x, at.z))
+    }
+
+    pub fn chunk(&self, at: ChunkPos) -> Option<&Chunk> {
+        self.chunks.get(&(at.x, at.z))
+    }
+
+    pub fn chunk_mut(&mut self, at: ChunkPos) -> Option<&mut Chunk> {
+        self.chunks.get_mut(&(at.x, at.z))
+    }
+
+    pub fn chunks(&self) -> impl Iterator<Item = &Chunk> {
+        self.chunks.values()
+    }
+
+    /// Generates the chunk at the given position if it is not loaded yet.
+    pub fn load_chunk<W>(&mut self, at: ChunkPos, generator: &W) -> &mut Chunk
+        where W: WorldGenerator + ?Sized
+    {
+        self.chunks
+            .entry((at.x, at.z))
+            .or_insert_with(|| generator.generate_chunk(ChunkPos::new(at.x, 0, at.z)))
+    }
+
+    /// Returns the block at the given position, or None if its chunk is not
+    /// loaded.
+    pub fn block(&self, at: BlockPos) -> Option<Block> {
+        let (chunk, [x, y, z]) = locate(at)?;
+        self.chunks.get(&chunk).map(|c| *c.block(x, y, z))
+    }
+
+    /// Replaces the block at the given position. Returns false if its chunk
+    /// is not loaded, in which case nothing is changed.
+    pub fn set_block(&mut self, at: BlockPos, block: Block) -> bool {
+        match locate(at) {
+            Some((chunk, [x, y, z])) => match self.chunks.get_mut(&chunk) {
+                Some(chunk) => {
+                    if *chunk.block(x, y, z) != block {
+                        chunk.set_block(x, y, z, block);
+                        self.events.push(BlockEvent::Change { at, block });
+                    }
+                    true
+                },
+                None => false,
+            },
+            None => false,
+        }
+    }
+
+    pub fn block_entity(&self, at: BlockPos) -> Option<&BlockEntity> {
+        let (chunk, _) = locate(at)?;
+        self.chunks.get(&chunk)?.block_entities().get(&at)
+    }
+
+    pub fn block_entity_mut(&mut self, at: BlockPos) -> Option<&mut BlockEntity> {
+        let (chunk, _) = locate(at)?;
+        self.chunks.get_mut(&chunk)?.block_entities_mut().get_mut(&at)
+    }
+
+    /// Attaches a block entity to the block at the given position. Returns
+    /// false if its chunk is not loaded, in which case nothing is changed.
+    pub fn set_block_entity(&mut self, at: BlockPos, entity: BlockEntity) -> bool {
+        let chunk = locate(at).and_then(|(chunk, _)| self.chunks.get_mut(&chunk));
+
+        match chunk {
+            Some(chunk) => {
+                chunk.block_entities_mut().insert(at, entity);
+                true
+            },
+            None => false,
+        }
+    }
+
+    pub fn remove_block_entity(&mut self, at: BlockPos) -> Option<BlockEntity> {
+        let (chunk, _) = locate(at)?;
+        self.chunks.get_mut(&chunk)?.block_entities_mut().remove(&at)
+    }
+
+    pub(super) fn push_event(&mut self, event: BlockEvent) {
+        self.events.push(event);
+    }
+
+    /// Records a block action for the players watching the level.
+    pub fn add_block_action(&mut self, at: BlockPos, data: [u8; 2]) {
+        self.push_event(BlockEvent::Action { at, data });
+    }
+
+    /// Takes out the changes made to the blocks since the last call, in the
+    /// order they were made in.
+    pub fn take_events(&mut self) -> Vec<BlockEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    /// Asks for an entity to be spawned, such as when sand starts falling.
+    /// The level does not hold the entities, so they are only spawned once
+    /// they are taken out with `take_spawns`.
+    pub fn spawn_entity(&mut self, kind: EntityKind, position: Point3<f64>) {
+        self.spawns.push((kind, position));
+    }
+
+    /// Takes out the entities asked for since the last call, in the order
+    /// they were asked for in.
+    pub fn take_spawns(&mut self) -> Vec<(EntityKind, Point3<f64>)> {
+        std::mem::take(&mut self.spawns)
+    }
+
+    /// The number of ticks which have run so far.
+    pub fn time(&self) -> u64 {
+        self.scheduler.time()
+    }
+
+    pub fn scheduler(&self) -> &TickScheduler {
+        &self.scheduler
+    }
+
+    pub fn scheduler_mut(&mut self) -> &mut TickScheduler {
+        &mut self.scheduler
+    }
+
+    /// Schedules an update of the block at the given position, `delay` ticks
+    /// from now. Returns false if its chunk is not loaded or if the block
+    /// already has an update scheduled.
+    pub fn schedule_tick(&mut self, at: BlockPos, block_id: u16, delay: u64, priority: i32) -> bool {
+        match locate(at) {
+            Some((chunk, _)) if self.chunks.contains_key(&chunk) => {
+                self.scheduler.schedule(at, block_id, delay, priority)
+            },
+            _ => false,
+        }
+    }
+
+    /// Moves on to the next tick, and takes out the scheduled updates which
+    /// are due. The updates of blocks which have changed since, or which are
+    /// in chunks which are not loaded anymore, are dropped.
+    pub fn advance_time(&mut self) -> Vec<ScheduledTick> {
+        let mut due = self.scheduler.advance();
+        due.retain(|tick| self.block(tick.at).is_some_and(|b| b.id == tick.block_id));
+        due
+    }
+
+    /// Picks the blocks which get a random tick this tick: a few random
+    /// blocks in every section of every loaded chunk. Air is never picked.
+    /// The chunks are visited in order, so the same random generator always
+    /// picks the same blocks.
+    pub fn random_ticks(&self, rng: &mut Random) -> Vec<BlockPos> {
+        let mut chunks = self.chunks.iter().collect::<Vec<_>>();
+        chunks.sort_by_key(|(&key, _)| key);
+
+        let mut result = Vec::new();
+        for ((chunk_x, chunk_z), chunk) in chunks {
+            for (index, section) in chunk.sections().iter().enumerate() {
+                for _ in 0..RANDOM_TICKS_PER_SECTION {
+                    let x = rng.gen_range(0, SECTION_LENGTH_X);
+                    let y = rng.gen_range(0, SECTION_LENGTH_Y);
+                    let z = rng.gen_range(0, SECTION_LENGTH_Z);
+
+                    if section[x][z][y].id == block_ids::AIR {
+                        continue
+                    }
+
+                    result.push(BlockPos::new(
+                        chunk_x * CHUNK_LENGTH_X as i32 + x as i32,
+                        (index * SECTION_LENGTH_Y + y) as i32,
+                        chunk_z * CHUNK_LENGTH_Z as i32 + z as i32,
+                    ));
+                }
+            }
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use super::*;
+
+    // The chunks around the origin of a world of stone up to y = 40, loaded
+    // in the given order.
+    fn level(chunks: &[(i32, i32)]) -> Level {
+        let generator = SuperflatGenerator::from_layers("7,39x1").unwrap();
+        let mut level = Level::new();
+        for &(x, z) in chunks {
+            level.load_chunk(ChunkPos::new(x, 0, z), &generator);
+        }
+        level
+    }
+
+    #[test]
+    fn random_ticks_do_not_depend_on_the_order_of_the_chunks() {
+        let chunks = (-2..2).flat_map(|x| (-2..2).map(move |z| (x, z))).collect::<Vec<_>>();
+        let forward = level(&chunks);
+        let backward = level(&chunks.iter().rev().cloned().collect::<Vec<_>>());
+
+        for seed in 0..3 {
+            let picked = forward.random_ticks(&mut Random::seed_from_u64(seed));
+            assert_eq!(picked, backward.random_ticks(&mut Random::seed_from_u64(seed)));
+
+            // The chunks are visited from the lowest x and z up, and the
+            // sections from the bottom up. Only the sections holding stone
+            // have blocks to pick.
+            let keys = picked.iter().map(|&at| {
+                let chunk = ChunkPos::from(at);
+                (chunk.x, chunk.z, at.y as usize / SECTION_LENGTH_Y)
+            });
+            assert!(keys.clone().zip(keys.skip(1)).all(|(a, b)| a <= b));
+            assert!(picked.iter().all(|&at| forward.block(at).unwrap().id != block_ids::AIR));
+            assert!(picked.iter().all(|at| at.y < 40));
+
+            // Every chunk gets its share of the blocks picked.
+            for &(x, z) in chunks.iter() {
+                let count = picked.iter().filter(|&&at| ChunkPos::from(at) == ChunkPos::new(x, 0, z)).count();
+                assert!(count > RANDOM_TICKS_PER_SECTION && count <= 3 * RANDOM_TICKS_PER_SECTION, "{}", count);
+            }
+        }
+
+        // Other seeds pick other blocks.
+        assert_ne!(
+            forward.random_ticks(&mut Random::seed_from_u64(0)),
+            forward.random_ticks(&mut Random::seed_from_u64(1)),
+        );
+    }
+}
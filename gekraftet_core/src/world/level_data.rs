@@ -0,0 +1,146 @@
+use std::path::{ Path, PathBuf };
+use crate::nbt::{ self, Compound, NamedBinaryTag, ValueData };
+use super::*;
+
+/// The version of the McRegion format, written in `level.dat`.
+pub const MCREGION_VERSION: i32 = 19132;
+
+#[derive(Debug)]
+pub enum LevelDataError {
+    Io(std::io::Error),
+    Nbt(nbt::Error),
+    MissingTag(&'static str),
+    InvalidTag(&'static str),
+}
+
+/// What is saved about a world as a whole, in its `level.dat` file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LevelData {
+    pub name: String,
+    pub seed: i64,
+    /// Where new players appear.
+    pub spawn: BlockPos,
+    /// The ticks which have run since the world was created. The time of day
+    /// is derived from it.
+    pub time: u64,
+    /// When the world was last saved, in milliseconds since the Unix epoch.
+    pub last_played: i64,
+    pub size_on_disk: i64,
+    pub weather: Weather,
+}
+
+// The level.dat file is laid out as follows:
+//
+//     "" (compound)
+//         Data (compound)
+//             LevelName (string)
+//             RandomSeed (long)
+//             SpawnX, SpawnY, SpawnZ (int)
+//             Time, LastPlayed, SizeOnDisk (long)
+//             version (int)                    see `MCREGION_VERSION`
+//             raining, thundering (byte)
+//             rainTime, thunderTime (int)
+
+impl LevelData {
+    pub fn new<S: Into<String>>(name: S, seed: i64, spawn: BlockPos) -> Self {
+        Self {
+            name: name.into(),
+            seed,
+            spawn,
+            time: 0,
+            last_played: 0,
+            size_on_disk: 0,
+            weather: Weather::new(),
+        }
+    }
+
+    /// Where the `level.dat` file is, in the directory of the world.
+    pub fn file_path<P: AsRef<Path>>(world: P) -> PathBuf {
+        world.as_ref().join("level.dat")
+    }
+
+    /// Loads the `level.dat` file from the directory of the world. Returns
+    /// None if the world does not have one yet.
+    pub fn load<P: AsRef<Path>>(world: P) -> Result<Option<Self>, LevelDataError> {
+        let path = Self::file_path(world);
+        if !path.exists() {
+            return Ok(None)
+        }
+
+        let bytes = std::fs::read(path).map_err(LevelDataError::Io)?;
+        let tag = NamedBinaryTag::from_gzip(&bytes).map_err(LevelDataError::Nbt)?;
+        Self::from_nbt(&tag).map(Some)
+    }
+
+    /// Saves the `level.dat` file into the directory of the world.
+    pub fn save<P: AsRef<Path>>(&self, world: P) -> Result<(), LevelDataError> {
+        std::fs::create_dir_all(world.as_ref()).map_err(LevelDataError::Io)?;
+        std::fs::write(Self::file_path(world), self.to_nbt().to_gzip()).map_err(LevelDataError::Io)
+    }
+
+    pub fn to_nbt(&self) -> NamedBinaryTag {
+        let mut data = Compound::new();
+        let mut insert = |name: &str, value| { data.insert(name.to_string(), value); };
+
+        insert("LevelName", ValueData::Str(self.name.clone()));
+        insert("RandomSeed", ValueData::Long(self.seed));
+        insert("SpawnX", ValueData::Int(self.spawn.x));
+        insert("SpawnY", ValueData::Int(self.spawn.y));
+        insert("SpawnZ", ValueData::Int(self.spawn.z));
+        insert("Time", ValueData::Long(self.time as i64));
+        insert("LastPlayed", ValueData::Long(self.last_played));
+        insert("SizeOnDisk", ValueData::Long(self.size_on_disk));
+        insert("version", ValueData::Int(MCREGION_VERSION));
+        insert("raining", ValueData::Byte(self.weather.raining as i8));
+        insert("rainTime", ValueData::Int(self.weather.rain_time));
+        insert("thundering", ValueData::Byte(self.weather.thundering as i8));
+        insert("thunderTime", ValueData::Int(self.weather.thunder_time));
+
+        let mut root = Compound::new();
+        root.insert("Data".to_string(), ValueData::Compound(data));
+
+        let mut tag = NamedBinaryTag::new();
+        tag.insert("", ValueData::Compound(root));
+        tag
+    }
+
+    pub fn from_nbt(tag: &NamedBinaryTag) -> Result<Self, LevelDataError> {
+        let data = tag.get("")
+            .ok_or(LevelDataError::MissingTag(""))?
+            .compound()
+            .ok_or(LevelDataError::InvalidTag(""))?
+            .get("Data")
+            .ok_or(LevelDataError::MissingTag("Data"))?
+            .compound()
+            .ok_or(LevelDataError::InvalidTag("Data"))?;
+
+        let get = |name: &'static str| data.get(name).ok_or(LevelDataError::MissingTag(name));
+        let int = |name: &'static str| -> Result<i32, LevelDataError> {
+            get(name)?.int().copied().ok_or(LevelDataError::InvalidTag(name))
+        };
+
+        // The tags which are not essential fall back to their defaults.
+        let long = |name: &str| data.get(name).and_then(ValueData::long).copied().unwrap_or(0);
+        let byte = |name: &str| data.get(name).and_then(ValueData::byte).copied().unwrap_or(0);
+        let optional_int = |name: &str| data.get(name).and_then(ValueData::int).copied().unwrap_or(0);
+
+        let seed = get("RandomSeed")?.long().copied().ok_or(LevelDataError::InvalidTag("RandomSeed"))?;
+        let spawn = BlockPos::new(int("SpawnX")?, int("SpawnY")?, int("SpawnZ")?);
+        let name = data.get("LevelName").and_then(ValueData::string).unwrap_or("world");
+
+        let mut result = Self::new(name, seed, spawn);
+        result.time = long("Time").max(0) as u64;
+        result.last_played = long("LastPlayed");
+        result.size_on_disk = long("SizeOnDisk");
+
+        result.weather.raining = byte("raining") != 0;
+        result.weather.rain_time = optional_int("rainTime");
+        result.weather.thundering = byte("thundering") != 0;
+        result.weather.thunder_time = optional_int("thunderTime");
+        // The weather is already at full strength when loading.
+        result.weather.rain_strength = if result.weather.raining { 1.0 } else { 0.0 };
+        result.weather.thunder_strength = if result.weather.thundering { 1.0 } else { 0.0 };
+
+        Ok(result)
+    }
+}
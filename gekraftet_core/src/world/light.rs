@@ -0,0 +1,98 @@
+use std::collections::{ HashMap, HashSet, VecDeque };
+use super::*;
+
+/// The brightest light there is, in full daylight or right at a torch.
+pub const MAX_LIGHT: u8 = 15;
+
+// The levels do not store their light. Instead, it is worked out when it is
+// needed by spreading out from the block, through the blocks which are not
+// opaque, until the brightest source within reach is found. The light loses
+// a level for every block it goes through, so sources further away than
+// `MAX_LIGHT` blocks never matter.
+
+impl Level {
+    /// How much darker than in full daylight the sky light is, from 0 to 11,
+    /// as given by `sky_darkness`.
+    pub fn sky_darkness(&self) -> u8 {
+        self.sky_darkness
+    }
+
+    /// Keeps the darkness of the sky up to date with the time of day and the
+    /// weather.
+    pub fn set_sky_darkness(&mut self, darkness: u8) {
+        self.sky_darkness = darkness.min(MAX_LIGHT);
+    }
+
+    /// The light which reaches the block from the sky, whatever the time of
+    /// day.
+    pub fn sky_light(&self, at: BlockPos) -> u8 {
+        // The highest opaque block of each column, which is looked up once.
+        let mut roofs = HashMap::new();
+        let mut roof = |x: i32, z: i32| *roofs.entry((x, z)).or_insert_with(|| {
+            (0..CHUNK_LENGTH_Y as i32).rev()
+                .find(|&y| self.block(BlockPos::new(x, y, z)).is_some_and(|b| b.info().opaque))
+        });
+
+        self.spread_light(at, 0, |pos, _| match roof(pos.x, pos.z) {
+            Some(y) if y >= pos.y => 0,
+            _ => MAX_LIGHT,
+        })
+    }
+
+    /// The light which reaches the block from the blocks giving off light,
+    /// such as torches and lava.
+    pub fn block_light(&self, at: BlockPos) -> u8 {
+        self.spread_light(at, 0, |_, block| block.info().light)
+    }
+
+    /// How bright the block is: the brighter of the sky light, darkened by
+    /// the time of day and the weather, and the block light.
+    pub fn light(&self, at: BlockPos) -> u8 {
+        let sky = self.sky_light(at).saturating_sub(self.sky_darkness);
+
+        // Only the block light brighter than the sky matters, so the sources
+        // which are too far away to beat it are not looked for.
+        self.spread_light(at, sky, |_, block| block.info().light)
+    }
+
+    /// Finds the brightest light reaching `at` from the blocks for which
+    /// `source` is not 0, or `floor` if none is brighter. The light goes
+    /// around the opaque blocks, but the ones giving off light still light up
+    /// their neighbours.
+    fn spread_light<F>(&self, at: BlockPos, floor: u8, mut source: F) -> u8
+        where F: FnMut(BlockPos, Block) -> u8
+    {
+        let mut best = floor;
+        let mut visited = HashSet::new();
+        let mut queue = VecDeque::new();
+
+        visited.insert(at);
+        queue.push_back((at, 0));
+
+        while let Some((pos, distance)) = queue.pop_front() {
+            // Nothing further away can be brighter than what was found.
+            if best >= MAX_LIGHT - distance {
+                break
+            }
+
+            let block = match self.block(pos) {
+                Some(block) => block,
+                None => continue,
+            };
+
+            best = best.max(source(pos, block).saturating_sub(distance));
+            if block.info().opaque && pos != at {
+                continue
+            }
+
+            for &face in BlockFace::ALL.iter() {
+                let next = pos.neighbor(face);
+                if visited.insert(next) {
+                    queue.push_back((next, distance + 1));
+                }
+            }
+        }
+
+        best
+    }
+}
@@ -1,12 +1,55 @@
+mod biome;
 mod block;
+mod block_entity;
+mod block_registry;
 mod chunk;
+mod dimension;
+mod explosion;
+mod face;
+mod falling;
+mod generation;
+mod grass;
+mod level;
+mod level_data;
+mod light;
 mod noise;
+mod pathfinding;
+mod portal;
 mod position;
+mod raycast;
+mod schematic;
+mod structure;
+mod tick;
+mod update;
+mod weather;
 
+pub mod block_ids;
+pub mod fluid;
+pub mod redstone;
+
+pub use biome::*;
 pub use block::*;
+pub use block_entity::*;
+pub use block_registry::{ block_info, is_known_block, BlockInfo };
 pub use chunk::*;
+pub use dimension::Dimension;
+pub use explosion::Explosion;
+pub use face::BlockFace;
+pub use falling::*;
+pub use fluid::Fluid;
+pub use generation::*;
+pub use level::{ BlockEvent, Level };
+pub use level_data::*;
+pub use light::MAX_LIGHT;
 pub use position::*;
+pub use raycast::{ raycast, RaycastHit };
 pub use noise::*;
+pub use pathfinding::Path;
+pub use portal::PORTAL_SEARCH_RADIUS;
+pub use schematic::{ Schematic, SchematicError, MAX_SCHEMATIC_LENGTH };
+pub use structure::*;
+pub use tick::*;
+pub use weather::*;
 
 pub const CHUNK_LENGTH_X: usize = 16;
 pub const CHUNK_LENGTH_Y: usize = 256;
@@ -24,3 +67,13 @@ pub const NOISE_SAMPLES_Z: usize = 2;
 pub const NOISE_FACTOR_X: usize = SECTION_LENGTH_X / NOISE_SAMPLES_X;
 pub const NOISE_FACTOR_Y: usize = SECTION_LENGTH_Y / NOISE_SAMPLES_Y;
 pub const NOISE_FACTOR_Z: usize = SECTION_LENGTH_Z / NOISE_SAMPLES_Z;
+
+// The height around which the terrain of the default generator lies.
+pub const DEFAULT_BASE_HEIGHT: f64 = 64.0;
+
+// The distance between two noise samples along each axis.
+const NOISE_STEP: cgmath::Vector3<f32> = cgmath::Vector3::new(
+    NOISE_FACTOR_X as f32,
+    NOISE_FACTOR_Y as f32,
+    NOISE_FACTOR_Z as f32,
+);
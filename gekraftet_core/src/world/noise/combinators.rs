@@ -0,0 +1,293 @@
+use cgmath::{ Point3, Vector3 };
+use crate::utils::lerp;
+use super::{ NoiseGen, NoiseGenOption, Perlin3D };
+
+// Every combinator can be created through `with_option_and_seed` like any
+// other generator, so that e.g. `Noise<Add<Perlin3D, Worley3D>>` works. The
+// option is passed down to every source, and each source receives its own
+// seed derived from the given one. Use the `new` constructors to combine
+// sources that were configured separately.
+
+/// Outputs a constant value everywhere.
+pub struct Constant {
+    pub value: f64,
+}
+
+impl Constant {
+    pub fn new(value: f64) -> Self {
+        Self { value }
+    }
+}
+
+impl NoiseGen for Constant {
+    fn with_option_and_seed(option: NoiseGenOption, _seed: u64) -> Self {
+        Self::new(option.amplitude)
+    }
+
+    fn generate_noise_at(&self, _pos: Point3<f32>) -> f64 {
+        self.value
+    }
+}
+
+/// Outputs the sum of two sources.
+pub struct Add<A, B> {
+    pub lhs: A,
+    pub rhs: B,
+}
+
+impl<A: NoiseGen, B: NoiseGen> Add<A, B> {
+    pub fn new(lhs: A, rhs: B) -> Self {
+        Self { lhs, rhs }
+    }
+}
+
+impl<A: NoiseGen, B: NoiseGen> NoiseGen for Add<A, B> {
+    fn with_option_and_seed(option: NoiseGenOption, seed: u64) -> Self {
+        Self::new(
+            A::with_option_and_seed(option, seed),
+            B::with_option_and_seed(option, seed.wrapping_add(1)),
+        )
+    }
+
+    fn generate_noise_at(&self, pos: Point3<f32>) -> f64 {
+        self.lhs.generate_noise_at(pos) + self.rhs.generate_noise_at(pos)
+    }
+}
+
+/// Outputs the product of two sources.
+pub struct Multiply<A, B> {
+    pub lhs: A,
+    pub rhs: B,
+}
+
+impl<A: NoiseGen, B: NoiseGen> Multiply<A, B> {
+    pub fn new(lhs: A, rhs: B) -> Self {
+        Self { lhs, rhs }
+    }
+}
+
+impl<A: NoiseGen, B: NoiseGen> NoiseGen for Multiply<A, B> {
+    fn with_option_and_seed(option: NoiseGenOption, seed: u64) -> Self {
+        Self::new(
+            A::with_option_and_seed(option, seed),
+            B::with_option_and_seed(option, seed.wrapping_add(1)),
+        )
+    }
+
+    fn generate_noise_at(&self, pos: Point3<f32>) -> f64 {
+        self.lhs.generate_noise_at(pos) * self.rhs.generate_noise_at(pos)
+    }
+}
+
+/// Outputs `source * scale + bias`.
+pub struct ScaleBias<G> {
+    pub source: G,
+    pub scale: f64,
+    pub bias: f64,
+}
+
+impl<G: NoiseGen> ScaleBias<G> {
+    pub fn new(source: G, scale: f64, bias: f64) -> Self {
+        Self { source, scale, bias }
+    }
+}
+
+impl<G: NoiseGen> NoiseGen for ScaleBias<G> {
+    fn with_option_and_seed(option: NoiseGenOption, seed: u64) -> Self {
+        Self::new(G::with_option_and_seed(option, seed), 1.0, 0.0)
+    }
+
+    fn generate_noise_at(&self, pos: Point3<f32>) -> f64 {
+        self.source.generate_noise_at(pos) * self.scale + self.bias
+    }
+}
+
+/// Clamps the output of a source to `[min, max]`.
+pub struct Clamp<G> {
+    pub source: G,
+    pub min: f64,
+    pub max: f64,
+}
+
+impl<G: NoiseGen> Clamp<G> {
+    pub fn new(source: G, min: f64, max: f64) -> Self {
+        debug_assert!(min <= max);
+        Self { source, min, max }
+    }
+}
+
+impl<G: NoiseGen> NoiseGen for Clamp<G> {
+    fn with_option_and_seed(option: NoiseGenOption, seed: u64) -> Self {
+        Self::new(G::with_option_and_seed(option, seed), -1.0, 1.0)
+    }
+
+    fn generate_noise_at(&self, pos: Point3<f32>) -> f64 {
+        self.source.generate_noise_at(pos).clamp(self.min, self.max)
+    }
+}
+
+/// Outputs `low` where `control` is below `threshold` and `high` otherwise.
+/// Within `falloff` of the threshold both sources are blended together, which
+/// avoids a hard seam between the two.
+pub struct Select<A, B, C> {
+    pub low: A,
+    pub high: B,
+    pub control: C,
+    pub threshold: f64,
+    pub falloff: f64,
+}
+
+impl<A: NoiseGen, B: NoiseGen, C: NoiseGen> Select<A, B, C> {
+    pub fn new(low: A, high: B, control: C, threshold: f64, falloff: f64) -> Self {
+        Self { low, high, control, threshold, falloff }
+    }
+}
+
+impl<A: NoiseGen, B: NoiseGen, C: NoiseGen> NoiseGen for Select<A, B, C> {
+    fn with_option_and_seed(option: NoiseGenOption, seed: u64) -> Self {
+        Self::new(
+            A::with_option_and_seed(option, seed),
+            B::with_option_and_seed(option, seed.wrapping_add(1)),
+            C::with_option_and_seed(option, seed.wrapping_add(2)),
+            0.0,
+            0.0,
+        )
+    }
+
+    fn generate_noise_at(&self, pos: Point3<f32>) -> f64 {
+        let control = self.control.generate_noise_at(pos);
+        let lower_edge = self.threshold - self.falloff;
+        let upper_edge = self.threshold + self.falloff;
+
+        if control < lower_edge {
+            self.low.generate_noise_at(pos)
+        } else if control >= upper_edge {
+            self.high.generate_noise_at(pos)
+        } else {
+            // Only reachable with a non-zero falloff.
+            let t = (control - lower_edge) / (upper_edge - lower_edge);
+            let low = self.low.generate_noise_at(pos);
+            let high = self.high.generate_noise_at(pos);
+            lerp(low, high, t)
+        }
+    }
+}
+
+/// Blends `lhs` and `rhs` together. A `control` output of -1 selects `lhs`
+/// only, 1 selects `rhs` only, and anything in between is interpolated.
+pub struct Blend<A, B, C> {
+    pub lhs: A,
+    pub rhs: B,
+    pub control: C,
+}
+
+impl<A: NoiseGen, B: NoiseGen, C: NoiseGen> Blend<A, B, C> {
+    pub fn new(lhs: A, rhs: B, control: C) -> Self {
+        Self { lhs, rhs, control }
+    }
+}
+
+impl<A: NoiseGen, B: NoiseGen, C: NoiseGen> NoiseGen for Blend<A, B, C> {
+    fn with_option_and_seed(option: NoiseGenOption, seed: u64) -> Self {
+        Self::new(
+            A::with_option_and_seed(option, seed),
+            B::with_option_and_seed(option, seed.wrapping_add(1)),
+            C::with_option_and_seed(option, seed.wrapping_add(2)),
+        )
+    }
+
+    fn generate_noise_at(&self, pos: Point3<f32>) -> f64 {
+        let control = self.control.generate_noise_at(pos);
+        let t = ((control + 1.0) * 0.5).clamp(0.0, 1.0);
+        let lhs = self.lhs.generate_noise_at(pos);
+        let rhs = self.rhs.generate_noise_at(pos);
+        lerp(lhs, rhs, t)
+    }
+}
+
+/// Displaces the input of `source` by the output of `warp`, sampled once per
+/// axis at different offsets. The displacement is scaled by `strength`.
+pub struct DomainWarp<G, W> {
+    pub source: G,
+    pub warp: W,
+    pub strength: f32,
+}
+
+// Arbitrary offsets, so that the three axes are not displaced identically.
+const WARP_OFFSET_X: Vector3<f32> = Vector3::new(12.414, 65.124, 24.512);
+const WARP_OFFSET_Y: Vector3<f32> = Vector3::new(26.519, 18.128, 60.493);
+const WARP_OFFSET_Z: Vector3<f32> = Vector3::new(53.820, 11.213, 44.845);
+
+impl<G: NoiseGen, W: NoiseGen> DomainWarp<G, W> {
+    pub fn new(source: G, warp: W, strength: f32) -> Self {
+        Self { source, warp, strength }
+    }
+}
+
+impl<G: NoiseGen, W: NoiseGen> NoiseGen for DomainWarp<G, W> {
+    fn with_option_and_seed(option: NoiseGenOption, seed: u64) -> Self {
+        Self::new(
+            G::with_option_and_seed(option, seed),
+            W::with_option_and_seed(option, seed.wrapping_add(1)),
+            1.0,
+        )
+    }
+
+    fn generate_noise_at(&self, pos: Point3<f32>) -> f64 {
+        let displacement = Vector3::new(
+            self.warp.generate_noise_at(pos + WARP_OFFSET_X) as f32,
+            self.warp.generate_noise_at(pos + WARP_OFFSET_Y) as f32,
+            self.warp.generate_noise_at(pos + WARP_OFFSET_Z) as f32,
+        );
+
+        self.source.generate_noise_at(pos + displacement * self.strength)
+    }
+}
+
+/// Randomly displaces the input of `source` using Perlin noise, which gives
+/// the output a turbulent look. `frequency` controls how quickly the
+/// displacement changes, `power` how far it reaches and `roughness` is the
+/// number of octaves used.
+pub struct Turbulence<G> {
+    pub source: G,
+    pub power: f32,
+    distortion: [Perlin3D; 3],
+}
+
+impl<G: NoiseGen> Turbulence<G> {
+    pub fn new(source: G, frequency: f32, power: f32, roughness: u32, seed: u64) -> Self {
+        let option = || NoiseGenOption::new()
+            .octaves(roughness)
+            .frequency(frequency)
+            .lacunarity(2.0)
+            .persistance(0.5);
+
+        Self {
+            source,
+            power,
+            distortion: [
+                Perlin3D::with_option_and_seed(option(), seed),
+                Perlin3D::with_option_and_seed(option(), seed.wrapping_add(1)),
+                Perlin3D::with_option_and_seed(option(), seed.wrapping_add(2)),
+            ],
+        }
+    }
+}
+
+impl<G: NoiseGen> NoiseGen for Turbulence<G> {
+    fn with_option_and_seed(option: NoiseGenOption, seed: u64) -> Self {
+        let source = G::with_option_and_seed(option, seed);
+        Self::new(source, 1.0, 1.0, 3, seed.wrapping_add(1))
+    }
+
+    fn generate_noise_at(&self, pos: Point3<f32>) -> f64 {
+        let [dx, dy, dz] = &self.distortion;
+        let displacement = Vector3::new(
+            dx.generate_noise_at(pos + WARP_OFFSET_X) as f32,
+            dy.generate_noise_at(pos + WARP_OFFSET_Y) as f32,
+            dz.generate_noise_at(pos + WARP_OFFSET_Z) as f32,
+        );
+
+        self.source.generate_noise_at(pos + displacement * self.power)
+    }
+}
@@ -1,8 +1,141 @@
-use cgmath::Point3;
-use super::NoiseGenOption;
+use cgmath::{ Point3, Vector3 };
+use std::sync::Arc;
+use super::{ NoiseGenOption, Perlin3D };
 
-pub trait NoiseGen<Output=f64> {
-    fn with_option_and_seed(option: NoiseGenOption, seed: u64) -> Self;
+/// A trait implemented by noise generators. Sampling never mutates the
+/// generator, so a single generator can be shared (e.g. through an `Arc`)
+/// between every thread generating chunks.
+pub trait NoiseGen<Output=f64>: Send + Sync {
+    fn with_option_and_seed(option: NoiseGenOption, seed: u64) -> Self
+        where Self: Sized;
 
-    fn generate_noise_at(&mut self, input: Point3<f32>) -> Output;
+    fn generate_noise_at(&self, input: Point3<f32>) -> Output;
+
+    /// Samples the generator on a regular lattice of `size[0] * size[1] *
+    /// size[2]` points, starting at `origin` and spaced by `step`. The results
+    /// are written to `output` in x, y, z order (z changes the fastest).
+    ///
+    /// Every sample is equal to the one `generate_noise_at` gives for the same
+    /// point, but generators may override this to share work between points.
+    fn generate_noise_lattice(
+        &self,
+        origin: Point3<f32>,
+        step: Vector3<f32>,
+        size: [usize; 3],
+        output: &mut [Output],
+    ) {
+        assert_eq!(output.len(), size[0] * size[1] * size[2]);
+
+        let mut output = output.iter_mut();
+
+        for x in 0..size[0] {
+            for y in 0..size[1] {
+                for z in 0..size[2] {
+                    let offset = Vector3::new(
+                        x as f32 * step.x,
+                        y as f32 * step.y,
+                        z as f32 * step.z,
+                    );
+
+                    *output.next().unwrap() = self.generate_noise_at(origin + offset);
+                }
+            }
+        }
+    }
+}
+
+/// Boxed generators are what `NoiseGraph` builds. When one is created from an
+/// option and a seed, it is backed by a `Perlin3D`.
+impl NoiseGen for Box<dyn NoiseGen> {
+    fn with_option_and_seed(option: NoiseGenOption, seed: u64) -> Self {
+        Box::new(Perlin3D::with_option_and_seed(option, seed))
+    }
+
+    fn generate_noise_at(&self, input: Point3<f32>) -> f64 {
+        (**self).generate_noise_at(input)
+    }
+
+    fn generate_noise_lattice(
+        &self,
+        origin: Point3<f32>,
+        step: Vector3<f32>,
+        size: [usize; 3],
+        output: &mut [f64],
+    ) {
+        (**self).generate_noise_lattice(origin, step, size, output)
+    }
+}
+
+/// Shared generators are how `NoiseGraph` builds a node referred to more than
+/// once. When one is created from an option and a seed, it is backed by a
+/// `Perlin3D`.
+impl NoiseGen for Arc<dyn NoiseGen> {
+    fn with_option_and_seed(option: NoiseGenOption, seed: u64) -> Self {
+        Arc::new(Perlin3D::with_option_and_seed(option, seed))
+    }
+
+    fn generate_noise_at(&self, input: Point3<f32>) -> f64 {
+        (**self).generate_noise_at(input)
+    }
+
+    fn generate_noise_lattice(
+        &self,
+        origin: Point3<f32>,
+        step: Vector3<f32>,
+        size: [usize; 3],
+        output: &mut [f64],
+    ) {
+        (**self).generate_noise_lattice(origin, step, size, output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+    use super::*;
+    use crate::world::noise::{ Add, Perlin2D, Value3D, Worley3D };
+
+    fn points(thread: usize) -> Vec<Point3<f32>> {
+        (0..256)
+            .map(|i| Point3::new(i as f32 * 0.73 - 90.0, thread as f32 * 11.0 + i as f32 * 0.11, i as f32 * -0.37))
+            .collect()
+    }
+
+    #[test]
+    fn shared_generator_samples_the_same_from_every_thread() {
+        let option = NoiseGenOption::new().octaves(4).frequency(0.05).amplitude(10.0);
+        let generator: Arc<dyn NoiseGen> = Arc::new(Add::new(
+            Add::new(Perlin3D::with_option_and_seed(option, 1), Perlin2D::with_option_and_seed(option, 2)),
+            Add::new(Value3D::with_option_and_seed(option, 3), Worley3D::with_option_and_seed(option, 4)),
+        ));
+
+        let expected = (0..8)
+            .map(|t| points(t).into_iter().map(|p| generator.generate_noise_at(p)).collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+
+        let sampled = thread::scope(|scope| {
+            let workers = (0..8)
+                .map(|t| {
+                    let generator = Arc::clone(&generator);
+                    scope.spawn(move || {
+                        // Every thread samples the points of every other
+                        // thread too, at the same time.
+                        (0..8)
+                            .map(|other| points((t + other) % 8).into_iter()
+                                .map(|p| generator.generate_noise_at(p))
+                                .collect::<Vec<_>>())
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            workers.into_iter().map(|w| w.join().unwrap()).collect::<Vec<_>>()
+        });
+
+        for (t, results) in sampled.iter().enumerate() {
+            for (other, result) in results.iter().enumerate() {
+                assert_eq!(*result, expected[(t + other) % 8]);
+            }
+        }
+    }
 }
@@ -0,0 +1,558 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use super::*;
+
+// A noise graph is described by a list of named nodes:
+//
+//     # lines starting with '#' are comments
+//     hills   = perlin3d(octaves: 8, frequency: 0.01, amplitude: 20)
+//     cells   = worley3d(distance: f2-f1, frequency: 0.05)
+//     mixed   = blend(hills, scale_bias(cells, scale: 4), perlin2d(frequency: 0.002))
+//     output  = clamp(mixed, min: -32, max: 32)
+//
+// Nodes may refer to the nodes defined anywhere in the description, and calls
+// can be nested. The node named `output` is the root of the graph, or if there
+// is no such node, the last node defined.
+//
+// Generators (perlin2d, perlin3d, sine2d, value3d, worley3d) accept the
+// `NoiseGenOption` fields as keyword arguments along with `seed`, which is
+// added to the seed the graph is built with, and may be negative. Leaves
+// without a `seed` receive distinct seeds in the order they are built. A node
+// is only built once, however many times it is referred to, so that every
+// reference samples the same noise.
+//
+// Combinators take their sources as positional arguments:
+//     constant(value)
+//     add(a, b)                        multiply(a, b)
+//     scale_bias(a, scale:, bias:)     clamp(a, min:, max:)
+//     select(low, high, control, threshold:, falloff:)
+//     blend(a, b, control)
+//     turbulence(a, frequency:, power:, roughness:, seed:)
+//     warp(a, warp, strength:)
+
+#[derive(Debug)]
+pub enum GraphError {
+    Io(std::io::Error),
+    UnexpectedChar(usize, char),
+    UnexpectedEnd,
+    UnexpectedToken(usize),
+    DuplicateNode(usize, String),
+    UnknownNode(usize, String),
+    UnknownKind(usize, String),
+    MissingArgument(usize, String),
+    InvalidArgument(usize, String),
+    CyclicReference(usize, String),
+    NoOutput,
+}
+
+/// A parsed noise graph description, which can be built into a generator as
+/// many times as needed (for example, once per seed).
+#[derive(Clone, Debug)]
+pub struct NoiseGraph {
+    nodes: HashMap<String, (usize, Expr)>,
+    output: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Ident(String),
+    Number(f64),
+    LeftParen,
+    RightParen,
+    Comma,
+    Colon,
+    Equals,
+}
+
+#[derive(Clone, Debug)]
+enum Expr {
+    Number(usize, f64),
+    Ref(usize, String),
+    Call {
+        line: usize,
+        kind: String,
+        args: Vec<(Option<String>, Expr)>,
+    },
+}
+
+impl Expr {
+    fn line(&self) -> usize {
+        match self {
+            Self::Number(line, _) => *line,
+            Self::Ref(line, _) => *line,
+            Self::Call { line, .. } => *line,
+        }
+    }
+}
+
+impl NoiseGraph {
+    /// Parses a graph description. The graph is also built once to check
+    /// that every node reference and argument is valid.
+    pub fn parse(description: &str) -> Result<Self, GraphError> {
+        let tokens = Self::tokenize(description)?;
+        let mut parser = Parser { tokens: &tokens, position: 0 };
+
+        let mut nodes = HashMap::new();
+        let mut last = None;
+
+        while let Some(&(line, _)) = parser.peek() {
+            let name = parser.ident()?;
+            parser.expect(Token::Equals)?;
+            let expr = parser.expr()?;
+
+            if nodes.insert(name.clone(), (line, expr)).is_some() {
+                return Err(GraphError::DuplicateNode(line, name))
+            }
+
+            last = Some(name);
+        }
+
+        let output = if nodes.contains_key("output") {
+            String::from("output")
+        } else {
+            last.ok_or(GraphError::NoOutput)?
+        };
+
+        let graph = Self { nodes, output };
+        graph.build(0)?;
+        Ok(graph)
+    }
+
+    /// Reads and parses a graph description from a file.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, GraphError> {
+        let description = std::fs::read_to_string(path)
+            .map_err(GraphError::Io)?;
+        Self::parse(&description)
+    }
+
+    /// Builds the generator described by the graph.
+    pub fn build(&self, seed: u64) -> Result<Box<dyn NoiseGen>, GraphError> {
+        let mut builder = Builder {
+            graph: self,
+            seed,
+            leaves: 0,
+            visiting: Vec::new(),
+            built: HashMap::new(),
+        };
+
+        let (line, _) = self.nodes[&self.output];
+        builder.node(line, &self.output)
+    }
+
+    fn tokenize(description: &str) -> Result<Vec<(usize, Token)>, GraphError> {
+        let mut tokens = Vec::new();
+
+        for (line, text) in description.lines().enumerate() {
+            let line = line + 1;
+            let text = text.split('#').next().unwrap_or("");
+            let mut chars = text.char_indices().peekable();
+
+            while let Some(&(start, c)) = chars.peek() {
+                let token = match c {
+                    c if c.is_whitespace() => { chars.next(); continue },
+                    '(' => Token::LeftParen,
+                    ')' => Token::RightParen,
+                    ',' => Token::Comma,
+                    ':' => Token::Colon,
+                    '=' => Token::Equals,
+
+                    c if c.is_ascii_digit() || c == '-' || c == '+' || c == '.' => {
+                        let mut end = start;
+                        while let Some(&(i, c)) = chars.peek() {
+                            if !(c.is_ascii_alphanumeric() || "-+.".contains(c)) {
+                                break
+                            }
+                            end = i + c.len_utf8();
+                            chars.next();
+                        }
+
+                        let number = text[start..end].parse()
+                            .map_err(|_| GraphError::UnexpectedChar(line, c))?;
+                        tokens.push((line, Token::Number(number)));
+                        continue
+                    },
+
+                    c if c.is_ascii_alphabetic() || c == '_' => {
+                        let mut end = start;
+                        while let Some(&(i, c)) = chars.peek() {
+                            if !(c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+                                break
+                            }
+                            end = i + c.len_utf8();
+                            chars.next();
+                        }
+
+                        tokens.push((line, Token::Ident(String::from(&text[start..end]))));
+                        continue
+                    },
+
+                    c => return Err(GraphError::UnexpectedChar(line, c)),
+                };
+
+                chars.next();
+                tokens.push((line, token));
+            }
+        }
+
+        Ok(tokens)
+    }
+}
+
+struct Parser<'a> {
+    tokens: &'a [(usize, Token)],
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&'a (usize, Token)> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Result<&'a (usize, Token), GraphError> {
+        let token = self.peek().ok_or(GraphError::UnexpectedEnd)?;
+        self.position += 1;
+        Ok(token)
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), GraphError> {
+        match self.next()? {
+            (_, token) if *token == expected => Ok(()),
+            (line, _) => Err(GraphError::UnexpectedToken(*line)),
+        }
+    }
+
+    fn ident(&mut self) -> Result<String, GraphError> {
+        match self.next()? {
+            (_, Token::Ident(name)) => Ok(name.clone()),
+            (line, _) => Err(GraphError::UnexpectedToken(*line)),
+        }
+    }
+
+    fn expr(&mut self) -> Result<Expr, GraphError> {
+        let (line, name) = match self.next()? {
+            (line, Token::Number(number)) => return Ok(Expr::Number(*line, *number)),
+            (line, Token::Ident(name)) => (*line, name.clone()),
+            (line, _) => return Err(GraphError::UnexpectedToken(*line)),
+        };
+
+        if self.peek().map(|(_, t)| t) != Some(&Token::LeftParen) {
+            return Ok(Expr::Ref(line, name))
+        }
+
+        self.next()?;
+        let mut args = Vec::new();
+
+        loop {
+            if self.peek().map(|(_, t)| t) == Some(&Token::RightParen) {
+                self.next()?;
+                break
+            }
+
+            let is_keyword = matches!(
+                (self.peek(), self.tokens.get(self.position + 1)),
+                (Some((_, Token::Ident(_))), Some((_, Token::Colon)))
+            );
+
+            let key = if is_keyword {
+                let key = self.ident()?;
+                self.expect(Token::Colon)?;
+                Some(key)
+            } else {
+                None
+            };
+
+            args.push((key, self.expr()?));
+
+            match self.next()? {
+                (_, Token::Comma) => continue,
+                (_, Token::RightParen) => break,
+                (line, _) => return Err(GraphError::UnexpectedToken(*line)),
+            }
+        }
+
+        Ok(Expr::Call { line, kind: name, args })
+    }
+}
+
+struct Builder<'a> {
+    graph: &'a NoiseGraph,
+    seed: u64,
+    leaves: u64,
+    visiting: Vec<&'a str>,
+    built: HashMap<&'a str, Arc<dyn NoiseGen>>,
+}
+
+impl<'a> Builder<'a> {
+    fn node(&mut self, line: usize, name: &'a str) -> Result<Box<dyn NoiseGen>, GraphError> {
+        let (_, expr) = self.graph.nodes.get(name)
+            .ok_or_else(|| GraphError::UnknownNode(line, String::from(name)))?;
+
+        if let Some(built) = self.built.get(name) {
+            return Ok(Box::new(Arc::clone(built)))
+        }
+
+        if self.visiting.contains(&name) {
+            return Err(GraphError::CyclicReference(line, String::from(name)))
+        }
+
+        self.visiting.push(name);
+        let result = self.expr(expr);
+        self.visiting.pop();
+
+        let built: Arc<dyn NoiseGen> = Arc::from(result?);
+        self.built.insert(name, Arc::clone(&built));
+        Ok(Box::new(built))
+    }
+
+    fn expr(&mut self, expr: &'a Expr) -> Result<Box<dyn NoiseGen>, GraphError> {
+        let (line, kind, args) = match expr {
+            Expr::Number(_, number) => return Ok(Box::new(Constant::new(*number))),
+            Expr::Ref(line, name) => return self.node(*line, name),
+            Expr::Call { line, kind, args } => (*line, kind.as_str(), args),
+        };
+
+        let mut args = Args::new(line, kind, args);
+
+        let result: Box<dyn NoiseGen> = match kind {
+            "perlin2d" => Box::new(self.leaf::<Perlin2D>(&mut args)?),
+            "perlin3d" => Box::new(self.leaf::<Perlin3D>(&mut args)?),
+            "sine2d" => Box::new(self.leaf::<Sine2D>(&mut args)?),
+            "value3d" => Box::new(self.leaf::<Value3D>(&mut args)?),
+
+            "worley3d" => {
+                let distance = match args.ident("distance")? {
+                    None | Some("f1") => WorleyDistance::F1,
+                    Some("f2") => WorleyDistance::F2,
+                    Some("f2-f1") => WorleyDistance::F2MinusF1,
+                    Some(x) => return Err(GraphError::InvalidArgument(line, String::from(x))),
+                };
+
+                Box::new(self.leaf::<Worley3D>(&mut args)?.with_distance(distance))
+            },
+
+            "constant" => {
+                let value = match args.source(0)? {
+                    Expr::Number(_, value) => *value,
+                    _ => return Err(GraphError::InvalidArgument(line, String::from("value"))),
+                };
+
+                Box::new(Constant::new(value))
+            },
+
+            "add" => Box::new(Add::new(
+                self.expr(args.source(0)?)?,
+                self.expr(args.source(1)?)?,
+            )),
+
+            "multiply" => Box::new(Multiply::new(
+                self.expr(args.source(0)?)?,
+                self.expr(args.source(1)?)?,
+            )),
+
+            "scale_bias" => Box::new(ScaleBias::new(
+                self.expr(args.source(0)?)?,
+                args.number("scale", 1.0)?,
+                args.number("bias", 0.0)?,
+            )),
+
+            "clamp" => {
+                let min = args.number("min", -1.0)?;
+                let max = args.number("max", 1.0)?;
+
+                if min > max {
+                    return Err(GraphError::InvalidArgument(line, String::from("min")))
+                }
+
+                Box::new(Clamp::new(self.expr(args.source(0)?)?, min, max))
+            },
+
+            "select" => Box::new(Select::new(
+                self.expr(args.source(0)?)?,
+                self.expr(args.source(1)?)?,
+                self.expr(args.source(2)?)?,
+                args.number("threshold", 0.0)?,
+                args.number("falloff", 0.0)?,
+            )),
+
+            "blend" => Box::new(Blend::new(
+                self.expr(args.source(0)?)?,
+                self.expr(args.source(1)?)?,
+                self.expr(args.source(2)?)?,
+            )),
+
+            "turbulence" => {
+                let source = self.expr(args.source(0)?)?;
+                let seed = self.next_seed(&mut args)?;
+
+                Box::new(Turbulence::new(
+                    source,
+                    args.number("frequency", 1.0)? as f32,
+                    args.number("power", 1.0)? as f32,
+                    args.number("roughness", 3.0)? as u32,
+                    seed,
+                ))
+            },
+
+            "warp" => Box::new(DomainWarp::new(
+                self.expr(args.source(0)?)?,
+                self.expr(args.source(1)?)?,
+                args.number("strength", 1.0)? as f32,
+            )),
+
+            _ => return Err(GraphError::UnknownKind(line, String::from(kind))),
+        };
+
+        args.finish()?;
+        Ok(result)
+    }
+
+    fn next_seed(&mut self, args: &mut Args<'a>) -> Result<u64, GraphError> {
+        let offset = match args.optional_number("seed")? {
+            // Casting a negative float to an unsigned integer gives 0, so
+            // the seed goes through a signed integer to wrap around instead.
+            Some(seed) => seed as i64 as u64,
+            None => {
+                self.leaves += 1;
+                self.leaves
+            },
+        };
+
+        Ok(self.seed.wrapping_add(offset))
+    }
+
+    fn leaf<G: NoiseGen>(&mut self, args: &mut Args<'a>) -> Result<G, GraphError> {
+        let defaults = NoiseGenOption::new();
+
+        let option = NoiseGenOption::new()
+            .octaves(args.number("octaves", defaults.octaves as f64)? as u32)
+            .amplitude(args.number("amplitude", defaults.amplitude)?)
+            .frequency(args.number("frequency", defaults.frequency as f64)? as f32)
+            .lacunarity(args.number("lacunarity", defaults.lacunarity as f64)? as f32)
+            .persistance(args.number("persistance", defaults.persistance)?);
+
+        let seed = self.next_seed(args)?;
+        Ok(G::with_option_and_seed(option, seed))
+    }
+}
+
+struct Args<'a> {
+    line: usize,
+    kind: &'a str,
+    positional: Vec<&'a Expr>,
+    keywords: Vec<(&'a str, &'a Expr, bool)>,
+    sources_used: usize,
+}
+
+impl<'a> Args<'a> {
+    fn new(line: usize, kind: &'a str, args: &'a [(Option<String>, Expr)]) -> Self {
+        let mut positional = Vec::new();
+        let mut keywords = Vec::new();
+
+        for (key, expr) in args {
+            match key {
+                Some(key) => keywords.push((key.as_str(), expr, false)),
+                None => positional.push(expr),
+            }
+        }
+
+        Self { line, kind, positional, keywords, sources_used: 0 }
+    }
+
+    fn source(&mut self, index: usize) -> Result<&'a Expr, GraphError> {
+        self.sources_used = self.sources_used.max(index + 1);
+        self.positional.get(index)
+            .copied()
+            .ok_or_else(|| GraphError::MissingArgument(
+                self.line,
+                format!("{} expects at least {} sources", self.kind, index + 1),
+            ))
+    }
+
+    fn keyword(&mut self, key: &str) -> Option<&'a Expr> {
+        self.keywords.iter_mut()
+            .find(|(k, _, _)| *k == key)
+            .map(|(_, expr, used)| {
+                *used = true;
+                *expr
+            })
+    }
+
+    fn optional_number(&mut self, key: &str) -> Result<Option<f64>, GraphError> {
+        match self.keyword(key) {
+            None => Ok(None),
+            Some(Expr::Number(_, number)) => Ok(Some(*number)),
+            Some(expr) => Err(GraphError::InvalidArgument(expr.line(), String::from(key))),
+        }
+    }
+
+    fn number(&mut self, key: &str, default: f64) -> Result<f64, GraphError> {
+        Ok(self.optional_number(key)?.unwrap_or(default))
+    }
+
+    fn ident(&mut self, key: &str) -> Result<Option<&'a str>, GraphError> {
+        match self.keyword(key) {
+            None => Ok(None),
+            Some(Expr::Ref(_, name)) => Ok(Some(name.as_str())),
+            Some(expr) => Err(GraphError::InvalidArgument(expr.line(), String::from(key))),
+        }
+    }
+
+    fn finish(&self) -> Result<(), GraphError> {
+        if self.positional.len() > self.sources_used {
+            return Err(GraphError::InvalidArgument(
+                self.line,
+                format!("{} takes {} sources", self.kind, self.sources_used),
+            ))
+        }
+
+        match self.keywords.iter().find(|(_, _, used)| !used) {
+            Some((key, _, _)) => Err(GraphError::InvalidArgument(self.line, String::from(*key))),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::Point3;
+    use super::*;
+
+    fn sample(generator: &dyn NoiseGen) -> Vec<f64> {
+        (0..64)
+            .map(|i| generator.generate_noise_at(Point3::new(i as f32 * 3.7, i as f32 * 1.3, i as f32 * -2.9)))
+            .collect()
+    }
+
+    #[test]
+    fn references_share_one_node() {
+        let twice = NoiseGraph::parse("hills = perlin3d(frequency: 0.05)\noutput = add(hills, hills)").unwrap();
+        let scaled = NoiseGraph::parse("hills = perlin3d(frequency: 0.05)\noutput = scale_bias(hills, scale: 2)").unwrap();
+
+        assert_eq!(sample(&*twice.build(42).unwrap()), sample(&*scaled.build(42).unwrap()));
+    }
+
+    #[test]
+    fn references_are_built_once() {
+        // Without sharing, every level would build the level below twice,
+        // each time with new seeds.
+        let mut description = String::from("n0 = perlin3d()\n");
+        for i in 1..=12 {
+            description += &format!("n{} = add(n{}, n{})\n", i, i - 1, i - 1);
+        }
+
+        let graph = NoiseGraph::parse(&description).unwrap();
+        let single = NoiseGraph::parse("output = scale_bias(perlin3d(), scale: 4096)").unwrap();
+
+        assert_eq!(sample(&*graph.build(7).unwrap()), sample(&*single.build(7).unwrap()));
+    }
+
+    #[test]
+    fn negative_seeds_wrap_around() {
+        let below = NoiseGraph::parse("output = perlin3d(seed: -1)").unwrap();
+        let zero = NoiseGraph::parse("output = perlin3d(seed: 0)").unwrap();
+
+        assert_eq!(sample(&*below.build(5).unwrap()), sample(&*zero.build(4).unwrap()));
+        assert_ne!(sample(&*below.build(5).unwrap()), sample(&*zero.build(5).unwrap()));
+    }
+}
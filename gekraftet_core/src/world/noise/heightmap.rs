@@ -0,0 +1,23 @@
+use crate::world::{ ChunkPos, CHUNK_LENGTH_X, CHUNK_LENGTH_Z };
+
+/// A trait implemented by 2D noise generators used for heightmaps. Unlike
+/// `NoiseGen`, the samples are taken in the horizontal (x, z) plane.
+pub trait HeightmapNoise: Send + Sync {
+    fn generate_height_at(&self, x: f32, z: f32) -> f64;
+
+    /// Samples the height of every block column in a chunk. The result is
+    /// indexed by the x and z coordinates of the column within the chunk.
+    fn generate_heightmap(&self, at: ChunkPos) -> [[f64; CHUNK_LENGTH_Z]; CHUNK_LENGTH_X] {
+        let mut heights = [[0.0; CHUNK_LENGTH_Z]; CHUNK_LENGTH_X];
+
+        for (x, column) in heights.iter_mut().enumerate() {
+            for (z, height) in column.iter_mut().enumerate() {
+                let block_x = at.x * CHUNK_LENGTH_X as i32 + x as i32;
+                let block_z = at.z * CHUNK_LENGTH_Z as i32 + z as i32;
+                *height = self.generate_height_at(block_x as f32, block_z as f32);
+            }
+        }
+
+        heights
+    }
+}
@@ -1,16 +1,26 @@
+mod combinators;
 mod generator;
+mod graph;
+mod heightmap;
 mod options;
 mod perlin2d;
 mod perlin3d;
 mod sine;
+mod value;
+mod worley;
 
-use cgmath::Point3;
+use cgmath::{ Point3, Vector3 };
 
+pub use combinators::*;
 pub use generator::NoiseGen;
+pub use graph::{ GraphError, NoiseGraph };
+pub use heightmap::HeightmapNoise;
 pub use options::NoiseGenOption;
 pub use perlin2d::Perlin2D;
 pub use perlin3d::Perlin3D;
 pub use sine::*;
+pub use value::Value3D;
+pub use worley::{ Worley3D, WorleyDistance };
 
 pub struct Noise<G: NoiseGen> {
     generator: G,
@@ -37,7 +47,32 @@ impl<G: NoiseGen> Noise<G> {
         }
     }
 
-    pub fn generate_noise(&mut self, at: Point3<f32>) -> f64 {
+    /// Wraps an already constructed generator, such as one built from a
+    /// `NoiseGraph`.
+    pub fn from_generator(generator: G) -> Self {
+        Self {
+            generator
+        }
+    }
+
+    pub fn generate_noise(&self, at: Point3<f32>) -> f64 {
         self.generator.generate_noise_at(at)
     }
+
+    /// See `NoiseGen::generate_noise_lattice`.
+    pub fn generate_noise_lattice(
+        &self,
+        origin: Point3<f32>,
+        step: Vector3<f32>,
+        size: [usize; 3],
+        output: &mut [f64],
+    ) {
+        self.generator.generate_noise_lattice(origin, step, size, output)
+    }
+}
+
+impl<G: NoiseGen + HeightmapNoise> Noise<G> {
+    pub fn generate_height(&self, x: f32, z: f32) -> f64 {
+        self.generator.generate_height_at(x, z)
+    }
 }
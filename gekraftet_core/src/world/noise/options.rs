@@ -1,3 +1,4 @@
+#[derive(Clone, Copy, Debug)]
 pub struct NoiseGenOption {
     pub(in super) octaves: u32,
     pub(in super) amplitude: f64,
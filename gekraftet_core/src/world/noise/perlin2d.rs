@@ -1,88 +1,79 @@
 use cgmath::{ Point2, Point3 };
 use crate::utils::{ lerp, fade, Random };
-use super::{ NoiseGen, NoiseGenOption };
+use super::{ HeightmapNoise, NoiseGen, NoiseGenOption };
 
 use rand::{ Rng, SeedableRng };
 
-/// A 2D Perlin noise generator. The implementation is based on the one used in
-/// TrueCraft.
+/// A 2D Perlin noise generator. The generator samples the horizontal plane:
+/// the y coordinate of the points given to `generate_noise_at` is ignored.
 pub struct Perlin2D {
     octaves: u32,
-    
+
     amplitude: f64,
     frequency: f32,
     lacunarity: f32, // lacunarity means "gap".
     persistance: f64,
-    
+
     permutations: Box<[u8; 512]>,
 }
 
-impl Perlin2D {
-    fn generate_noise(&mut self, pos: Point2<f32>) -> f64 {
-        // for finding the point inside a cube (see `relative` below)
-        let round_down = |a, b| if a as f32 > b { 
-            b - (a - 1) as f32
-        } else {
-            b - a as f32
-        };
+// The gradients picked by the lowest 3 bits of a hash. The diagonal ones are
+// scaled down so that every gradient has unit length, which keeps the output
+// within [-1, 1].
+const GRADIENTS: [[f64; 2]; 8] = [
+    [ 1.0,  0.0], [-1.0,  0.0], [ 0.0,  1.0], [ 0.0, -1.0],
+    [ std::f64::consts::FRAC_1_SQRT_2,  std::f64::consts::FRAC_1_SQRT_2],
+    [-std::f64::consts::FRAC_1_SQRT_2,  std::f64::consts::FRAC_1_SQRT_2],
+    [ std::f64::consts::FRAC_1_SQRT_2, -std::f64::consts::FRAC_1_SQRT_2],
+    [-std::f64::consts::FRAC_1_SQRT_2, -std::f64::consts::FRAC_1_SQRT_2],
+];
 
-        let grid = pos.cast::<i32>().unwrap();
-        let cube = grid.map(|i| i & 255);
-        let relative = Point2::<f64>::new(
-            round_down(grid.x, pos.x) as f64,
-            round_down(grid.y, pos.y) as f64,
-        );
-
-        let weight_m = fade(relative.y);
-        let weight_l = fade(relative.x);
-
-        let  c = cube.x as usize;
-        let  a = (self.permutations[c + 0] as i32 + cube.y) as usize;
-        let aa = (self.permutations[a + 0]) as usize;
-        let ab = (self.permutations[a + 1]) as usize;
-
-        let grad = |hash, x: f64, y, z| -> f64 {
-            let hash = hash & 15;
-            let u = if hash < 8 { x } else { y };
-            let v = if hash < 4 { 
-                y 
-            } else {
-                if hash == 12 || hash == 14 { 
-                    x 
-                } else { 
-                    z
-                }
-            };
-            let r0 = if hash & 1 == 0 { u } else { -u };
-            let r1 = if hash & 2 == 0 { v } else { -v };
-            r0 + r1
+impl Perlin2D {
+    fn generate_noise(&self, pos: Point2<f64>) -> f64 {
+        let grid = Point2::new(pos.x.floor(), pos.y.floor());
+        let (x, y) = (pos.x - grid.x, pos.y - grid.y);
+
+        // Wrapping the cell coordinates makes the noise repeat every 256
+        // units, like the reference implementation does.
+        let cx = (grid.x as i64 & 255) as usize;
+        let cy = (grid.y as i64 & 255) as usize;
+
+        let p = &self.permutations;
+        let a = p[cx] as usize + cy;
+        let b = p[cx + 1] as usize + cy;
+
+        let grad = |hash: u8, x: f64, y: f64| {
+            let [gx, gy] = GRADIENTS[(hash & 7) as usize];
+            gx * x + gy * y
         };
 
-        let (x, y) = (relative.x, relative.y);
+        let noise_00 = grad(p[a],     x,       y      );
+        let noise_10 = grad(p[b],     x - 1.0, y      );
+        let noise_01 = grad(p[a + 1], x,       y - 1.0);
+        let noise_11 = grad(p[b + 1], x - 1.0, y - 1.0);
 
-        let noise_000 = grad(self.permutations[aa+0], x, y,       0.0 + 0.0);
-        let noise_010 = grad(self.permutations[ab+0], x, y - 1.0, 0.0 + 0.0);
-        let noise_100 = grad(self.permutations[aa+1], x, y,       0.0 - 1.0);
-        let noise_110 = grad(self.permutations[ab+1], x, y - 1.0, 0.0 - 1.0);
+        let weight_x = fade(x);
+        let weight_y = fade(y);
 
-        let m1 = lerp(noise_000, noise_010, weight_m);
-        let m2 = lerp(noise_100, noise_110, weight_m);
+        let m1 = lerp(noise_00, noise_10, weight_x);
+        let m2 = lerp(noise_01, noise_11, weight_x);
 
-        let result = lerp(m1, m2, weight_l);
-        result
+        // The largest possible value of a gradient noise in n dimensions is
+        // sqrt(n) / 2, so this maps the output onto [-1, 1].
+        lerp(m1, m2, weight_y) * std::f64::consts::SQRT_2
     }
 }
 
 impl NoiseGen for Perlin2D {
     fn with_option_and_seed(option: NoiseGenOption, seed: u64) -> Self {
         let mut rng = Random::seed_from_u64(seed);
-        
+
         let mut permutations = Box::new([0; 512]);
         for i in 0..256 {
             permutations[i] = rng.gen::<u8>();
             permutations[i + 256] = permutations[i];
         };
-        
+
         Self {
             octaves: option.octaves,
             amplitude: option.amplitude,
@@ -93,20 +84,112 @@ impl NoiseGen for Perlin2D {
         }
     }
 
-    fn generate_noise_at(&mut self, pos: Point3<f32>) -> f64 {
+    fn generate_noise_at(&self, pos: Point3<f32>) -> f64 {
+        self.generate_height_at(pos.x, pos.z)
+    }
+}
+
+impl HeightmapNoise for Perlin2D {
+    fn generate_height_at(&self, x: f32, z: f32) -> f64 {
         let mut total = 0.0;
 
         let mut amplitude = self.amplitude;
-        let mut frequency = self.frequency;
-        
-        let pos = Point2::new(pos.x, pos.y);
+        let mut frequency = self.frequency as f64;
+
+        let pos = Point2::new(x as f64, z as f64);
 
         for _ in 0..self.octaves {
             total += self.generate_noise(pos * frequency) * amplitude;
             amplitude *= self.persistance;
-            frequency *= self.lacunarity;
+            frequency *= self.lacunarity as f64;
         };
 
         total
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn single_octave(seed: u64) -> Perlin2D {
+        Perlin2D::with_option_and_seed(NoiseGenOption::new(), seed)
+    }
+
+    // A grid of points spread over several repetitions of the noise, off the
+    // lattice.
+    fn points() -> impl Iterator<Item = (f32, f32)> {
+        (0..200).flat_map(|i| (0..200).map(move |j| (i as f32 * 2.71 - 300.0, j as f32 * 3.17 - 250.0)))
+    }
+
+    #[test]
+    fn output_stays_within_range() {
+        for seed in 0..4 {
+            let noise = single_octave(seed);
+            let samples = points().map(|(x, z)| noise.generate_height_at(x, z)).collect::<Vec<_>>();
+
+            let min = samples.iter().cloned().fold(f64::INFINITY, f64::min);
+            let max = samples.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+            let mean = samples.iter().sum::<f64>() / samples.len() as f64;
+            let variance = samples.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / samples.len() as f64;
+
+            assert!(min >= -1.0 && max <= 1.0, "{} to {}", min, max);
+            // The noise uses most of its range, and is centered on 0.
+            assert!(min < -0.6 && max > 0.6, "{} to {}", min, max);
+            assert!(mean.abs() < 0.05, "mean {}", mean);
+            assert!(variance.sqrt() > 0.15, "deviation {}", variance.sqrt());
+        }
+    }
+
+    #[test]
+    fn octaves_stay_within_summed_amplitudes() {
+        let option = NoiseGenOption::new().octaves(6).amplitude(10.0).persistance(0.5).lacunarity(2.0);
+        let noise = Perlin2D::with_option_and_seed(option, 9);
+        let bound = (0..6).map(|i| 10.0 * 0.5f64.powi(i)).sum::<f64>();
+
+        assert!(points().all(|(x, z)| noise.generate_height_at(x, z).abs() <= bound));
+    }
+
+    #[test]
+    fn output_is_continuous() {
+        // The slope of the noise is bounded, so close points have close
+        // values, including across the edges of the cells.
+        let noise = single_octave(3);
+        let step = 1.0e-3;
+
+        for (x, z) in points() {
+            let here = noise.generate_height_at(x, z);
+            for (dx, dz) in [(step, 0.0), (0.0, step), (step, step)] {
+                let there = noise.generate_height_at(x + dx, z + dz);
+                assert!((here - there).abs() < 0.01, "jump at ({}, {})", x, z);
+            }
+        }
+
+        for cell in -5..5 {
+            let edge = cell as f32;
+            let below = noise.generate_height_at(edge - step, 0.5);
+            let above = noise.generate_height_at(edge + step, 0.5);
+            assert!((below - above).abs() < 0.01, "jump at the edge {}", edge);
+        }
+    }
+
+    #[test]
+    fn lattice_points_are_zero() {
+        let noise = single_octave(5);
+        for x in -20..20 {
+            for z in -20..20 {
+                assert!(noise.generate_height_at(x as f32, z as f32).abs() < 1.0e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn samples_the_horizontal_plane() {
+        let noise = single_octave(7);
+        for (x, z) in points().take(500) {
+            let at = noise.generate_height_at(x, z);
+            assert_eq!(noise.generate_noise_at(Point3::new(x, 0.0, z)), at);
+            assert_eq!(noise.generate_noise_at(Point3::new(x, 123.4, z)), at);
+        }
+    }
+}
@@ -1,3 +1,5 @@
+#[cfg(feature = "simd")]
+use std::convert::TryFrom;
 use cgmath::{ Point3, Vector3 };
 use crate::utils::{ lerp, fade, Random };
 use super::{ NoiseGen, NoiseGenOption };
@@ -18,8 +20,16 @@ pub struct Perlin3D {
     permutations: Box<[u8; 512]>,
 }
 
-impl Perlin3D {
-    fn generate_noise(&mut self, pos: Point3<f32>) -> f64 {
+/// Where a coordinate falls in the lattice, along a single axis.
+#[derive(Clone, Copy, Debug)]
+struct AxisSample {
+    cube: i32,
+    relative: f64,
+    weight: f64,
+}
+
+impl AxisSample {
+    fn new(coord: f32) -> Self {
         // for finding the point inside a cube (see `relative` below)
         let round_down = |a, b| if a as f32 > b { 
             b - (a - 1) as f32
@@ -27,65 +37,154 @@ impl Perlin3D {
             b - a as f32
         };
 
-        let grid = pos.cast::<i32>().unwrap();
-        let cube = grid.map(|i| i & 255);
-        let relative = Vector3::<f64>::new(
-            round_down(grid.x, pos.x) as f64,
-            round_down(grid.y, pos.y) as f64,
-            round_down(grid.z, pos.z) as f64,
-        );
+        let grid = coord as i32;
+        let relative = round_down(grid, coord) as f64;
 
-        let weight_n = fade(relative.z);
-        let weight_m = fade(relative.y);
-        let weight_l = fade(relative.x);
-
-        let  c = cube.x as usize;
-        let  a = (self.permutations[c + 0] as i32 + cube.y) as usize;
-        let aa = (self.permutations[a + 0] as i32 + cube.z) as usize;
-        let ab = (self.permutations[a + 1] as i32 + cube.z) as usize;
-        let  b = (self.permutations[c + 1] as i32 + cube.y) as usize;
-        let ba = (self.permutations[b + 0] as i32 + cube.z) as usize;
-        let bb = (self.permutations[b + 1] as i32 + cube.z) as usize;
-
-        let grad = |hash, x: f64, y, z| -> f64 {
-            let hash = hash & 15;
-            let u = if hash < 8 { x } else { y };
-            let v = if hash < 4 { 
-                y 
-            } else {
-                if hash == 12 || hash == 14 { 
-                    x 
-                } else { 
-                    z 
-                }
-            };
-            let r0 = if hash & 1 == 0 { u } else { -u };
-            let r1 = if hash & 2 == 0 { v } else { -v };
-            r0 + r1
-        };
+        Self {
+            cube: grid & 255,
+            relative,
+            weight: fade(relative),
+        }
+    }
+}
 
-        let (x, y, z) = (relative.x, relative.y, relative.z);
+// The number of samples computed at once by `sample_cells` when generating a
+// lattice. The kernel is plain arithmetic on arrays of this length, which the
+// compiler turns into SIMD instructions.
+#[cfg(feature = "simd")]
+const LANES: usize = 4;
 
-        let noise_000 = grad(self.permutations[aa+0], x,       y,       z      );
-        let noise_001 = grad(self.permutations[ba+0], x - 1.0, y,       z      );
-        let noise_010 = grad(self.permutations[ab+0], x,       y - 1.0, z      );
-        let noise_011 = grad(self.permutations[bb+0], x - 1.0, y - 1.0, z      );
-        let noise_100 = grad(self.permutations[aa+1], x,       y,       z - 1.0);
-        let noise_101 = grad(self.permutations[ba+1], x - 1.0, y,       z - 1.0);
-        let noise_110 = grad(self.permutations[ab+1], x,       y - 1.0, z - 1.0);
-        let noise_111 = grad(self.permutations[bb+1], x - 1.0, y - 1.0, z - 1.0);
+// The gradients picked by the lowest 4 bits of a hash. This is the same set
+// of gradients as in the reference implementation, but looking them up avoids
+// the hard-to-predict branches it uses.
+const GRADIENTS: [[f64; 3]; 16] = [
+    [ 1.0,  1.0,  0.0], [-1.0,  1.0,  0.0], [ 1.0, -1.0,  0.0], [-1.0, -1.0,  0.0],
+    [ 1.0,  0.0,  1.0], [-1.0,  0.0,  1.0], [ 1.0,  0.0, -1.0], [-1.0,  0.0, -1.0],
+    [ 0.0,  1.0,  1.0], [ 0.0, -1.0,  1.0], [ 0.0,  1.0, -1.0], [ 0.0, -1.0, -1.0],
+    [ 1.0,  1.0,  0.0], [ 0.0, -1.0,  1.0], [-1.0,  1.0,  0.0], [ 0.0, -1.0, -1.0],
+];
 
-        let n1 = lerp(noise_000, noise_001, weight_n);
-        let n2 = lerp(noise_010, noise_011, weight_n);
-        let n3 = lerp(noise_100, noise_101, weight_n);
-        let n4 = lerp(noise_110, noise_111, weight_n);
+#[inline(always)]
+fn grad(hash: u8, x: f64, y: f64, z: f64) -> f64 {
+    let [gx, gy, gz] = GRADIENTS[(hash & 15) as usize];
+    gx * x + gy * y + gz * z
+}
 
-        let m1 = lerp(n1, n2, weight_m);
-        let m2 = lerp(n3, n4, weight_m);
+impl Perlin3D {
+    fn generate_noise(&self, pos: Point3<f32>) -> f64 {
+        let (x, y, z) = (
+            AxisSample::new(pos.x),
+            AxisSample::new(pos.y),
+            AxisSample::new(pos.z),
+        );
 
-        let result = lerp(m1, m2, weight_l);
+        let [result] = self.sample_cells(self.hash_column(x, y), x, y, &[z]);
         result
     }
+
+    /// The part of the permutation hashes that only depends on x and y.
+    #[inline(always)]
+    fn hash_column(&self, x: AxisSample, y: AxisSample) -> [i32; 4] {
+        let  c = x.cube as usize;
+        let  a = (self.permutations[c] as i32 + y.cube) as usize;
+        let  b = (self.permutations[c + 1] as i32 + y.cube) as usize;
+
+        [
+            self.permutations[a] as i32,
+            self.permutations[a + 1] as i32,
+            self.permutations[b] as i32,
+            self.permutations[b + 1] as i32,
+        ]
+    }
+
+    /// Samples `L` points that share the same x and y coordinates.
+    #[inline(always)]
+    fn sample_cells<const L: usize>(
+        &self,
+        column: [i32; 4],
+        x: AxisSample,
+        y: AxisSample,
+        z: &[AxisSample; L],
+    ) -> [f64; L] {
+        let mut hashes = [[0u8; L]; 8];
+
+        for (i, z) in z.iter().enumerate() {
+            let aa = (column[0] + z.cube) as usize;
+            let ab = (column[1] + z.cube) as usize;
+            let ba = (column[2] + z.cube) as usize;
+            let bb = (column[3] + z.cube) as usize;
+
+            hashes[0][i] = self.permutations[aa];
+            hashes[1][i] = self.permutations[ba];
+            hashes[2][i] = self.permutations[ab];
+            hashes[3][i] = self.permutations[bb];
+            hashes[4][i] = self.permutations[aa+1];
+            hashes[5][i] = self.permutations[ba+1];
+            hashes[6][i] = self.permutations[ab+1];
+            hashes[7][i] = self.permutations[bb+1];
+        }
+
+        let (weight_l, weight_m) = (x.weight, y.weight);
+        let (x, y) = (x.relative, y.relative);
+
+        std::array::from_fn(|i| {
+            let (z, weight_n) = (z[i].relative, z[i].weight);
+
+            let noise_000 = grad(hashes[0][i], x,       y,       z      );
+            let noise_001 = grad(hashes[1][i], x - 1.0, y,       z      );
+            let noise_010 = grad(hashes[2][i], x,       y - 1.0, z      );
+            let noise_011 = grad(hashes[3][i], x - 1.0, y - 1.0, z      );
+            let noise_100 = grad(hashes[4][i], x,       y,       z - 1.0);
+            let noise_101 = grad(hashes[5][i], x - 1.0, y,       z - 1.0);
+            let noise_110 = grad(hashes[6][i], x,       y - 1.0, z - 1.0);
+            let noise_111 = grad(hashes[7][i], x - 1.0, y - 1.0, z - 1.0);
+
+            let n1 = lerp(noise_000, noise_001, weight_n);
+            let n2 = lerp(noise_010, noise_011, weight_n);
+            let n3 = lerp(noise_100, noise_101, weight_n);
+            let n4 = lerp(noise_110, noise_111, weight_n);
+
+            let m1 = lerp(n1, n2, weight_m);
+            let m2 = lerp(n3, n4, weight_m);
+
+            lerp(m1, m2, weight_l)
+        })
+    }
+
+    /// Adds `amplitude` times the samples of a row of points (which differ
+    /// only in their z coordinates) onto `output`.
+    fn sample_row(
+        &self,
+        x: AxisSample,
+        y: AxisSample,
+        z: &[AxisSample],
+        amplitude: f64,
+        output: &mut [f64],
+    ) {
+        let column = self.hash_column(x, y);
+
+        #[cfg(feature = "simd")]
+        let (z, output) = {
+            let mut z_lanes = z.chunks_exact(LANES);
+            let mut output_lanes = output.chunks_exact_mut(LANES);
+
+            for (z, output) in (&mut z_lanes).zip(&mut output_lanes) {
+                let z = <&[AxisSample; LANES]>::try_from(z).unwrap();
+                let noise = self.sample_cells(column, x, y, z);
+
+                for (output, noise) in output.iter_mut().zip(noise.iter()) {
+                    *output += noise * amplitude;
+                }
+            }
+
+            (z_lanes.remainder(), output_lanes.into_remainder())
+        };
+
+        for (z, output) in z.iter().zip(output.iter_mut()) {
+            let [noise] = self.sample_cells(column, x, y, &[*z]);
+            *output += noise * amplitude;
+        }
+    }
 }
 
 impl NoiseGen for Perlin3D {
@@ -108,7 +207,54 @@ impl NoiseGen for Perlin3D {
         }
     }
 
-    fn generate_noise_at(&mut self, pos: Point3<f32>) -> f64 {
+    fn generate_noise_lattice(
+        &self,
+        origin: Point3<f32>,
+        step: Vector3<f32>,
+        size: [usize; 3],
+        output: &mut [f64],
+    ) {
+        assert_eq!(output.len(), size[0] * size[1] * size[2]);
+
+        if output.is_empty() {
+            return
+        }
+
+        for sample in output.iter_mut() {
+            *sample = 0.0;
+        }
+
+        let mut amplitude = self.amplitude;
+        let mut frequency = self.frequency;
+
+        // The lattice is axis-aligned, so the lattice cell of every point can
+        // be found axis by axis instead of point by point.
+        let axis = |origin: f32, step: f32, size: usize, frequency: f32| {
+            (0..size)
+                .map(|i| AxisSample::new((origin + i as f32 * step) * frequency))
+                .collect::<Vec<_>>()
+        };
+
+        for _ in 0..self.octaves {
+            let xs = axis(origin.x, step.x, size[0], frequency);
+            let ys = axis(origin.y, step.y, size[1], frequency);
+            let zs = axis(origin.z, step.z, size[2], frequency);
+
+            let mut rows = output.chunks_exact_mut(size[2]);
+
+            for x in xs.iter() {
+                for y in ys.iter() {
+                    let row = rows.next().unwrap();
+                    self.sample_row(*x, *y, &zs, amplitude, row);
+                }
+            }
+
+            amplitude *= self.persistance;
+            frequency *= self.lacunarity;
+        };
+    }
+
+    fn generate_noise_at(&self, pos: Point3<f32>) -> f64 {
         let mut total = 0.0;
 
         let mut amplitude = self.amplitude;
@@ -123,3 +269,57 @@ impl NoiseGen for Perlin3D {
         total
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Several octaves, so that the lattice is sampled at several frequencies,
+    // some of them across more than one repetition of the noise.
+    fn generator(seed: u64) -> Perlin3D {
+        let option = NoiseGenOption::new()
+            .octaves(4)
+            .amplitude(2.0)
+            .persistance(0.5)
+            .frequency(0.37)
+            .lacunarity(2.0);
+
+        Perlin3D::with_option_and_seed(option, seed)
+    }
+
+    // The rows along z are built so that, with the `simd` feature, they are
+    // split into full lanes and a remainder.
+    #[test]
+    fn lattice_equals_sampling_every_point() {
+        let lattices = [
+            (Point3::new(0.0, 0.0, 0.0), Vector3::new(1.0, 1.0, 1.0), [3, 2, 9]),
+            (Point3::new(-37.5, -4.25, -100.0), Vector3::new(4.0, 8.0, 4.0), [5, 17, 5]),
+            (Point3::new(1000.3, 64.0, -0.7), Vector3::new(0.5, 3.0, 1.75), [2, 3, 1]),
+            (Point3::new(12.0, 5.0, 800.0), Vector3::new(2.0, 2.0, 2.0), [1, 1, 12]),
+        ];
+
+        for seed in 0..3 {
+            let noise = generator(seed);
+
+            for &(origin, step, size) in lattices.iter() {
+                let mut lattice = vec![f64::NAN; size[0] * size[1] * size[2]];
+                noise.generate_noise_lattice(origin, step, size, &mut lattice);
+
+                let mut lattice = lattice.into_iter();
+                for x in 0..size[0] {
+                    for y in 0..size[1] {
+                        for z in 0..size[2] {
+                            let point = Point3::new(
+                                origin.x + x as f32 * step.x,
+                                origin.y + y as f32 * step.y,
+                                origin.z + z as f32 * step.z,
+                            );
+
+                            assert_eq!(lattice.next().unwrap(), noise.generate_noise_at(point), "{:?}", point);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
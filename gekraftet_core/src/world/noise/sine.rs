@@ -21,7 +21,7 @@ impl NoiseGen for Sine2D {
         }
     }
 
-    fn generate_noise_at(&mut self, pos: Point3<f32>) -> f64 {
+    fn generate_noise_at(&self, pos: Point3<f32>) -> f64 {
         let mut total = 0.0;
 
         let mut amplitude = self.amplitude;
@@ -0,0 +1,142 @@
+use cgmath::{ Point3, Vector3 };
+use crate::utils::Random;
+use super::{ NoiseGen, NoiseGenOption };
+
+use rand::{ Rng, SeedableRng };
+
+/// Which feature point distances a `Worley3D` generator reports.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WorleyDistance {
+    /// Distance to the closest feature point. Gives the classic "cells" look.
+    F1,
+    /// Distance to the second closest feature point.
+    F2,
+    /// The difference between `F2` and `F1`. It is zero on the cell borders,
+    /// which makes it handy for biome borders and cave tunnels.
+    F2MinusF1,
+}
+
+/// A 3D Worley (cellular) noise generator. Every unit cell of the lattice owns
+/// exactly one feature point, placed using the seeded permutation table.
+pub struct Worley3D {
+    pub octaves: u32,
+
+    pub amplitude: f64,
+    pub frequency: f32,
+    pub lacunarity: f32, // lacunarity means "gap".
+    pub persistance: f64,
+
+    pub distance: WorleyDistance,
+
+    permutations: Box<[u8; 512]>,
+}
+
+impl Worley3D {
+    /// Changes the distance mode of the generator (the default is `F1`).
+    pub fn with_distance(mut self, distance: WorleyDistance) -> Self {
+        self.distance = distance;
+        self
+    }
+
+    fn hash(&self, cell: Point3<i32>) -> usize {
+        let p = &self.permutations;
+        let x = p[(cell.x & 255) as usize] as i32;
+        let y = p[((x + cell.y) & 255) as usize] as i32;
+        p[((y + cell.z) & 255) as usize] as usize
+    }
+
+    fn feature_point(&self, cell: Point3<i32>) -> Point3<f64> {
+        let p = &self.permutations;
+        let hash = self.hash(cell);
+
+        // Two table lookups per axis give the offset 16 bits of precision,
+        // otherwise the feature points visibly snap onto a 1/256 grid.
+        let offset = |i: usize| {
+            let hi = p[hash + i] as f64;
+            let lo = p[p[hash + i] as usize + i] as f64;
+            (hi * 256.0 + lo) / 65536.0
+        };
+
+        Point3::new(
+            cell.x as f64 + offset(0),
+            cell.y as f64 + offset(1),
+            cell.z as f64 + offset(2),
+        )
+    }
+
+    fn generate_noise(&self, pos: Point3<f32>) -> f64 {
+        let pos = pos.cast::<f64>().unwrap();
+        let cell = Point3::new(
+            pos.x.floor() as i32,
+            pos.y.floor() as i32,
+            pos.z.floor() as i32,
+        );
+
+        let mut f1 = f64::MAX;
+        let mut f2 = f64::MAX;
+
+        // The closest two feature points are always within the 3x3x3 block of
+        // cells around the sampled point.
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                for dz in -1..=1 {
+                    let neighbour = cell + Vector3::new(dx, dy, dz);
+                    let offset = self.feature_point(neighbour) - pos;
+                    let distance = (offset.x * offset.x
+                        + offset.y * offset.y
+                        + offset.z * offset.z).sqrt();
+
+                    if distance < f1 {
+                        f2 = f1;
+                        f1 = distance;
+                    } else if distance < f2 {
+                        f2 = distance;
+                    }
+                }
+            }
+        }
+
+        match self.distance {
+            WorleyDistance::F1 => f1,
+            WorleyDistance::F2 => f2,
+            WorleyDistance::F2MinusF1 => f2 - f1,
+        }
+    }
+}
+
+impl NoiseGen for Worley3D {
+    fn with_option_and_seed(option: NoiseGenOption, seed: u64) -> Self {
+        let mut rng = Random::seed_from_u64(seed);
+
+        let mut permutations = Box::new([0; 512]);
+        for i in 0..256 {
+            permutations[i] = rng.gen::<u8>();
+            permutations[i + 256] = permutations[i];
+        };
+
+        Self {
+            octaves: option.octaves,
+            amplitude: option.amplitude,
+            frequency: option.frequency,
+            lacunarity: option.lacunarity,
+            persistance: option.persistance,
+            distance: WorleyDistance::F1,
+            permutations,
+        }
+    }
+
+    fn generate_noise_at(&self, pos: Point3<f32>) -> f64 {
+        let mut total = 0.0;
+
+        let mut amplitude = self.amplitude;
+        let mut frequency = self.frequency;
+
+        for _ in 0..self.octaves {
+            total += self.generate_noise(pos * frequency) * amplitude;
+            amplitude *= self.persistance;
+            frequency *= self.lacunarity;
+        };
+
+        total
+    }
+}
@@ -0,0 +1,295 @@
+use std::cmp::Reverse;
+use std::collections::{ BinaryHeap, HashMap };
+use super::*;
+
+// The furthest a mob drops down along a path.
+const MAX_DROP: i32 = 3;
+// The costs are kept in tenths of a block, so that they can be ordered.
+const STEP_COST: u32 = 10;
+
+/// The blocks a mob walks through to reach somewhere, one block apart.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Path {
+    points: Vec<BlockPos>,
+    index: usize,
+}
+
+impl Path {
+    pub fn points(&self) -> &[BlockPos] {
+        &self.points
+    }
+
+    /// The block the mob is walking towards, or None if it has arrived.
+    pub fn next(&self) -> Option<BlockPos> {
+        self.points.get(self.index).copied()
+    }
+
+    /// Moves on to the next block of the path.
+    pub fn advance(&mut self) {
+        self.index += 1;
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.index >= self.points.len()
+    }
+
+    /// The last block of the path, which is not always where the mob wanted
+    /// to go if it could not get there.
+    pub fn end(&self) -> BlockPos {
+        self.points[self.points.len() - 1]
+    }
+}
+
+/// Whether a mob can walk through the block. Mobs keep out of lava and
+/// fire, and of the blocks which hurt them.
+fn is_passable(block: Block) -> bool {
+    use block_ids::*;
+
+    !block.info().solid && !matches!(block.id, FLOWING_LAVA | LAVA | FIRE | CACTUS)
+}
+
+fn is_water(block: Block) -> bool {
+    matches!(block.id, block_ids::FLOWING_WATER | block_ids::WATER)
+}
+
+impl Level {
+    /// Whether a mob taking up `size` blocks (across and up) fits with its
+    /// lowest corner at `at`, and has something to stand on: a full solid
+    /// block, or water to swim in.
+    pub fn can_stand_at(&self, at: BlockPos, size: (i32, i32)) -> bool {
+        let (width, height) = size;
+
+        for x in 0..width {
+            for y in 0..height {
+                for z in 0..width {
+                    if !self.block(at.offset(x, y, z)).is_some_and(is_passable) {
+                        return false
+                    }
+                }
+            }
+        }
+
+        match self.block(at.offset(0, -1, 0)) {
+            // Fences are too high to stand on.
+            Some(below) if below.info().solid => below.info().height <= 1.0,
+            _ => self.block(at).is_some_and(is_water),
+        }
+    }
+
+    /// Whether a mob taking up `size` blocks has room to go up a block from
+    /// `at`, such as to step or jump onto a higher block.
+    fn has_headroom(&self, at: BlockPos, size: (i32, i32)) -> bool {
+        let (width, height) = size;
+
+        (0..width).all(|x| (0..width).all(|z| {
+            self.block(at.offset(x, height, z)).is_some_and(is_passable)
+        }))
+    }
+
+    /// The blocks a mob standing at `at` can get to in a single step: the
+    /// blocks next to it at the same height, a block higher, or a few blocks
+    /// lower.
+    fn path_neighbors(&self, at: BlockPos, size: (i32, i32)) -> Vec<BlockPos> {
+        let mut result = Vec::new();
+        let headroom = self.has_headroom(at, size);
+
+        for &(dx, dz) in [(1, 0), (-1, 0), (0, 1), (0, -1)].iter() {
+            let side = at.offset(dx, 0, dz);
+
+            if self.can_stand_at(side, size) {
+                result.push(side);
+                continue
+            }
+
+            let above = side.offset(0, 1, 0);
+            if headroom && self.can_stand_at(above, size) {
+                result.push(above);
+                continue
+            }
+
+            // Walk off the edge if the mob does not fall too far.
+            let (width, height) = size;
+            let mut below = side;
+            let mut open = (0..width).all(|x| (0..height).all(|y| (0..width).all(|z| {
+                self.block(side.offset(x, y, z)).is_some_and(is_passable)
+            })));
+
+            for _ in 0..MAX_DROP {
+                if !open {
+                    break
+                }
+
+                below = below.offset(0, -1, 0);
+                if self.can_stand_at(below, size) {
+                    result.push(below);
+                    break
+                }
+
+                open = (0..width).all(|x| (0..width).all(|z| {
+                    self.block(below.offset(x, 0, z)).is_some_and(is_passable)
+                }));
+            }
+        }
+
+        result
+    }
+
+    /// Finds the shortest path for a mob taking up `size` blocks (across and
+    /// up) from `from` to `to`, with A*, the one with the fewest steps. The
+    /// search stays within `max_distance` blocks of `from`. If `to` cannot be
+    /// reached, the path goes to the block closest to it instead. Returns
+    /// None if the mob cannot get any closer.
+    ///
+    /// Ties are broken by the order the blocks were found in, so the same
+    /// blocks always give the same path.
+    pub fn find_path(&self, from: BlockPos, to: BlockPos, size: (i32, i32), max_distance: i32) -> Option<Path> {
+        // Every step goes a block across, whether it goes up or down or not,
+        // so there are at least as many steps left as blocks across. This
+        // never overestimates the cost, which keeps the path the shortest.
+        let estimate = |at: BlockPos| {
+            ((to.x - at.x).abs() + (to.z - at.z).abs()) as u32 * STEP_COST
+        };
+        let distance = |at: BlockPos| {
+            let (dx, dy, dz) = ((to.x - at.x) as f64, (to.y - at.y) as f64, (to.z - at.z) as f64);
+            ((dx * dx + dy * dy + dz * dz).sqrt() * STEP_COST as f64) as u32
+        };
+
+        // The blocks found so far, along with the block they were reached
+        // from and the cost of getting there.
+        let mut found = vec![(from, None, 0)];
+        let mut indices = HashMap::new();
+        indices.insert(from, 0);
+
+        let mut open = BinaryHeap::new();
+        open.push(Reverse((estimate(from), 0)));
+
+        let mut closest = (distance(from), 0);
+
+        while let Some(Reverse((_, index))) = open.pop() {
+            let (at, _, cost) = found[index];
+            if at == to {
+                closest = (0, index);
+                break
+            }
+
+            for next in self.path_neighbors(at, size) {
+                let away = (next.x - from.x).abs().max((next.y - from.y).abs()).max((next.z - from.z).abs());
+                if away > max_distance {
+                    continue
+                }
+
+                let next_cost = cost + STEP_COST;
+                let next_index = match indices.get(&next) {
+                    Some(&i) if found[i].2 <= next_cost => continue,
+                    Some(&i) => {
+                        found[i] = (next, Some(index), next_cost);
+                        i
+                    },
+                    None => {
+                        found.push((next, Some(index), next_cost));
+                        indices.insert(next, found.len() - 1);
+                        found.len() - 1
+                    },
+                };
+
+                closest = closest.min((distance(next), next_index));
+                open.push(Reverse((next_cost + estimate(next), next_index)));
+            }
+        }
+
+        let (_, mut index) = closest;
+        if index == 0 {
+            return None
+        }
+
+        let mut points = Vec::new();
+        while let (at, Some(previous), _) = found[index] {
+            points.push(at);
+            index = previous;
+        }
+        points.reverse();
+
+        Some(Path { points, index: 0 })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+    use rand::{ Rng, SeedableRng };
+    use crate::utils::Random;
+    use super::*;
+
+    const SIZE: (i32, i32) = (1, 2);
+    const RANGE: i32 = 16;
+
+    /// A chunk of hills of stone, whose columns are between 10 and 14 blocks
+    /// high, so that the mobs have to step up and drop down.
+    fn hills(rng: &mut Random) -> (Level, Vec<BlockPos>) {
+        let mut level = Level::new();
+        level.insert_chunk(Chunk::empty(ChunkPos::new(0, 0, 0)));
+
+        let mut ground = Vec::new();
+        for x in 0..CHUNK_LENGTH_X as i32 {
+            for z in 0..CHUNK_LENGTH_Z as i32 {
+                let height = rng.gen_range(10, 15);
+                for y in 0..height {
+                    level.set_block(BlockPos::new(x, y, z), Block::new(block_ids::STONE));
+                }
+                ground.push(BlockPos::new(x, height, z));
+            }
+        }
+
+        (level, ground)
+    }
+
+    /// The fewest steps from `from` to `to`, found by visiting every block
+    /// in range.
+    fn fewest_steps(level: &Level, from: BlockPos, to: BlockPos) -> Option<usize> {
+        let mut steps = HashMap::new();
+        steps.insert(from, 0);
+        let mut queue = VecDeque::new();
+        queue.push_back(from);
+
+        while let Some(at) = queue.pop_front() {
+            if at == to {
+                return Some(steps[&at])
+            }
+
+            for next in level.path_neighbors(at, SIZE) {
+                let away = (next.x - from.x).abs().max((next.y - from.y).abs()).max((next.z - from.z).abs());
+                if away <= RANGE && !steps.contains_key(&next) {
+                    steps.insert(next, steps[&at] + 1);
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        None
+    }
+
+    #[test]
+    fn finds_the_shortest_path() {
+        let mut rng = Random::seed_from_u64(47);
+
+        for _ in 0..20 {
+            let (level, ground) = hills(&mut rng);
+
+            for _ in 0..20 {
+                let from = ground[rng.gen_range(0, ground.len())];
+                let to = ground[rng.gen_range(0, ground.len())];
+
+                let path = level.find_path(from, to, SIZE, RANGE);
+                match fewest_steps(&level, from, to) {
+                    Some(0) => assert_eq!(path, None),
+                    Some(steps) => {
+                        let path = path.expect("a path");
+                        assert_eq!(path.end(), to);
+                        assert_eq!(path.points().len(), steps, "from {:?} to {:?}", from, to);
+                    },
+                    None => assert!(path.is_none_or(|path| path.end() != to)),
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,275 @@
+use super::*;
+
+/// How far around where a player arrives in another dimension a portal is
+/// looked for, across, before a new one is built.
+pub const PORTAL_SEARCH_RADIUS: i32 = 128;
+
+// The inside of a portal frame, across and up.
+const PORTAL_WIDTH: i32 = 2;
+const PORTAL_HEIGHT: i32 = 3;
+// How far around where a player arrives a new portal can be built, across.
+const PORTAL_PLACEMENT_RADIUS: i32 = 16;
+// Portals are built below this height, which leaves room for them under the
+// ceiling of the Nether.
+const PORTAL_MAX_Y: i32 = 118;
+// A portal built where there is no room for it floats on a platform of
+// obsidian, at least this high.
+const PORTAL_MIN_Y: i32 = 70;
+
+// The directions a frame can be built along, as offsets in x and z.
+const AXES: [(i32, i32); 2] = [(1, 0), (0, 1)];
+
+/// A position in the plane of a portal: `across` along its axis, `up`, and
+/// `depth` across its axis, from the lowest block inside the frame.
+fn frame_pos(origin: BlockPos, (ax, az): (i32, i32), across: i32, up: i32, depth: i32) -> BlockPos {
+    origin.offset(ax * across + az * depth, up, az * across + ax * depth)
+}
+
+fn is_border(across: i32, up: i32) -> bool {
+    across == -1 || across == PORTAL_WIDTH || up == -1 || up == PORTAL_HEIGHT
+}
+
+fn is_corner(across: i32, up: i32) -> bool {
+    (across == -1 || across == PORTAL_WIDTH) && (up == -1 || up == PORTAL_HEIGHT)
+}
+
+fn is_id(level: &Level, at: BlockPos, id: u16) -> bool {
+    level.block(at).is_some_and(|b| b.id == id)
+}
+
+/// Whether the obsidian around the inside of a portal is complete and the
+/// inside is empty, apart from fire. The corners do not matter.
+fn is_frame_complete(level: &Level, origin: BlockPos, axis: (i32, i32)) -> bool {
+    (-1..=PORTAL_WIDTH).all(|across| (-1..=PORTAL_HEIGHT).all(|up| {
+        let at = frame_pos(origin, axis, across, up, 0);
+
+        if is_corner(across, up) {
+            true
+        } else if is_border(across, up) {
+            is_id(level, at, block_ids::OBSIDIAN)
+        } else {
+            level.block(at).is_some_and(|b| matches!(b.id, block_ids::AIR | block_ids::FIRE))
+        }
+    }))
+}
+
+/// Fills the inside of a frame with portal blocks, and lets the blocks
+/// around react once the portal is complete.
+fn fill_portal(level: &mut Level, origin: BlockPos, axis: (i32, i32)) {
+    // The portal blocks are all placed before any of them are checked, since
+    // a portal which is not complete breaks.
+    let inside = (0..PORTAL_WIDTH)
+        .flat_map(|across| (0..PORTAL_HEIGHT).map(move |up| frame_pos(origin, axis, across, up, 0)))
+        .collect::<Vec<_>>();
+
+    for &at in inside.iter() {
+        level.set_block(at, Block::new(block_ids::PORTAL));
+    }
+    for &at in inside.iter() {
+        level.notify_neighbors(at);
+    }
+}
+
+/// Called when fire is placed. If the fire is on the bottom of an obsidian
+/// frame of the right size, the frame is lit and filled with portal blocks.
+pub(crate) fn on_fire_added(level: &mut Level, at: BlockPos) {
+    let frame = AXES.iter().find_map(|&axis| {
+        (0..PORTAL_WIDTH)
+            .map(|shift| frame_pos(at, axis, -shift, 0, 0))
+            .find(|&origin| is_frame_complete(level, origin, axis))
+            .map(|origin| (origin, axis))
+    });
+
+    if let Some((origin, axis)) = frame {
+        fill_portal(level, origin, axis);
+    }
+}
+
+/// Called when a neighbour of a portal block changes. The portal breaks if
+/// its frame is not complete anymore, as in Beta: every portal block checks
+/// that it is part of a column of 3 portal blocks between two blocks of
+/// obsidian, next to obsidian on one side and to another column on the
+/// other. A block which breaks makes its neighbours check again, so the whole
+/// portal breaks.
+pub(crate) fn on_neighbor_changed(level: &mut Level, at: BlockPos) {
+    let along = |(ax, az): (i32, i32)| {
+        is_id(level, at.offset(ax, 0, az), block_ids::PORTAL)
+            || is_id(level, at.offset(-ax, 0, -az), block_ids::PORTAL)
+    };
+
+    let axis = match (along(AXES[0]), along(AXES[1])) {
+        (true, false) => Some(AXES[0]),
+        (false, true) => Some(AXES[1]),
+        _ => None,
+    };
+
+    let intact = axis.is_some_and(|(ax, az)| {
+        let mut bottom = at;
+        while is_id(level, bottom.offset(0, -1, 0), block_ids::PORTAL) {
+            bottom = bottom.offset(0, -1, 0);
+        }
+
+        let height = (0..).take_while(|&up| is_id(level, bottom.offset(0, up, 0), block_ids::PORTAL)).count() as i32;
+
+        let (front, back) = (at.offset(ax, 0, az), at.offset(-ax, 0, -az));
+        let sides = (is_id(level, front, block_ids::OBSIDIAN) && is_id(level, back, block_ids::PORTAL))
+            || (is_id(level, front, block_ids::PORTAL) && is_id(level, back, block_ids::OBSIDIAN));
+
+        is_id(level, bottom.offset(0, -1, 0), block_ids::OBSIDIAN)
+            && height == PORTAL_HEIGHT
+            && is_id(level, bottom.offset(0, PORTAL_HEIGHT, 0), block_ids::OBSIDIAN)
+            && sides
+    });
+
+    if !intact {
+        level.set_block_with_notify(at, Block::new(block_ids::AIR));
+    }
+}
+
+impl Level {
+    /// Finds the portal closest to `near`, at most `radius` blocks away
+    /// across, in the loaded chunks. Returns the lowest block of the portal
+    /// column it found, where an entity stands in the portal.
+    pub fn find_portal(&self, near: BlockPos, radius: i32) -> Option<BlockPos> {
+        let mut best: Option<(i64, BlockPos)> = None;
+
+        for chunk in self.chunks() {
+            let ChunkPos(pos) = chunk.position();
+            let (min_x, min_z) = (pos.x * CHUNK_LENGTH_X as i32, pos.z * CHUNK_LENGTH_Z as i32);
+
+            for x in 0..CHUNK_LENGTH_X {
+                for z in 0..CHUNK_LENGTH_Z {
+                    let (dx, dz) = (min_x + x as i32 - near.x, min_z + z as i32 - near.z);
+                    if dx.abs() > radius || dz.abs() > radius {
+                        continue
+                    }
+
+                    for y in 0..CHUNK_LENGTH_Y {
+                        let bottom = chunk.block(x, y, z).id == block_ids::PORTAL
+                            && (y == 0 || chunk.block(x, y - 1, z).id != block_ids::PORTAL);
+                        if !bottom {
+                            continue
+                        }
+
+                        let at = BlockPos::new(min_x + x as i32, y as i32, min_z + z as i32);
+                        let dy = y as i32 - near.y;
+                        let distance = (dx as i64).pow(2) + (dy as i64).pow(2) + (dz as i64).pow(2);
+
+                        // Portals as far away are told apart by their
+                        // position, whatever the order of the chunks.
+                        let closer = best.is_none_or(|(best, best_at)| {
+                            (distance, at.x, at.y, at.z) < (best, best_at.x, best_at.y, best_at.z)
+                        });
+                        if closer {
+                            best = Some((distance, at));
+                        }
+                    }
+                }
+            }
+        }
+
+        best.map(|(_, at)| at)
+    }
+
+    /// Whether a portal can be built with the lowest block of its inside at
+    /// `origin`: the portal and the blocks in front of and behind it must be
+    /// empty, and stand on opaque blocks.
+    fn has_room_for_portal(&self, origin: BlockPos, axis: (i32, i32)) -> bool {
+        (-1..=PORTAL_WIDTH).all(|across| (-1..=1).all(|depth| {
+            let ground = self.block(frame_pos(origin, axis, across, -1, depth))
+                .is_some_and(|b| b.info().opaque);
+
+            ground && (0..=PORTAL_HEIGHT).all(|up| {
+                is_id(self, frame_pos(origin, axis, across, up, depth), block_ids::AIR)
+            })
+        }))
+    }
+
+    /// Builds a portal around `near`, in the loaded chunks. The portal is
+    /// built where there is room for it closest to `near`, or floating on a
+    /// platform of obsidian otherwise. Returns the lowest block inside the
+    /// portal, where an entity stands in it.
+    pub fn create_portal(&mut self, near: BlockPos) -> BlockPos {
+        let mut best: Option<(i64, BlockPos, (i32, i32))> = None;
+
+        for x in -PORTAL_PLACEMENT_RADIUS..=PORTAL_PLACEMENT_RADIUS {
+            for z in -PORTAL_PLACEMENT_RADIUS..=PORTAL_PLACEMENT_RADIUS {
+                for y in 1..=PORTAL_MAX_Y {
+                    let origin = BlockPos::new(near.x + x, y, near.z + z);
+
+                    // Most blocks are not even empty above the ground.
+                    let standing = is_id(self, origin, block_ids::AIR)
+                        && self.block(origin.offset(0, -1, 0)).is_some_and(|b| b.info().opaque);
+                    if !standing {
+                        continue
+                    }
+
+                    let dy = y - near.y;
+                    let distance = (x as i64).pow(2) + (dy as i64).pow(2) + (z as i64).pow(2);
+                    if best.is_some_and(|(best, ..)| distance >= best) {
+                        continue
+                    }
+
+                    if let Some(&axis) = AXES.iter().find(|&&axis| self.has_room_for_portal(origin, axis)) {
+                        best = Some((distance, origin, axis));
+                    }
+                }
+            }
+        }
+
+        let (origin, axis) = match best {
+            Some((_, origin, axis)) => (origin, axis),
+            None => {
+                let origin = BlockPos::new(near.x, near.y.clamp(PORTAL_MIN_Y, PORTAL_MAX_Y), near.z);
+                let axis = AXES[0];
+
+                for across in -1..=PORTAL_WIDTH {
+                    for depth in -1..=1 {
+                        let ground = frame_pos(origin, axis, across, -1, depth);
+                        self.set_block(ground, Block::new(block_ids::OBSIDIAN));
+
+                        for up in 0..=PORTAL_HEIGHT {
+                            self.set_block(frame_pos(origin, axis, across, up, depth), Block::new(block_ids::AIR));
+                        }
+                    }
+                }
+
+                (origin, axis)
+            },
+        };
+
+        for across in -1..=PORTAL_WIDTH {
+            for up in -1..=PORTAL_HEIGHT {
+                if is_border(across, up) {
+                    let at = frame_pos(origin, axis, across, up, 0);
+                    self.set_block(at, Block::new(block_ids::OBSIDIAN));
+                    self.notify_neighbors(at);
+                }
+            }
+        }
+
+        fill_portal(self, origin, axis);
+        origin
+    }
+
+    /// Finds where an entity arriving through a portal comes out, around
+    /// `near`: at the closest portal in the loaded chunks, or at a new one.
+    /// The chunks where a new portal could be built are generated with
+    /// `generator` first if they are not loaded.
+    pub fn portal_destination<W>(&mut self, near: BlockPos, generator: &W) -> BlockPos
+        where W: WorldGenerator + ?Sized
+    {
+        let from = ChunkPos::from(near.offset(-PORTAL_PLACEMENT_RADIUS - 1, 0, -PORTAL_PLACEMENT_RADIUS - 1));
+        let to = ChunkPos::from(near.offset(PORTAL_PLACEMENT_RADIUS + 1, 0, PORTAL_PLACEMENT_RADIUS + 1));
+        for x in from.x..=to.x {
+            for z in from.z..=to.z {
+                self.load_chunk(ChunkPos::new(x, 0, z), generator);
+            }
+        }
+
+        match self.find_portal(near, PORTAL_SEARCH_RADIUS) {
+            Some(portal) => portal,
+            None => self.create_portal(near),
+        }
+    }
+}
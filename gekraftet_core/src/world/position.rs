@@ -1,27 +1,33 @@
 use std::ops::{ Deref, DerefMut };
 use cgmath::{ Point2, Point3 };
+use super::BlockFace;
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub struct BlockPos(pub Point3<i32>);
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub struct ChunkPos(pub Point3<i32>);
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub struct SectionPos(pub Point3<i32>);
 
 impl From<BlockPos> for ChunkPos {
     fn from(b: BlockPos) -> Self {
-        let x = (b.0).x / 16;
-        let y = (b.0).y / 256;
-        let z = (b.0).z / 16;
+        // Rounding towards negative infinity, so that the blocks at negative
+        // coordinates end up in the right chunk.
+        let x = (b.0).x.div_euclid(16);
+        let y = (b.0).y.div_euclid(256);
+        let z = (b.0).z.div_euclid(16);
         Self(Point3::<i32>::new(x, y, z))
     }
 }
 
 impl From<BlockPos> for SectionPos {
     fn from(b: BlockPos) -> Self {
-        Self(b.0 / 16)
+        let x = (b.0).x.div_euclid(16);
+        let y = (b.0).y.div_euclid(16);
+        let z = (b.0).z.div_euclid(16);
+        Self(Point3::<i32>::new(x, y, z))
     }
 }
 
@@ -56,6 +62,15 @@ impl BlockPos {
     pub fn new(x: i32, y: i32, z: i32) -> Self {
         Self(Point3::<i32>::new(x, y, z))
     }
+
+    pub fn offset(self, x: i32, y: i32, z: i32) -> Self {
+        Self::new(self.x + x, self.y + y, self.z + z)
+    }
+
+    /// The block next to this one, on the given face.
+    pub fn neighbor(self, face: BlockFace) -> Self {
+        Self(self.0 + face.normal())
+    }
 }
 
 impl ChunkPos {
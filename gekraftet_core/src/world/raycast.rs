@@ -0,0 +1,256 @@
+use cgmath::{ InnerSpace, Point3, Vector3 };
+use super::*;
+
+/// Where a ray hit a block.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RaycastHit {
+    pub block: BlockPos,
+    /// The face of the block the ray entered through.
+    pub face: BlockFace,
+    /// The distance travelled by the ray before hitting the block.
+    pub distance: f32,
+}
+
+/// Walks through the blocks crossed by a ray, in order, until `is_hit`
+/// returns true for one of them or the ray is longer than `max_distance`.
+///
+/// This is the grid traversal of Amanatides and Woo: the ray moves from a
+/// block to the next by crossing whichever block border is the closest along
+/// the ray, so every block touched by the ray is visited exactly once.
+///
+/// If the ray starts inside a block which is hit, the distance is zero and
+/// the face is the one the ray would have entered through.
+pub fn raycast<F>(
+    origin: Point3<f32>,
+    direction: Vector3<f32>,
+    max_distance: f32,
+    mut is_hit: F,
+) -> Option<RaycastHit>
+    where F: FnMut(BlockPos) -> bool
+{
+    assert!(max_distance.is_finite(), "the ray must not be infinitely long");
+
+    let origin = origin.cast::<f64>().unwrap();
+    let direction = direction.cast::<f64>().unwrap();
+    if direction.magnitude2() == 0.0 {
+        return None
+    }
+    let direction = direction.normalize();
+
+    let origin = [origin.x, origin.y, origin.z];
+    let direction = [direction.x, direction.y, direction.z];
+
+    let mut block = [0i32; 3];
+    let mut step = [0i32; 3];
+    // The distance along the ray at which the next border is crossed, and the
+    // distance between two borders, for every axis.
+    let mut t_max = [f64::INFINITY; 3];
+    let mut t_delta = [f64::INFINITY; 3];
+
+    for axis in 0..3 {
+        block[axis] = origin[axis].floor() as i32;
+
+        if direction[axis] > 0.0 {
+            step[axis] = 1;
+            t_max[axis] = (block[axis] as f64 + 1.0 - origin[axis]) / direction[axis];
+            t_delta[axis] = 1.0 / direction[axis];
+        } else if direction[axis] < 0.0 {
+            step[axis] = -1;
+            t_max[axis] = (block[axis] as f64 - origin[axis]) / direction[axis];
+            t_delta[axis] = -1.0 / direction[axis];
+        }
+    }
+
+    // The face entered when stepping along an axis.
+    let entered = |axis: usize| match (axis, step[axis] > 0) {
+        (0, true) => BlockFace::Left,
+        (0, false) => BlockFace::Right,
+        (1, true) => BlockFace::Bottom,
+        (1, false) => BlockFace::Top,
+        (_, true) => BlockFace::Back,
+        (_, false) => BlockFace::Front,
+    };
+
+    let to_pos = |block: [i32; 3]| BlockPos::new(block[0], block[1], block[2]);
+
+    if is_hit(to_pos(block)) {
+        let major = (0..3)
+            .max_by(|&a, &b| direction[a].abs().partial_cmp(&direction[b].abs()).unwrap())
+            .unwrap();
+
+        return Some(RaycastHit {
+            block: to_pos(block),
+            face: entered(major),
+            distance: 0.0,
+        })
+    }
+
+    loop {
+        // On ties, x goes before y, which goes before z.
+        let axis = (0..3)
+            .min_by(|&a, &b| t_max[a].partial_cmp(&t_max[b]).unwrap())
+            .unwrap();
+
+        let distance = t_max[axis];
+        if distance > max_distance as f64 {
+            return None
+        }
+
+        block[axis] += step[axis];
+        t_max[axis] += t_delta[axis];
+
+        if is_hit(to_pos(block)) {
+            return Some(RaycastHit {
+                block: to_pos(block),
+                face: entered(axis),
+                distance: distance as f32,
+            })
+        }
+    }
+}
+
+impl Level {
+    /// Casts a ray through the level, returning the first block which is not
+    /// air. Chunks which are not loaded are treated as if they were empty.
+    pub fn raycast(
+        &self,
+        origin: Point3<f32>,
+        direction: Vector3<f32>,
+        max_distance: f32,
+    ) -> Option<RaycastHit> {
+        raycast(origin, direction, max_distance, |at| {
+            self.block(at).is_some_and(|block| block.id != block_ids::AIR)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{ Rng, SeedableRng };
+    use crate::utils::Random;
+    use super::*;
+
+    /// A level with the chunks around the origin loaded, empty but for the
+    /// given stone blocks.
+    fn level(stone: &[BlockPos]) -> Level {
+        let mut level = Level::new();
+        for x in -2..2 {
+            for z in -2..2 {
+                level.insert_chunk(Chunk::empty(ChunkPos::new(x, 0, z)));
+            }
+        }
+        for &at in stone {
+            assert!(level.set_block(at, Block::new(block_ids::STONE)));
+        }
+        level
+    }
+
+    fn hit(block: BlockPos, face: BlockFace, distance: f32) -> Option<RaycastHit> {
+        Some(RaycastHit { block, face, distance })
+    }
+
+    #[test]
+    fn crosses_chunk_borders() {
+        let level = level(&[BlockPos::new(17, 64, 3), BlockPos::new(3, 64, 18)]);
+
+        assert_eq!(
+            level.raycast(Point3::new(14.5, 64.5, 3.5), Vector3::unit_x(), 10.0),
+            hit(BlockPos::new(17, 64, 3), BlockFace::Left, 2.5),
+        );
+        assert_eq!(
+            level.raycast(Point3::new(3.5, 64.5, 14.25), Vector3::unit_z(), 10.0),
+            hit(BlockPos::new(3, 64, 18), BlockFace::Back, 3.75),
+        );
+    }
+
+    #[test]
+    fn walks_negative_coordinates() {
+        let level = level(&[BlockPos::new(-3, 10, -1), BlockPos::new(-17, 10, -20)]);
+
+        // -0.5 is in the block -1, not 0, so the block -3 starts 1.5 away.
+        assert_eq!(
+            level.raycast(Point3::new(-0.5, 10.5, -0.5), -Vector3::unit_x(), 10.0),
+            hit(BlockPos::new(-3, 10, -1), BlockFace::Right, 1.5),
+        );
+        // Across the border of the chunks -1 and -2.
+        assert_eq!(
+            level.raycast(Point3::new(-16.5, 10.5, -14.5), -Vector3::unit_z(), 10.0),
+            hit(BlockPos::new(-17, 10, -20), BlockFace::Front, 4.5),
+        );
+    }
+
+    #[test]
+    fn crosses_zero() {
+        let level = level(&[BlockPos::new(-1, 5, 0), BlockPos::new(0, 5, -2)]);
+
+        assert_eq!(
+            level.raycast(Point3::new(1.5, 5.5, 0.5), -Vector3::unit_x(), 10.0),
+            hit(BlockPos::new(-1, 5, 0), BlockFace::Right, 1.5),
+        );
+        assert_eq!(
+            level.raycast(Point3::new(0.5, 5.5, 1.0), -Vector3::unit_z(), 10.0),
+            hit(BlockPos::new(0, 5, -2), BlockFace::Front, 2.0),
+        );
+    }
+
+    #[test]
+    fn stops_at_the_maximum_distance() {
+        let level = level(&[BlockPos::new(-5, 20, 0)]);
+        let origin = Point3::new(0.5, 20.5, 0.5);
+
+        assert_eq!(level.raycast(origin, -Vector3::unit_x(), 4.0), None);
+        assert_eq!(
+            level.raycast(origin, -Vector3::unit_x(), 4.5),
+            hit(BlockPos::new(-5, 20, 0), BlockFace::Right, 4.5),
+        );
+    }
+
+    #[test]
+    fn starts_inside_a_block() {
+        let level = level(&[BlockPos::new(-1, 30, -1)]);
+
+        assert_eq!(
+            level.raycast(Point3::new(-0.5, 30.5, -0.2), Vector3::new(0.2, 1.0, 0.1), 10.0),
+            hit(BlockPos::new(-1, 30, -1), BlockFace::Bottom, 0.0),
+        );
+    }
+
+    #[test]
+    fn visits_every_block_along_the_ray_in_order() {
+        let mut rng = Random::seed_from_u64(35);
+
+        for _ in 0..200 {
+            let origin = Point3::new(rng.gen_range(-40.0, 40.0), rng.gen_range(1.0, 100.0), rng.gen_range(-40.0, 40.0));
+            let direction = Vector3::new(rng.gen_range(-1.0, 1.0), rng.gen_range(-1.0, 1.0), rng.gen_range(-1.0, 1.0));
+            let max_distance = 30.0;
+
+            let mut visited = Vec::new();
+            let result = raycast(origin, direction, max_distance, |at| {
+                visited.push(at);
+                false
+            });
+            assert_eq!(result, None);
+
+            // Every block is next to the one before it.
+            for pair in visited.windows(2) {
+                let (a, b) = (pair[0], pair[1]);
+                let steps = (a.x - b.x).abs() + (a.y - b.y).abs() + (a.z - b.z).abs();
+                assert_eq!(steps, 1, "{:?} to {:?}", a, b);
+            }
+
+            // The blocks found by sampling the ray finely are visited, in
+            // the same order.
+            let direction = direction.normalize();
+            let mut next = 0;
+            for i in 0..(max_distance as usize * 100) {
+                let point = origin + direction * (i as f32 / 100.0);
+                let at = BlockPos::new(point.x.floor() as i32, point.y.floor() as i32, point.z.floor() as i32);
+
+                match visited[next..].iter().position(|&v| v == at) {
+                    Some(found) => next += found,
+                    None => panic!("{:?} was not visited in order", at),
+                }
+            }
+        }
+    }
+}
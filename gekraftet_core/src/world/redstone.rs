@@ -0,0 +1,787 @@
+use std::collections::{ HashMap, VecDeque };
+use super::*;
+use block_ids::*;
+
+/// The ticks between a redstone torch being powered or unpowered and it
+/// switching.
+pub const TORCH_TICK_RATE: u64 = 2;
+/// The ticks a button stays pressed for.
+pub const BUTTON_TICK_RATE: u64 = 20;
+/// The ticks a pressure plate stays pressed for after it was last stepped on.
+pub const PLATE_TICK_RATE: u64 = 20;
+/// The most blocks a piston can push at once.
+pub const MAX_PUSHED_BLOCKS: usize = 12;
+
+// A torch which turns off this many times within the window burns out, and
+// stays off until the cooldown is over.
+const BURNOUT_TOGGLES: usize = 8;
+const BURNOUT_WINDOW: u64 = 100;
+const BURNOUT_COOLDOWN: u64 = 160;
+
+// The bit set in the metadata of levers and buttons which are on, and of
+// pistons which are extended.
+const ON: u16 = 8;
+// The bits of the metadata of doors.
+const DOOR_OPEN: u16 = 4;
+const DOOR_TOP: u16 = 8;
+
+// The horizontal directions, in the order Beta checks them.
+const SIDES: [BlockFace; 4] = [BlockFace::Left, BlockFace::Right, BlockFace::Back, BlockFace::Front];
+
+/// The state of the redstone of a level which is not stored in its blocks.
+#[derive(Clone, Debug, Default)]
+pub(super) struct RedstoneState {
+    // The times redstone torches turned off at, oldest first.
+    torch_toggles: VecDeque<(BlockPos, u64)>,
+    // The last time every pressed pressure plate was stepped on.
+    plates: HashMap<BlockPos, u64>,
+}
+
+/// The ways blocks in front of a piston react to being pushed.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Push {
+    Move,
+    Break,
+    Blocked,
+}
+
+/// Whether blocks of the given id give out or take in power, so that their
+/// neighbours react to them changing.
+pub fn is_power_source(id: u16) -> bool {
+    matches!(id,
+        REDSTONE_WIRE | REDSTONE_TORCH | UNLIT_REDSTONE_TORCH | LEVER | STONE_BUTTON |
+        STONE_PRESSURE_PLATE | WOODEN_PRESSURE_PLATE | UNPOWERED_REPEATER | POWERED_REPEATER
+    )
+}
+
+fn is_normal_cube(level: &Level, at: BlockPos) -> bool {
+    level.block(at).is_some_and(|b| b.info().opaque)
+}
+
+fn is_wire(level: &Level, at: BlockPos) -> bool {
+    level.block(at).is_some_and(|b| b.id == REDSTONE_WIRE)
+}
+
+fn wire_level(level: &Level, at: BlockPos) -> u16 {
+    match level.block(at) {
+        Some(block) if block.id == REDSTONE_WIRE => block.metadata,
+        _ => 0,
+    }
+}
+
+/// The face of the block a torch, lever or button is attached to.
+fn attached_face(block: Block) -> Option<BlockFace> {
+    match block.metadata & 7 {
+        1 => Some(BlockFace::Left),
+        2 => Some(BlockFace::Right),
+        3 => Some(BlockFace::Back),
+        4 => Some(BlockFace::Front),
+        5 | 6 => Some(BlockFace::Bottom),
+        _ => None,
+    }
+}
+
+/// The direction a repeater gives out power in. It takes in power from the
+/// opposite direction.
+fn repeater_output(block: Block) -> BlockFace {
+    match block.metadata & 3 {
+        0 => BlockFace::Back,
+        1 => BlockFace::Right,
+        2 => BlockFace::Front,
+        _ => BlockFace::Left,
+    }
+}
+
+fn repeater_delay(block: Block) -> u64 {
+    (u64::from(block.metadata >> 2 & 3) + 1) * 2
+}
+
+fn piston_facing(block: Block) -> Option<BlockFace> {
+    BlockFace::from_protocol_id((block.metadata & 7) as i8)
+}
+
+fn perpendicular(side: BlockFace) -> [BlockFace; 2] {
+    match side {
+        BlockFace::Left | BlockFace::Right => [BlockFace::Back, BlockFace::Front],
+        _ => [BlockFace::Left, BlockFace::Right],
+    }
+}
+
+/// Whether wire leading in the given direction connects to the block there.
+fn connects_to(level: &Level, at: BlockPos, side: BlockFace) -> bool {
+    match level.block(at) {
+        Some(block) => match block.id {
+            UNPOWERED_REPEATER | POWERED_REPEATER => {
+                let output = repeater_output(block);
+                side == output || side == output.opposite()
+            },
+            id => is_power_source(id),
+        },
+        None => false,
+    }
+}
+
+/// Whether the wire is connected in the given direction, either to a block
+/// next to it or to wire one block up or down.
+fn wire_connected(level: &Level, at: BlockPos, side: BlockFace) -> bool {
+    let next = at.neighbor(side);
+    if connects_to(level, next, side) {
+        return true
+    }
+
+    if is_normal_cube(level, next) {
+        !is_normal_cube(level, at.offset(0, 1, 0)) && is_wire(level, next.offset(0, 1, 0))
+    } else {
+        is_wire(level, next.offset(0, -1, 0))
+    }
+}
+
+/// Whether the wire powers the block next to it in the given direction. Wire
+/// powers the block below it, and the block it points into: the one at the
+/// end of a line, or every side of a lone dot.
+fn wire_points(level: &Level, at: BlockPos, towards: BlockFace) -> bool {
+    match towards {
+        BlockFace::Bottom => true,
+        BlockFace::Top => false,
+        side => {
+            let connected = |side| wire_connected(level, at, side);
+            if !SIDES.iter().any(|&side| connected(side)) {
+                return true
+            }
+
+            connected(side.opposite()) && !perpendicular(side).iter().any(|&side| connected(side))
+        },
+    }
+}
+
+/// Whether the block at `at` powers the block next to it in the given
+/// direction. Wire is ignored unless `wires` is set.
+fn emits(level: &Level, at: BlockPos, towards: BlockFace, wires: bool) -> bool {
+    let block = match level.block(at) {
+        Some(block) => block,
+        None => return false,
+    };
+
+    match block.id {
+        REDSTONE_WIRE => wires && block.metadata > 0 && wire_points(level, at, towards),
+        REDSTONE_TORCH => attached_face(block) != Some(towards),
+        POWERED_REPEATER => repeater_output(block) == towards,
+        LEVER | STONE_BUTTON => block.metadata & ON != 0,
+        STONE_PRESSURE_PLATE | WOODEN_PRESSURE_PLATE => block.metadata != 0,
+        _ => false,
+    }
+}
+
+/// Whether the block at `at` powers the block next to it strongly enough for
+/// that block to power its own neighbours.
+fn emits_strongly(level: &Level, at: BlockPos, towards: BlockFace, wires: bool) -> bool {
+    let block = match level.block(at) {
+        Some(block) => block,
+        None => return false,
+    };
+
+    match block.id {
+        REDSTONE_WIRE | POWERED_REPEATER => emits(level, at, towards, wires),
+        REDSTONE_TORCH => towards == BlockFace::Top && emits(level, at, towards, wires),
+        LEVER | STONE_BUTTON => block.metadata & ON != 0 && attached_face(block) == Some(towards),
+        STONE_PRESSURE_PLATE | WOODEN_PRESSURE_PLATE => block.metadata != 0 && towards == BlockFace::Bottom,
+        _ => false,
+    }
+}
+
+fn is_strongly_powered(level: &Level, at: BlockPos, wires: bool) -> bool {
+    BlockFace::ALL.iter().any(|&face| emits_strongly(level, at.neighbor(face), face.opposite(), wires))
+}
+
+/// Whether the block at `at` powers the block next to it in the given
+/// direction, either by itself or by being a full block which is powered
+/// strongly.
+fn provides_power(level: &Level, at: BlockPos, towards: BlockFace, wires: bool) -> bool {
+    if is_normal_cube(level, at) {
+        is_strongly_powered(level, at, wires)
+    } else {
+        emits(level, at, towards, wires)
+    }
+}
+
+fn powered_by_neighbors(level: &Level, at: BlockPos, wires: bool) -> bool {
+    BlockFace::ALL.iter().any(|&face| provides_power(level, at.neighbor(face), face.opposite(), wires))
+}
+
+/// Whether the block at the given position is powered by any of its
+/// neighbours.
+pub fn is_powered(level: &Level, at: BlockPos) -> bool {
+    powered_by_neighbors(level, at, true)
+}
+
+/// The power level wire at the given position should have: 15 next to a
+/// power source, otherwise one less than the wire around it.
+fn wire_power(level: &Level, at: BlockPos) -> u16 {
+    if powered_by_neighbors(level, at, false) {
+        return 15
+    }
+
+    let above_open = !is_normal_cube(level, at.offset(0, 1, 0));
+    let mut power = 0;
+    for &side in SIDES.iter() {
+        let next = at.neighbor(side);
+        power = power.max(wire_level(level, next));
+
+        if !is_normal_cube(level, next) {
+            power = power.max(wire_level(level, next.offset(0, -1, 0)));
+        } else if above_open {
+            power = power.max(wire_level(level, next.offset(0, 1, 0)));
+        }
+    }
+
+    power.saturating_sub(1)
+}
+
+/// Lets the blocks around the given position react to a change in the power
+/// it gives out: its neighbours, and the neighbours of the blocks it might
+/// power strongly.
+fn notify_around(level: &mut Level, at: BlockPos, source: u16) {
+    level.notify_neighbors_from(at, source);
+    for &face in BlockFace::ALL.iter() {
+        level.notify_neighbors_from(at.neighbor(face), source);
+    }
+}
+
+fn update_wire(level: &mut Level, at: BlockPos) {
+    let power = wire_power(level, at);
+    if wire_level(level, at) != power {
+        level.set_block(at, Block::with_metadata(REDSTONE_WIRE, power));
+        notify_around(level, at, REDSTONE_WIRE);
+    }
+}
+
+fn torch_powered(level: &Level, at: BlockPos, block: Block) -> bool {
+    attached_face(block).is_some_and(|face| provides_power(level, at.neighbor(face), face.opposite(), true))
+}
+
+/// Switches a torch to the opposite of the power it receives. Torches which
+/// turn off too often burn out for a while.
+fn torch_tick(level: &mut Level, at: BlockPos, block: Block) {
+    let time = level.time();
+    let toggles = &mut level.redstone.torch_toggles;
+    while toggles.front().is_some_and(|&(_, toggled)| time - toggled > BURNOUT_WINDOW) {
+        toggles.pop_front();
+    }
+
+    let powered = torch_powered(level, at, block);
+    if block.id == REDSTONE_TORCH && powered {
+        level.redstone.torch_toggles.push_back((at, time));
+        level.set_block(at, Block::with_metadata(UNLIT_REDSTONE_TORCH, block.metadata));
+        notify_around(level, at, UNLIT_REDSTONE_TORCH);
+    } else if block.id == UNLIT_REDSTONE_TORCH && !powered {
+        let toggles = level.redstone.torch_toggles.iter().filter(|&&(pos, _)| pos == at).count();
+        if toggles >= BURNOUT_TOGGLES {
+            level.schedule_tick(at, UNLIT_REDSTONE_TORCH, BURNOUT_COOLDOWN, 0);
+        } else {
+            level.set_block(at, Block::with_metadata(REDSTONE_TORCH, block.metadata));
+            notify_around(level, at, REDSTONE_TORCH);
+        }
+    }
+}
+
+fn repeater_input(level: &Level, at: BlockPos, block: Block) -> bool {
+    let output = repeater_output(block);
+    let back = at.neighbor(output.opposite());
+    provides_power(level, back, output, true) || wire_level(level, back) > 0
+}
+
+/// Switches a repeater on, or off if it has stopped receiving power. A pulse
+/// shorter than the delay keeps it on for the whole delay.
+fn repeater_tick(level: &mut Level, at: BlockPos, block: Block) {
+    let powered = repeater_input(level, at, block);
+
+    if block.id == POWERED_REPEATER && !powered {
+        level.set_block(at, Block::with_metadata(UNPOWERED_REPEATER, block.metadata));
+        notify_around(level, at, UNPOWERED_REPEATER);
+    } else if block.id == UNPOWERED_REPEATER {
+        level.set_block(at, Block::with_metadata(POWERED_REPEATER, block.metadata));
+        notify_around(level, at, POWERED_REPEATER);
+
+        if !powered {
+            level.schedule_tick(at, POWERED_REPEATER, repeater_delay(block), 0);
+        }
+    }
+}
+
+fn repeater_changed(level: &mut Level, at: BlockPos, block: Block) {
+    let powered = repeater_input(level, at, block);
+    if powered != (block.id == POWERED_REPEATER) {
+        level.schedule_tick(at, block.id, repeater_delay(block), 0);
+    }
+}
+
+fn is_door(block: Block) -> bool {
+    block.id == WOODEN_DOOR || block.id == IRON_DOOR
+}
+
+/// Opens or closes both halves of the door whose bottom half is at the given
+/// position.
+fn set_door_open(level: &mut Level, bottom: BlockPos, open: bool) {
+    for &at in [bottom, bottom.offset(0, 1, 0)].iter() {
+        if let Some(block) = level.block(at).filter(|&b| is_door(b)) {
+            let metadata = if open { block.metadata | DOOR_OPEN } else { block.metadata & !DOOR_OPEN };
+            level.set_block(at, Block::with_metadata(block.id, metadata));
+        }
+    }
+}
+
+fn door_bottom(at: BlockPos, block: Block) -> BlockPos {
+    if block.metadata & DOOR_TOP != 0 {
+        at.offset(0, -1, 0)
+    } else {
+        at
+    }
+}
+
+/// Plays a note block when it gets powered.
+fn note_block_changed(level: &mut Level, at: BlockPos) {
+    let powered = is_powered(level, at);
+
+    let was_powered = match level.block_entity_mut(at) {
+        Some(BlockEntity::NoteBlock { powered: was_powered, .. }) => std::mem::replace(was_powered, powered),
+        _ => return,
+    };
+
+    if powered && !was_powered {
+        play_note(level, at);
+    }
+}
+
+fn door_changed(level: &mut Level, at: BlockPos, block: Block) {
+    let bottom = door_bottom(at, block);
+    let powered = is_powered(level, bottom) || is_powered(level, bottom.offset(0, 1, 0));
+    set_door_open(level, bottom, powered);
+}
+
+fn push_behavior(level: &Level, at: BlockPos) -> Push {
+    let block = match level.block(at) {
+        Some(block) => block,
+        None => return Push::Blocked,
+    };
+
+    match block.id {
+        OBSIDIAN | BEDROCK | PISTON_HEAD | MOVING_PISTON | CHEST | LOCKED_CHEST | FURNACE |
+        LIT_FURNACE | DISPENSER | SPAWNER | NOTE_BLOCK | JUKEBOX | SIGN_POST | WALL_SIGN => Push::Blocked,
+        PISTON | STICKY_PISTON if block.metadata & ON != 0 => Push::Blocked,
+        _ if level.block_entity(at).is_some() => Push::Blocked,
+        _ if !block.info().solid => Push::Break,
+        _ => Push::Move,
+    }
+}
+
+fn piston_powered(level: &Level, at: BlockPos, facing: BlockFace) -> bool {
+    let powers = |at: BlockPos, face: BlockFace| provides_power(level, at.neighbor(face), face.opposite(), true);
+
+    // Pistons are also powered by what would power the block above them.
+    let above = at.offset(0, 1, 0);
+    BlockFace::ALL.iter().any(|&face| face != facing && powers(at, face))
+        || BlockFace::ALL.iter().any(|&face| face != BlockFace::Bottom && powers(above, face))
+}
+
+/// Pushes the blocks in front of the piston and puts its head in front of
+/// it. Returns false if the blocks cannot be pushed.
+fn extend(level: &mut Level, at: BlockPos, block: Block, facing: BlockFace) -> bool {
+    let front = at.neighbor(facing);
+    let mut end = front;
+    let mut pushed = 0;
+
+    loop {
+        match level.block(end) {
+            None => return false,
+            Some(b) if b.id == AIR => break,
+            _ => {},
+        }
+
+        match push_behavior(level, end) {
+            Push::Blocked => return false,
+            Push::Break => break,
+            Push::Move => {
+                pushed += 1;
+                if pushed > MAX_PUSHED_BLOCKS {
+                    return false
+                }
+                end = end.neighbor(facing);
+            },
+        }
+    }
+
+    level.add_block_action(at, [0, facing.protocol_id() as u8]);
+
+    let mut changed = vec![at];
+    while end != front {
+        let behind = end.neighbor(facing.opposite());
+        let moved = level.block(behind).unwrap();
+        level.set_block(end, moved);
+        changed.push(end);
+        end = behind;
+    }
+
+    let sticky = if block.id == STICKY_PISTON { ON } else { 0 };
+    level.set_block(front, Block::with_metadata(PISTON_HEAD, facing.protocol_id() as u16 | sticky));
+    level.set_block(at, Block::with_metadata(block.id, block.metadata | ON));
+    changed.push(front);
+
+    for at in changed {
+        level.notify_neighbors(at);
+    }
+    true
+}
+
+/// Takes the head of the piston back in. Sticky pistons pull back the block
+/// in front of their head.
+fn retract(level: &mut Level, at: BlockPos, block: Block, facing: BlockFace) {
+    level.add_block_action(at, [1, facing.protocol_id() as u8]);
+
+    let front = at.neighbor(facing);
+    if level.block(front).is_some_and(|b| b.id == PISTON_HEAD) {
+        level.set_block(front, Block::new(AIR));
+    }
+    level.set_block(at, Block::with_metadata(block.id, block.metadata & !ON));
+
+    let pulled = front.neighbor(facing);
+    let pulls = block.id == STICKY_PISTON
+        && level.block(pulled).is_some_and(|b| b.id != AIR)
+        && push_behavior(level, pulled) == Push::Move;
+
+    if pulls {
+        let moved = level.block(pulled).unwrap();
+        level.set_block(front, moved);
+        level.set_block(pulled, Block::new(AIR));
+        level.notify_neighbors(pulled);
+    }
+
+    level.notify_neighbors(at);
+    level.notify_neighbors(front);
+}
+
+fn piston_changed(level: &mut Level, at: BlockPos, block: Block) {
+    let facing = match piston_facing(block) {
+        Some(facing) => facing,
+        None => return,
+    };
+
+    let extended = block.metadata & ON != 0;
+    let powered = piston_powered(level, at, facing);
+
+    if powered && !extended {
+        extend(level, at, block, facing);
+    } else if !powered && extended {
+        retract(level, at, block, facing);
+    }
+}
+
+/// Called when a block is placed.
+pub(crate) fn on_added(level: &mut Level, at: BlockPos, block: Block) {
+    match block.id {
+        REDSTONE_WIRE => {
+            update_wire(level, at);
+            notify_around(level, at, REDSTONE_WIRE);
+        },
+        REDSTONE_TORCH | UNLIT_REDSTONE_TORCH => {
+            level.schedule_tick(at, block.id, TORCH_TICK_RATE, 0);
+            notify_around(level, at, block.id);
+        },
+        UNPOWERED_REPEATER | POWERED_REPEATER => repeater_changed(level, at, block),
+        PISTON | STICKY_PISTON => piston_changed(level, at, block),
+        _ => {},
+    }
+}
+
+/// Called when a block is replaced by a block of another id.
+pub(crate) fn on_removed(level: &mut Level, at: BlockPos, old: Block) {
+    match old.id {
+        PISTON | STICKY_PISTON if old.metadata & ON != 0 => {
+            let head = piston_facing(old).map(|facing| at.neighbor(facing));
+            if let Some(head) = head.filter(|&head| level.block(head).is_some_and(|b| b.id == PISTON_HEAD)) {
+                level.set_block_with_notify(head, Block::new(AIR));
+            }
+        },
+        id if is_power_source(id) => notify_around(level, at, id),
+        _ => {},
+    }
+}
+
+/// Called when a neighbour of a block changes. `source` is the id of the
+/// block which caused the change.
+pub(crate) fn on_neighbor_changed(level: &mut Level, at: BlockPos, block: Block, source: u16) {
+    match block.id {
+        REDSTONE_WIRE => update_wire(level, at),
+        REDSTONE_TORCH | UNLIT_REDSTONE_TORCH => {
+            level.schedule_tick(at, block.id, TORCH_TICK_RATE, 0);
+        },
+        UNPOWERED_REPEATER | POWERED_REPEATER => repeater_changed(level, at, block),
+        // Only changes in power move doors, so that doors opened by hand stay
+        // open.
+        WOODEN_DOOR | IRON_DOOR if is_power_source(source) => door_changed(level, at, block),
+        PISTON | STICKY_PISTON => piston_changed(level, at, block),
+        NOTE_BLOCK if is_power_source(source) => note_block_changed(level, at),
+        _ => {},
+    }
+}
+
+pub(crate) fn on_scheduled_tick(level: &mut Level, at: BlockPos, block: Block) {
+    match block.id {
+        REDSTONE_TORCH | UNLIT_REDSTONE_TORCH => torch_tick(level, at, block),
+        UNPOWERED_REPEATER | POWERED_REPEATER => repeater_tick(level, at, block),
+        STONE_BUTTON if block.metadata & ON != 0 => {
+            level.set_block(at, Block::with_metadata(STONE_BUTTON, block.metadata & !ON));
+            notify_around(level, at, STONE_BUTTON);
+        },
+        STONE_PRESSURE_PLATE | WOODEN_PRESSURE_PLATE if block.metadata != 0 => {
+            let stepped = level.redstone.plates.get(&at).copied().unwrap_or(0);
+            if level.time() - stepped < PLATE_TICK_RATE {
+                level.schedule_tick(at, block.id, PLATE_TICK_RATE, 0);
+            } else {
+                level.redstone.plates.remove(&at);
+                level.set_block(at, Block::new(block.id));
+                notify_around(level, at, block.id);
+            }
+        },
+        _ => {},
+    }
+}
+
+impl Level {
+    /// Uses the block at the given position, as a player right-clicking it
+    /// does: levers are flipped, buttons pressed, wooden doors opened or
+    /// closed, repeaters set to their next delay and note blocks tuned to
+    /// their next note. Returns false if the block cannot be used.
+    pub fn activate_block(&mut self, at: BlockPos) -> bool {
+        let block = match self.block(at) {
+            Some(block) => block,
+            None => return false,
+        };
+
+        match block.id {
+            LEVER => {
+                self.set_block(at, Block::with_metadata(LEVER, block.metadata ^ ON));
+                notify_around(self, at, LEVER);
+            },
+            STONE_BUTTON => if block.metadata & ON == 0 {
+                self.set_block(at, Block::with_metadata(STONE_BUTTON, block.metadata | ON));
+                notify_around(self, at, STONE_BUTTON);
+                self.schedule_tick(at, STONE_BUTTON, BUTTON_TICK_RATE, 0);
+            },
+            WOODEN_DOOR => set_door_open(self, door_bottom(at, block), block.metadata & DOOR_OPEN == 0),
+            UNPOWERED_REPEATER | POWERED_REPEATER => {
+                let metadata = (block.metadata + 4) & 15;
+                self.set_block(at, Block::with_metadata(block.id, metadata));
+            },
+            NOTE_BLOCK => {
+                if let Some(BlockEntity::NoteBlock { note, .. }) = self.block_entity_mut(at) {
+                    *note = (*note + 1) % NOTE_COUNT;
+                }
+                play_note(self, at);
+            },
+            _ => return false,
+        }
+
+        true
+    }
+
+    /// Presses the pressure plate at the given position, as something
+    /// standing on it does. Plates are let go of once they have not been
+    /// stepped on for `PLATE_TICK_RATE` ticks. Wooden plates are pressed by
+    /// every entity, stone plates only by mobs and players, which is left for
+    /// the caller to check. Returns false if there is no plate there.
+    pub fn step_on_plate(&mut self, at: BlockPos) -> bool {
+        let block = match self.block(at) {
+            Some(block) if block.id == STONE_PRESSURE_PLATE || block.id == WOODEN_PRESSURE_PLATE => block,
+            _ => return false,
+        };
+
+        self.redstone.plates.insert(at, self.time());
+        if block.metadata == 0 {
+            self.set_block(at, Block::with_metadata(block.id, 1));
+            notify_around(self, at, block.id);
+            self.schedule_tick(at, block.id, PLATE_TICK_RATE, 0);
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use crate::utils::Random;
+    use super::*;
+
+    const FLOOR: i32 = 10;
+
+    /// A single chunk with a floor of stone.
+    fn level() -> Level {
+        let mut level = Level::new();
+        level.insert_chunk(Chunk::empty(ChunkPos::new(0, 0, 0)));
+        for x in 0..CHUNK_LENGTH_X as i32 {
+            for z in 0..CHUNK_LENGTH_Z as i32 {
+                level.set_block(BlockPos::new(x, FLOOR, z), Block::new(STONE));
+            }
+        }
+        level
+    }
+
+    /// Places blocks on the floor, or `up` blocks above it.
+    fn place(level: &mut Level, up: i32, blocks: &[(i32, i32, Block)]) {
+        for &(x, z, block) in blocks {
+            level.set_block_with_notify(BlockPos::new(x, FLOOR + up, z), block);
+        }
+    }
+
+    fn wire() -> Block {
+        Block::new(REDSTONE_WIRE)
+    }
+
+    /// A repeater giving out power towards `output`, with a delay of
+    /// `delay` redstone ticks (1 to 4).
+    fn repeater(output: BlockFace, delay: u16) -> Block {
+        let facing = match output {
+            BlockFace::Back => 0,
+            BlockFace::Right => 1,
+            BlockFace::Front => 2,
+            _ => 3,
+        };
+        Block::with_metadata(UNPOWERED_REPEATER, facing | (delay - 1) << 2)
+    }
+
+    /// Runs the level for a number of ticks, and returns the changes made
+    /// to the blocks at the given positions with the tick they were made at.
+    fn run(level: &mut Level, rng: &mut Random, ticks: u64, watched: &[BlockPos]) -> Vec<(u64, BlockEvent)> {
+        let mut events = Vec::new();
+        for _ in 0..ticks {
+            level.tick(rng);
+            let time = level.time();
+            events.extend(level.take_events().into_iter()
+                .filter(|event| match event {
+                    BlockEvent::Change { at, .. } | BlockEvent::Action { at, .. } => watched.contains(at),
+                    _ => false,
+                })
+                .map(|event| (time, event)));
+        }
+        events
+    }
+
+    #[test]
+    fn torch_and_repeaters_make_a_clock() {
+        let mut level = level();
+        let mut rng = Random::seed_from_u64(42);
+
+        // The torch turns off once its power has gone through both
+        // repeaters and back to the stone it stands on, then on again once
+        // that has gone through them too: 8 + 8 + 2 ticks each way.
+        place(&mut level, 1, &[
+            (5, 5, Block::new(STONE)),
+            (6, 6, repeater(BlockFace::Front, 4)),
+            (6, 7, wire()),
+            (5, 7, wire()),
+            (5, 6, repeater(BlockFace::Back, 4)),
+            (6, 5, Block::with_metadata(REDSTONE_TORCH, 1)),
+        ]);
+        level.take_events();
+
+        let at = |x, z| BlockPos::new(x, FLOOR + 1, z);
+        let (torch, first, second) = (at(6, 5), at(6, 6), at(5, 6));
+        let change = |time, at, id, metadata| (time, BlockEvent::Change { at, block: Block::with_metadata(id, metadata) });
+
+        let mut expected = Vec::new();
+        for period in 0..5 {
+            let start = period * 36;
+            expected.extend(vec![
+                change(start + 8, first, POWERED_REPEATER, 14),
+                change(start + 16, second, POWERED_REPEATER, 12),
+                change(start + 18, torch, UNLIT_REDSTONE_TORCH, 1),
+                change(start + 26, first, UNPOWERED_REPEATER, 14),
+                change(start + 34, second, UNPOWERED_REPEATER, 12),
+                change(start + 36, torch, REDSTONE_TORCH, 1),
+            ]);
+        }
+
+        // The torch toggles too rarely to burn out.
+        assert_eq!(run(&mut level, &mut rng, 180, &[torch, first, second]), expected);
+    }
+
+    #[test]
+    fn latch_and_piston_make_a_t_flip_flop() {
+        let mut level = level();
+        let mut rng = Random::seed_from_u64(42);
+
+        // The state is held by a latch of two torches, and is shown by the
+        // sticky piston, which is extended while the state is on. The piston
+        // moves a block between two places under the input, so that the
+        // input powers the repeater setting the latch when it is off, and the
+        // one resetting it when it is on. The piston only moves once the
+        // button has been let go of, so the input goes one way only.
+        place(&mut level, 1, &[
+            // The latch.
+            (4, 10, Block::new(STONE)),
+            (4, 11, Block::with_metadata(REDSTONE_TORCH, 3)),
+            (4, 12, repeater(BlockFace::Front, 1)),
+            (4, 13, Block::new(STONE)),
+            (4, 14, Block::with_metadata(UNLIT_REDSTONE_TORCH, 3)),
+            (4, 15, wire()), (3, 15, wire()), (2, 15, wire()), (2, 14, wire()), (2, 13, wire()),
+            (2, 12, wire()), (2, 11, wire()), (2, 10, wire()),
+            (3, 10, repeater(BlockFace::Right, 1)),
+            (1, 10, wire()), (1, 9, wire()), (1, 8, wire()),
+            (2, 8, repeater(BlockFace::Right, 4)),
+            // The piston and the block it moves.
+            (3, 8, Block::with_metadata(STICKY_PISTON, BlockFace::Right.protocol_id() as u16)),
+            (4, 8, Block::new(STONE)),
+            // Setting the latch.
+            (4, 9, repeater(BlockFace::Front, 4)),
+            // Resetting the latch.
+            (5, 7, repeater(BlockFace::Back, 4)),
+            (5, 6, Block::new(STONE)),
+            (6, 6, repeater(BlockFace::Right, 4)),
+            (7, 6, wire()), (7, 7, wire()), (7, 8, wire()), (7, 9, wire()), (7, 10, wire()),
+            (7, 11, wire()), (7, 12, wire()), (7, 13, wire()), (6, 13, wire()), (5, 13, wire()),
+        ]);
+        // The input, over both places of the block.
+        place(&mut level, 2, &[
+            (4, 3, Block::new(STONE)),
+            (4, 4, Block::with_metadata(STONE_BUTTON, 3)),
+            (4, 5, wire()), (4, 6, wire()), (4, 7, wire()), (4, 8, wire()), (5, 8, wire()),
+        ]);
+        run(&mut level, &mut rng, 100, &[]);
+        level.take_events();
+
+        let at = |x, z| BlockPos::new(x, FLOOR + 1, z);
+        let (piston, near, far, set_torch, reset_torch) = (at(3, 8), at(4, 8), at(5, 8), at(4, 11), at(4, 14));
+        let button = BlockPos::new(4, FLOOR + 2, 4);
+
+        let change = |time, at, id, metadata| (time, BlockEvent::Change { at, block: Block::with_metadata(id, metadata) });
+        let action = |time, at, data| (time, BlockEvent::Action { at, data });
+
+        for press in 0..6 {
+            let start = level.time();
+            assert!(level.activate_block(button));
+            let events = run(&mut level, &mut rng, 60, &[piston, near, far, set_torch, reset_torch]);
+
+            let expected = if press % 2 == 0 {
+                vec![
+                    change(start + 10, set_torch, UNLIT_REDSTONE_TORCH, 3),
+                    change(start + 14, reset_torch, REDSTONE_TORCH, 3),
+                    action(start + 22, piston, [0, 5]),
+                    change(start + 22, far, STONE, 0),
+                    change(start + 22, near, PISTON_HEAD, 5 | ON),
+                    change(start + 22, piston, STICKY_PISTON, 5 | ON),
+                ]
+            } else {
+                vec![
+                    change(start + 18, reset_torch, UNLIT_REDSTONE_TORCH, 3),
+                    change(start + 22, set_torch, REDSTONE_TORCH, 3),
+                    action(start + 26, piston, [1, 5]),
+                    change(start + 26, near, AIR, 0),
+                    change(start + 26, piston, STICKY_PISTON, 5),
+                    change(start + 26, near, STONE, 0),
+                    change(start + 26, far, AIR, 0),
+                ]
+            };
+            assert_eq!(events, expected, "press {}", press);
+        }
+    }
+}
@@ -0,0 +1,364 @@
+use std::collections::HashMap;
+use std::path::Path;
+use crate::nbt::{ self, Compound, NamedBinaryTag, ValueData };
+use super::*;
+
+// A schematic is a gzip-compressed NBT file, as written by MCEdit:
+//
+//     Schematic (compound)
+//         Width, Height, Length (short)    the size of the structure
+//         Materials (string)               always "Alpha"
+//         Blocks (byte array)              the block ids
+//         Data (byte array)                the block metadata
+//         Entities (list)                  kept as is, but not pasted
+//         TileEntities (list)              block entities, with x, y and z
+//                                          relative to the structure
+//
+// The blocks are ordered by y, then z, then x: the block at (x, y, z) is at
+// the index (y * Length + z) * Width + x.
+
+#[derive(Debug)]
+pub enum SchematicError {
+    Io(std::io::Error),
+    Nbt(nbt::Error),
+    MissingTag(&'static str),
+    InvalidTag(&'static str),
+    InvalidSize,
+    /// The schematic is larger than a schematic file can hold, which is
+    /// `MAX_SCHEMATIC_LENGTH` blocks along every axis.
+    TooLarge(usize, usize, usize),
+}
+
+/// The most blocks a schematic file holds along each axis, since its sizes
+/// are saved as shorts.
+pub const MAX_SCHEMATIC_LENGTH: usize = i16::MAX as usize;
+
+/// A box of blocks which can be copied out of a `Level` and pasted back into
+/// it, possibly somewhere else.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Schematic {
+    width: usize,
+    height: usize,
+    length: usize,
+    blocks: Vec<Block>,
+    block_entities: HashMap<BlockPos, Compound>,
+    entities: Vec<ValueData>,
+}
+
+/// Replaces the coordinates stored inside a block entity.
+fn move_block_entity(entity: &Compound, to: BlockPos) -> Compound {
+    let mut entity = entity.clone();
+    entity.insert("x".to_string(), ValueData::Int(to.x));
+    entity.insert("y".to_string(), ValueData::Int(to.y));
+    entity.insert("z".to_string(), ValueData::Int(to.z));
+    entity
+}
+
+impl Schematic {
+    /// Creates a schematic of the given size, filled with air.
+    pub fn new(width: usize, height: usize, length: usize) -> Self {
+        Self {
+            width,
+            height,
+            length,
+            blocks: vec![Block::new(block_ids::AIR); width * height * length],
+            block_entities: HashMap::new(),
+            entities: Vec::new(),
+        }
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, SchematicError> {
+        let bytes = std::fs::read(path).map_err(SchematicError::Io)?;
+        let tag = NamedBinaryTag::from_gzip(&bytes).map_err(SchematicError::Nbt)?;
+        Self::from_nbt(&tag)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), SchematicError> {
+        std::fs::write(path, self.to_nbt()?.to_gzip()).map_err(SchematicError::Io)
+    }
+
+    pub fn from_nbt(tag: &NamedBinaryTag) -> Result<Self, SchematicError> {
+        let root = tag.get("Schematic")
+            .ok_or(SchematicError::MissingTag("Schematic"))?
+            .compound()
+            .ok_or(SchematicError::InvalidTag("Schematic"))?;
+
+        let get = |name: &'static str| root.get(name).ok_or(SchematicError::MissingTag(name));
+
+        let size = |name: &'static str| {
+            get(name)?
+                .short()
+                .filter(|&&size| size >= 0)
+                .map(|&size| size as usize)
+                .ok_or(SchematicError::InvalidTag(name))
+        };
+        let (width, height, length) = (size("Width")?, size("Height")?, size("Length")?);
+
+        let ids = get("Blocks")?.byte_array().ok_or(SchematicError::InvalidTag("Blocks"))?;
+        let data = get("Data")?.byte_array().ok_or(SchematicError::InvalidTag("Data"))?;
+
+        if ids.len() != width * height * length || data.len() != ids.len() {
+            return Err(SchematicError::InvalidSize)
+        }
+
+        let blocks = ids.iter()
+            .zip(data.iter())
+            .map(|(&id, &data)| Block::with_metadata(id as u8 as u16, (data & 0xF) as u16))
+            .collect();
+
+        let list = |name: &'static str| match root.get(name) {
+            Some(list) => list.list()
+                .map(|(_, values)| values.clone())
+                .ok_or(SchematicError::InvalidTag(name)),
+            None => Ok(Vec::new()),
+        };
+
+        let mut block_entities = HashMap::new();
+        for entity in list("TileEntities")? {
+            let entity = entity.into_compound()
+                .ok_or(SchematicError::InvalidTag("TileEntities"))?;
+
+            let coord = |name| entity.get(name)
+                .and_then(ValueData::int)
+                .copied()
+                .ok_or(SchematicError::InvalidTag("TileEntities"));
+            let at = BlockPos::new(coord("x")?, coord("y")?, coord("z")?);
+
+            block_entities.insert(at, entity);
+        }
+
+        Ok(Self {
+            width,
+            height,
+            length,
+            blocks,
+            block_entities,
+            entities: list("Entities")?,
+        })
+    }
+
+    /// Writes the schematic as NBT, or fails if it is too large for a
+    /// schematic file.
+    pub fn to_nbt(&self) -> Result<NamedBinaryTag, SchematicError> {
+        let (width, height, length) = self.size();
+        if width.max(height).max(length) > MAX_SCHEMATIC_LENGTH {
+            return Err(SchematicError::TooLarge(width, height, length))
+        }
+
+        let ids = self.blocks.iter().map(|b| b.id as u8 as i8).collect();
+        let data = self.blocks.iter().map(|b| (b.metadata & 0xF) as i8).collect();
+
+        let mut block_entities = self.block_entities.iter().collect::<Vec<_>>();
+        block_entities.sort_by_key(|(at, _)| (at.y, at.z, at.x));
+        let block_entities = block_entities.into_iter()
+            .map(|(&at, entity)| ValueData::Compound(move_block_entity(entity, at)))
+            .collect();
+
+        let mut root = Compound::new();
+        root.insert("Width".to_string(), ValueData::Short(self.width as i16));
+        root.insert("Height".to_string(), ValueData::Short(self.height as i16));
+        root.insert("Length".to_string(), ValueData::Short(self.length as i16));
+        root.insert("Materials".to_string(), ValueData::Str("Alpha".to_string()));
+        root.insert("Blocks".to_string(), ValueData::ByteArray(ids));
+        root.insert("Data".to_string(), ValueData::ByteArray(data));
+        root.insert("Entities".to_string(), ValueData::List(10, self.entities.clone()));
+        root.insert("TileEntities".to_string(), ValueData::List(10, block_entities));
+
+        let mut tag = NamedBinaryTag::new();
+        tag.insert("Schematic", ValueData::Compound(root));
+        Ok(tag)
+    }
+
+    /// Copies the blocks in a box out of the level. `from` is the corner of
+    /// the box with the lowest coordinates. Blocks in chunks which are not
+    /// loaded are copied as air.
+    pub fn copy(level: &Level, from: BlockPos, width: usize, height: usize, length: usize) -> Self {
+        let mut result = Self::new(width, height, length);
+
+        for (x, y, z) in result.coordinates() {
+            let at = BlockPos::new(from.x + x as i32, from.y + y as i32, from.z + z as i32);
+
+            if let Some(block) = level.block(at) {
+                result.set_block(x, y, z, block);
+            }
+
+            if let Some(entity) = level.block_entity(at) {
+                let relative = BlockPos::new(x as i32, y as i32, z as i32);
+                result.block_entities.insert(relative, entity.to_nbt(relative));
+            }
+        }
+
+        result
+    }
+
+    /// Pastes the blocks into the level, so that the block at (0, 0, 0) ends
+    /// up at `at`. The block entities in the way are replaced. Blocks which
+    /// fall into chunks which are not loaded are skipped.
+    ///
+    /// The block entities of the schematic which are unknown or invalid are
+    /// skipped too, and returned with their positions in the schematic and
+    /// why they could not be read, ordered as the blocks are.
+    pub fn paste(&self, level: &mut Level, at: BlockPos) -> Vec<(BlockPos, BlockEntityError)> {
+        for (x, y, z) in self.coordinates() {
+            let to = BlockPos::new(at.x + x as i32, at.y + y as i32, at.z + z as i32);
+
+            if level.set_block(to, *self.block(x, y, z)) {
+                level.remove_block_entity(to);
+            }
+        }
+
+        let mut skipped = Vec::new();
+        for (&offset, entity) in self.block_entities.iter() {
+            let to = BlockPos::new(at.x + offset.x, at.y + offset.y, at.z + offset.z);
+            match BlockEntity::from_nbt(entity) {
+                Ok((_, entity)) => {
+                    level.set_block_entity(to, entity);
+                },
+                Err(e) => skipped.push((offset, e)),
+            }
+        }
+
+        skipped.sort_by_key(|(at, _)| (at.y, at.z, at.x));
+        skipped
+    }
+
+    /// Returns the width, the height and the length of the schematic, which
+    /// are its sizes along the x, y and z axes.
+    pub fn size(&self) -> (usize, usize, usize) {
+        (self.width, self.height, self.length)
+    }
+
+    pub fn block(&self, x: usize, y: usize, z: usize) -> &Block {
+        &self.blocks[self.index(x, y, z)]
+    }
+
+    pub fn set_block(&mut self, x: usize, y: usize, z: usize, block: Block) {
+        let index = self.index(x, y, z);
+        self.blocks[index] = block;
+    }
+
+    /// The block entities of the schematic, keyed by their position relative
+    /// to the schematic.
+    pub fn block_entities(&self) -> &HashMap<BlockPos, Compound> {
+        &self.block_entities
+    }
+
+    pub fn block_entities_mut(&mut self) -> &mut HashMap<BlockPos, Compound> {
+        &mut self.block_entities
+    }
+
+    fn index(&self, x: usize, y: usize, z: usize) -> usize {
+        assert!(x < self.width && y < self.height && z < self.length);
+        (y * self.length + z) * self.width + x
+    }
+
+    fn coordinates(&self) -> impl Iterator<Item = (usize, usize, usize)> {
+        let (width, height, length) = self.size();
+
+        (0..height).flat_map(move |y| (0..length)
+            .flat_map(move |z| (0..width)
+                .map(move |x| (x, y, z))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::Point3;
+    use crate::item::{ item_ids, Inventory, ItemStack };
+    use super::*;
+
+    fn level() -> Level {
+        let mut level = Level::new();
+        for x in -1..2 {
+            for z in -1..2 {
+                level.insert_chunk(Chunk::empty(ChunkPos::new(x, 0, z)));
+            }
+        }
+        level
+    }
+
+    // A few blocks with metadata, a chest with items and a sign, on both
+    // sides of a chunk border.
+    fn build(level: &mut Level) {
+        level.set_block(BlockPos::new(14, 64, 3), Block::new(block_ids::STONE));
+        level.set_block(BlockPos::new(15, 65, 3), Block::with_metadata(block_ids::WOOL, 14));
+        level.set_block(BlockPos::new(16, 64, 4), Block::with_metadata(block_ids::LOG, 2));
+
+        let mut chest = BlockEntity::Chest { items: Inventory::new(CHEST_SIZE) };
+        chest.items_mut().unwrap().set(4, Some(ItemStack::new(item_ids::DIAMOND, 3)));
+        level.set_block(BlockPos::new(15, 64, 4), Block::new(block_ids::CHEST));
+        level.set_block_entity(BlockPos::new(15, 64, 4), chest);
+
+        level.set_block(BlockPos::new(17, 65, 5), Block::with_metadata(block_ids::SIGN_POST, 8));
+        level.set_block_entity(BlockPos::new(17, 65, 5), BlockEntity::sign(&["a", "schematic", "", "!"]));
+    }
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        let directory = std::env::temp_dir().join("gekraftet-schematic-tests");
+        std::fs::create_dir_all(&directory).unwrap();
+        directory.join(name)
+    }
+
+    #[test]
+    fn saved_schematics_load_the_same() {
+        let mut from = level();
+        build(&mut from);
+
+        let schematic = Schematic::copy(&from, BlockPos::new(14, 64, 3), 4, 2, 3);
+        assert_eq!(schematic.size(), (4, 2, 3));
+        assert_eq!(*schematic.block(1, 1, 0), Block::with_metadata(block_ids::WOOL, 14));
+        assert_eq!(schematic.block_entities().len(), 2);
+
+        let path = temp_path("saved.schematic");
+        schematic.save(&path).unwrap();
+        let loaded = Schematic::load(&path).unwrap();
+        assert_eq!(loaded, schematic);
+
+        let mut to = level();
+        assert!(loaded.paste(&mut to, BlockPos::new(-10, 70, -8)).is_empty());
+        for (x, y, z) in schematic.coordinates() {
+            let (from_at, to_at) = (
+                BlockPos::new(14 + x as i32, 64 + y as i32, 3 + z as i32),
+                BlockPos::new(-10 + x as i32, 70 + y as i32, -8 + z as i32),
+            );
+            assert_eq!(to.block(to_at), from.block(from_at), "{:?}", (x, y, z));
+            assert_eq!(to.block_entity(to_at), from.block_entity(from_at), "{:?}", (x, y, z));
+        }
+    }
+
+    #[test]
+    fn oversized_schematics_are_not_saved() {
+        let schematic = Schematic::new(MAX_SCHEMATIC_LENGTH + 1, 1, 0);
+        assert!(matches!(schematic.to_nbt(), Err(SchematicError::TooLarge(32768, 1, 0))));
+        assert!(matches!(schematic.save(temp_path("oversized.schematic")), Err(SchematicError::TooLarge(..))));
+
+        let schematic = Schematic::new(MAX_SCHEMATIC_LENGTH, 0, 1);
+        let loaded = Schematic::from_nbt(&schematic.to_nbt().unwrap()).unwrap();
+        assert_eq!(loaded.size(), (MAX_SCHEMATIC_LENGTH, 0, 1));
+    }
+
+    #[test]
+    fn invalid_block_entities_are_reported() {
+        let mut schematic = Schematic::new(2, 1, 2);
+        let sign = BlockEntity::sign(&["", "", "", ""]);
+        let mut unknown = sign.to_nbt(BlockPos::new(1, 0, 1));
+        unknown.insert("id".to_string(), ValueData::Str("Banner".to_string()));
+        let mut invalid = sign.to_nbt(BlockPos::new(0, 0, 1));
+        invalid.remove("id");
+
+        let entities = schematic.block_entities_mut();
+        entities.insert(BlockPos::new(0, 0, 0), sign.to_nbt(BlockPos::new(0, 0, 0)));
+        entities.insert(BlockPos::new(1, 0, 1), unknown);
+        entities.insert(BlockPos::new(0, 0, 1), invalid);
+
+        let mut level = level();
+        let skipped = schematic.paste(&mut level, BlockPos::new(3, 60, 3));
+        assert!(matches!(&skipped[..], [
+            (BlockPos(a), BlockEntityError::MissingTag("id")),
+            (BlockPos(b), BlockEntityError::UnknownBlockEntity(name)),
+        ] if *a == Point3::new(0, 0, 1) && *b == Point3::new(1, 0, 1) && name == "Banner"));
+
+        assert_eq!(level.block_entity(BlockPos::new(3, 60, 3)), Some(&sign));
+        assert_eq!(level.block_entity(BlockPos::new(4, 60, 4)), None);
+    }
+}
@@ -0,0 +1,77 @@
+use crate::world::{ BlockPos, ChunkPos, CHUNK_LENGTH_X, CHUNK_LENGTH_Y, CHUNK_LENGTH_Z };
+
+/// A box of blocks, aligned to the axes. Both corners are inside the box.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct BoundingBox {
+    pub min: BlockPos,
+    pub max: BlockPos,
+}
+
+impl BoundingBox {
+    /// Creates the smallest box containing both blocks.
+    pub fn new(a: BlockPos, b: BlockPos) -> Self {
+        Self {
+            min: BlockPos::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z)),
+            max: BlockPos::new(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z)),
+        }
+    }
+
+    /// The box containing every block of a chunk.
+    pub fn of_chunk(at: ChunkPos) -> Self {
+        let min = BlockPos::new(
+            at.x * CHUNK_LENGTH_X as i32,
+            at.y * CHUNK_LENGTH_Y as i32,
+            at.z * CHUNK_LENGTH_Z as i32,
+        );
+        let max = BlockPos::new(
+            min.x + CHUNK_LENGTH_X as i32 - 1,
+            min.y + CHUNK_LENGTH_Y as i32 - 1,
+            min.z + CHUNK_LENGTH_Z as i32 - 1,
+        );
+
+        Self {
+            min,
+            max,
+        }
+    }
+
+    pub fn contains(&self, at: BlockPos) -> bool {
+        (self.min.x..=self.max.x).contains(&at.x)
+            && (self.min.y..=self.max.y).contains(&at.y)
+            && (self.min.z..=self.max.z).contains(&at.z)
+    }
+
+    pub fn intersects(&self, other: &Self) -> bool {
+        self.min.x <= other.max.x && other.min.x <= self.max.x
+            && self.min.y <= other.max.y && other.min.y <= self.max.y
+            && self.min.z <= other.max.z && other.min.z <= self.max.z
+    }
+
+    pub fn intersection(&self, other: &Self) -> Option<Self> {
+        if !self.intersects(other) {
+            return None
+        }
+
+        Some(Self {
+            min: BlockPos::new(
+                self.min.x.max(other.min.x),
+                self.min.y.max(other.min.y),
+                self.min.z.max(other.min.z),
+            ),
+            max: BlockPos::new(
+                self.max.x.min(other.max.x),
+                self.max.y.min(other.max.y),
+                self.max.z.min(other.max.z),
+            ),
+        })
+    }
+
+    /// Iterates over every block in the box.
+    pub fn blocks(&self) -> impl Iterator<Item = BlockPos> {
+        let (min, max) = (self.min, self.max);
+
+        (min.y..=max.y).flat_map(move |y| (min.z..=max.z)
+            .flat_map(move |z| (min.x..=max.x)
+                .map(move |x| BlockPos::new(x, y, z))))
+    }
+}
@@ -0,0 +1,62 @@
+use crate::world::{ Block, BlockEntity, BlockPos, Chunk };
+use super::BoundingBox;
+
+/// A chunk being decorated with structures. The blocks are addressed by their
+/// position in the world, and the ones outside of the chunk are ignored, so
+/// structures can place themselves without caring about chunk borders.
+pub struct ChunkCanvas<'a> {
+    chunk: &'a mut Chunk,
+    bounds: BoundingBox,
+}
+
+impl<'a> ChunkCanvas<'a> {
+    pub fn new(chunk: &'a mut Chunk) -> Self {
+        let bounds = BoundingBox::of_chunk(chunk.position());
+
+        Self {
+            chunk,
+            bounds,
+        }
+    }
+
+    /// The box containing the blocks of the chunk.
+    pub fn bounds(&self) -> BoundingBox {
+        self.bounds
+    }
+
+    fn local(&self, at: BlockPos) -> Option<(usize, usize, usize)> {
+        if !self.bounds.contains(at) {
+            return None
+        }
+
+        Some((
+            (at.x - self.bounds.min.x) as usize,
+            (at.y - self.bounds.min.y) as usize,
+            (at.z - self.bounds.min.z) as usize,
+        ))
+    }
+
+    /// Returns the block at the given position, or None if it is outside of
+    /// the chunk.
+    pub fn block(&self, at: BlockPos) -> Option<Block> {
+        let (x, y, z) = self.local(at)?;
+        Some(*self.chunk.block(x, y, z))
+    }
+
+    /// Replaces the block at the given position if it is inside the chunk.
+    /// Any block entity of the old block is removed.
+    pub fn set_block(&mut self, at: BlockPos, block: Block) {
+        if let Some((x, y, z)) = self.local(at) {
+            self.chunk.set_block(x, y, z, block);
+            self.chunk.block_entities_mut().remove(&at);
+        }
+    }
+
+    /// Attaches a block entity to the block at the given position if it is
+    /// inside the chunk.
+    pub fn set_block_entity(&mut self, at: BlockPos, entity: BlockEntity) {
+        if self.bounds.contains(at) {
+            self.chunk.block_entities_mut().insert(at, entity);
+        }
+    }
+}
@@ -0,0 +1,313 @@
+use crate::item::{ Inventory, ItemStack };
+use crate::utils::Random;
+use crate::world::{ block_ids, Block, BlockEntity, BlockPos, ChunkPos, CHEST_SIZE, CHUNK_LENGTH_X, CHUNK_LENGTH_Z };
+use super::{ BoundingBox, ChunkCanvas, Structure, StructureFeature };
+
+use rand::{ Rng, RngCore };
+
+// The mobs a dungeon spawner may spawn. Zombies are listed twice to make them
+// twice as likely, as in Beta.
+const MOBS: [&str; 4] = ["Skeleton", "Zombie", "Zombie", "Spider"];
+
+// The items which may be found in dungeon chests: item id, damage and the
+// largest stack size, along with the weight of the entry.
+const LOOT: [(u16, u16, u8, u32); 11] = [
+    (329, 0, 1, 1),  // saddle
+    (265, 0, 4, 1),  // iron ingot
+    (297, 0, 1, 1),  // bread
+    (296, 0, 4, 1),  // wheat
+    (289, 0, 4, 1),  // gunpowder
+    (287, 0, 4, 1),  // string
+    (325, 0, 1, 1),  // bucket
+    (322, 0, 1, 1),  // golden apple, only kept 1 time out of 100
+    (331, 0, 4, 1),  // redstone
+    (2256, 0, 1, 1), // a music disc, one of the two
+    (351, 3, 1, 1),  // cocoa beans
+];
+
+const LOOT_ROLLS: usize = 8;
+
+/// Places a dungeon in some of the chunks: a cobblestone room underground,
+/// with a mob spawner in its middle and up to two chests along its walls.
+pub struct DungeonFeature {
+    spacing: i32,
+}
+
+impl DungeonFeature {
+    pub fn new() -> Self {
+        Self {
+            spacing: 4,
+        }
+    }
+
+    /// Changes the side length of the regions holding at most one dungeon
+    /// each, in chunks (the default is 4).
+    pub fn spacing(mut self, spacing: i32) -> Self {
+        assert!(spacing > 0, "the spacing must be positive");
+        self.spacing = spacing;
+        self
+    }
+}
+
+impl Default for DungeonFeature {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StructureFeature for DungeonFeature {
+    fn spacing(&self) -> i32 {
+        self.spacing
+    }
+
+    fn salt(&self) -> u64 {
+        0x44554E47 // "DUNG"
+    }
+
+    fn reach(&self) -> i32 {
+        // A dungeon is at most 11 blocks wide, so it can only spill into the
+        // next chunk.
+        1
+    }
+
+    fn create(&self, start: ChunkPos, rng: &mut Random) -> Option<Box<dyn Structure>> {
+        // Only half of the regions have a dungeon.
+        if rng.gen::<bool>() {
+            return None
+        }
+
+        let center = BlockPos::new(
+            start.x * CHUNK_LENGTH_X as i32 + rng.gen_range(0, CHUNK_LENGTH_X as i32),
+            rng.gen_range(8, 40),
+            start.z * CHUNK_LENGTH_Z as i32 + rng.gen_range(0, CHUNK_LENGTH_Z as i32),
+        );
+
+        Some(Box::new(Dungeon::new(center, rng)))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Dungeon {
+    /// The walls, the floor and the ceiling included.
+    bounds: BoundingBox,
+    spawner: BlockPos,
+    mob: &'static str,
+    chests: Vec<(BlockPos, Inventory)>,
+    seed: u64,
+}
+
+impl Dungeon {
+    /// Creates a dungeon whose spawner is at `center`, on the floor.
+    pub fn new(center: BlockPos, rng: &mut Random) -> Self {
+        // The distance from the center to the walls.
+        let radius_x = rng.gen_range(3, 5);
+        let radius_z = rng.gen_range(3, 5);
+
+        let bounds = BoundingBox::new(
+            BlockPos::new(center.x - radius_x, center.y - 1, center.z - radius_z),
+            BlockPos::new(center.x + radius_x, center.y + 4, center.z + radius_z),
+        );
+
+        let mob = MOBS[rng.gen_range(0, MOBS.len())];
+
+        // The chests stand against the walls, but never in the same place.
+        let mut chests: Vec<(BlockPos, Inventory)> = Vec::new();
+        for _ in 0..2 {
+            let along_x = rng.gen::<bool>();
+            let side = if rng.gen::<bool>() { 1 } else { -1 };
+
+            let at = if along_x {
+                let x = rng.gen_range(-radius_x + 1, radius_x);
+                BlockPos::new(center.x + x, center.y, center.z + side * (radius_z - 1))
+            } else {
+                let z = rng.gen_range(-radius_z + 1, radius_z);
+                BlockPos::new(center.x + side * (radius_x - 1), center.y, center.z + z)
+            };
+
+            if chests.iter().all(|(other, _)| *other != at) {
+                chests.push((at, Self::roll_loot(rng)));
+            }
+        }
+
+        Self {
+            bounds,
+            spawner: center,
+            mob,
+            chests,
+            seed: rng.next_u64(),
+        }
+    }
+
+    fn roll_loot(rng: &mut Random) -> Inventory {
+        let total_weight = LOOT.iter().map(|l| l.3).sum::<u32>();
+        let mut items = Inventory::new(CHEST_SIZE);
+
+        for _ in 0..LOOT_ROLLS {
+            let mut roll = rng.gen_range(0, total_weight);
+            let &(id, damage, max_count, _) = LOOT.iter()
+                .find(|l| if roll < l.3 { true } else { roll -= l.3; false })
+                .unwrap();
+
+            // The golden apples are rare, and the discs come in two kinds.
+            if id == 322 && rng.gen_range(0, 100) != 0 {
+                continue
+            }
+            let id = if id == 2256 { id + rng.gen_range(0, 2) } else { id };
+
+            let slot = rng.gen_range(0, CHEST_SIZE as u32) as usize;
+            let count = rng.gen_range(1, max_count + 1);
+
+            // A later roll landing in an occupied slot replaces the item.
+            items.set(slot, Some(ItemStack::with_damage(id, count, damage)));
+        }
+
+        items
+    }
+
+    pub fn spawner(&self) -> BlockPos {
+        self.spawner
+    }
+
+    pub fn mob(&self) -> &'static str {
+        self.mob
+    }
+
+    /// Whether the floor block at the given position is mossy. This depends
+    /// only on the position, so that it does not matter which chunk of the
+    /// dungeon is generated first.
+    fn is_mossy(&self, at: BlockPos) -> bool {
+        let mut hash = self.seed
+            ^ (at.x as u64).wrapping_mul(0x9E3779B97F4A7C15)
+            ^ (at.z as u64).wrapping_mul(0xC2B2AE3D27D4EB4F);
+        hash ^= hash >> 31;
+        hash = hash.wrapping_mul(0xBF58476D1CE4E5B9);
+        hash ^= hash >> 29;
+
+        hash.is_multiple_of(4)
+    }
+}
+
+impl Structure for Dungeon {
+    fn bounding_box(&self) -> BoundingBox {
+        self.bounds
+    }
+
+    fn place(&self, canvas: &mut ChunkCanvas) {
+        let area = match self.bounds.intersection(&canvas.bounds()) {
+            Some(area) => area,
+            None => return,
+        };
+
+        let (min, max) = (self.bounds.min, self.bounds.max);
+
+        for at in area.blocks() {
+            let is_floor = at.y == min.y;
+            let is_shell = is_floor || at.y == max.y
+                || at.x == min.x || at.x == max.x
+                || at.z == min.z || at.z == max.z;
+
+            let id = if is_floor && self.is_mossy(at) {
+                block_ids::MOSSY_COBBLESTONE
+            } else if is_shell {
+                block_ids::COBBLESTONE
+            } else {
+                block_ids::AIR
+            };
+
+            canvas.set_block(at, Block::new(id));
+        }
+
+        if area.contains(self.spawner) {
+            let spawner = BlockEntity::MobSpawner {
+                mob: self.mob.to_string(),
+                delay: 20,
+            };
+
+            canvas.set_block(self.spawner, Block::new(block_ids::SPAWNER));
+            canvas.set_block_entity(self.spawner, spawner);
+        }
+
+        for (at, items) in self.chests.iter() {
+            if area.contains(*at) {
+                let chest = BlockEntity::Chest { items: items.clone() };
+
+                canvas.set_block(*at, Block::new(block_ids::CHEST));
+                canvas.set_block_entity(*at, chest);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::world::{ Level, SuperflatGenerator, StructureGenerator, WorldGenerator };
+    use super::*;
+
+    fn generator(seed: u64) -> StructureGenerator<SuperflatGenerator> {
+        let stone = SuperflatGenerator::from_layers("7,63x1").unwrap();
+        StructureGenerator::new(stone, seed).with_feature(DungeonFeature::new())
+    }
+
+    // The chunks overlapped by the first dungeon found which spans four of
+    // them, along with its bounds.
+    fn spanning_dungeon(generator: &StructureGenerator<SuperflatGenerator>) -> (BoundingBox, Vec<ChunkPos>) {
+        for x in 0..32 {
+            for z in 0..32 {
+                for structure in generator.structures_in(ChunkPos::new(x, 0, z)) {
+                    let bounds = structure.bounding_box();
+                    let (min, max) = (ChunkPos::from(bounds.min), ChunkPos::from(bounds.max));
+                    if min.x != max.x && min.z != max.z {
+                        let chunks = (min.x..=max.x)
+                            .flat_map(|x| (min.z..=max.z).map(move |z| ChunkPos::new(x, 0, z)))
+                            .collect();
+                        return (bounds, chunks)
+                    }
+                }
+            }
+        }
+
+        panic!("no dungeon spans four chunks")
+    }
+
+    fn generate(generator: &StructureGenerator<SuperflatGenerator>, chunks: &[ChunkPos]) -> Level {
+        let mut level = Level::new();
+        for &at in chunks {
+            level.insert_chunk(generator.generate_chunk(at));
+        }
+        level
+    }
+
+    #[test]
+    fn dungeons_do_not_depend_on_the_order_of_the_chunks() {
+        for seed in 0..4 {
+            let generator = generator(seed);
+            let (bounds, chunks) = spanning_dungeon(&generator);
+
+            let forward = generate(&generator, &chunks);
+            let backward = generate(&generator, &chunks.iter().rev().cloned().collect::<Vec<_>>());
+
+            for at in bounds.blocks() {
+                assert_eq!(forward.block(at), backward.block(at), "{:?}", at);
+                assert_eq!(forward.block_entity(at), backward.block_entity(at), "{:?}", at);
+            }
+
+            // The room is whole, whichever chunk each part of it is in.
+            let (min, max) = (bounds.min, bounds.max);
+            let mut spawners = 0;
+            for at in bounds.blocks() {
+                let id = forward.block(at).unwrap().id;
+                let inside = at.x > min.x && at.x < max.x && at.z > min.z && at.z < max.z && at.y > min.y && at.y < max.y;
+
+                match id {
+                    block_ids::SPAWNER => spawners += 1,
+                    block_ids::CHEST => assert!(matches!(forward.block_entity(at), Some(BlockEntity::Chest { .. }))),
+                    block_ids::AIR => assert!(inside, "{:?}", at),
+                    block_ids::COBBLESTONE => assert!(!inside, "{:?}", at),
+                    block_ids::MOSSY_COBBLESTONE => assert_eq!(at.y, min.y),
+                    _ => panic!("{} at {:?}", id, at),
+                }
+            }
+            assert_eq!(spawners, 1);
+        }
+    }
+}
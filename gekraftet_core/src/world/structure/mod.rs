@@ -0,0 +1,129 @@
+mod bounding_box;
+mod canvas;
+mod dungeon;
+
+pub use bounding_box::BoundingBox;
+pub use canvas::ChunkCanvas;
+pub use dungeon::{ Dungeon, DungeonFeature };
+
+use crate::utils::Random;
+use super::{ Biome, Chunk, ChunkPos, WorldGenerator };
+
+use rand::{ Rng, SeedableRng };
+
+/// A structure which has been placed in the world. Structures can span many
+/// chunks, and are stamped onto every chunk they overlap as it generates.
+pub trait Structure: Send + Sync {
+    fn bounding_box(&self) -> BoundingBox;
+
+    /// Places the part of the structure which lies inside the canvas. Every
+    /// block placed must only depend on the structure and its position, so
+    /// that the chunks can be generated in any order.
+    fn place(&self, canvas: &mut ChunkCanvas);
+}
+
+/// A kind of structure, such as dungeons.
+///
+/// The world is divided into square regions of `spacing` chunks, and every
+/// region may contain one structure of each kind. Where the structure starts,
+/// and whether there is one at all, is decided by a random generator seeded
+/// with the world seed, the `salt` and the position of the region.
+pub trait StructureFeature: Send + Sync {
+    /// The side length of the regions, in chunks.
+    fn spacing(&self) -> i32;
+
+    /// Distinguishes the placement of this kind of structure from the others.
+    fn salt(&self) -> u64;
+
+    /// How many chunks away from its starting chunk a structure can reach.
+    fn reach(&self) -> i32;
+
+    /// Creates the structure starting in the given chunk, if any.
+    fn create(&self, start: ChunkPos, rng: &mut Random) -> Option<Box<dyn Structure>>;
+}
+
+/// Adds structures on top of the chunks of another generator.
+pub struct StructureGenerator<W: WorldGenerator> {
+    inner: W,
+    seed: u64,
+    features: Vec<Box<dyn StructureFeature>>,
+}
+
+impl<W: WorldGenerator> StructureGenerator<W> {
+    pub fn new(inner: W, seed: u64) -> Self {
+        Self {
+            inner,
+            seed,
+            features: Vec::new(),
+        }
+    }
+
+    pub fn with_feature<F: StructureFeature + 'static>(mut self, feature: F) -> Self {
+        self.features.push(Box::new(feature));
+        self
+    }
+
+    /// Returns every structure overlapping the given chunk.
+    pub fn structures_in(&self, at: ChunkPos) -> Vec<Box<dyn Structure>> {
+        let bounds = BoundingBox::of_chunk(at);
+        let mut result = Vec::new();
+
+        for feature in self.features.iter() {
+            let (spacing, reach) = (feature.spacing(), feature.reach());
+
+            // Only the regions within reach can hold structures overlapping
+            // this chunk.
+            let regions_x = (at.x - reach).div_euclid(spacing)..=(at.x + reach).div_euclid(spacing);
+            let regions_z = (at.z - reach).div_euclid(spacing)..=(at.z + reach).div_euclid(spacing);
+
+            for region_x in regions_x {
+                for region_z in regions_z.clone() {
+                    let structure = self.structure_in_region(&**feature, region_x, region_z)
+                        .filter(|s| s.bounding_box().intersects(&bounds));
+
+                    result.extend(structure);
+                }
+            }
+        }
+
+        result
+    }
+
+    fn structure_in_region(
+        &self,
+        feature: &dyn StructureFeature,
+        region_x: i32,
+        region_z: i32,
+    ) -> Option<Box<dyn Structure>> {
+        let seed = (region_x as i64 as u64).wrapping_mul(341873128712)
+            .wrapping_add((region_z as i64 as u64).wrapping_mul(132897987541))
+            .wrapping_add(self.seed)
+            .wrapping_add(feature.salt());
+        let mut rng = Random::seed_from_u64(seed);
+
+        let spacing = feature.spacing();
+        let start = ChunkPos::new(
+            region_x * spacing + rng.gen_range(0, spacing),
+            0,
+            region_z * spacing + rng.gen_range(0, spacing),
+        );
+
+        feature.create(start, &mut rng)
+    }
+}
+
+impl<W: WorldGenerator> WorldGenerator for StructureGenerator<W> {
+    fn generate_chunk(&self, at: ChunkPos) -> Chunk {
+        let mut chunk = self.inner.generate_chunk(at);
+
+        for structure in self.structures_in(at) {
+            structure.place(&mut ChunkCanvas::new(&mut chunk));
+        }
+
+        chunk
+    }
+
+    fn biome_at(&self, x: i32, z: i32) -> Biome {
+        self.inner.biome_at(x, z)
+    }
+}
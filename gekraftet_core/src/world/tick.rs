@@ -0,0 +1,119 @@
+use std::cmp::Ordering;
+use std::collections::{ BTreeSet, HashSet };
+use super::BlockPos;
+
+/// The most scheduled ticks run in a single tick. The ones left over run in
+/// the next ticks.
+pub const MAX_SCHEDULED_TICKS: usize = 1000;
+
+/// The blocks picked at random in every section of 16x16x16 blocks at every
+/// tick. This is the rate of Beta, which picks 80 blocks in its chunks of 8
+/// sections.
+pub const RANDOM_TICKS_PER_SECTION: usize = 10;
+
+/// An update of a block which is due at a given time, such as water flowing
+/// or a repeater switching on.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ScheduledTick {
+    pub at: BlockPos,
+    /// The tick is dropped if the block has changed to another id by the time
+    /// it is due.
+    pub block_id: u16,
+    pub time: u64,
+    /// Among the ticks due at the same time, the ones with the lowest priority
+    /// run first.
+    pub priority: i32,
+    // Ties are broken by the order the ticks were scheduled in.
+    order: u64,
+}
+
+impl Ord for ScheduledTick {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.time, self.priority, self.order).cmp(&(other.time, other.priority, other.order))
+    }
+}
+
+impl PartialOrd for ScheduledTick {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Keeps track of the time of a world, and of the block updates scheduled
+/// for later. The ticks come out in the same order every time, so that the
+/// world behaves the same way given the same updates.
+#[derive(Clone, Debug, Default)]
+pub struct TickScheduler {
+    time: u64,
+    next_order: u64,
+    queue: BTreeSet<ScheduledTick>,
+    // A block is only scheduled once at a time.
+    pending: HashSet<(BlockPos, u16)>,
+}
+
+impl TickScheduler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of ticks which have run so far.
+    pub fn time(&self) -> u64 {
+        self.time
+    }
+
+    pub fn set_time(&mut self, time: u64) {
+        self.time = time;
+    }
+
+    /// Schedules an update of the block `delay` ticks from now. Returns false
+    /// if the block already has an update scheduled, in which case nothing
+    /// changes.
+    pub fn schedule(&mut self, at: BlockPos, block_id: u16, delay: u64, priority: i32) -> bool {
+        if !self.pending.insert((at, block_id)) {
+            return false
+        }
+
+        self.queue.insert(ScheduledTick {
+            at,
+            block_id,
+            time: self.time + delay,
+            priority,
+            order: self.next_order,
+        });
+        self.next_order += 1;
+        true
+    }
+
+    pub fn is_scheduled(&self, at: BlockPos, block_id: u16) -> bool {
+        self.pending.contains(&(at, block_id))
+    }
+
+    /// The number of updates waiting to run.
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    /// Moves on to the next tick, and takes out the updates which are due,
+    /// in the order they should run in.
+    pub fn advance(&mut self) -> Vec<ScheduledTick> {
+        self.time += 1;
+
+        let mut due = Vec::new();
+        while due.len() < MAX_SCHEDULED_TICKS {
+            match self.queue.first() {
+                Some(tick) if tick.time <= self.time => {
+                    let tick = self.queue.pop_first().unwrap();
+                    self.pending.remove(&(tick.at, tick.block_id));
+                    due.push(tick);
+                },
+                _ => break,
+            }
+        }
+
+        due
+    }
+}
@@ -0,0 +1,129 @@
+use cgmath::Point3;
+use crate::entity::EntityKind;
+use crate::item::Inventory;
+use crate::utils::Random;
+use super::*;
+
+// The neighbours of a block, in the order Beta notifies them.
+const NEIGHBORS: [BlockFace; 6] = [
+    BlockFace::Left, BlockFace::Right, BlockFace::Bottom,
+    BlockFace::Top, BlockFace::Back, BlockFace::Front,
+];
+
+impl Level {
+    /// Sets the block at the given position, then lets it and its neighbours
+    /// react to the change. Returns false if the chunk of the block is not
+    /// loaded.
+    pub fn set_block_with_notify(&mut self, at: BlockPos, block: Block) -> bool {
+        let old = match self.block(at) {
+            Some(old) => old,
+            None => return false,
+        };
+
+        self.set_block(at, block);
+        if old.id != block.id {
+            block_removed(self, at, old);
+        }
+        block_added(self, at, block);
+        self.notify_neighbors(at);
+        true
+    }
+
+    /// Lets the neighbours of the given position react to a change of the
+    /// block there.
+    pub fn notify_neighbors(&mut self, at: BlockPos) {
+        let source = self.block(at).map_or(block_ids::AIR, |b| b.id);
+        self.notify_neighbors_from(at, source);
+    }
+
+    /// Lets the neighbours of the given position react to a change caused by
+    /// a block of the given id.
+    pub(super) fn notify_neighbors_from(&mut self, at: BlockPos, source: u16) {
+        for &face in NEIGHBORS.iter() {
+            let neighbor = at.neighbor(face);
+            if let Some(block) = self.block(neighbor) {
+                neighbor_changed(self, neighbor, block, source);
+            }
+        }
+    }
+
+    /// Runs a tick of the world, updating the blocks whose scheduled ticks
+    /// are due, then the blocks picked by `random_ticks` and then the block
+    /// entities.
+    pub fn tick(&mut self, rng: &mut Random) {
+        for tick in self.advance_time() {
+            // An earlier tick of this one might have changed the block.
+            if let Some(block) = self.block(tick.at).filter(|b| b.id == tick.block_id) {
+                scheduled_tick(self, tick.at, block, rng);
+            }
+        }
+
+        for at in self.random_ticks(rng) {
+            if let Some(block) = self.block(at) {
+                random_tick(self, at, block, rng);
+            }
+        }
+
+        self.tick_block_entities();
+    }
+}
+
+fn block_added(level: &mut Level, at: BlockPos, block: Block) {
+    if level.block_entity(at).is_none() {
+        if let Some(entity) = BlockEntity::for_block(block.id) {
+            level.set_block_entity(at, entity);
+        }
+    }
+
+    if Fluid::of(block).is_some() {
+        fluid::on_added(level, at);
+    } else if falls(block.id) {
+        falling::on_changed(level, at, block);
+    } else if block.id == block_ids::TNT {
+        explosion::on_tnt_changed(level, at, None);
+    } else if block.id == block_ids::FIRE {
+        portal::on_fire_added(level, at);
+    } else {
+        redstone::on_added(level, at, block);
+    }
+}
+
+fn block_removed(level: &mut Level, at: BlockPos, old: Block) {
+    // The items held by the block are dropped where it was.
+    if let Some(mut entity) = level.remove_block_entity(at) {
+        let center = Point3::new(at.x as f64 + 0.5, at.y as f64 + 0.5, at.z as f64 + 0.5);
+        for stack in entity.items_mut().map_or_else(Vec::new, Inventory::take_all) {
+            level.spawn_entity(EntityKind::item(stack), center);
+        }
+    }
+
+    redstone::on_removed(level, at, old);
+}
+
+fn neighbor_changed(level: &mut Level, at: BlockPos, block: Block, source: u16) {
+    if Fluid::of(block).is_some() {
+        fluid::on_neighbor_changed(level, at);
+    } else if falls(block.id) {
+        falling::on_changed(level, at, block);
+    } else if block.id == block_ids::TNT {
+        explosion::on_tnt_changed(level, at, Some(source));
+    } else if block.id == block_ids::PORTAL {
+        portal::on_neighbor_changed(level, at);
+    } else {
+        redstone::on_neighbor_changed(level, at, block, source);
+    }
+}
+
+fn scheduled_tick(level: &mut Level, at: BlockPos, block: Block, rng: &mut Random) {
+    match block.id {
+        block_ids::FLOWING_WATER | block_ids::FLOWING_LAVA => fluid::on_scheduled_tick(level, at, rng),
+        block_ids::SAND | block_ids::GRAVEL => falling::on_scheduled_tick(level, at, block),
+        _ => redstone::on_scheduled_tick(level, at, block),
+    }
+}
+
+fn random_tick(level: &mut Level, at: BlockPos, block: Block, rng: &mut Random) {
+    if block.id == block_ids::GRASS {
+        grass::on_random_tick(level, at, rng);
+    }
+}
@@ -0,0 +1,111 @@
+use rand::Rng;
+use crate::utils::Random;
+
+/// The ticks in a day. The time of day is the time of the world modulo this,
+/// with 0 being sunrise and 12000 sunset.
+pub const DAY_LENGTH: u64 = 24000;
+
+/// A change of the weather, which the players are told about.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum WeatherChange {
+    RainStarted,
+    RainStopped,
+    ThunderStarted,
+    ThunderStopped,
+}
+
+/// The rain and the thunder of a world. Both come and go on their own, each
+/// lasting for a random number of ticks.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Weather {
+    pub raining: bool,
+    /// The ticks left before it starts or stops raining. A new duration is
+    /// picked when this is 0.
+    pub rain_time: i32,
+    pub thundering: bool,
+    /// The ticks left before a thunderstorm starts or stops.
+    pub thunder_time: i32,
+    /// How strong the rain and the thunder are, from 0 to 1. They fade in and
+    /// out over 100 ticks, and are not saved.
+    pub rain_strength: f32,
+    pub thunder_strength: f32,
+}
+
+impl Weather {
+    /// Clear weather, which picks how long it lasts at the next tick.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Advances the weather by a tick, as in Beta. Clear skies last from
+    /// half a day to a week, rain half a day to a day and thunderstorms from
+    /// 3 to 13 minutes. Returns the changes of the weather, thunder first.
+    pub fn tick(&mut self, rng: &mut Random) -> Vec<WeatherChange> {
+        let mut changes = Vec::new();
+
+        if self.thunder_time <= 0 {
+            self.thunder_time = if self.thundering {
+                rng.gen_range(0, 12000) + 3600
+            } else {
+                rng.gen_range(0, 168000) + 12000
+            };
+        } else {
+            self.thunder_time -= 1;
+            if self.thunder_time <= 0 {
+                self.thundering = !self.thundering;
+                changes.push(if self.thundering { WeatherChange::ThunderStarted } else { WeatherChange::ThunderStopped });
+            }
+        }
+
+        if self.rain_time <= 0 {
+            self.rain_time = if self.raining {
+                rng.gen_range(0, 12000) + 12000
+            } else {
+                rng.gen_range(0, 168000) + 12000
+            };
+        } else {
+            self.rain_time -= 1;
+            if self.rain_time <= 0 {
+                self.raining = !self.raining;
+                changes.push(if self.raining { WeatherChange::RainStarted } else { WeatherChange::RainStopped });
+            }
+        }
+
+        let fade = |strength: f32, on: bool| (strength + if on { 0.01 } else { -0.01 }).clamp(0.0, 1.0);
+        self.rain_strength = fade(self.rain_strength, self.raining);
+        self.thunder_strength = fade(self.thunder_strength, self.thundering);
+
+        changes
+    }
+
+    /// Whether there is a thunderstorm, rather than only rain.
+    pub fn is_storming(&self) -> bool {
+        self.raining && self.thundering
+    }
+}
+
+/// How far the sun is along its path at the given time, from 0 to 1, with 0
+/// being noon and 0.5 midnight. The sun moves faster around noon and midnight
+/// than around sunrise and sunset.
+pub fn celestial_angle(time: u64) -> f32 {
+    let mut angle = (time % DAY_LENGTH) as f32 / DAY_LENGTH as f32 - 0.25;
+    if angle < 0.0 {
+        angle += 1.0;
+    }
+
+    let eased = 1.0 - ((angle as f64 * std::f64::consts::PI).cos() as f32 + 1.0) / 2.0;
+    angle + (eased - angle) / 3.0
+}
+
+/// How much darker than in full daylight the sky light is, from 0 at noon
+/// to 11 at night. Rain and thunder each make it darker by up to a third.
+pub fn sky_darkness(time: u64, weather: &Weather) -> u8 {
+    let angle = celestial_angle(time);
+    let brightness = 1.0 - ((angle * std::f32::consts::PI * 2.0).cos() * 2.0 + 0.5);
+    let mut light = 1.0 - brightness.clamp(0.0, 1.0);
+
+    light *= 1.0 - weather.rain_strength * 5.0 / 16.0;
+    light *= 1.0 - weather.thunder_strength * 5.0 / 16.0;
+
+    ((1.0 - light) * 11.0) as u8
+}
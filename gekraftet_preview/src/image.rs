@@ -0,0 +1,93 @@
+use std::fs::File;
+use std::io::{ BufWriter, Result as IoResult };
+use std::path::Path;
+use gekraftet_core::world::{ Biome, block_ids };
+
+pub type Rgb = [u8; 3];
+
+/// An RGB image, stored row by row.
+pub struct Image {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+}
+
+impl Image {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![0; width * height * 3],
+        }
+    }
+
+    pub fn set_pixel(&mut self, x: usize, y: usize, color: Rgb) {
+        let start = (y * self.width + x) * 3;
+        self.pixels[start..start + 3].copy_from_slice(&color);
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> IoResult<()> {
+        let file = File::create(path).map(BufWriter::new)?;
+
+        let mut encoder = png::Encoder::new(file, self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        encoder.write_header()
+            .and_then(|mut writer| writer.write_image_data(&self.pixels))
+            .map_err(std::io::Error::other)
+    }
+}
+
+pub fn biome_color(biome: Biome) -> Rgb {
+    match biome {
+        Biome::Rainforest => [0x08, 0xf9, 0x3a],
+        Biome::Swampland => [0x07, 0xf9, 0xb2],
+        Biome::SeasonalForest => [0x9b, 0xe0, 0x23],
+        Biome::Forest => [0x05, 0x66, 0x21],
+        Biome::Savanna => [0xd9, 0xe0, 0x23],
+        Biome::Shrubland => [0xa1, 0xad, 0x20],
+        Biome::Taiga => [0x2e, 0xb1, 0x53],
+        Biome::Desert => [0xfa, 0x94, 0x18],
+        Biome::Plains => [0xff, 0xd8, 0x44],
+        Biome::IceDesert => [0xff, 0xed, 0x93],
+        Biome::Tundra => [0x57, 0xeb, 0xf9],
+        Biome::Hell => [0x70, 0x02, 0x00],
+    }
+}
+
+pub fn block_color(id: u16) -> Rgb {
+    match id {
+        block_ids::AIR => [0x00, 0x00, 0x00],
+        block_ids::STONE => [0x70, 0x70, 0x70],
+        block_ids::GRASS => [0x7f, 0xb2, 0x38],
+        block_ids::DIRT | block_ids::FARMLAND => [0x97, 0x6d, 0x4d],
+        block_ids::COBBLESTONE | block_ids::MOSSY_COBBLESTONE => [0x60, 0x60, 0x60],
+        block_ids::PLANKS | block_ids::LOG | block_ids::WORKBENCH | block_ids::CHEST => [0x8f, 0x77, 0x48],
+        block_ids::BEDROCK => [0x30, 0x30, 0x30],
+        block_ids::FLOWING_WATER | block_ids::WATER => [0x40, 0x40, 0xff],
+        block_ids::FLOWING_LAVA | block_ids::LAVA | block_ids::FIRE => [0xff, 0x00, 0x00],
+        block_ids::SAND | block_ids::SANDSTONE => [0xf7, 0xe9, 0xa3],
+        block_ids::GRAVEL => [0x88, 0x7e, 0x7e],
+        block_ids::LEAVES | block_ids::SAPLING | block_ids::TALL_GRASS | block_ids::CACTUS => [0x00, 0x7c, 0x00],
+        block_ids::SNOW | block_ids::SNOW_LAYER => [0xff, 0xff, 0xff],
+        block_ids::ICE => [0xa0, 0xa0, 0xff],
+        block_ids::CLAY => [0xa4, 0xa8, 0xb8],
+        block_ids::WOOL => [0xc7, 0xc7, 0xc7],
+        block_ids::OBSIDIAN => [0x14, 0x12, 0x1d],
+        block_ids::NETHERRACK | block_ids::SOUL_SAND => [0x70, 0x02, 0x00],
+        block_ids::GLOWSTONE => [0xf0, 0xd0, 0x70],
+        block_ids::GOLD_BLOCK => [0xfa, 0xee, 0x4d],
+        block_ids::IRON_BLOCK => [0xa7, 0xa7, 0xa7],
+        block_ids::DIAMOND_BLOCK => [0x5c, 0xdb, 0xd5],
+        block_ids::LAPIS_BLOCK => [0x4a, 0x80, 0xff],
+        _ => [0xff, 0x00, 0xff], // anything without a color stands out
+    }
+}
+
+/// Darkens a color by the given factor, which is between 0 and 1.
+pub fn shade(color: Rgb, factor: f64) -> Rgb {
+    let [r, g, b] = color;
+    let shade = |c: u8| (c as f64 * factor) as u8;
+    [shade(r), shade(g), shade(b)]
+}
@@ -0,0 +1,116 @@
+use std::cmp::Ordering;
+use std::sync::Mutex;
+
+mod image;
+mod options;
+
+use gekraftet_core::world::*;
+use image::*;
+use options::Options;
+
+/// What the previews need to know about a block column.
+#[derive(Clone, Copy)]
+struct Column {
+    height: usize,
+    block: u16,
+    biome: Biome,
+}
+
+fn main() {
+    let options = Options::from_args();
+    let generator = options.generator();
+
+    let chunks = (options.radius * 2) as usize;
+    let size = chunks * CHUNK_LENGTH_X;
+    let columns = Mutex::new(vec![None; size * size]);
+
+    let workers = std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(4);
+
+    // Every worker generates every n-th row of chunks.
+    std::thread::scope(|scope| {
+        for worker in 0..workers {
+            let (generator, columns, options) = (&generator, &columns, &options);
+
+            scope.spawn(move || {
+                for row in (worker..chunks).step_by(workers) {
+                    for col in 0..chunks {
+                        let pos = ChunkPos::new(
+                            col as i32 - options.radius,
+                            0,
+                            row as i32 - options.radius,
+                        );
+                        let found = scan_chunk(&**generator, pos);
+
+                        let mut columns = columns.lock().unwrap();
+                        for (x, found) in found.iter().enumerate() {
+                            for (z, found) in found.iter().enumerate() {
+                                let px = col * CHUNK_LENGTH_X + x;
+                                let py = row * CHUNK_LENGTH_Z + z;
+                                columns[py * size + px] = Some(*found);
+                            }
+                        }
+                    }
+                }
+            });
+        }
+    });
+
+    let columns = columns.into_inner().unwrap()
+        .into_iter()
+        .map(Option::unwrap)
+        .collect::<Vec<_>>();
+
+    let mut heightmap = Image::new(size, size);
+    let mut biomes = Image::new(size, size);
+    let mut blocks = Image::new(size, size);
+
+    for y in 0..size {
+        for x in 0..size {
+            let column = columns[y * size + x];
+            let height = column.height.min(255) as u8;
+
+            // Like on maps, the slopes facing north are brighter and the ones
+            // facing south are darker.
+            let north = if y > 0 { columns[(y - 1) * size + x].height } else { column.height };
+            let factor = match column.height.cmp(&north) {
+                Ordering::Greater => 1.0,
+                Ordering::Equal => 0.86,
+                Ordering::Less => 0.71,
+            };
+
+            heightmap.set_pixel(x, y, [height; 3]);
+            biomes.set_pixel(x, y, biome_color(column.biome));
+            blocks.set_pixel(x, y, shade(block_color(column.block), factor));
+        }
+    }
+
+    std::fs::create_dir_all(&options.out).expect("unable to create the output directory");
+
+    for (name, image) in [("heightmap.png", heightmap), ("biome.png", biomes), ("blocks.png", blocks)] {
+        let path = options.out.join(name);
+        image.save(&path).expect("unable to write the image");
+        println!("wrote {}", path.display());
+    }
+}
+
+/// Finds the highest block of every column of a chunk.
+fn scan_chunk(generator: &dyn WorldGenerator, at: ChunkPos) -> [[Column; CHUNK_LENGTH_Z]; CHUNK_LENGTH_X] {
+    let chunk = generator.generate_chunk(at);
+
+    std::array::from_fn(|x| std::array::from_fn(|z| {
+        let height = (0..CHUNK_LENGTH_Y)
+            .rev()
+            .find(|&y| chunk.block(x, y, z).id != block_ids::AIR);
+
+        let block_x = at.x * CHUNK_LENGTH_X as i32 + x as i32;
+        let block_z = at.z * CHUNK_LENGTH_Z as i32 + z as i32;
+
+        Column {
+            height: height.unwrap_or(0),
+            block: height.map(|y| chunk.block(x, y, z).id).unwrap_or(block_ids::AIR),
+            biome: generator.biome_at(block_x, block_z),
+        }
+    }))
+}
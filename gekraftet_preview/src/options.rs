@@ -0,0 +1,130 @@
+use std::path::PathBuf;
+use gekraftet_core::world::*;
+
+const USAGE: &str = "\
+usage: gekraftet_preview [options]
+
+    --preset <preset>       world type: default, amplified, flat, flat;<layers>, void
+    --seed <seed>           world seed (default: 123456)
+    --radius <chunks>       render the chunks within this distance of the origin (default: 8)
+    --out <directory>       where the images are written (default: the current directory)
+
+the following options replace the density noise of the default world type:
+
+    --graph <file>          a noise graph description
+    --octaves <n>
+    --amplitude <n>
+    --frequency <n>
+    --lacunarity <n>
+    --persistance <n>
+    --height-scale <n>";
+
+pub struct Options {
+    pub preset: WorldPreset,
+    pub seed: u64,
+    pub radius: i32,
+    pub out: PathBuf,
+
+    graph: Option<NoiseGraph>,
+    noise: Option<NoiseGenOption>,
+    height_scale: Option<f64>,
+}
+
+impl Options {
+    pub fn from_args() -> Self {
+        let mut result = Self {
+            preset: WorldPreset::Default,
+            seed: 123456,
+            radius: 8,
+            out: PathBuf::from("."),
+            graph: None,
+            noise: None,
+            height_scale: None,
+        };
+
+        let mut args = std::env::args().skip(1);
+
+        while let Some(name) = args.next() {
+            if name == "--help" || name == "-h" {
+                println!("{}", USAGE);
+                std::process::exit(0);
+            }
+
+            let value = match args.next() {
+                Some(value) => value,
+                None => usage_error(&format!("missing value for {}", name)),
+            };
+
+            let noise = || result.noise.unwrap_or_else(TerrainGenerator::default_option);
+
+            match name.as_str() {
+                "--preset" => result.preset = WorldPreset::parse(&value)
+                    .unwrap_or_else(|e| usage_error(&format!("invalid world type: {:?}", e))),
+                "--seed" => result.seed = parse(&name, &value),
+                "--radius" => result.radius = parse(&name, &value),
+                "--out" => result.out = PathBuf::from(value),
+                "--graph" => result.graph = Some(NoiseGraph::from_file(&value)
+                    .unwrap_or_else(|e| usage_error(&format!("invalid noise graph: {:?}", e)))),
+                "--octaves" => result.noise = Some(noise().octaves(parse(&name, &value))),
+                "--amplitude" => result.noise = Some(noise().amplitude(parse(&name, &value))),
+                "--frequency" => result.noise = Some(noise().frequency(parse(&name, &value))),
+                "--lacunarity" => result.noise = Some(noise().lacunarity(parse(&name, &value))),
+                "--persistance" => result.noise = Some(noise().persistance(parse(&name, &value))),
+                "--height-scale" => result.height_scale = Some(parse(&name, &value)),
+                _ => usage_error(&format!("unknown option {}", name)),
+            }
+        }
+
+        if result.radius <= 0 {
+            usage_error("the radius must be positive")
+        }
+
+        result
+    }
+
+    /// Builds the generator described by the options. Without any noise
+    /// option, this is the generator of the world type, as the server would
+    /// build it.
+    pub fn generator(&self) -> Box<dyn WorldGenerator> {
+        let customized = self.graph.is_some() || self.noise.is_some() || self.height_scale.is_some();
+        if !customized {
+            return self.preset.clone().into_generator(self.seed)
+        }
+
+        let height_scale = match self.preset {
+            WorldPreset::Default => 1.0,
+            WorldPreset::Amplified => 4.0,
+            _ => usage_error("only the default and amplified world types have noise options"),
+        };
+        let height_scale = self.height_scale.unwrap_or(height_scale);
+
+        let terrain: Box<dyn WorldGenerator> = if let Some(graph) = &self.graph {
+            let noise = graph.build(self.seed)
+                .unwrap_or_else(|e| usage_error(&format!("invalid noise graph: {:?}", e)));
+
+            Box::new(TerrainGenerator::with_noise(Noise::from_generator(noise))
+                .biomes(BiomeSource::new(self.seed))
+                .height_scale(height_scale))
+        } else {
+            let option = self.noise.unwrap_or_else(TerrainGenerator::default_option);
+
+            Box::new(TerrainGenerator::with_noise(Noise::<Perlin3D>::with_option(option, self.seed))
+                .biomes(BiomeSource::new(self.seed))
+                .height_scale(height_scale))
+        };
+
+        // Only the noise is replaced, so the structures of the world type
+        // are still generated.
+        Box::new(StructureGenerator::new(terrain, self.seed).with_feature(DungeonFeature::new()))
+    }
+}
+
+fn parse<T: std::str::FromStr>(name: &str, value: &str) -> T {
+    value.parse()
+        .unwrap_or_else(|_| usage_error(&format!("invalid value for {}: {}", name, value)))
+}
+
+fn usage_error(message: &str) -> ! {
+    eprintln!("{}\n\n{}", message, USAGE);
+    std::process::exit(1)
+}
@@ -1,32 +1,116 @@
 mod config;
 mod packet;
+mod session;
 mod world;
 
+use std::sync::{ Arc, Mutex };
 use config::Config;
-use tokio::io::BufReader;
+use gekraftet_core::world::{ BlockPos, LevelData, WorldPreset };
+use packet::{ Packet, PacketData };
+use session::Session;
+use tokio::io::{ AsyncBufReadExt, BufReader };
 use tokio::net::TcpListener;
+use tokio::sync::broadcast;
+use world::{ WorldClock, WorldState, TICK_DURATION };
+
+// The directory the world is saved in.
+const WORLD_DIRECTORY: &str = "world";
+// The packets kept for a player who is slow to receive them.
+const UPDATE_CAPACITY: usize = 64;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let conf = Config::try_read()?;
-    let mut listener = TcpListener::bind((conf.ip, conf.port)).await?;
+    let listener = TcpListener::bind((conf.ip, conf.port)).await?;
+
+    let data = match LevelData::load(WORLD_DIRECTORY) {
+        Ok(Some(data)) => data,
+        Ok(None) => LevelData::new(WORLD_DIRECTORY, rand::random(), BlockPos::new(0, 64, 0)),
+        Err(e) => panic!("could not load level.dat: {:?}", e),
+    };
+
+    let state = WorldState::new(WORLD_DIRECTORY.into(), data.seed, data.spawn, WorldPreset::default());
+    let state = Arc::new(Mutex::new(state));
+    let clock = Arc::new(Mutex::new(WorldClock::new(data)));
+    let (updates, _) = broadcast::channel(UPDATE_CAPACITY);
+    tokio::spawn(world::run(clock.clone(), state.clone(), updates.clone(), WORLD_DIRECTORY.into()));
 
     loop {
         let (stream, addr) = listener.accept().await?;
         println!("received connection: {}", addr);
 
+        let clock = clock.clone();
+        let state = state.clone();
+        let updates = updates.clone();
         tokio::spawn(async move {
             let mut buffer = BufReader::new(stream);
+            let mut session = Session::new();
+            // Only players who have logged in hear about the world.
+            let mut receiver: Option<broadcast::Receiver<PacketData>> = None;
+            // The player is told what happened to it every tick.
+            let mut ticks = tokio::time::interval(TICK_DURATION);
+
             loop {
-                match packet::Packet::read_packet(&mut buffer).await {
-                    Err(e) if e.kind() == tokio::io::ErrorKind::UnexpectedEof => {
-                        println!("connection to {} ended", addr);
-                        break;
+                tokio::select! {
+                    // Waiting for the next packet to arrive does not consume
+                    // anything, so a packet is never read halfway.
+                    ready = buffer.fill_buf() => {
+                        match ready {
+                            Ok([]) => {
+                                println!("connection to {} ended", addr);
+                                break;
+                            }
+                            Err(e) => Err(e).unwrap(),
+                            _ => {},
+                        }
+
+                        match Packet::read_packet(&mut buffer).await {
+                            Err(e) if e.kind() == tokio::io::ErrorKind::UnexpectedEof => {
+                                println!("connection to {} ended", addr);
+                                break;
+                            }
+                            Err(e) => Err(e).unwrap(),
+                            Ok(packet) => {
+                                let mut replies = session.handle(&mut state.lock().unwrap(), packet.data());
+                                if let PacketData::LoginRequest { .. } = packet.data() {
+                                    if session.player().is_none() {
+                                        // The client was kicked.
+                                        for data in replies {
+                                            Packet::new(data).write_packet(&mut buffer).await.unwrap();
+                                        }
+                                        break;
+                                    }
+
+                                    if receiver.is_none() {
+                                        receiver = Some(updates.subscribe());
+                                        replies.extend(clock.lock().unwrap().login_packets());
+                                    }
+                                }
+
+                                for data in replies {
+                                    Packet::new(data).write_packet(&mut buffer).await.unwrap();
+                                }
+                            },
+                        };
+                    }
+                    _ = ticks.tick(), if session.player().is_some() => {
+                        let replies = session.update(&state.lock().unwrap());
+                        for data in replies {
+                            Packet::new(data).write_packet(&mut buffer).await.unwrap();
+                        }
                     }
-                    Err(e) => Err(e).unwrap(),
-                    _ => {},
-                };
+                    update = async { receiver.as_mut().unwrap().recv().await }, if receiver.is_some() => {
+                        match update {
+                            Ok(data) => Packet::new(data).write_packet(&mut buffer).await.unwrap(),
+                            // The missed time updates are made up for by the next one.
+                            Err(broadcast::error::RecvError::Lagged(_)) => {},
+                            Err(broadcast::error::RecvError::Closed) => break,
+                        }
+                    }
+                }
             }
+
+            session.leave(&mut state.lock().unwrap());
         });
     }
 }
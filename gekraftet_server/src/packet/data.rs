@@ -1,3 +1,4 @@
+use gekraftet_core::world::BlockEvent;
 use super::Metadata;
 
 /// This enum represents the packets sent by the client to server and vice versa.
@@ -398,3 +399,31 @@ pub enum PacketData {
         reason: Box<str>,
     },
 }
+
+impl From<BlockEvent> for PacketData {
+    fn from(event: BlockEvent) -> Self {
+        match event {
+            BlockEvent::Change { at, block } => Self::BlockChange {
+                x: at.x,
+                y: at.y,
+                z: at.z,
+                block_type: block.id as i8,
+                block_metadata: block.metadata as i8,
+            },
+            BlockEvent::Action { at, data } => Self::BlockAction {
+                x: at.x,
+                y: at.y,
+                z: at.z,
+                states: data,
+            },
+            BlockEvent::Explosion { center, power, records } => Self::Explosion {
+                x: center.x,
+                y: center.y,
+                z: center.z,
+                radius: power,
+                record_count: records.len() as i32,
+                record: records.into_boxed_slice(),
+            },
+        }
+    }
+}
@@ -1,4 +1,5 @@
 use super::utils::*;
+use gekraftet_core::entity::{ EntityMetadata, MetadataValue };
 use std::collections::BTreeMap;
 use tokio::io::{
     AsyncReadExt, 
@@ -117,7 +118,7 @@ impl Metadata {
                 }
 
                 MetadataNode::Entity(i0, i1, i2) => {
-                    input.write_i8((5 << 5) | key).await?;
+                    input.write_i8((6 << 5) | key).await?;
                     input.write_i32(*i0).await?;
                     input.write_i32(*i1).await?;
                     input.write_i32(*i2).await?;
@@ -129,3 +130,27 @@ impl Metadata {
         Ok(())
     }
 }
+
+impl From<MetadataValue> for MetadataNode {
+    fn from(value: MetadataValue) -> Self {
+        match value {
+            MetadataValue::Byte(b) => Self::Byte(b),
+            MetadataValue::Short(s) => Self::Short(s),
+            MetadataValue::Int(i) => Self::Int(i),
+            MetadataValue::Float(f) => Self::Float(f),
+            MetadataValue::Str(s) => Self::Str(s),
+            MetadataValue::ItemStack(id, count, damage) => Self::ItemStack(id, count, damage),
+            MetadataValue::Position(x, y, z) => Self::Entity(x, y, z),
+        }
+    }
+}
+
+impl From<EntityMetadata> for Metadata {
+    fn from(metadata: EntityMetadata) -> Self {
+        Self {
+            data: metadata.into_iter()
+                .map(|(key, value)| (key, value.into()))
+                .collect(),
+        }
+    }
+}
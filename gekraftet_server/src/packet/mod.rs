@@ -20,6 +20,10 @@ impl Packet {
         Self { data }
     }
 
+    pub fn data(&self) -> &PacketData {
+        &self.data
+    }
+
     pub async fn read_packet<I>(input: &mut I) -> IoResult<Self> 
         where I: AsyncReadExt + Unpin + tokio::io::AsyncWriteExt
     {
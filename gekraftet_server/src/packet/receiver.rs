@@ -12,22 +12,14 @@ impl PacketData {
 
     pub(super) async fn read_login_request<I>(input: &mut I) -> IoResult<Self>
     where
-        I: AsyncReadExt + Unpin + tokio::io::AsyncWriteExt,
+        I: AsyncReadExt + Unpin,
     {
         let id = input.read_i32().await?;
         let username = read_ucs2(input).await?;
         let seed = input.read_u64().await?;
         let dimension = input.read_u8().await?;
 
-        let reply = Packet::new(PacketData::LoginRequest {
-            id: 31415,
-            username: "".into(),
-            seed: 129903167457028573,
-            dimension: 0,
-        });
-
-        reply.write_packet(input).await?;
-
+        // The reply is sent by the session, once the player is loaded.
         Ok(PacketData::LoginRequest {
             id,
             username,
@@ -960,7 +960,7 @@ impl PacketData {
         input.write_i32(x).await?;
         input.write_i32(y).await?;
         input.write_i32(z).await?;
-        for t in text.lines() {
+        for t in text.split('\n') {
             write_ucs2(input, t).await?;
         }
         Ok(())
@@ -0,0 +1,655 @@
+use cgmath::Point3;
+use gekraftet_core::entity::EntityId;
+use gekraftet_core::player::{ self, Player };
+use gekraftet_core::world::Dimension;
+use crate::packet::PacketData;
+use crate::world::WorldState;
+
+/// What the server keeps track of for a connection: the player, once it has
+/// logged in, and what its client knows about it.
+#[derive(Default)]
+pub struct Session {
+    player: Option<EntityId>,
+    // The health and the dimension the client was last told about, which
+    // tell when the player was hurt or went through a portal outside of
+    // `handle`, such as by another player or at a tick.
+    health: i16,
+    dimension: Dimension,
+}
+
+impl Session {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The entity id of the player, once it has logged in.
+    pub fn player(&self) -> Option<EntityId> {
+        self.player
+    }
+
+    fn kick(reason: &str) -> Vec<PacketData> {
+        vec![PacketData::DisconnectOrKick {
+            reason: reason.into(),
+        }]
+    }
+
+    fn update_health(&mut self, player: &Player) -> PacketData {
+        self.health = player.health;
+        PacketData::UpdateHealth {
+            health: player.health,
+        }
+    }
+
+    fn respawn(&mut self, player: &Player) -> PacketData {
+        self.dimension = player.dimension;
+        PacketData::Respawn {
+            dimension: player.dimension.id() as i8,
+        }
+    }
+
+    fn position_and_look(player: &Player) -> PacketData {
+        let feet = player.position();
+
+        // The client reads the stance before the height of the feet.
+        PacketData::PlayerPositionAndLook {
+            x: feet.x,
+            y: player.stance(),
+            stance: feet.y,
+            z: feet.z,
+            yaw: player.yaw,
+            pitch: player.pitch,
+            on_ground: player.body.on_ground,
+        }
+    }
+
+    /// Handles a packet from the client, returning the packets to send back.
+    /// Players who log in are loaded from their file, in the dimension they
+    /// left, or appear at the spawn if they are new. Clients whose username
+    /// is invalid or already playing are kicked instead.
+    ///
+    /// Players move as far as the blocks let them, and clients are told where
+    /// their players are when they disagree. Players who fall too far are
+    /// hurt, and die when they run out of health, until they respawn. Players
+    /// hit mobs and other players when they left-click them.
+    pub fn handle(&mut self, world: &mut WorldState, packet: &PacketData) -> Vec<PacketData> {
+        if let PacketData::LoginRequest { username, .. } = packet {
+            if self.player.is_some() {
+                return Vec::new()
+            }
+            if !player::is_valid_username(username) {
+                return Self::kick("Invalid username")
+            }
+            if world.player_named(username).is_some() {
+                return Self::kick("Already logged in")
+            }
+
+            let id = world.allocate_player_id();
+            let player = match Player::load(&world.directory, username, id) {
+                Ok(Some(player)) => player,
+                Ok(None) => {
+                    let at = world.spawn;
+                    Player::new(username.as_ref(), id, Point3::new(at.x as f64 + 0.5, at.y as f64, at.z as f64 + 0.5))
+                },
+                Err(e) => {
+                    println!("could not load player {}: {:?}", username, e);
+                    return Self::kick("Could not load the player")
+                },
+            };
+
+            let packets = vec![
+                PacketData::LoginRequest {
+                    id: player.entity_id,
+                    username: "".into(),
+                    seed: world.seed as u64,
+                    // The Nether is sent as -1.
+                    dimension: player.dimension.id() as u8,
+                },
+                PacketData::SpawnPosition { x: world.spawn.x, y: world.spawn.y, z: world.spawn.z },
+                Self::position_and_look(&player),
+                self.update_health(&player),
+            ];
+            self.player = Some(id);
+            self.dimension = player.dimension;
+            world.players.insert(id, player);
+            return packets
+        }
+
+        let id = match self.player {
+            Some(id) => id,
+            None => return Vec::new(),
+        };
+        let player = match world.players.get_mut(&id) {
+            Some(player) => player,
+            None => return Vec::new(),
+        };
+
+        match *packet {
+            PacketData::PlayerPosition { x, y, z, on_ground, .. } |
+            PacketData::PlayerPositionAndLook { x, y, z, on_ground, .. } => {
+                // The client is corrected when its player runs into blocks,
+                // or moves too far at once.
+                let level = &world.levels[&player.dimension];
+                if !player.is_dead() && !player.move_to(level, Point3::new(x, y, z), on_ground) {
+                    let mut packets = vec![Self::position_and_look(player)];
+                    packets.extend(self.update(world));
+                    return packets
+                }
+            },
+
+            PacketData::UseEntity { target_entity, left_click: true, .. } if target_entity != id => {
+                let dimension = player.dimension;
+
+                // The other player is taken out while it is hit, since both
+                // players change.
+                match world.players.remove(&target_entity) {
+                    Some(mut target) => {
+                        if target.dimension == dimension {
+                            world.players.get_mut(&id).unwrap().attack(&mut target);
+                        }
+                        world.players.insert(target_entity, target);
+                    },
+                    None => {
+                        let player = world.players.get_mut(&id).unwrap();
+                        world.entities.get_mut(&dimension).unwrap().attack(player, target_entity);
+                    },
+                }
+            },
+
+            PacketData::Respawn { .. } if player.is_dead() => {
+                player.respawn(world.spawn);
+                let player = &world.players[&id];
+                return vec![
+                    self.respawn(player),
+                    Self::position_and_look(player),
+                    self.update_health(player),
+                ]
+            },
+
+            _ => {},
+        }
+
+        self.update(world)
+    }
+
+    /// The packets telling the client what happened to its player since it
+    /// was last told: the dimension it went to and where it arrived, if it
+    /// went through a portal, and its health, if it was hurt.
+    pub fn update(&mut self, world: &WorldState) -> Vec<PacketData> {
+        let player = match self.player.and_then(|id| world.players.get(&id)) {
+            Some(player) => player,
+            None => return Vec::new(),
+        };
+
+        let mut packets = Vec::new();
+        if player.dimension != self.dimension {
+            packets.push(self.respawn(player));
+            packets.push(Self::position_and_look(player));
+        }
+        if player.health != self.health {
+            packets.push(self.update_health(player));
+        }
+
+        packets
+    }
+
+    /// Saves the player into its file and takes it out of the world, such as
+    /// when it disconnects.
+    pub fn leave(&mut self, world: &mut WorldState) {
+        let player = match self.player.take().and_then(|id| world.players.remove(&id)) {
+            Some(player) => player,
+            None => return,
+        };
+
+        if let Err(e) = player.save(&world.directory) {
+            println!("could not save player {}: {:?}", player.username, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use gekraftet_core::entity::{ EntityKind, Mob, MobType };
+    use gekraftet_core::item::{ item_ids, ItemStack };
+    use gekraftet_core::player::{ DROWNING_DAMAGE, HURT_TIME, LAVA_DAMAGE, MAX_AIR, MAX_HEALTH, PORTAL_TIME };
+    use gekraftet_core::world::{ block_ids, Block, BlockPos, ChunkPos, WorldPreset };
+    use super::*;
+
+    // The players are only saved when they leave, which the tests do not do,
+    // so the directory is never created.
+    fn world() -> WorldState {
+        WorldState::new(
+            std::env::temp_dir().join("gekraftet-session-tests"),
+            42,
+            BlockPos::new(0, 64, 0),
+            WorldPreset::parse("flat").unwrap(),
+        )
+    }
+
+    fn login(world: &mut WorldState, username: &str) -> (Session, Vec<PacketData>) {
+        let mut session = Session::new();
+        let packets = session.handle(world, &PacketData::LoginRequest {
+            id: 14,
+            username: username.into(),
+            seed: 0,
+            dimension: 0,
+        });
+
+        (session, packets)
+    }
+
+    fn move_to(session: &mut Session, world: &mut WorldState, y: f64, on_ground: bool) -> Vec<PacketData> {
+        session.handle(world, &PacketData::PlayerPosition {
+            x: 0.5,
+            y,
+            stance: y + 1.62,
+            z: 0.5,
+            on_ground,
+        })
+    }
+
+    fn hit(session: &mut Session, world: &mut WorldState, target: EntityId) -> Vec<PacketData> {
+        session.handle(world, &PacketData::UseEntity {
+            source_entity: session.player().unwrap(),
+            target_entity: target,
+            left_click: true,
+        })
+    }
+
+    fn health(packets: &[PacketData]) -> Option<i16> {
+        match packets {
+            [PacketData::UpdateHealth { health }] => Some(*health),
+            _ => None,
+        }
+    }
+
+    /// Logs a player in, standing on the ground of the flat world with the
+    /// chunk around it loaded.
+    fn login_on_the_ground(world: &mut WorldState, username: &str) -> (Session, EntityId) {
+        let (session, _) = login(world, username);
+        let id = session.player().unwrap();
+
+        let overworld = world.levels.get_mut(&Dimension::Overworld).unwrap();
+        overworld.load_chunk(ChunkPos::new(0, 0, 0), &world.generators[&Dimension::Overworld]);
+        world.players.get_mut(&id).unwrap().body.position = Point3::new(0.5, 4.0, 0.5);
+
+        (session, id)
+    }
+
+    fn place(world: &mut WorldState, at: BlockPos, id: u16) {
+        world.levels.get_mut(&Dimension::Overworld).unwrap().set_block(at, Block::new(id));
+    }
+
+    /// Ticks the world, returning the health the player is told about at
+    /// every tick.
+    fn ticks(session: &mut Session, world: &mut WorldState, ticks: i16) -> Vec<Option<i16>> {
+        (0..ticks)
+            .map(|_| {
+                world.tick();
+                health(&session.update(world))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn rejects_invalid_usernames() {
+        let mut world = world();
+
+        for &username in ["", "../../level", "players/x", "a b", "seventeen_letters", "Ünïcode"].iter() {
+            let (session, packets) = login(&mut world, username);
+            assert!(matches!(packets[..], [PacketData::DisconnectOrKick { .. }]), "{:?}", username);
+            assert_eq!(session.player(), None);
+        }
+        assert!(world.players.is_empty());
+
+        let (session, _) = login(&mut world, "Sixteen_Letters1");
+        assert!(session.player().is_some());
+
+        // The same player cannot play twice.
+        let (session, packets) = login(&mut world, "Sixteen_Letters1");
+        assert!(matches!(packets[..], [PacketData::DisconnectOrKick { .. }]));
+        assert_eq!(session.player(), None);
+    }
+
+    #[test]
+    fn falling_hurts_and_kills() {
+        let mut world = world();
+        let (mut session, packets) = login(&mut world, "faller");
+        assert!(matches!(packets[..], [
+            PacketData::LoginRequest { dimension: 0, .. },
+            PacketData::SpawnPosition { x: 0, y: 64, z: 0 },
+            PacketData::PlayerPositionAndLook { .. },
+            PacketData::UpdateHealth { health: MAX_HEALTH },
+        ]));
+
+        let id = session.player().unwrap();
+        world.players.get_mut(&id).unwrap().inventory.add_item(ItemStack::new(block_ids::STONE, 10));
+
+        // Falling 3 blocks does not hurt.
+        assert!(move_to(&mut session, &mut world, 67.0, true).is_empty());
+        assert!(move_to(&mut session, &mut world, 64.0, true).is_empty());
+
+        // Falling 10 blocks takes 7 points of health.
+        move_to(&mut session, &mut world, 74.0, false);
+        assert_eq!(health(&move_to(&mut session, &mut world, 64.0, true)), Some(MAX_HEALTH - 7));
+
+        // The player is not hurt again right away, but is once it has ticked
+        // for long enough.
+        move_to(&mut session, &mut world, 74.0, false);
+        assert!(move_to(&mut session, &mut world, 64.0, true).is_empty());
+        for _ in 0..HURT_TIME {
+            world.tick();
+        }
+        move_to(&mut session, &mut world, 84.0, false);
+        assert_eq!(health(&move_to(&mut session, &mut world, 64.0, true)), Some(0));
+
+        // What it carried is dropped at the next tick.
+        assert!(world.entities[&Dimension::Overworld].is_empty());
+        world.tick();
+        let items = world.entities[&Dimension::Overworld].iter()
+            .map(|e| match &e.kind {
+                EntityKind::Item { stack, .. } => (stack.id, stack.count),
+                kind => panic!("{:?}", kind),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(items, vec![(block_ids::STONE, 10)]);
+        assert!(world.players[&id].inventory.main().iter().all(Option::is_none));
+
+        // Dead players do not move.
+        assert!(move_to(&mut session, &mut world, 100.0, false).is_empty());
+        assert_eq!(world.players[&id].position().y, 64.0);
+    }
+
+    #[test]
+    fn players_are_stopped_by_blocks() {
+        let mut world = world();
+        let (mut session, _) = login(&mut world, "walker");
+        let id = session.player().unwrap();
+
+        // The flat world is 4 blocks high, with a wall of stone in the way.
+        let overworld = world.levels.get_mut(&Dimension::Overworld).unwrap();
+        overworld.load_chunk(ChunkPos::new(0, 0, 0), &world.generators[&Dimension::Overworld]);
+        for y in 4..6 {
+            overworld.set_block(BlockPos::new(3, y, 0), Block::new(block_ids::STONE));
+        }
+        world.players.get_mut(&id).unwrap().body.position = Point3::new(0.5, 4.0, 0.5);
+
+        let walk = |session: &mut Session, world: &mut WorldState, x: f64, y: f64| {
+            session.handle(world, &PacketData::PlayerPosition { x, y, stance: y + 1.62, z: 0.5, on_ground: true })
+        };
+
+        // The player walks freely until it reaches the wall, where it stops.
+        assert!(walk(&mut session, &mut world, 1.5, 4.0).is_empty());
+        assert!(walk(&mut session, &mut world, 2.6, 4.0).is_empty());
+        assert!(matches!(walk(&mut session, &mut world, 3.5, 4.0)[..], [
+            PacketData::PlayerPositionAndLook { x, stance, .. },
+        ] if (x - 2.7).abs() < 1e-9 && stance == 4.0));
+        assert!((world.players[&id].position().x - 2.7).abs() < 1e-9);
+
+        // Nor does it go through the ground.
+        assert!(matches!(walk(&mut session, &mut world, 2.0, 2.0)[..], [
+            PacketData::PlayerPositionAndLook { stance, .. },
+        ] if stance == 4.0));
+
+        // Moving too far at once does not move the player at all.
+        let before = world.players[&id].position();
+        assert!(matches!(walk(&mut session, &mut world, 2.0, 200.0)[..], [PacketData::PlayerPositionAndLook { .. }]));
+        assert_eq!(world.players[&id].position(), before);
+    }
+
+    #[test]
+    fn dead_players_respawn_at_the_spawn() {
+        let mut world = world();
+        let (mut session, _) = login(&mut world, "respawner");
+        let id = session.player().unwrap();
+
+        // Only dead players respawn.
+        assert!(session.handle(&mut world, &PacketData::Respawn { dimension: 0 }).is_empty());
+
+        {
+            let player = world.players.get_mut(&id).unwrap();
+            player.body.position = Point3::new(100.5, -70.0, -40.5);
+            player.dimension = Dimension::Nether;
+        }
+
+        // The void hurts the player every time it can be hurt, until it dies.
+        let mut healths = Vec::new();
+        for _ in 0..60 {
+            world.tick();
+            healths.extend(session.update(&world).into_iter().filter_map(|p| health(&[p])));
+        }
+        assert_eq!(healths, vec![16, 12, 8, 4, 0]);
+
+        let packets = session.handle(&mut world, &PacketData::Respawn { dimension: -1 });
+        assert!(matches!(packets[..], [
+            PacketData::Respawn { dimension: 0 },
+            PacketData::PlayerPositionAndLook { x, stance, z, .. },
+            PacketData::UpdateHealth { health: MAX_HEALTH },
+        ] if (x, stance, z) == (0.5, 64.0, 0.5)));
+
+        let player = &world.players[&id];
+        assert_eq!(player.dimension, Dimension::Overworld);
+        assert!(session.update(&world).is_empty());
+    }
+
+    #[test]
+    fn players_drown_under_water() {
+        let mut world = world();
+        let (mut session, id) = login_on_the_ground(&mut world, "diver");
+        place(&mut world, BlockPos::new(0, 4, 0), block_ids::WATER);
+        place(&mut world, BlockPos::new(0, 5, 0), block_ids::WATER);
+
+        // The player holds its breath for 15 seconds, and then a second more
+        // before it starts drowning.
+        let healths = ticks(&mut session, &mut world, MAX_AIR + 20);
+        assert!(healths[..healths.len() - 1].iter().all(Option::is_none));
+        assert_eq!(healths.last(), Some(&Some(MAX_HEALTH - DROWNING_DAMAGE)));
+
+        // It keeps drowning every second.
+        let healths = ticks(&mut session, &mut world, 20);
+        assert_eq!(healths.into_iter().flatten().collect::<Vec<_>>(), vec![MAX_HEALTH - 2 * DROWNING_DAMAGE]);
+
+        // With its head out of the water, it gets its breath back.
+        place(&mut world, BlockPos::new(0, 5, 0), block_ids::AIR);
+        world.tick();
+        assert_eq!(world.players[&id].air, MAX_AIR);
+        assert!(ticks(&mut session, &mut world, 40).iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn players_burn_in_lava_and_fire() {
+        let mut world = world();
+        let (mut session, id) = login_on_the_ground(&mut world, "burner");
+        let move_x = |world: &mut WorldState, x: f64| world.players.get_mut(&id).unwrap().body.position.x = x;
+        place(&mut world, BlockPos::new(0, 4, 0), block_ids::FIRE);
+        place(&mut world, BlockPos::new(6, 4, 0), block_ids::WATER);
+        place(&mut world, BlockPos::new(9, 4, 0), block_ids::LAVA);
+
+        // Fire hurts the player and sets it on fire, so that it keeps burning
+        // once it has stepped out.
+        assert_eq!(ticks(&mut session, &mut world, 1), vec![Some(MAX_HEALTH - 1)]);
+        assert!(world.players[&id].fire > 0);
+        move_x(&mut world, 3.5);
+        let burnt = ticks(&mut session, &mut world, 40).into_iter().flatten().collect::<Vec<_>>();
+        assert_eq!(burnt, vec![MAX_HEALTH - 2, MAX_HEALTH - 3]);
+
+        // Water puts it out.
+        move_x(&mut world, 6.5);
+        world.tick();
+        assert!(world.players[&id].fire <= 0);
+        move_x(&mut world, 3.5);
+        assert!(ticks(&mut session, &mut world, 40).iter().all(Option::is_none));
+
+        // Lava hurts more, and sets the player on fire for longer.
+        move_x(&mut world, 9.5);
+        assert_eq!(ticks(&mut session, &mut world, 1), vec![Some(MAX_HEALTH - 3 - LAVA_DAMAGE)]);
+        assert!(world.players[&id].fire > 300);
+    }
+
+    #[test]
+    fn armor_protects_from_hits_only() {
+        let mut world = world();
+        let (mut alice, _) = login(&mut world, "alice");
+        let (mut bob, b) = login_on_the_ground(&mut world, "bob");
+        world.players.values_mut().for_each(|p| p.body.position = Point3::new(0.5, 4.0, 0.5));
+
+        let armor = [item_ids::DIAMOND_BOOTS, item_ids::DIAMOND_LEGGINGS, item_ids::DIAMOND_CHESTPLATE, item_ids::DIAMOND_HELMET];
+        for (slot, &id) in world.players.get_mut(&b).unwrap().inventory.armor_mut().iter_mut().zip(armor.iter()) {
+            *slot = Some(ItemStack::new(id, 1));
+        }
+        let sword = ItemStack::new(item_ids::DIAMOND_SWORD, 1);
+        let damage = sword.attack_damage();
+        world.players.get_mut(&alice.player().unwrap()).unwrap().inventory.add_item(sword);
+
+        // The armor takes away a share of the damage of a hit, as much as it
+        // is worth out of 25, and wears out.
+        let value = world.players[&b].inventory.armor_value();
+        assert!(value > 0);
+        hit(&mut alice, &mut world, b);
+        assert_eq!(health(&bob.update(&world)), Some(MAX_HEALTH - damage * (25 - value) / 25));
+        assert!(world.players[&b].inventory.armor().iter().flatten().all(|stack| stack.damage > 0));
+
+        // It does not help against falling.
+        for _ in 0..HURT_TIME {
+            world.tick();
+        }
+        let before = world.players[&b].health;
+        move_to(&mut bob, &mut world, 14.0, false);
+        assert_eq!(health(&move_to(&mut bob, &mut world, 4.0, true)), Some(before - 7));
+    }
+
+    #[test]
+    fn players_hit_each_other() {
+        let mut world = world();
+        let (mut alice, _) = login(&mut world, "alice");
+        let (mut bob, _) = login(&mut world, "bob");
+        let (a, b) = (alice.player().unwrap(), bob.player().unwrap());
+        assert_ne!(a, b);
+
+        // Alice hits with her hand, and Bob hears about it at the next tick.
+        assert!(hit(&mut alice, &mut world, b).is_empty());
+        world.tick();
+        assert_eq!(health(&bob.update(&world)), Some(MAX_HEALTH - 1));
+        assert!(bob.update(&world).is_empty());
+
+        // Bob cannot be hurt again right away.
+        hit(&mut alice, &mut world, b);
+        assert_eq!(world.players[&b].health, MAX_HEALTH - 1);
+
+        // Players do not hit themselves, nor players out of reach.
+        for _ in 0..HURT_TIME {
+            world.tick();
+        }
+        hit(&mut bob, &mut world, b);
+        world.players.get_mut(&a).unwrap().body.position.x += 10.0;
+        hit(&mut alice, &mut world, b);
+        assert_eq!(world.players[&b].health, MAX_HEALTH - 1);
+
+        // A sword hurts more.
+        world.players.get_mut(&a).unwrap().body.position.x -= 10.0;
+        let sword = ItemStack::new(item_ids::DIAMOND_SWORD, 1);
+        let damage = sword.attack_damage();
+        world.players.get_mut(&a).unwrap().inventory.add_item(sword);
+        hit(&mut alice, &mut world, b);
+        assert_eq!(health(&bob.update(&world)), Some(MAX_HEALTH - 1 - damage));
+
+        // Players in other dimensions are out of reach.
+        world.players.get_mut(&b).unwrap().dimension = Dimension::Nether;
+        for _ in 0..HURT_TIME {
+            world.tick();
+        }
+        hit(&mut alice, &mut world, b);
+        assert_eq!(world.players[&b].health, MAX_HEALTH - 1 - damage);
+    }
+
+    #[test]
+    fn players_hit_mobs() {
+        let mut world = world();
+        let (mut session, _) = login(&mut world, "hunter");
+
+        let entities = world.entities.get_mut(&Dimension::Overworld).unwrap();
+        let mob = entities.spawn(EntityKind::Mob(Mob::new(MobType::Pig)), Point3::new(1.5, 64.0, 0.5));
+        let health = |world: &WorldState| world.entities[&Dimension::Overworld].get(mob).unwrap().mob().unwrap().health;
+        let before = health(&world);
+
+        assert!(hit(&mut session, &mut world, mob).is_empty());
+        assert_eq!(health(&world), before - 1);
+
+        // Mobs are not hurt again right away either.
+        hit(&mut session, &mut world, mob);
+        assert_eq!(health(&world), before - 1);
+
+        // Only left clicks hit.
+        for _ in 0..HURT_TIME {
+            world.tick();
+        }
+        session.handle(&mut world, &PacketData::UseEntity {
+            source_entity: session.player().unwrap(),
+            target_entity: mob,
+            left_click: false,
+        });
+        assert_eq!(health(&world), before - 1);
+    }
+
+    #[test]
+    fn players_go_through_portals() {
+        let mut world = world();
+        let (mut session, _) = login(&mut world, "traveller");
+        let id = session.player().unwrap();
+
+        let overworld = world.levels.get_mut(&Dimension::Overworld).unwrap();
+        for x in -1..=1 {
+            for z in -1..=1 {
+                overworld.load_chunk(ChunkPos::new(x, 0, z), &world.generators[&Dimension::Overworld]);
+            }
+        }
+        let portal = overworld.create_portal(BlockPos::new(8, 4, 8));
+
+        let in_portal = |world: &WorldState| {
+            let feet = world.players[&id].position();
+            let at = BlockPos::new(feet.x.floor() as i32, feet.y.floor() as i32, feet.z.floor() as i32);
+            world.levels[&world.players[&id].dimension].block(at).is_some_and(|b| b.id == block_ids::PORTAL)
+        };
+        let step_in = |world: &mut WorldState, at: BlockPos| {
+            let feet = Point3::new(at.x as f64 + 0.5, at.y as f64, at.z as f64 + 0.5);
+            world.players.get_mut(&id).unwrap().body.position = feet;
+        };
+
+        // The player goes through once it has stood in the portal for long
+        // enough, and arrives in a portal in the Nether.
+        step_in(&mut world, portal);
+        for _ in 1..PORTAL_TIME {
+            world.tick();
+            assert!(session.update(&world).is_empty());
+        }
+        world.tick();
+        assert!(matches!(session.update(&world)[..], [
+            PacketData::Respawn { dimension: -1 },
+            PacketData::PlayerPositionAndLook { .. },
+        ]));
+        assert_eq!(world.players[&id].dimension, Dimension::Nether);
+        assert!(in_portal(&world));
+
+        // It does not go back until it has stepped out of the portal.
+        for _ in 0..2 * PORTAL_TIME {
+            world.tick();
+        }
+        assert!(session.update(&world).is_empty());
+
+        let arrival = world.players[&id].position();
+        world.players.get_mut(&id).unwrap().body.position.y += 10.0;
+        for _ in 0..PORTAL_TIME {
+            world.tick();
+        }
+        world.players.get_mut(&id).unwrap().body.position = arrival;
+        for _ in 0..PORTAL_TIME {
+            world.tick();
+        }
+
+        assert!(matches!(session.update(&world)[..], [
+            PacketData::Respawn { dimension: 0 },
+            PacketData::PlayerPositionAndLook { .. },
+        ]));
+        assert_eq!(world.players[&id].dimension, Dimension::Overworld);
+        assert!(in_portal(&world));
+    }
+}
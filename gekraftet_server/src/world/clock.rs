@@ -0,0 +1,123 @@
+use std::path::PathBuf;
+use std::sync::{ Arc, Mutex };
+use std::time::Duration;
+use gekraftet_core::utils::Random;
+use gekraftet_core::world::{ sky_darkness, LevelData, WeatherChange };
+use rand::SeedableRng;
+use tokio::sync::broadcast::Sender;
+use crate::packet::PacketData;
+use super::WorldState;
+
+/// The length of a tick.
+pub const TICK_DURATION: Duration = Duration::from_millis(50);
+
+// The ticks between two `TimeUpdate` packets, as in Beta.
+const TIME_UPDATE_INTERVAL: u64 = 20;
+// The ticks between two saves of level.dat.
+const SAVE_INTERVAL: u64 = 6000;
+
+// The reasons sent in `NewState` packets for the rain.
+const BEGIN_RAINING: u8 = 1;
+const END_RAINING: u8 = 2;
+
+/// Keeps the time and the weather of the world going, and tells the players
+/// about them.
+pub struct WorldClock {
+    data: LevelData,
+    rng: Random,
+    ticks: u64,
+}
+
+impl WorldClock {
+    pub fn new(data: LevelData) -> Self {
+        // The weather picks the same durations when the world is loaded at
+        // the same time again.
+        let rng = Random::seed_from_u64(data.seed as u64 ^ data.time);
+
+        Self {
+            data,
+            rng,
+            ticks: 0,
+        }
+    }
+
+    /// Advances the time and the weather by a tick. Returns the packets for
+    /// the players: the time every second, and the rain when it starts or
+    /// stops. Thunder is not sent, since the clients do not show it.
+    pub fn tick(&mut self) -> Vec<PacketData> {
+        let mut packets = Vec::new();
+
+        self.data.time += 1;
+        self.ticks += 1;
+
+        for change in self.data.weather.tick(&mut self.rng) {
+            match change {
+                WeatherChange::RainStarted => packets.push(PacketData::NewState { reason_code: BEGIN_RAINING }),
+                WeatherChange::RainStopped => packets.push(PacketData::NewState { reason_code: END_RAINING }),
+                _ => {},
+            }
+        }
+
+        if self.ticks.is_multiple_of(TIME_UPDATE_INTERVAL) {
+            packets.push(self.time_update());
+        }
+
+        packets
+    }
+
+    fn time_update(&self) -> PacketData {
+        PacketData::TimeUpdate {
+            ticks: self.data.time as i64,
+        }
+    }
+
+    /// How much darker than in full daylight the sky is now.
+    pub fn sky_darkness(&self) -> u8 {
+        sky_darkness(self.data.time, &self.data.weather)
+    }
+
+    /// The packets telling a player who just logged in about the time and
+    /// the weather.
+    pub fn login_packets(&self) -> Vec<PacketData> {
+        let mut packets = vec![self.time_update()];
+        if self.data.weather.raining {
+            packets.push(PacketData::NewState { reason_code: BEGIN_RAINING });
+        }
+        packets
+    }
+}
+
+/// Ticks the clock and the world for as long as the server runs, sending the
+/// packets to every player listening and saving level.dat into the world
+/// directory from time to time.
+pub async fn run(clock: Arc<Mutex<WorldClock>>, state: Arc<Mutex<WorldState>>, players: Sender<PacketData>, world: PathBuf) {
+    let mut interval = tokio::time::interval(TICK_DURATION);
+
+    loop {
+        interval.tick().await;
+
+        let (packets, save, darkness) = {
+            let mut clock = clock.lock().unwrap();
+            let packets = clock.tick();
+            let save = Some(clock.data.clone()).filter(|_| clock.ticks.is_multiple_of(SAVE_INTERVAL));
+            (packets, save, clock.sky_darkness())
+        };
+
+        {
+            let mut state = state.lock().unwrap();
+            state.set_sky_darkness(darkness);
+            state.tick();
+        }
+
+        for packet in packets {
+            // Nobody might be listening, which is fine.
+            let _ = players.send(packet);
+        }
+
+        if let Some(data) = save {
+            if let Err(e) = data.save(&world) {
+                println!("could not save level.dat: {:?}", e);
+            }
+        }
+    }
+}
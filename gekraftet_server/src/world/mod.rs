@@ -1 +1,6 @@
+mod clock;
 mod region;
+mod state;
+
+pub use clock::*;
+pub use state::WorldState;
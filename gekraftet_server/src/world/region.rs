@@ -1,103 +1,567 @@
-use gekraftet_core::world::Chunk;
-use gekraftet_core::utils::PartialHeapArray;
-use std::path::Path;
+use gekraftet_core::entity::Entities;
+use gekraftet_core::nbt::{ self, NamedBinaryTag, ValueData };
+use gekraftet_core::world::{ Block, Chunk, ChunkPos, Dimension, CHUNK_LENGTH_X, CHUNK_LENGTH_Z };
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::path::{ Path, PathBuf };
 use std::fs::File;
-use std::io::{ BufReader, Read, Error as IoError };
+use std::io::{ Read, Seek, SeekFrom, Error as IoError };
 
-const REGION_SECTOR_SIZE: usize = 4096;
-const REGION_ENTRY_COUNT: usize = 1024;
+/// The chunks of a region along each axis.
+pub const REGION_LENGTH: i32 = 32;
 
-#[derive(Clone, Debug, PartialEq)]
+const REGION_SECTOR_SIZE: u64 = 4096;
+const REGION_ENTRY_COUNT: usize = (REGION_LENGTH * REGION_LENGTH) as usize;
+// The length of the payload and its compression type come before it.
+const CHUNK_HEADER_SIZE: u64 = 5;
+
+const COMPRESSION_GZIP: u8 = 1;
+const COMPRESSION_ZLIB: u8 = 2;
+
+// The chunks of Beta are 128 blocks high.
+const SAVED_CHUNK_HEIGHT: usize = 128;
+const SAVED_CHUNK_VOLUME: usize = CHUNK_LENGTH_X * SAVED_CHUNK_HEIGHT * CHUNK_LENGTH_Z;
+
+// The errors are only ever printed, which does not count as reading them.
+#[allow(dead_code)]
+#[derive(Debug)]
+pub enum RegionError {
+    Io(IoError),
+    Nbt(nbt::Error),
+    /// The chunk is not in the region, as its coordinates are not between 0
+    /// and `REGION_LENGTH`.
+    OutOfRegion(usize, usize),
+    /// The payload of the chunk does not fit in the sectors it was given.
+    InvalidLength(u32),
+    UnknownCompression(u8),
+    MissingTag(&'static str),
+    InvalidTag(&'static str),
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
 struct ChunkLocation {
-    sector_count: u8,
     sector_offset: u32,
+    sector_count: u8,
 }
 
-#[derive(Clone, Debug)]
-struct ChunkHeader {
-    length: u32,
-    compression_type: u8,
+// Region files are laid out as follows, every number being big-endian:
+//
+//     locations (1024 * 4 bytes)       the offset of the first sector of a
+//                                      chunk (3 bytes), then the number of
+//                                      sectors it takes up (1 byte), or 0 if
+//                                      the chunk has not been saved
+//     timestamps (1024 * 4 bytes)      when each chunk was last saved
+//     sectors (4096 bytes each)        the chunks, each starting with the
+//                                      length of the payload (4 bytes, the
+//                                      compression type included), the
+//                                      compression type (1 byte) and the
+//                                      compressed NBT
+//
+// The chunks are ordered by x, then z, within each table. The header takes
+// up the first two sectors.
+
+/// A region file of a world in the McRegion format, which holds 32 by 32
+/// chunks. Only the locations of the chunks are read when the file is
+/// opened, and every chunk is read from the file when it is asked for.
+pub struct RegionFile {
+    file: File,
+    locations: Vec<ChunkLocation>,
 }
 
-pub struct RegionHeader {
-    locations: Box<[ChunkLocation; REGION_ENTRY_COUNT]>,
-    timestamps: Box<[u32; REGION_ENTRY_COUNT]>,
+impl RegionFile {
+    /// Where the region file holding a chunk is, as in Beta:
+    /// `region/r.<x>.<z>.mcr` in the directory of the dimension.
+    pub fn file_path<P: AsRef<Path>>(world: P, dimension: Dimension, at: ChunkPos) -> PathBuf {
+        let (x, z) = (at.x.div_euclid(REGION_LENGTH), at.z.div_euclid(REGION_LENGTH));
+        dimension.directory(world).join("region").join(format!("r.{}.{}.mcr", x, z))
+    }
+
+    /// The position of a chunk within its region.
+    pub fn local_position(at: ChunkPos) -> (usize, usize) {
+        (at.x.rem_euclid(REGION_LENGTH) as usize, at.z.rem_euclid(REGION_LENGTH) as usize)
+    }
+
+    /// Opens a region file and reads the locations of its chunks. When the
+    /// chunks were saved is not needed.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, RegionError> {
+        let mut file = File::open(path).map_err(RegionError::Io)?;
+
+        let mut locations = vec![0u8; REGION_ENTRY_COUNT * 4];
+        file.read_exact(&mut locations).map_err(RegionError::Io)?;
+
+        let locations = locations.chunks_exact(4)
+            .map(|bytes| ChunkLocation {
+                sector_offset: u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]),
+                sector_count: bytes[3],
+            })
+            .collect();
+
+        Ok(Self {
+            file,
+            locations,
+        })
+    }
+
+    fn index(x: usize, z: usize) -> Result<usize, RegionError> {
+        let length = REGION_LENGTH as usize;
+        if x >= length || z >= length {
+            return Err(RegionError::OutOfRegion(x, z))
+        }
+
+        Ok(x + z * length)
+    }
+
+    /// Where a chunk is in the file, or None if it has not been saved. The
+    /// first two sectors hold the header, so no chunk starts there.
+    fn location(&self, x: usize, z: usize) -> Result<Option<ChunkLocation>, RegionError> {
+        let location = self.locations[Self::index(x, z)?];
+        Ok(Some(location).filter(|l| l.sector_count > 0 && l.sector_offset >= 2))
+    }
+
+    /// Reads the chunk at the given position within the region, or returns
+    /// None if it has not been saved. The chunk is decompressed and parsed,
+    /// but not checked.
+    pub fn read_chunk(&mut self, x: usize, z: usize) -> Result<Option<NamedBinaryTag>, RegionError> {
+        let location = match self.location(x, z)? {
+            Some(location) => location,
+            None => return Ok(None),
+        };
+
+        self.file.seek(SeekFrom::Start(location.sector_offset as u64 * REGION_SECTOR_SIZE))
+            .map_err(RegionError::Io)?;
+
+        let mut header = [0u8; CHUNK_HEADER_SIZE as usize];
+        self.file.read_exact(&mut header).map_err(RegionError::Io)?;
+        let length = u32::from_be_bytes([header[0], header[1], header[2], header[3]]);
+        let compression = header[4];
+
+        // The length counts the compression type, which was already read.
+        let available = location.sector_count as u64 * REGION_SECTOR_SIZE - CHUNK_HEADER_SIZE;
+        if length == 0 || length as u64 - 1 > available {
+            return Err(RegionError::InvalidLength(length))
+        }
+
+        let mut payload = vec![0u8; length as usize - 1];
+        self.file.read_exact(&mut payload).map_err(RegionError::Io)?;
+
+        let tag = match compression {
+            COMPRESSION_GZIP => NamedBinaryTag::from_gzip(&payload),
+            COMPRESSION_ZLIB => NamedBinaryTag::from_zlib(&payload),
+            _ => return Err(RegionError::UnknownCompression(compression)),
+        };
+
+        tag.map(Some).map_err(RegionError::Nbt)
+    }
 }
 
-pub struct Region {
-    header: RegionHeader,
-    data: Vec<(ChunkHeader, Box<[u8]>)>,
+/// The region files of a world which were opened. They are kept open, so
+/// that the locations of the chunks of a region are only read once.
+pub struct Regions {
+    world: PathBuf,
+    files: HashMap<PathBuf, RegionFile>,
 }
 
-impl Region {
-    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, IoError> {
-        let path = path.as_ref();
-        let file = File::open(path)?;
-        let mut reader = BufReader::new(file);
-        
-        let mut locations = PartialHeapArray::<_, REGION_ENTRY_COUNT>::new();
-        let mut timestamps = PartialHeapArray::<_, REGION_ENTRY_COUNT>::new();
-        let mut data_size = 0; // used later when allocating for `data`
-        
-        for _ in 0..REGION_ENTRY_COUNT {
-            let mut bytes = [0u8; 4];
-            reader.read_exact(&mut bytes)?;
-            let location = ChunkLocation {
-                sector_count: bytes[4],
-                sector_offset: u32::from_be_bytes([
-                    0, bytes[0], bytes[1], bytes[2]
-                ]),
-            };
-
-            if location.sector_count > 0 {
-                data_size += 1
-            };
-
-            locations.push(location).unwrap();
+impl Regions {
+    pub fn new(world: PathBuf) -> Self {
+        Self {
+            world,
+            files: HashMap::new(),
         }
+    }
 
-        for _ in 0..REGION_ENTRY_COUNT {
-            let mut bytes = [0u8; 4];
-            reader.read_exact(&mut bytes)?;
-            timestamps.push(u32::from_be_bytes(bytes)).unwrap();
+    /// Reads a chunk of a dimension from its region file in the directory of
+    /// the world, adding its entities to `entities`, or returns None if it
+    /// has not been saved.
+    pub fn load_chunk(&mut self, dimension: Dimension, at: ChunkPos, entities: &mut Entities) -> Result<Option<Chunk>, RegionError> {
+        let region = match self.files.entry(RegionFile::file_path(&self.world, dimension, at)) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                // The file is looked for again next time, in case it was
+                // saved since.
+                if !entry.key().exists() {
+                    return Ok(None)
+                }
+
+                let region = RegionFile::open(entry.key())?;
+                entry.insert(region)
+            },
+        };
+
+        let (x, z) = RegionFile::local_position(at);
+        match region.read_chunk(x, z)? {
+            Some(tag) => chunk_from_nbt(at, &tag, entities).map(Some),
+            None => Ok(None),
         }
+    }
+}
 
-        let mut data = Vec::with_capacity(data_size);
-        for loc in locations.iter() {
-            // The first chunk sector starts at offset 2. (0 and 1 are occupied
-            // by the region header).
-            if loc.sector_count > 0 && loc.sector_offset >= 2 {
-                reader.seek_relative((loc.sector_offset - 2) as i64 * REGION_SECTOR_SIZE as i64)?;
-                let mut header_bytes = [0u8; 5];
-                reader.read_exact(&mut header_bytes)?;
-
-                let header = ChunkHeader {
-                    length: u32::from_be_bytes([
-                        header_bytes[0], header_bytes[1], 
-                        header_bytes[2], header_bytes[3]
-                    ]),
-
-                    compression_type: header_bytes[4],
-                };
-                
-                // We have read the first 5 bytes.
-                let bytes_to_read = loc.sector_count as usize * REGION_SECTOR_SIZE - 5;
-                let mut chunk_bytes = Vec::with_capacity(bytes_to_read);
-                unsafe { chunk_bytes.set_len(bytes_to_read) };
-                reader.read_exact(chunk_bytes.as_mut_slice())?;
-
-                data.push((header, chunk_bytes.into_boxed_slice()));
+// The chunks are saved as follows, as in Beta:
+//
+//     "" (compound)
+//         Level (compound)
+//             xPos, zPos (int)
+//             Blocks (32768 bytes)         the block ids, ordered by x, then
+//                                          z, then y
+//             Data (16384 bytes)           the block metadata, in the same
+//                                          order, two blocks in every byte,
+//                                          the first one in the lower half
+//             SkyLight, BlockLight (16384 bytes)
+//             HeightMap (256 bytes)
+//             Entities, TileEntities (list of compounds)
+//             LastUpdate (long)
+//             TerrainPopulated (byte)
+//
+// Only the blocks, the block entities and the entities are read, since the
+// light is worked out when it is needed.
+
+/// Reads the blocks and the block entities of a chunk read from a region
+/// file, and adds its entities to `entities` with new ids. Block entities
+/// and entities which are unknown or invalid are skipped.
+pub fn chunk_from_nbt(at: ChunkPos, tag: &NamedBinaryTag, entities: &mut Entities) -> Result<Chunk, RegionError> {
+    let level = tag.get("")
+        .ok_or(RegionError::MissingTag(""))?
+        .compound()
+        .ok_or(RegionError::InvalidTag(""))?
+        .get("Level")
+        .ok_or(RegionError::MissingTag("Level"))?
+        .compound()
+        .ok_or(RegionError::InvalidTag("Level"))?;
+
+    let array = |name: &'static str, length: usize| {
+        level.get(name)
+            .ok_or(RegionError::MissingTag(name))?
+            .byte_array()
+            .filter(|array| array.len() == length)
+            .ok_or(RegionError::InvalidTag(name))
+    };
+    let blocks = array("Blocks", SAVED_CHUNK_VOLUME)?;
+    let data = array("Data", SAVED_CHUNK_VOLUME / 2)?;
+
+    let mut chunk = Chunk::empty(ChunkPos::new(at.x, 0, at.z));
+    for x in 0..CHUNK_LENGTH_X {
+        for z in 0..CHUNK_LENGTH_Z {
+            for y in 0..SAVED_CHUNK_HEIGHT {
+                let index = y + (z + x * CHUNK_LENGTH_Z) * SAVED_CHUNK_HEIGHT;
+                let id = blocks[index] as u8 as u16;
+                if id == 0 {
+                    continue
+                }
+
+                let metadata = (data[index / 2] as u8 >> (index % 2 * 4)) & 0xF;
+                chunk.set_block(x, y, z, Block::with_metadata(id, metadata as u16));
             }
         }
+    }
 
-        let region_header = RegionHeader {
-            locations: locations.into_full_array().unwrap(),
-            timestamps: timestamps.into_full_array().unwrap(),
-        };
+    if let Some((_, block_entities)) = level.get("TileEntities").and_then(ValueData::list) {
+        chunk.load_block_entities_nbt(block_entities);
+    }
+    if let Some((_, saved)) = level.get("Entities").and_then(ValueData::list) {
+        entities.load_chunk_nbt(saved);
+    }
 
-        Ok(Self {
-            header: region_header,
-            data,
-        })
+    Ok(chunk)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::fs;
+    use cgmath::Point3;
+    use gekraftet_core::entity::{ EntityKind, MobType };
+    use gekraftet_core::item::{ item_ids, ItemStack };
+    use gekraftet_core::world::{ block_ids, BlockEntity, BlockPos, WorldPreset };
+    use crate::world::WorldState;
+    use super::*;
+
+    // A region file written as Beta writes them. It holds the chunk at (1, 2),
+    // which is grassland with a few blocks, a chest, a pig and an item on it,
+    // and the chunk at (31, 31), which is only bedrock.
+    const BETA_REGION: &[u8] = include_bytes!("fixtures/r.0.0.mcr");
+
+    /// A chunk to write into a region file: its position within the region,
+    /// its length as written before it, its compression type and its payload.
+    type SavedChunk = ((usize, usize), u32, u8, Vec<u8>);
+
+    // Every test writes its fixtures to a directory of its own, as the tests
+    // run at the same time.
+    fn world(name: &str) -> PathBuf {
+        let world = std::env::temp_dir().join("gekraftet-region-tests").join(name);
+        let _ = fs::remove_dir_all(&world);
+        world
+    }
+
+    // A chunk of stone with a block of wool of the given colour in it.
+    fn chunk_nbt(at: ChunkPos, wool: (usize, usize, usize), color: u8) -> NamedBinaryTag {
+        let mut blocks = vec![0i8; SAVED_CHUNK_VOLUME];
+        let mut data = vec![0i8; SAVED_CHUNK_VOLUME / 2];
+        for x in 0..CHUNK_LENGTH_X {
+            for z in 0..CHUNK_LENGTH_Z {
+                blocks[(z + x * CHUNK_LENGTH_Z) * SAVED_CHUNK_HEIGHT] = block_ids::STONE as i8;
+            }
+        }
+
+        let (x, y, z) = wool;
+        let index = y + (z + x * CHUNK_LENGTH_Z) * SAVED_CHUNK_HEIGHT;
+        blocks[index] = block_ids::WOOL as i8;
+        data[index / 2] = (color << (index % 2 * 4)) as i8;
+
+        let mut level = HashMap::new();
+        level.insert("xPos".into(), ValueData::Int(at.x));
+        level.insert("zPos".into(), ValueData::Int(at.z));
+        level.insert("Blocks".into(), ValueData::ByteArray(blocks));
+        level.insert("Data".into(), ValueData::ByteArray(data));
+
+        let mut root = HashMap::new();
+        root.insert("Level".into(), ValueData::Compound(level));
+
+        let mut tag = NamedBinaryTag::new();
+        tag.insert("", ValueData::Compound(root));
+        tag
+    }
+
+    // Writes a region file holding the given chunks. Every chunk gets a
+    // sector of its own.
+    fn write_region(path: &Path, chunks: &[SavedChunk]) {
+        let mut locations = vec![0u8; REGION_ENTRY_COUNT * 4];
+        let mut sectors = Vec::new();
+        for (i, ((x, z), length, compression, payload)) in chunks.iter().enumerate() {
+            let index = RegionFile::index(*x, *z).unwrap();
+            let offset = (2 + i as u32).to_be_bytes();
+            locations[index * 4..index * 4 + 4].copy_from_slice(&[offset[1], offset[2], offset[3], 1]);
+
+            let mut sector = length.to_be_bytes().to_vec();
+            sector.push(*compression);
+            sector.extend_from_slice(payload);
+            sector.resize(REGION_SECTOR_SIZE as usize, 0);
+            sectors.extend(sector);
+        }
+
+        let mut file = locations;
+        file.resize(2 * REGION_SECTOR_SIZE as usize, 0);
+        file.extend(sectors);
+
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, file).unwrap();
+    }
+
+    // A chunk saved with the right length.
+    fn saved(at: (usize, usize), compression: u8, payload: Vec<u8>) -> SavedChunk {
+        (at, payload.len() as u32 + 1, compression, payload)
+    }
+
+    #[test]
+    fn region_files_are_in_the_directory_of_the_dimension() {
+        let world = Path::new("world");
+        assert_eq!(
+            RegionFile::file_path(world, Dimension::Overworld, ChunkPos::new(31, 0, 32)),
+            world.join("region").join("r.0.1.mcr"),
+        );
+        assert_eq!(
+            RegionFile::file_path(world, Dimension::Nether, ChunkPos::new(-1, 0, 0)),
+            world.join("DIM-1").join("region").join("r.-1.0.mcr"),
+        );
+        assert_eq!(RegionFile::local_position(ChunkPos::new(-1, 0, 33)), (31, 1));
+    }
+
+    #[test]
+    fn saved_chunks_are_loaded() {
+        let world = world("saved");
+        let at = ChunkPos::new(-3, 0, 5);
+        let path = RegionFile::file_path(&world, Dimension::Overworld, at);
+        let payload = chunk_nbt(at, (2, 70, 9), 14).to_zlib();
+        write_region(&path, &[saved(RegionFile::local_position(at), COMPRESSION_ZLIB, payload)]);
+
+        let mut regions = Regions::new(world);
+        let chunk = regions.load_chunk(Dimension::Overworld, at, &mut Entities::new()).unwrap().unwrap();
+        assert_eq!(chunk.position(), at);
+        assert_eq!(*chunk.block(2, 70, 9), Block::with_metadata(block_ids::WOOL, 14));
+        assert_eq!(*chunk.block(2, 71, 9), Block::new(block_ids::AIR));
+        assert_eq!(*chunk.block(15, 0, 15), Block::new(block_ids::STONE));
+
+        // The block next to the wool shares its byte of metadata.
+        assert_eq!(*chunk.block(2, 69, 9), Block::new(block_ids::AIR));
+    }
+
+    #[test]
+    fn the_world_loads_saved_chunks_before_generating_them() {
+        let world = world("state");
+        let (saved_at, generated_at) = (ChunkPos::new(0, 0, 0), ChunkPos::new(1, 0, 0));
+        let path = RegionFile::file_path(&world, Dimension::Nether, saved_at);
+        write_region(&path, &[saved((0, 0), COMPRESSION_ZLIB, chunk_nbt(saved_at, (3, 40, 3), 5).to_zlib())]);
+
+        let mut state = WorldState::new(world, 42, BlockPos::new(0, 64, 0), WorldPreset::default());
+        state.load_chunk(Dimension::Nether, saved_at);
+        state.load_chunk(Dimension::Nether, generated_at);
+
+        let level = &state.levels[&Dimension::Nether];
+        let chunk = level.chunk(saved_at).unwrap();
+        assert_eq!(*chunk.block(3, 40, 3), Block::with_metadata(block_ids::WOOL, 5));
+        assert_eq!(*chunk.block(3, 0, 3), Block::new(block_ids::STONE));
+
+        // The Nether generator lays bedrock at the bottom of every column.
+        assert_eq!(level.chunk(generated_at).unwrap().block(0, 0, 0).id, block_ids::BEDROCK);
+    }
+
+    #[test]
+    fn gzip_and_zlib_payloads_are_read() {
+        let world = world("compression");
+        let path = world.join("r.0.0.mcr");
+        let (gzip, zlib) = (ChunkPos::new(0, 0, 0), ChunkPos::new(1, 0, 0));
+        write_region(&path, &[
+            saved((0, 0), COMPRESSION_GZIP, chunk_nbt(gzip, (0, 1, 0), 3).to_gzip()),
+            saved((1, 0), COMPRESSION_ZLIB, chunk_nbt(zlib, (0, 1, 0), 4).to_zlib()),
+        ]);
+
+        let mut region = RegionFile::open(&path).unwrap();
+        assert_eq!(region.read_chunk(0, 0).unwrap(), Some(chunk_nbt(gzip, (0, 1, 0), 3)));
+        assert_eq!(region.read_chunk(1, 0).unwrap(), Some(chunk_nbt(zlib, (0, 1, 0), 4)));
+    }
+
+    #[test]
+    fn missing_chunks_are_none() {
+        let world = world("missing");
+        let mut regions = Regions::new(world.clone());
+        let mut load = |at| regions.load_chunk(Dimension::Nether, at, &mut Entities::new()).unwrap();
+        let at = ChunkPos::new(0, 0, 0);
+        assert!(load(at).is_none());
+
+        // The region file is found once it has been saved.
+        let path = RegionFile::file_path(&world, Dimension::Nether, at);
+        write_region(&path, &[saved((0, 0), COMPRESSION_ZLIB, chunk_nbt(at, (0, 1, 0), 0).to_zlib())]);
+        assert!(load(ChunkPos::new(0, 0, 1)).is_none());
+        assert!(load(at).is_some());
+    }
+
+    #[test]
+    fn invalid_chunks_are_errors() {
+        let world = world("invalid");
+        let path = world.join("r.0.0.mcr");
+        let payload = chunk_nbt(ChunkPos::new(0, 0, 0), (0, 1, 0), 0).to_zlib();
+        write_region(&path, &[
+            ((0, 0), 0, COMPRESSION_ZLIB, payload.clone()),
+            ((1, 0), REGION_SECTOR_SIZE as u32, COMPRESSION_ZLIB, payload.clone()),
+            saved((2, 0), 3, payload.clone()),
+            saved((3, 0), COMPRESSION_GZIP, payload),
+        ]);
+
+        let mut region = RegionFile::open(&path).unwrap();
+        assert!(matches!(region.read_chunk(0, 0), Err(RegionError::InvalidLength(0))));
+        assert!(matches!(region.read_chunk(1, 0), Err(RegionError::InvalidLength(4096))));
+        assert!(matches!(region.read_chunk(2, 0), Err(RegionError::UnknownCompression(3))));
+        assert!(matches!(region.read_chunk(3, 0), Err(RegionError::Nbt(_))));
+        assert!(matches!(region.read_chunk(32, 0), Err(RegionError::OutOfRegion(32, 0))));
+    }
+
+    #[test]
+    fn chunks_need_their_blocks() {
+        let at = ChunkPos::new(0, 0, 0);
+        let mut entities = Entities::new();
+        assert!(matches!(chunk_from_nbt(at, &NamedBinaryTag::new(), &mut entities), Err(RegionError::MissingTag(""))));
+
+        let mut tag = chunk_nbt(at, (0, 1, 0), 0);
+        let level = tag.root_mut().get_mut("").unwrap()
+            .compound_mut().unwrap()
+            .get_mut("Level").unwrap()
+            .compound_mut().unwrap();
+        level.insert("Data".into(), ValueData::ByteArray(vec![0; 16]));
+        assert!(matches!(chunk_from_nbt(at, &tag, &mut entities), Err(RegionError::InvalidTag("Data"))));
+        assert!(entities.is_empty());
+    }
+
+    #[test]
+    fn region_files_are_kept_open() {
+        let world = world("open");
+        let (first, second) = (ChunkPos::new(0, 0, 0), ChunkPos::new(1, 0, 0));
+        let path = RegionFile::file_path(&world, Dimension::Overworld, first);
+        write_region(&path, &[
+            saved((0, 0), COMPRESSION_ZLIB, chunk_nbt(first, (0, 1, 0), 1).to_zlib()),
+            saved((1, 0), COMPRESSION_ZLIB, chunk_nbt(second, (0, 1, 0), 2).to_zlib()),
+        ]);
+
+        let mut regions = Regions::new(world);
+        let mut entities = Entities::new();
+        assert!(regions.load_chunk(Dimension::Overworld, first, &mut entities).unwrap().is_some());
+
+        // The file is not opened again for the other chunks of the region, so
+        // they are still read once it is gone.
+        fs::remove_file(&path).unwrap();
+        let chunk = regions.load_chunk(Dimension::Overworld, second, &mut entities).unwrap().unwrap();
+        assert_eq!(*chunk.block(0, 1, 0), Block::with_metadata(block_ids::WOOL, 2));
+    }
+
+    #[test]
+    fn beta_region_files_are_read() {
+        let world = world("beta");
+        let path = RegionFile::file_path(&world, Dimension::Overworld, ChunkPos::new(0, 0, 0));
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, BETA_REGION).unwrap();
+
+        let mut regions = Regions::new(world);
+        let mut entities = Entities::new();
+        let at = ChunkPos::new(1, 0, 2);
+        let chunk = regions.load_chunk(Dimension::Overworld, at, &mut entities).unwrap().unwrap();
+
+        let id = |x, y, z| chunk.block(x, y, z).id;
+        assert_eq!(id(3, 0, 4), block_ids::BEDROCK);
+        assert_eq!(id(3, 30, 4), block_ids::STONE);
+        assert_eq!(id(3, 62, 4), block_ids::DIRT);
+        assert_eq!(id(3, 63, 4), block_ids::GRASS);
+        assert_eq!(id(3, 64, 4), block_ids::AIR);
+        assert_eq!(id(0, 63, 0), block_ids::WATER);
+        assert_eq!(id(10, 64, 10), block_ids::GLASS);
+        assert_eq!(id(12, 66, 12), block_ids::STONE);
+        assert_eq!(*chunk.block(2, 64, 3), Block::with_metadata(block_ids::WOOL, 14));
+        assert_eq!(*chunk.block(5, 64, 7), Block::with_metadata(block_ids::TORCH, 5));
+
+        // The block entities are at their place in the world, not in the
+        // chunk.
+        match chunk.block_entities().get(&BlockPos::new(24, 64, 40)) {
+            Some(BlockEntity::Chest { items }) => {
+                assert_eq!(items.get(0), Some(&ItemStack::new(block_ids::TORCH, 16)));
+                assert_eq!(items.get(13), Some(&ItemStack::with_damage(item_ids::IRON_PICKAXE, 1, 27)));
+                assert_eq!(items.slots().iter().flatten().count(), 2);
+            },
+            entity => panic!("{:?}", entity),
+        }
+
+        let mut loaded = entities.iter().collect::<Vec<_>>();
+        loaded.sort_by(|a, b| a.position().x.partial_cmp(&b.position().x).unwrap());
+        match &loaded[..] {
+            [pig, item] => {
+                let mob = pig.mob().unwrap();
+                assert_eq!((mob.mob, mob.health, mob.saddled), (MobType::Pig, 10, true));
+                assert_eq!(pig.position(), Point3::new(20.5, 64.0, 36.5));
+                assert!(matches!(item.kind, EntityKind::Item { stack, age: 1200, .. } if stack == ItemStack::new(item_ids::DIAMOND, 2)));
+            },
+            loaded => panic!("{:?}", loaded),
+        }
+
+        // The light is not read, but the height map Beta saved agrees with
+        // the blocks: it is right above the highest block which dims the
+        // light, such as the wool and the water, but not the glass, the torch
+        // or the chest.
+        let tag = RegionFile::open(&path).unwrap().read_chunk(1, 2).unwrap().unwrap();
+        let level = tag.get("").unwrap().compound().unwrap()
+            .get("Level").unwrap().compound().unwrap();
+        let height_map = level.get("HeightMap").unwrap().byte_array().unwrap();
+        for x in 0..CHUNK_LENGTH_X {
+            for z in 0..CHUNK_LENGTH_Z {
+                let expected = match (x, z) {
+                    (2, 3) => 65,
+                    (12, 12) => 67,
+                    _ => 64,
+                };
+                assert_eq!(height_map[z << 4 | x], expected, "{:?}", (x, z));
+            }
+        }
+        assert_eq!(id(12, 67, 12), block_ids::AIR);
+
+        // The other chunk has nothing on it, and the rest were never saved.
+        let corner = regions.load_chunk(Dimension::Overworld, ChunkPos::new(31, 0, 31), &mut entities).unwrap().unwrap();
+        assert_eq!(corner.block(0, 0, 0).id, block_ids::BEDROCK);
+        assert_eq!(corner.block(0, 1, 0).id, block_ids::AIR);
+        assert!(regions.load_chunk(Dimension::Overworld, ChunkPos::new(0, 0, 0), &mut entities).unwrap().is_none());
+        assert_eq!(entities.len(), 2);
     }
 }
@@ -0,0 +1,270 @@
+use std::collections::{ BTreeMap, HashMap };
+use std::path::PathBuf;
+use cgmath::Point3;
+use gekraftet_core::entity::{ Entities, EntityId };
+use gekraftet_core::player::Player;
+use gekraftet_core::utils::Random;
+use gekraftet_core::world::{ BlockPos, ChunkPos, Dimension, Level, WorldGenerator, WorldPreset };
+use rand::SeedableRng;
+use super::region::Regions;
+
+// The chunks this close to a player, in chunks, are loaded.
+const PLAYER_CHUNK_RADIUS: i32 = 1;
+// The chunks this close to where a player arrives through a portal, in
+// chunks, are loaded before it arrives, which covers where a portal can be
+// built for it.
+const ARRIVAL_CHUNK_RADIUS: i32 = 2;
+// The ticks between two rounds of mobs appearing around the players.
+const MOB_SPAWN_INTERVAL: u64 = 20;
+
+/// Loads a chunk into the level of a dimension if it is not loaded yet: from
+/// the region file of the dimension if it was saved there, along with its
+/// entities, or from the generator of the dimension otherwise.
+fn load_chunk(level: &mut Level, entities: &mut Entities, regions: &mut Regions, dimension: Dimension,
+              generator: &dyn WorldGenerator, at: ChunkPos) {
+    if level.chunk(at).is_some() {
+        return
+    }
+
+    match regions.load_chunk(dimension, at, entities) {
+        Ok(Some(chunk)) => {
+            level.insert_chunk(chunk);
+        },
+        Ok(None) => {
+            level.load_chunk(at, generator);
+        },
+        Err(e) => {
+            println!("could not load chunk {:?} of {:?}: {:?}", (at.x, at.z), dimension, e);
+            level.load_chunk(at, generator);
+        },
+    }
+}
+
+fn block_at(position: Point3<f64>) -> BlockPos {
+    BlockPos::new(position.x.floor() as i32, position.y.floor() as i32, position.z.floor() as i32)
+}
+
+/// The chunks within `radius` chunks of the chunk holding `at`.
+fn chunks_around(at: BlockPos, radius: i32) -> impl Iterator<Item = ChunkPos> {
+    let ChunkPos(center) = ChunkPos::from(at);
+    (-radius..=radius).flat_map(move |x| (-radius..=radius).map(move |z| ChunkPos::new(center.x + x, 0, center.z + z)))
+}
+
+/// What the sessions share about the world: the level of every dimension,
+/// its generator, the entities in it and the players who are logged in.
+pub struct WorldState {
+    /// The directory the world is saved in.
+    pub directory: PathBuf,
+    pub seed: i64,
+    /// Where new players appear, and where dead players respawn if they have
+    /// not slept in a bed.
+    pub spawn: BlockPos,
+    /// The loaded chunks of every dimension. The chunks around the players
+    /// are loaded as they move, and are never unloaded.
+    pub levels: HashMap<Dimension, Level>,
+    pub generators: HashMap<Dimension, Box<dyn WorldGenerator>>,
+    pub entities: HashMap<Dimension, Entities>,
+    /// The players who are logged in, keyed by their entity ids.
+    pub players: BTreeMap<EntityId, Player>,
+    pub rng: Random,
+    /// The region files the chunks are loaded from.
+    regions: Regions,
+}
+
+impl WorldState {
+    /// Creates the state of a world whose overworld is shaped by `preset`.
+    pub fn new(directory: PathBuf, seed: i64, spawn: BlockPos, preset: WorldPreset) -> Self {
+        Self {
+            regions: Regions::new(directory.clone()),
+            directory,
+            seed,
+            spawn,
+            levels: Dimension::ALL.iter().map(|&d| (d, Level::new())).collect(),
+            generators: Dimension::ALL.iter().map(|&d| (d, d.generator(preset.clone(), seed as u64))).collect(),
+            entities: Dimension::ALL.iter().map(|&d| (d, Entities::new())).collect(),
+            players: BTreeMap::new(),
+            rng: Random::seed_from_u64(seed as u64),
+        }
+    }
+
+    /// Reserves an entity id for a player. The players go from a dimension
+    /// to another, so the id is one which no entity of any dimension has, nor
+    /// will have.
+    pub fn allocate_player_id(&mut self) -> EntityId {
+        let id = self.entities.values_mut().map(Entities::allocate_id).max().unwrap();
+        for entities in self.entities.values_mut() {
+            while entities.allocate_id() < id {}
+        }
+
+        id
+    }
+
+    /// Loads a chunk of a dimension if it is not loaded yet, from its region
+    /// file or from the generator of the dimension.
+    pub fn load_chunk(&mut self, dimension: Dimension, at: ChunkPos) {
+        let level = self.levels.get_mut(&dimension).unwrap();
+        let entities = self.entities.get_mut(&dimension).unwrap();
+        load_chunk(level, entities, &mut self.regions, dimension, &self.generators[&dimension], at);
+    }
+
+    /// The logged in player with the given name, if any.
+    pub fn player_named(&self, username: &str) -> Option<&Player> {
+        self.players.values().find(|p| p.username == username)
+    }
+
+    /// Darkens the sky of every dimension which has one, as given by
+    /// `sky_darkness` for the time and the weather of the world.
+    pub fn set_sky_darkness(&mut self, darkness: u8) {
+        for dimension in Dimension::ALL.iter().filter(|d| d.has_sky()) {
+            self.levels.get_mut(dimension).unwrap().set_sky_darkness(darkness);
+        }
+    }
+
+    /// Advances the levels, the players and the entities by a tick. The
+    /// chunks around the players are loaded first, and then the blocks of
+    /// every level are updated. The players run out of breath, burn and fall
+    /// into the void, and the dead players drop everything they carry. The
+    /// players who have stood in a portal for long enough go through it,
+    /// between the overworld and the Nether. Every second, mobs appear around
+    /// the players. The entities of every dimension are ticked with the
+    /// players in it.
+    pub fn tick(&mut self) {
+        let around = self.players.values()
+            .map(|p| (p.dimension, block_at(p.position())))
+            .collect::<Vec<_>>();
+        for (dimension, at) in around {
+            for chunk in chunks_around(at, PLAYER_CHUNK_RADIUS) {
+                self.load_chunk(dimension, chunk);
+            }
+        }
+
+        // The levels are ticked in the same order every time, so that they
+        // draw from the random generator in the same order.
+        for dimension in Dimension::ALL.iter() {
+            let level = self.levels.get_mut(dimension).unwrap();
+            level.tick(&mut self.rng);
+            // The clients are not sent the chunks, so they are not told about
+            // the blocks changing either.
+            level.take_events();
+        }
+
+        for player in self.players.values_mut() {
+            let level = &self.levels[&player.dimension];
+            player.tick(level);
+
+            if player.tick_portal(level) {
+                let to = match player.dimension {
+                    Dimension::Overworld => Dimension::Nether,
+                    Dimension::Nether => Dimension::Overworld,
+                };
+                let level = self.levels.get_mut(&to).unwrap();
+                let entities = self.entities.get_mut(&to).unwrap();
+                let arrival = block_at(player.dimension.convert_position(to, player.position()));
+                for chunk in chunks_around(arrival, ARRIVAL_CHUNK_RADIUS) {
+                    load_chunk(level, entities, &mut self.regions, to, &self.generators[&to], chunk);
+                }
+                player.travel(to, level, &self.generators[&to]);
+            }
+
+            if player.is_dead() {
+                let items = player.die();
+                let entities = self.entities.get_mut(&player.dimension).unwrap();
+                entities.scatter_items(player, items, &mut self.rng);
+            }
+        }
+
+        for dimension in Dimension::ALL.iter() {
+            let ids = self.players.values()
+                .filter(|p| p.dimension == *dimension)
+                .map(|p| p.entity_id)
+                .collect::<Vec<_>>();
+            let mut players = ids.iter()
+                .filter_map(|id| self.players.remove(id))
+                .collect::<Vec<_>>();
+
+            let level = self.levels.get_mut(dimension).unwrap();
+            let entities = self.entities.get_mut(dimension).unwrap();
+            if level.time().is_multiple_of(MOB_SPAWN_INTERVAL) {
+                entities.spawn_mobs(level, &*self.generators[dimension], &players, &mut self.rng);
+            }
+            entities.tick(level, &mut players, &mut self.rng);
+            // The players hear about their health from their sessions.
+            entities.take_attacks();
+
+            self.players.extend(players.into_iter().map(|p| (p.entity_id, p)));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use gekraftet_core::entity::EntityKind;
+    use gekraftet_core::world::{ block_ids, sky_darkness, Block, Weather };
+    use super::*;
+
+    fn flat_world(seed: i64) -> WorldState {
+        WorldState::new(
+            std::env::temp_dir().join("gekraftet-state-tests"),
+            seed,
+            BlockPos::new(0, 64, 0),
+            WorldPreset::parse("flat").unwrap(),
+        )
+    }
+
+    #[test]
+    fn blocks_are_updated_at_every_tick() {
+        let mut world = flat_world(40);
+        world.load_chunk(Dimension::Overworld, ChunkPos::new(0, 0, 0));
+
+        // Sand falls onto the grass, as a falling block for a while.
+        let (from, to) = (BlockPos::new(8, 20, 8), BlockPos::new(8, 4, 8));
+        let overworld = world.levels.get_mut(&Dimension::Overworld).unwrap();
+        overworld.set_block_with_notify(from, Block::new(block_ids::SAND));
+
+        let mut fell = false;
+        for _ in 0..100 {
+            world.tick();
+            fell |= world.entities[&Dimension::Overworld].iter()
+                .any(|e| matches!(e.kind, EntityKind::FallingBlock { .. }));
+        }
+
+        let overworld = &world.levels[&Dimension::Overworld];
+        assert!(fell);
+        assert_eq!(overworld.block(from), Some(Block::new(block_ids::AIR)));
+        assert_eq!(overworld.block(to), Some(Block::new(block_ids::SAND)));
+        assert!(world.entities[&Dimension::Overworld].is_empty());
+        assert_eq!(overworld.time(), 100);
+    }
+
+    #[test]
+    fn mobs_appear_around_the_players_at_night() {
+        let mut world = flat_world(47);
+        let id = world.allocate_player_id();
+        world.players.insert(id, Player::new("player", id, Point3::new(8.5, 4.0, 8.5)));
+        // The mobs keep away from the player, so more chunks are loaded than
+        // the ones around it.
+        for chunk in chunks_around(BlockPos::new(8, 4, 8), 3) {
+            world.load_chunk(Dimension::Overworld, chunk);
+        }
+
+        // Hostile mobs only appear in the dark.
+        let midnight = 18000;
+        world.set_sky_darkness(sky_darkness(midnight, &Weather::default()));
+        for _ in 0..2 * MOB_SPAWN_INTERVAL {
+            world.tick();
+        }
+
+        let overworld = &world.entities[&Dimension::Overworld];
+        assert!(overworld.iter().any(|e| e.mob().is_some_and(|mob| mob.mob.is_hostile())));
+        assert!(world.entities[&Dimension::Nether].is_empty());
+    }
+
+    #[test]
+    fn only_the_overworld_has_a_sky_to_darken() {
+        let mut world = flat_world(47);
+        world.set_sky_darkness(11);
+
+        assert_eq!(world.levels[&Dimension::Overworld].sky_darkness(), 11);
+        assert_eq!(world.levels[&Dimension::Nether].sky_darkness(), 0);
+    }
+}